@@ -16,6 +16,9 @@ pub enum LspError {
         path: String,
         reason: String,
     },
+    NotALeanProject {
+        searched_from: PathBuf,
+    },
 }
 
 impl fmt::Display for LspError {
@@ -77,6 +80,20 @@ Run from a directory containing lakefile.lean (or a subdirectory).
                 writeln!(f)?;
                 writeln!(f, "Try rebuilding: lake build lean-dag")
             }
+            Self::NotALeanProject { searched_from } => {
+                writeln!(
+                    f,
+                    "No Lean project found at {} or above it.",
+                    searched_from.display()
+                )?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "lake serve needs a lakefile.lean or lakefile.toml. Run from a Lake \
+                     project (or a subdirectory), create one with `lake new`, or pass \
+                     --server-cmd."
+                )
+            }
         }
     }
 }
@@ -0,0 +1,146 @@
+//! HTML rendering of proof states, with diff highlighting as CSS classes.
+
+use std::{
+    fmt::Write,
+    fs, io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::lean_rpc::{DiffTag, ProofState, TaggedText};
+
+const STYLE: &str = "body { background: #1e1e2e; color: #cdd6f4; font-family: monospace; }
+.hyp-name { color: #89b4fa; }
+.goal { color: #89dceb; }
+.inserted { background: #1f3d2a; color: #a6e3a1; }
+.changed { background: #3d3a1f; color: #f9e2af; }
+.removed { background: #3d1f24; color: #f38ba8; text-decoration: line-through; }";
+
+/// CSS class for a diff status.
+const fn diff_class(tag: DiffTag) -> &'static str {
+    match tag {
+        DiffTag::WasInserted | DiffTag::WillInsert => "inserted",
+        DiffTag::WasChanged | DiffTag::WillChange => "changed",
+        DiffTag::WasDeleted | DiffTag::WillDelete => "removed",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Render tagged text, wrapping diff-tagged sub-expressions in classed spans.
+fn tagged_to_html(text: &TaggedText, out: &mut String) {
+    match text {
+        TaggedText::Text { text } => out.push_str(&escape(text)),
+        TaggedText::Tag { info, content } => match info.diff_status {
+            Some(tag) => {
+                let _ = write!(out, "<span class=\"{}\">", diff_class(tag));
+                tagged_to_html(content, out);
+                out.push_str("</span>");
+            }
+            None => tagged_to_html(content, out),
+        },
+        TaggedText::Append { items } => {
+            for item in items {
+                tagged_to_html(item, out);
+            }
+        }
+    }
+}
+
+/// Render hypotheses and goals as a standalone HTML document.
+pub fn goals_to_html(state: &ProofState) -> String {
+    let mut body = String::from("<pre>\n");
+    for h in &state.hypotheses {
+        let class = if h.is_removed { " removed" } else { "" };
+        let _ = write!(
+            body,
+            "<span class=\"hyp-name{class}\">{}</span> : ",
+            escape(&h.name)
+        );
+        tagged_to_html(&h.type_, &mut body);
+        body.push('\n');
+    }
+    for g in &state.goals {
+        if let Some(name) = g.username.as_str() {
+            let _ = writeln!(body, "case {}", escape(name));
+        }
+        body.push_str("<span class=\"goal\">⊢ ");
+        tagged_to_html(&g.type_, &mut body);
+        body.push_str("</span>\n");
+    }
+    body.push_str("</pre>");
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Lean \
+         goals</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n{body}\n</body>\n</html>\n"
+    )
+}
+
+/// Write the goals to a fresh temporary HTML file and open it with the
+/// system opener. The file is kept, since the browser may read it long
+/// after this returns. Returns the path of the written file.
+pub fn open_in_browser(state: &ProofState) -> io::Result<PathBuf> {
+    let file = tempfile::Builder::new()
+        .prefix("lean-tui-goals-")
+        .suffix(".html")
+        .tempfile()?;
+    let (_, path) = file.keep().map_err(|e| e.error)?;
+    fs::write(&path, goals_to_html(state))?;
+
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let mut child = Command::new(opener)
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the opener once it hands the file over, so it leaves no zombie
+    thread::spawn(move || child.wait());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, SubexprInfo};
+
+    /// Inserted sub-expressions are wrapped in a span with the `inserted`
+    /// class.
+    #[test]
+    fn test_inserted_span() {
+        let state = ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Append {
+                    items: vec![
+                        TaggedText::Text {
+                            text: "a < ".to_string(),
+                        },
+                        TaggedText::Tag {
+                            info: SubexprInfo {
+                                diff_status: Some(DiffTag::WasInserted),
+                                ..Default::default()
+                            },
+                            content: Box::new(TaggedText::Text {
+                                text: "b + 1".to_string(),
+                            }),
+                        },
+                    ],
+                },
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        };
+
+        let html = goals_to_html(&state);
+        assert!(html.contains("⊢ a &lt; <span class=\"inserted\">b + 1</span>"));
+    }
+}
@@ -0,0 +1,69 @@
+//! Goal states as a Lean block comment, for pasting into documentation.
+
+use std::fmt::Write;
+
+use crate::lean_rpc::ProofState;
+
+/// Break comment delimiters so goal text cannot end (or, since Lean block
+/// comments nest, open) the surrounding comment.
+fn escape(text: &str) -> String {
+    text.replace("-/", "- /").replace("/-", "/ -")
+}
+
+/// Format hypotheses and goals as a `/- … -/` block comment.
+pub fn goals_to_lean_comment(state: &ProofState) -> String {
+    let mut out = String::from("/-\n");
+    for h in &state.hypotheses {
+        let _ = writeln!(
+            out,
+            "{} : {}",
+            escape(&h.name),
+            escape(&h.type_.to_plain_text())
+        );
+    }
+    for g in &state.goals {
+        if let Some(name) = g.username.as_str() {
+            let _ = writeln!(out, "case {}", escape(name));
+        }
+        let _ = writeln!(out, "⊢ {}", escape(&g.type_.to_plain_text()));
+    }
+    if state.goals.is_empty() {
+        out.push_str("No goals\n");
+    }
+    out.push_str("-/\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo, TaggedText};
+
+    fn text(s: &str) -> TaggedText {
+        TaggedText::Text {
+            text: s.to_string(),
+        }
+    }
+
+    /// The comment has one opening and one closing delimiter, even when the
+    /// goal text contains some.
+    #[test]
+    fn test_comment_escapes_delimiters() {
+        let state = ProofState {
+            goals: vec![GoalInfo {
+                type_: text("f /- odd -/ x = 0"),
+                ..Default::default()
+            }],
+            hypotheses: vec![HypothesisInfo {
+                name: "n".to_string(),
+                type_: text("Nat"),
+                ..Default::default()
+            }],
+        };
+
+        let comment = goals_to_lean_comment(&state);
+        assert_eq!(comment, "/-\nn : Nat\n⊢ f / - odd - / x = 0\n-/\n");
+        assert_eq!(comment.matches("/-").count(), 1);
+        assert_eq!(comment.matches("-/").count(), 1);
+    }
+}
@@ -0,0 +1,107 @@
+//! Goal states as Markdown, for pasting into GitHub issues or docs.
+
+use std::fmt::Write;
+
+use crate::lean_rpc::{GoalInfo, ProofState};
+
+/// Fence longer than any run of backticks in `body`, so goal text cannot
+/// close the code block.
+fn fence_for(body: &str) -> String {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Diff annotation as a trailing Lean comment.
+const fn diff_comment(is_inserted: bool, is_removed: bool) -> &'static str {
+    match (is_inserted, is_removed) {
+        (true, _) => " -- inserted",
+        (_, true) => " -- removed",
+        _ => "",
+    }
+}
+
+/// Hypotheses and target of one goal inside a `lean` code fence.
+fn goal_block(state: &ProofState, goal: &GoalInfo) -> String {
+    let mut body = String::new();
+    for h in &state.hypotheses {
+        let _ = writeln!(
+            body,
+            "{} : {}{}",
+            h.name,
+            h.type_.to_plain_text(),
+            diff_comment(h.is_inserted, h.is_removed)
+        );
+    }
+    let _ = writeln!(
+        body,
+        "⊢ {}{}",
+        goal.type_.to_plain_text(),
+        diff_comment(false, goal.is_removed)
+    );
+    let fence = fence_for(&body);
+    format!("{fence}lean\n{body}{fence}\n")
+}
+
+/// Format the goals as Markdown, one `lean` code fence per goal, with a
+/// header for each when there are several.
+pub fn goals_to_markdown(state: &ProofState) -> String {
+    if state.goals.is_empty() {
+        return "No goals\n".to_string();
+    }
+    if let [goal] = state.goals.as_slice() {
+        return goal_block(state, goal);
+    }
+    let mut out = String::new();
+    for (i, goal) in state.goals.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "#### Goal {}", i + 1);
+        if let Some(name) = goal.username.as_str() {
+            let _ = write!(out, " (case {name})");
+        }
+        out.push_str("\n\n");
+        out.push_str(&goal_block(state, goal));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{HypothesisInfo, TaggedText};
+
+    fn text(s: &str) -> TaggedText {
+        TaggedText::Text {
+            text: s.to_string(),
+        }
+    }
+
+    /// Each goal gets a header and a `lean` fence with its target, and
+    /// inserted hypotheses are annotated.
+    #[test]
+    fn test_markdown_has_lean_fence() {
+        let goal = |target: &str| GoalInfo {
+            type_: text(target),
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: vec![goal("n + 0 = n"), goal("0 = 0")],
+            hypotheses: vec![HypothesisInfo {
+                name: "n".to_string(),
+                type_: text("Nat"),
+                is_inserted: true,
+                ..Default::default()
+            }],
+        };
+
+        let markdown = goals_to_markdown(&state);
+        assert!(
+            markdown.starts_with("#### Goal 1\n\n```lean\n"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("#### Goal 2"));
+        assert!(markdown.contains("n : Nat -- inserted\n⊢ n + 0 = n\n```\n"));
+        assert!(markdown.contains("⊢ 0 = 0"));
+    }
+}
@@ -0,0 +1,5 @@
+//! Export of goal states for sharing outside the terminal.
+
+pub mod html;
+pub mod lean_comment;
+pub mod markdown;
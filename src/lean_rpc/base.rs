@@ -1,39 +1,69 @@
 //! Shared LSP client infrastructure for both library and standalone modes.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     env,
     future::Future,
+    mem,
     ops::ControlFlow,
     pin::Pin,
-    sync::atomic::{AtomicI64, Ordering},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 
 use async_lsp::{
     lsp_types::{
-        notification::{DidChangeTextDocument, DidOpenTextDocument, Initialized},
-        request::{Initialize, Request},
-        ClientCapabilities, DidChangeTextDocumentParams, DidOpenTextDocumentParams,
-        InitializeParams, InitializedParams, Position, PublishDiagnosticsParams,
-        TextDocumentIdentifier, Url,
+        notification::{
+            DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Initialized,
+            Notification,
+        },
+        request::{GotoImplementation, Initialize, Request, Shutdown, WorkspaceSymbolRequest},
+        ClientCapabilities, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams,
+        GotoDefinitionResponse, InitializeParams, InitializedParams, OneOf, Position,
+        PublishDiagnosticsParams, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+        WorkspaceSymbolParams, WorkspaceSymbolResponse,
     },
     AnyEvent, AnyNotification, AnyRequest, LspService, ResponseError, ServerSocket,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio::sync::{Mutex, RwLock};
+use tokio::{
+    sync::{Mutex, RwLock},
+    time::interval,
+};
 use tower_service::Service;
 
-use super::{ProofDag, RpcConnectResponse, GET_PROOF_DAG, RPC_CALL, RPC_CONNECT};
+use super::{
+    GotoLocation, ProofDag, RpcConnectResponse, SymbolMatch, GET_PROOF_DAG, RPC_CALL, RPC_CONNECT,
+    RPC_KEEP_ALIVE,
+};
 use crate::error::LspError;
 
 /// Lean pretty-printer options for the server.
-pub const LEAN_PP_OPTIONS: &[&str] = &["pp.showLetValues=true"];
+const LEAN_PP_OPTIONS: &[&str] = &["pp.showLetValues=true"];
+
+/// Pretty-printer options to start the server with, showing implicit
+/// arguments (`@f α a`) when `explicit` is set. Lean reads them only at
+/// startup, so changing them takes a new server.
+pub fn pp_options(explicit: bool) -> Vec<&'static str> {
+    let mut options = LEAN_PP_OPTIONS.to_vec();
+    if explicit {
+        options.push("pp.explicit=true");
+    }
+    options
+}
 
 /// Lean LSP error code for outdated RPC session.
 const RPC_SESSION_OUTDATED: i32 = -32900;
 
+/// How often sessions are kept alive; Lean drops them after ~30s of silence.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Document state tracked by the client.
 pub struct DocumentState {
     pub version: u32,
@@ -84,6 +114,21 @@ pub struct RpcConnectParams {
     pub uri: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcKeepAliveParams {
+    pub uri: String,
+    pub session_id: String,
+}
+
+/// `$/lean/rpc/keepAlive` notification.
+pub enum RpcKeepAlive {}
+
+impl Notification for RpcKeepAlive {
+    type Params = RpcKeepAliveParams;
+    const METHOD: &'static str = RPC_KEEP_ALIVE;
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcCallParams<P> {
@@ -102,6 +147,10 @@ pub struct GetProofDagParams {
     pub mode: String,
 }
 
+/// Levels of detail lean-dag can build the proof DAG at, the first being
+/// the default.
+pub const DAG_MODES: &[&str] = &["tree", "tactics-only", "terms"];
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetProofDagResult {
@@ -123,6 +172,7 @@ pub struct BaseLspClient {
     socket: ServerSocket,
     documents: RwLock<HashMap<String, DocumentState>>,
     sessions: Mutex<HashMap<String, u64>>,
+    dag_mode: RwLock<&'static str>,
     next_id: AtomicI64,
 }
 
@@ -134,6 +184,7 @@ impl BaseLspClient {
             socket,
             documents: RwLock::new(HashMap::new()),
             sessions: Mutex::new(HashMap::new()),
+            dag_mode: RwLock::new(DAG_MODES[0]),
             next_id: AtomicI64::new(1),
         }
     }
@@ -244,6 +295,17 @@ impl BaseLspClient {
 
         tracing::debug!("[{}] didChange {} v{}", self.name, uri, version);
 
+        // Already part of the text a restarted server was opened with
+        let stale = self
+            .documents
+            .read()
+            .await
+            .get(&uri)
+            .is_some_and(|doc| doc.version >= version);
+        if stale {
+            return Ok(());
+        }
+
         self.socket
             .notify::<DidChangeTextDocument>(params)
             .map_err(|e| LspError::RpcError {
@@ -263,6 +325,29 @@ impl BaseLspClient {
         Ok(())
     }
 
+    /// Close a document in the server, dropping its RPC session.
+    pub async fn did_close(&self, uri: &Url) -> Result<(), LspError> {
+        tracing::debug!("[{}] didClose {}", self.name, uri);
+
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+        };
+        self.socket
+            .notify::<DidCloseTextDocument>(params)
+            .map_err(|e| LspError::RpcError {
+                code: None,
+                message: format!(
+                    "Lost connection to {} while closing document: {e:?}",
+                    self.name
+                ),
+            })?;
+
+        self.documents.write().await.remove(uri.as_str());
+        self.sessions.lock().await.remove(uri.as_str());
+
+        Ok(())
+    }
+
     /// Wait for diagnostics to complete for a document.
     pub async fn wait_for_diagnostics(&self, uri: &Url, version: u32) -> Result<(), LspError> {
         let params = WaitForDiagnosticsParams {
@@ -308,11 +393,153 @@ impl BaseLspClient {
         Ok(session_id)
     }
 
+    /// Send a keep-alive for every open RPC session.
+    pub async fn keep_alive(&self) {
+        let sessions: Vec<_> = self
+            .sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(uri, &id)| (uri.clone(), id))
+            .collect();
+
+        for (uri, session_id) in sessions {
+            let params = RpcKeepAliveParams {
+                uri,
+                session_id: session_id.to_string(),
+            };
+            if let Err(e) = self.socket.notify::<RpcKeepAlive>(params) {
+                tracing::debug!("[{}] keepAlive failed: {e:?}", self.name);
+            }
+        }
+    }
+
+    /// Keep sessions alive in the background until the client is dropped.
+    pub fn spawn_keep_alive(self: &Arc<Self>) {
+        let client = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = interval(KEEP_ALIVE_INTERVAL);
+            ticker.tick().await;
+            while let Some(client) = client.upgrade() {
+                client.keep_alive().await;
+                drop(client);
+                ticker.tick().await;
+            }
+        });
+    }
+
     /// Invalidate the RPC session for a document.
     pub async fn invalidate_session(&self, uri: &Url) {
         self.sessions.lock().await.remove(uri.as_str());
     }
 
+    /// Drop the RPC session for a document and connect a fresh one.
+    pub async fn reset_session(&self, uri: &Url) -> Result<u64, LspError> {
+        self.invalidate_session(uri).await;
+        self.create_session(uri).await
+    }
+
+    /// Find symbols in the workspace whose names match `query`.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolMatch>, LspError> {
+        let params = WorkspaceSymbolParams {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        let response = self.request(WorkspaceSymbolRequest::METHOD, params).await?;
+        let symbols: Option<WorkspaceSymbolResponse> = serde_json::from_value(response)
+            .map_err(|e| LspError::ParseError(format!("Invalid workspace symbols: {e}")))?;
+
+        Ok(match symbols {
+            None => Vec::new(),
+            Some(WorkspaceSymbolResponse::Flat(symbols)) => symbols
+                .into_iter()
+                .map(|s| SymbolMatch {
+                    name: s.name,
+                    container: s.container_name,
+                    location: GotoLocation {
+                        uri: s.location.uri,
+                        position: s.location.range.start,
+                    },
+                })
+                .collect(),
+            Some(WorkspaceSymbolResponse::Nested(symbols)) => symbols
+                .into_iter()
+                .map(|s| {
+                    let (uri, position) = match s.location {
+                        OneOf::Left(location) => (location.uri, location.range.start),
+                        OneOf::Right(location) => (location.uri, Position::default()),
+                    };
+                    SymbolMatch {
+                        name: s.name,
+                        container: s.container_name,
+                        location: GotoLocation { uri, position },
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Where the symbol at `position` is implemented, by
+    /// `textDocument/implementation`. The first location when the server
+    /// reports several.
+    pub async fn implementation(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Result<Option<GotoLocation>, LspError> {
+        let params =
+            TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri.clone()), position);
+        let response = self.request(GotoImplementation::METHOD, params).await?;
+        let locations: Option<GotoDefinitionResponse> = serde_json::from_value(response)
+            .map_err(|e| LspError::ParseError(format!("Invalid implementation locations: {e}")))?;
+
+        Ok(match locations {
+            None => None,
+            Some(GotoDefinitionResponse::Scalar(location)) => Some(location),
+            Some(GotoDefinitionResponse::Array(locations)) => locations.into_iter().next(),
+            Some(GotoDefinitionResponse::Link(links)) => {
+                return Ok(links.into_iter().next().map(|link| GotoLocation {
+                    uri: link.target_uri,
+                    position: link.target_selection_range.start,
+                }));
+            }
+        }
+        .map(|location| GotoLocation {
+            uri: location.uri,
+            position: location.range.start,
+        }))
+    }
+
+    /// Ask the server to shut down and exit, e.g. once a restarted one has
+    /// taken its place.
+    pub async fn shutdown(&self) {
+        if let Err(e) = self.request(Shutdown::METHOD, ()).await {
+            tracing::warn!("[{}] Shutdown failed: {e}", self.name);
+        }
+        if let Err(e) = self.socket.notify::<Exit>(()) {
+            tracing::warn!("[{}] Exit failed: {e:?}", self.name);
+        }
+    }
+
+    /// Set the DAG mode for subsequent proof DAG requests.
+    pub async fn set_dag_mode(&self, mode: &'static str) {
+        *self.dag_mode.write().await = mode;
+    }
+
+    /// DAG mode proof DAG requests ask for.
+    pub async fn dag_mode(&self) -> &'static str {
+        *self.dag_mode.read().await
+    }
+
+    /// Version of a document as last opened or changed, if open.
+    pub async fn document_version(&self, uri: &Url) -> Option<u32> {
+        self.documents
+            .read()
+            .await
+            .get(uri.as_str())
+            .map(|d| d.version)
+    }
+
     /// Get the proof DAG at a position.
     pub async fn get_proof_dag(
         &self,
@@ -320,18 +547,28 @@ impl BaseLspClient {
         position: Position,
         mode: &str,
     ) -> Result<Option<ProofDag>, LspError> {
+        // Positions that are already elaborated answer right away
+        match self.fetch_proof_dag(uri, position, mode).await {
+            Ok(Some(dag)) if !dag.is_empty() => return Ok(Some(dag)),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("[{}] Early fetch failed for {}: {e}", self.name, uri),
+        }
+
         // Get document version
-        let version = self
-            .documents
-            .read()
-            .await
-            .get(uri.as_str())
-            .map_or(1, |d| d.version);
+        let version = self.document_version(uri).await.unwrap_or(1);
 
-        // Wait for diagnostics first
+        // Otherwise wait for diagnostics and fetch again
         self.wait_for_diagnostics(uri, version).await?;
+        self.fetch_proof_dag(uri, position, mode).await
+    }
 
-        // Try with existing session, retry once if session is outdated
+    /// Fetch the proof DAG, retrying once if the session is outdated.
+    async fn fetch_proof_dag(
+        &self,
+        uri: &Url,
+        position: Position,
+        mode: &str,
+    ) -> Result<Option<ProofDag>, LspError> {
         match self.try_get_proof_dag(uri, position, mode).await {
             Ok(result) => Ok(result),
             Err(LspError::RpcError {
@@ -375,10 +612,212 @@ impl BaseLspClient {
             return Ok(None);
         }
 
-        let result: GetProofDagResult = serde_json::from_value(response).map_err(|e| {
+        let result = GetProofDagResult::deserialize(&response).map_err(|e| {
+            tracing::warn!("Unparseable proof DAG response ({e}): {response}");
             LspError::ParseError(format!("Failed to parse proof DAG response: {e}"))
         })?;
 
         Ok(Some(result.proof_dag))
     }
 }
+
+/// Put a freshly started server in the place of the one in `slot`, taking
+/// over its DAG mode and reopening those of the `documents` it had open, as
+/// they are at the time of the swap, then shut the old one down.
+///
+/// Edits wait for the swap, so each lands on exactly one of the servers.
+pub async fn swap_server(
+    slot: &RwLock<Arc<BaseLspClient>>,
+    fresh: Arc<BaseLspClient>,
+    documents: impl FnOnce() -> Vec<DidOpenTextDocumentParams>,
+) -> Result<(), LspError> {
+    let mut current = slot.write().await;
+    fresh.set_dag_mode(current.dag_mode().await).await;
+    let open: HashSet<String> = current.documents.read().await.keys().cloned().collect();
+    let reopened = documents()
+        .into_iter()
+        .filter(|params| open.contains(params.text_document.uri.as_str()));
+    for params in reopened {
+        fresh.did_open(params).await?;
+    }
+    let old = mem::replace(&mut *current, fresh);
+    drop(current);
+    old.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::ready, sync::atomic::AtomicU64};
+
+    use async_lsp::{
+        lsp_types::{TextDocumentItem, VersionedTextDocumentIdentifier},
+        router::Router,
+        MainLoop,
+    };
+    use tokio::io::{duplex, split};
+    use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+    use super::*;
+    use crate::lean_rpc::ProofDagNode;
+
+    /// Client connected to an in-process server that answers each request
+    /// with `respond(method, params)`.
+    fn mock_client(
+        respond: impl Fn(&str, &serde_json::Value) -> serde_json::Value + Send + 'static,
+    ) -> BaseLspClient {
+        let (client_io, server_io) = duplex(1 << 16);
+
+        let (server, server_socket) = MainLoop::new_server(|_| {
+            let mut router = Router::new(());
+            router.unhandled_request(move |(), req| ready(Ok(respond(&req.method, &req.params))));
+            router.unhandled_notification(|(), _| ControlFlow::Continue(()));
+            router
+        });
+        let (server_read, server_write) = split(server_io);
+        tokio::spawn(async move {
+            // The main loop stops once its own socket is dropped
+            let _socket = server_socket;
+            server
+                .run_buffered(server_read.compat(), server_write.compat_write())
+                .await
+        });
+
+        let (client, socket) = MainLoop::new_client(|_| LeanService::new("Mock"));
+        let (client_read, client_write) = split(client_io);
+        tokio::spawn(client.run_buffered(client_read.compat(), client_write.compat_write()));
+
+        BaseLspClient::new("Mock", socket)
+    }
+
+    /// A restarted server takes over the DAG mode and the documents the old
+    /// one had open, at their latest text; an edit already in that text is
+    /// not sent again.
+    #[tokio::test]
+    async fn test_swap_server_reopens_documents() {
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        let other = Url::parse("file:///tmp/Other.lean").unwrap();
+        let open = |uri: &Url, version, text: &str| DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "lean4".to_string(),
+                version,
+                text.to_string(),
+            ),
+        };
+        let old = Arc::new(mock_client(|_, _| json!(null)));
+        old.did_open(open(&uri, 1, "a")).await.unwrap();
+        old.set_dag_mode("terms").await;
+        let slot = RwLock::new(old);
+        let fresh = Arc::new(mock_client(|_, _| json!(null)));
+
+        let documents = || vec![open(&uri, 3, "abc"), open(&other, 1, "x")];
+        swap_server(&slot, fresh.clone(), documents).await.unwrap();
+
+        let current = slot.read().await.clone();
+        assert!(Arc::ptr_eq(&current, &fresh));
+        assert_eq!(current.dag_mode().await, "terms");
+        assert_eq!(current.document_version(&uri).await, Some(3));
+        assert_eq!(current.document_version(&other).await, None, "never open");
+
+        let replayed = DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: Vec::new(),
+        };
+        current.did_change(replayed).await.unwrap();
+        assert_eq!(current.document_version(&uri).await, Some(3));
+    }
+
+    /// Resetting drops the old session and stores the freshly connected one.
+    #[tokio::test]
+    async fn test_reset_session_recreates_entry() {
+        let connects = AtomicU64::new(0);
+        let client = mock_client(move |method, _| {
+            assert_eq!(method, RPC_CONNECT);
+            let id = connects.fetch_add(1, Ordering::Relaxed) + 1;
+            json!({ "sessionId": id.to_string() })
+        });
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+
+        assert_eq!(client.get_session(&uri).await.unwrap(), 1);
+        assert_eq!(client.reset_session(&uri).await.unwrap(), 2);
+        assert_eq!(client.sessions.lock().await.get(uri.as_str()), Some(&2));
+        assert_eq!(client.get_session(&uri).await.unwrap(), 2, "reused");
+    }
+
+    /// The keep-alive notification carries the document and session id.
+    #[test]
+    fn test_keep_alive_params() {
+        let params = RpcKeepAliveParams {
+            uri: "file:///tmp/Test.lean".to_string(),
+            session_id: 42u64.to_string(),
+        };
+
+        assert_eq!(RpcKeepAlive::METHOD, "$/lean/rpc/keepAlive");
+        assert_eq!(
+            serde_json::to_value(params).unwrap(),
+            json!({ "uri": "file:///tmp/Test.lean", "sessionId": "42" })
+        );
+    }
+
+    /// Number of `waitForDiagnostics` requests made while fetching goals
+    /// from a server whose RPC call answers `result`.
+    async fn diagnostics_waits(result: serde_json::Value) -> u64 {
+        let waits = Arc::new(AtomicU64::new(0));
+        let counter = waits.clone();
+        let client = mock_client(move |method, _| match method {
+            RPC_CONNECT => json!({ "sessionId": "1" }),
+            RPC_CALL => result.clone(),
+            _ => json!(counter.fetch_add(1, Ordering::Relaxed)),
+        });
+
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        let _ = client
+            .get_proof_dag(&uri, Position::new(3, 2), "tree")
+            .await
+            .unwrap();
+        waits.load(Ordering::Relaxed)
+    }
+
+    /// Goals available before elaboration finishes are returned without
+    /// waiting for diagnostics; an empty answer falls back to waiting.
+    #[tokio::test]
+    async fn test_nonempty_fetch_skips_wait() {
+        let dag = ProofDag {
+            nodes: vec![ProofDagNode::default()],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        assert_eq!(diagnostics_waits(json!({ "proofDag": dag })).await, 0);
+        assert_eq!(diagnostics_waits(json!(null)).await, 1);
+    }
+
+    /// Symbol search sends the query as a `workspace/symbol` request and
+    /// reads back the flat symbol list.
+    #[tokio::test]
+    async fn test_workspace_symbol_request() {
+        let client = mock_client(|method, params| {
+            assert_eq!(method, "workspace/symbol");
+            assert_eq!(params["query"], "add_comm");
+            json!([{
+                "name": "Nat.add_comm",
+                "kind": 12,
+                "containerName": "Nat",
+                "location": {
+                    "uri": "file:///lib/Nat.lean",
+                    "range": {
+                        "start": { "line": 41, "character": 8 },
+                        "end": { "line": 41, "character": 16 }
+                    }
+                }
+            }])
+        });
+
+        let symbols = client.workspace_symbols("add_comm").await.unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "Nat.add_comm");
+        assert_eq!(symbols[0].container.as_deref(), Some("Nat"));
+        assert_eq!(symbols[0].location.position, Position::new(41, 8));
+    }
+}
@@ -4,7 +4,9 @@ use std::sync::Arc;
 
 use async_lsp::lsp_types::{DidChangeTextDocumentParams, DidOpenTextDocumentParams, Position, Url};
 
-use super::{lean_dag::LeanDagClient, lean_server::LeanServerClient, ProofDag};
+use super::{
+    lean_dag::LeanDagClient, lean_server::LeanServerClient, GotoLocation, ProofDag, SymbolMatch,
+};
 use crate::error::LspError;
 
 /// RPC client for fetching proof DAGs.
@@ -51,6 +53,14 @@ impl RpcClient {
         }
     }
 
+    /// Close a document in the server.
+    pub async fn did_close(&self, uri: &Url) -> Result<(), LspError> {
+        match self {
+            Self::LeanServer(client) => client.did_close(uri).await,
+            Self::LeanDag(client) => client.did_close(uri).await,
+        }
+    }
+
     /// Get the proof DAG at a position.
     pub async fn get_proof_dag(
         &self,
@@ -63,4 +73,77 @@ impl RpcClient {
             Self::LeanDag(client) => client.get_proof_dag(uri, position, mode).await,
         }
     }
+
+    /// Version of a document as last opened or changed, if open.
+    pub async fn document_version(&self, uri: &Url) -> Option<u32> {
+        match self {
+            Self::LeanServer(client) => client.document_version(uri).await,
+            Self::LeanDag(client) => client.document_version(uri).await,
+        }
+    }
+
+    /// Wait until the server has elaborated a version of a document.
+    pub async fn wait_for_diagnostics(&self, uri: &Url, version: u32) -> Result<(), LspError> {
+        match self {
+            Self::LeanServer(client) => client.wait_for_diagnostics(uri, version).await,
+            Self::LeanDag(client) => client.wait_for_diagnostics(uri, version).await,
+        }
+    }
+
+    /// Set the DAG mode for subsequent proof DAG requests.
+    pub async fn set_dag_mode(&self, mode: &'static str) {
+        match self {
+            Self::LeanServer(client) => client.set_dag_mode(mode).await,
+            Self::LeanDag(client) => client.set_dag_mode(mode).await,
+        }
+    }
+
+    /// DAG mode proof DAG requests ask for.
+    pub async fn dag_mode(&self) -> &'static str {
+        match self {
+            Self::LeanServer(client) => client.dag_mode().await,
+            Self::LeanDag(client) => client.dag_mode().await,
+        }
+    }
+
+    /// Find symbols in the workspace whose names match `query`.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolMatch>, LspError> {
+        match self {
+            Self::LeanServer(client) => client.workspace_symbols(query).await,
+            Self::LeanDag(client) => client.workspace_symbols(query).await,
+        }
+    }
+
+    /// Where the symbol at `position` is implemented.
+    pub async fn implementation(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Result<Option<GotoLocation>, LspError> {
+        match self {
+            Self::LeanServer(client) => client.implementation(uri, position).await,
+            Self::LeanDag(client) => client.implementation(uri, position).await,
+        }
+    }
+
+    /// Restart the server, with `pp.explicit` when `explicit` is set, and
+    /// reopen the `documents` on it.
+    pub async fn restart(
+        &self,
+        explicit: bool,
+        documents: impl FnOnce() -> Vec<DidOpenTextDocumentParams>,
+    ) -> Result<(), LspError> {
+        match self {
+            Self::LeanServer(client) => client.restart(explicit, documents).await,
+            Self::LeanDag(client) => client.restart(explicit, documents).await,
+        }
+    }
+
+    /// Replace the RPC session for a document with a fresh one.
+    pub async fn reset_session(&self, uri: &Url) -> Result<u64, LspError> {
+        match self {
+            Self::LeanServer(client) => client.reset_session(uri).await,
+            Self::LeanDag(client) => client.reset_session(uri).await,
+        }
+    }
 }
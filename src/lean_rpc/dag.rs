@@ -1,9 +1,13 @@
-use std::fmt;
+use std::{
+    collections::HashSet,
+    fmt::{self, Write},
+    iter,
+};
 
-use async_lsp::lsp_types::Position;
+use async_lsp::lsp_types::{Position, Range};
 use serde::{Deserialize, Serialize};
 
-use super::{GotoLocations, TaggedText};
+use super::{lenient, GotoLocations, TaggedText};
 /// Unique identifier for a node in the proof DAG.
 pub type NodeId = u32;
 
@@ -14,6 +18,15 @@ fn is_hygienic_name(name: &str) -> bool {
     name.contains("._hyg.") || name.contains("._@.")
 }
 
+/// Identifiers occurring in pretty-printed Lean text.
+///
+/// Dotted names are split into their components, so `h.1` mentions `h`.
+fn identifiers(text: &str) -> HashSet<&str> {
+    text.split(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '\'' | '!' | '?' | '✝')))
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 /// User-visible name for a goal.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum UserName {
@@ -70,15 +83,115 @@ impl UserName {
 
 /// A proof state (goals and hypotheses at a point in the proof).
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct ProofState {
     pub goals: Vec<GoalInfo>,
     pub hypotheses: Vec<HypothesisInfo>,
 }
 
+impl ProofState {
+    /// Names of hypotheses mentioned in the main goal's target.
+    ///
+    /// A name-based heuristic: shadowed or bound names can match spuriously.
+    pub fn mentioned_hypotheses(&self) -> HashSet<String> {
+        let Some(goal) = self.goals.first() else {
+            return HashSet::new();
+        };
+        let target = goal.type_.to_plain_text();
+        let idents = identifiers(&target);
+        self.hypotheses
+            .iter()
+            .filter(|h| idents.contains(h.name.as_str()))
+            .map(|h| h.name.clone())
+            .collect()
+    }
+
+    /// Names of hypotheses the main goal's target or another hypothesis's
+    /// type or value mentions.
+    pub fn referenced_hypotheses(&self) -> HashSet<String> {
+        let mut names = self.mentioned_hypotheses();
+        let deps = self.hypothesis_dependencies().into_iter().flatten();
+        names.extend(deps.map(|j| self.hypotheses[j].name.clone()));
+        names
+    }
+
+    /// For each hypothesis, the indices of the others its type or value
+    /// mentions.
+    fn hypothesis_dependencies(&self) -> Vec<Vec<usize>> {
+        let hyps = &self.hypotheses;
+        hyps.iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let mut text = h.type_.to_plain_text();
+                if let Some(value) = &h.value {
+                    text.push(' ');
+                    text.push_str(&value.to_plain_text());
+                }
+                let idents = identifiers(&text);
+                hyps.iter()
+                    .enumerate()
+                    .filter(|&(j, other)| j != i && idents.contains(other.name.as_str()))
+                    .map(|(j, _)| j)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Hypothesis indices ordered so each hypothesis comes after those its
+    /// type or value mentions, keeping declaration order otherwise. Mentions
+    /// that form a cycle leave the declaration order as it is.
+    pub fn dependency_order(&self) -> Vec<usize> {
+        let hyps = &self.hypotheses;
+        let deps = self.hypothesis_dependencies();
+
+        let mut placed = vec![false; hyps.len()];
+        let mut order = Vec::with_capacity(hyps.len());
+        while order.len() < hyps.len() {
+            let ready = (0..hyps.len()).find(|&i| !placed[i] && deps[i].iter().all(|&d| placed[d]));
+            let Some(next) = ready else {
+                return (0..hyps.len()).collect();
+            };
+            placed[next] = true;
+            order.push(next);
+        }
+        order
+    }
+
+    /// Render as a definition signature, e.g. `(a b : Nat) [Monoid M] : P a`.
+    ///
+    /// Each binder keeps the brackets it was introduced with, e.g. `{α : Type}`.
+    /// Consecutive binders of the same kind and type share brackets, except
+    /// instances, which are each shown in their own square brackets.
+    pub fn signature(&self) -> Option<String> {
+        let goal = self.goals.first()?;
+        let mut binders: Vec<(Vec<&str>, String, (&str, &str))> = Vec::new();
+        for h in &self.hypotheses {
+            let ty = h.type_.to_plain_text();
+            let brackets = h.binder_brackets().unwrap_or(("(", ")"));
+            let shareable = brackets.0 != "[";
+            match binders.last_mut() {
+                Some((names, last_ty, last))
+                    if shareable && *last == brackets && *last_ty == ty =>
+                {
+                    names.push(&h.name);
+                }
+                _ => binders.push((vec![h.name.as_str()], ty, brackets)),
+            }
+        }
+
+        let mut signature = String::new();
+        for (names, ty, (open, close)) in &binders {
+            let _ = write!(signature, "{open}{} : {ty}{close} ", names.join(" "));
+        }
+        signature.push_str(": ");
+        signature.push_str(&goal.type_.to_plain_text());
+        Some(signature)
+    }
+}
+
 /// A goal to prove.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct GoalInfo {
     /// Goal type expression (with diff highlighting).
     #[serde(rename = "type")]
@@ -88,16 +201,47 @@ pub struct GoalInfo {
     /// Internal goal ID (for tracking across steps).
     pub id: String,
     /// Whether this goal was removed (for diff display in "before" view).
-    #[serde(default)]
     pub is_removed: bool,
     /// Pre-resolved `goto` locations for navigation.
-    #[serde(default)]
+    #[serde(deserialize_with = "lenient")]
     pub goto_locations: GotoLocations,
+    /// Source range of the term, for term-mode (expected type) goals.
+    #[serde(deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+}
+
+impl GoalInfo {
+    /// Subtitle locating a term goal in the source, e.g. `term at 3:5`.
+    pub fn term_location(&self) -> Option<String> {
+        self.range.map(|range| {
+            format!(
+                "term at {}:{}",
+                range.start.line + 1,
+                range.start.character + 1
+            )
+        })
+    }
+}
+
+/// Kind of binder, as in Lean's `BinderInfo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum BinderInfo {
+    /// `(x : α)`
+    #[default]
+    Default,
+    /// `{x : α}`
+    Implicit,
+    /// `⦃x : α⦄`
+    StrictImplicit,
+    /// `[inst : C]`
+    InstImplicit,
 }
 
 /// A hypothesis in scope.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
 pub struct HypothesisInfo {
     /// User-visible name.
     pub name: String,
@@ -112,17 +256,47 @@ pub struct HypothesisInfo {
     pub is_proof: bool,
     /// Whether this is a type class instance.
     pub is_instance: bool,
+    /// Kind of binder that introduced the hypothesis, when the server
+    /// reports it.
+    #[serde(deserialize_with = "lenient")]
+    pub binder_info: BinderInfo,
     /// Whether this hypothesis was removed (for diff display in "before" view).
-    #[serde(default)]
     pub is_removed: bool,
+    /// Whether this hypothesis is new (for diff display in "after" view).
+    pub is_inserted: bool,
     /// Pre-resolved `goto` locations for navigation.
-    #[serde(default)]
+    #[serde(deserialize_with = "lenient")]
     pub goto_locations: GotoLocations,
 }
 
+impl HypothesisInfo {
+    /// Brackets of the binder that introduced the hypothesis. Explicit
+    /// binders get none, keeping the plain `name : type` form; instances
+    /// are instance-implicit even when the server reports no binder.
+    pub const fn binder_brackets(&self) -> Option<(&'static str, &'static str)> {
+        match self.binder_info {
+            BinderInfo::Implicit => Some(("{", "}")),
+            BinderInfo::StrictImplicit => Some(("⦃", "⦄")),
+            BinderInfo::InstImplicit => Some(("[", "]")),
+            BinderInfo::Default if self.is_instance => Some(("[", "]")),
+            BinderInfo::Default => None,
+        }
+    }
+}
+
+/// The same goal in two states: by id when the server sends ids, else by
+/// target.
+fn same_goal(a: &GoalInfo, b: &GoalInfo) -> bool {
+    if a.id.is_empty() || b.id.is_empty() {
+        a.type_.to_plain_text() == b.type_.to_plain_text()
+    } else {
+        a.id == b.id
+    }
+}
+
 /// A node representing a proof state after applying a tactic.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct ProofDagNode {
     pub id: NodeId,
 
@@ -141,8 +315,18 @@ pub struct ProofDagNode {
 
     /// True if this node has spawned goals (e.g., inline `by` blocks) that are
     /// not solved.
-    #[serde(default)]
     pub has_unsolved_spawned_goals: bool,
+
+    /// For the root of a side proof, the `where` or `let rec` clause it
+    /// proves (e.g. `where helper`), when the source shows one.
+    #[serde(deserialize_with = "lenient")]
+    pub clause: Option<String>,
+
+    /// Milliseconds Lean spent elaborating the tactic, when the server
+    /// reports it. The proxy only times whole documents, shown in the
+    /// status bar instead.
+    #[serde(deserialize_with = "lenient")]
+    pub elab_time_ms: Option<u64>,
 }
 
 impl ProofDagNode {
@@ -155,11 +339,76 @@ impl ProofDagNode {
     pub const fn is_leaf(&self) -> bool {
         self.children.is_empty()
     }
+
+    /// The tactic closes goals without proof (`sorry` or `admit`).
+    pub fn is_sorry(&self) -> bool {
+        let words = identifiers(&self.tactic.text);
+        words.contains("sorry") || words.contains("admit")
+    }
+
+    /// The tactic is automation that does its work opaquely (`omega`,
+    /// `decide`, `norm_num`, ...).
+    pub fn is_automation(&self) -> bool {
+        self.tactic
+            .text
+            .split_whitespace()
+            .next()
+            .is_some_and(|head| AUTOMATION_TACTICS.contains(&head.trim_end_matches(['!', '?'])))
+    }
+
+    /// Indices into `state_after.goals` of the goals the tactic split one
+    /// goal into (`constructor` on `A ∧ B`), when it made two or more.
+    pub fn split_goals(&self) -> Vec<usize> {
+        let (before, after) = (&self.state_before.goals, &self.state_after.goals);
+        let closed = before
+            .iter()
+            .filter(|b| !after.iter().any(|a| same_goal(a, b)))
+            .count();
+        let opened: Vec<usize> = (0..after.len())
+            .filter(|&i| !before.iter().any(|b| same_goal(&after[i], b)))
+            .collect();
+        if closed == 1 && opened.len() >= 2 {
+            opened
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Label for the goal at `goal_idx` of `state_after`.
+    ///
+    /// Named goals use their case name. Anonymous goals opened by an
+    /// anonymous constructor (`refine ⟨?_, ?_⟩`) are labelled by the
+    /// component they fill, e.g. `component 2 of ⟨⟩`.
+    pub fn goal_label(&self, goal_idx: usize) -> Option<String> {
+        let goal = self.state_after.goals.get(goal_idx)?;
+        if let Some(name) = goal.username.as_str() {
+            return Some(name.to_string());
+        }
+        if !self.tactic.text.contains('⟨') {
+            return None;
+        }
+        let components: Vec<usize> = self
+            .state_after
+            .goals
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| {
+                g.username == UserName::Anonymous
+                    && !self.state_before.goals.iter().any(|before| before.id == g.id)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        if components.len() < 2 {
+            return None;
+        }
+        let component = components.iter().position(|&idx| idx == goal_idx)?;
+        Some(format!("component {} of ⟨⟩", component + 1))
+    }
 }
 
 /// Information about a tactic application.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct DagTacticInfo {
     /// The tactic text (e.g., "intro n").
     pub text: String,
@@ -169,10 +418,29 @@ pub struct DagTacticInfo {
     pub theorems_used: Vec<String>,
 }
 
+/// Decision procedures and search tactics that close goals without showing
+/// how.
+const AUTOMATION_TACTICS: &[&str] = &[
+    "omega",
+    "decide",
+    "norm_num",
+    "linarith",
+    "nlinarith",
+    "positivity",
+    "polyrith",
+    "ring",
+    "aesop",
+    "tauto",
+    "simp_arith",
+];
+
+/// Tactics that structure a proof wherever they occur.
+const OUTLINE_TACTICS: &[&str] = &["have", "obtain", "suffices", "show", "calc"];
+
 /// The complete proof DAG - single source of truth for all display modes.
 /// Contains all semantic information precomputed by the server.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct ProofDag {
     /// All nodes indexed by `NodeId`.
     pub nodes: Vec<ProofDagNode>,
@@ -190,11 +458,72 @@ pub struct ProofDag {
     pub definition_name: Option<String>,
 
     /// Orphan nodes not connected to main tree (e.g., inline `by` blocks).
-    #[serde(default)]
     pub orphans: Vec<NodeId>,
 }
 
 impl ProofDag {
+    /// The expected type while `cursor` is still on the statement, before
+    /// the first tactic: there are no tactic goals yet, at most the
+    /// statement's term goal. `None` once the proof has started, including
+    /// when it is complete.
+    pub fn statement_goal(&self, cursor: Position) -> Option<&GoalInfo> {
+        let current = self.current_node.and_then(|id| self.get(id));
+        let has_tactic_goals =
+            current.is_some_and(|node| node.state_after.goals.iter().any(|g| g.range.is_none()));
+        let before_proof = self.root.and_then(|id| self.get(id)).is_none_or(|root| {
+            (cursor.line, cursor.character) < (root.position.line, root.position.character)
+        });
+        if has_tactic_goals || !before_proof {
+            return None;
+        }
+        current
+            .and_then(|node| node.state_after.goals.iter().find(|g| g.range.is_some()))
+            .or_else(|| self.initial_state.goals.first())
+    }
+
+    /// Positions of the proof's structural steps, in source order: the
+    /// top-level tactics (those at the first tactic's column) and every
+    /// `have`-like step, however deep.
+    pub fn outline(&self) -> Vec<Position> {
+        let Some(root) = self.root.and_then(|id| self.get(id)) else {
+            return Vec::new();
+        };
+        let mut positions: Vec<Position> = self
+            .nodes
+            .iter()
+            .filter(|node| {
+                let keyword = node.tactic.text.split_whitespace().next().unwrap_or_default();
+                node.position.character == root.position.character
+                    || OUTLINE_TACTICS.contains(&keyword)
+            })
+            .map(|node| node.position)
+            .collect();
+        positions.sort_by_key(|p| (p.line, p.character));
+        positions.dedup();
+        positions
+    }
+
+    /// Names of hypotheses in scope somewhere in the proof that no tactic
+    /// uses, as far as the nodes' `depends_on` tell, and that no goal
+    /// target or other hypothesis mentions: candidates for `clear`.
+    pub fn unused_hypotheses(&self) -> HashSet<String> {
+        let states: Vec<&ProofState> = iter::once(&self.initial_state)
+            .chain(self.nodes.iter().flat_map(|node| [&node.state_before, &node.state_after]))
+            .collect();
+        let mut used: HashSet<String> = self
+            .nodes
+            .iter()
+            .flat_map(|node| node.tactic.depends_on.iter().cloned())
+            .collect();
+        used.extend(states.iter().flat_map(|state| state.referenced_hypotheses()));
+        states
+            .iter()
+            .flat_map(|state| &state.hypotheses)
+            .filter(|h| !used.contains(&h.name))
+            .map(|h| h.name.clone())
+            .collect()
+    }
+
     /// Get a node by ID.
     pub fn get(&self, id: NodeId) -> Option<&ProofDagNode> {
         self.nodes.get(id as usize)
@@ -202,10 +531,147 @@ impl ProofDag {
 
     /// Iterate nodes in depth-first order (for `StepsView`).
     pub fn dfs_iter(&self) -> impl Iterator<Item = &ProofDagNode> {
-        DfsIterator {
-            dag: self,
-            stack: self.root.into_iter().collect(),
+        DfsIterator::new(self, self.root.into_iter().collect())
+    }
+
+    /// Iterate orphan subtrees (side proofs) in depth-first order.
+    pub fn orphan_dfs_iter(&self) -> impl Iterator<Item = &ProofDagNode> {
+        DfsIterator::new(self, self.orphans.iter().rev().copied().collect())
+    }
+
+    /// State after the tactic following a node, for the Next column; `None`
+    /// at the last tactic. The DAG already holds every step, so this never
+    /// needs a round trip to the server.
+    pub fn next_state(&self, id: NodeId) -> Option<&ProofState> {
+        let child = *self.get(id)?.children.first()?;
+        Some(&self.get(child)?.state_after)
+    }
+
+    /// Copy with each run of single-child steps merged into one node listing
+    /// the run's tactics, for a shorter tree. The merged node keeps the id
+    /// and the state after of the run's last step, so selections on it
+    /// resolve against the original DAG too. A run ends at the current
+    /// node: the steps leading to it share its box, which keeps its id, and
+    /// the steps after it start a box of their own. Depths below a run
+    /// shrink with it.
+    pub fn merge_chains(&self) -> Self {
+        let mut merged = self.clone();
+        let mut seen: HashSet<NodeId> = HashSet::new();
+        let starts: Vec<NodeId> = self
+            .dfs_iter()
+            .chain(self.orphan_dfs_iter())
+            .map(|node| node.id)
+            .collect();
+        for start in starts {
+            if seen.contains(&start) {
+                continue;
+            }
+            let run: Vec<NodeId> = iter::successors(Some(start), |&id| self.chain_child(id))
+                .take(self.len())
+                .collect();
+            seen.extend(&run);
+            if run.len() > 1 {
+                merged.merge_run(self, &run);
+            }
         }
+        merged.renumber_depths();
+        merged
+    }
+
+    /// Put each child one level below its parent again, parents first.
+    fn renumber_depths(&mut self) {
+        let order: Vec<NodeId> = self
+            .dfs_iter()
+            .chain(self.orphan_dfs_iter())
+            .map(|node| node.id)
+            .collect();
+        for id in order {
+            let Some(node) = self.get(id) else {
+                continue;
+            };
+            let depth = node.depth + 1;
+            for child in node.children.clone() {
+                let child = self.nodes.get_mut(child as usize);
+                child
+                    .filter(|c| c.parent == Some(id))
+                    .into_iter()
+                    .for_each(|c| c.depth = depth);
+            }
+        }
+    }
+
+    /// The only child of a node, when the two belong to one run.
+    fn chain_child(&self, id: NodeId) -> Option<NodeId> {
+        let node = self.get(id)?;
+        let [child] = node.children[..] else {
+            return None;
+        };
+        (!self.is_current(id) && self.get(child)?.parent == Some(id)).then_some(child)
+    }
+
+    /// Fold the nodes of `run`, as found in `original`, into its last node
+    /// and link that in place of the first.
+    fn merge_run(&mut self, original: &Self, run: &[NodeId]) {
+        let steps: Vec<&ProofDagNode> = run.iter().filter_map(|&id| original.get(id)).collect();
+        let (Some(&head), Some(&tail)) = (steps.first(), steps.last()) else {
+            return;
+        };
+        let new_names: HashSet<&str> = steps
+            .iter()
+            .flat_map(|node| {
+                let hyps = &node.state_after.hypotheses;
+                node.new_hypotheses.iter().filter_map(|&i| hyps.get(i))
+            })
+            .map(|h| h.name.as_str())
+            .collect();
+        let texts: Vec<&str> = steps.iter().map(|node| node.tactic.text.as_str()).collect();
+
+        let merged = ProofDagNode {
+            tactic: DagTacticInfo {
+                text: texts.join("; "),
+                depends_on: steps
+                    .iter()
+                    .flat_map(|n| n.tactic.depends_on.clone())
+                    .collect(),
+                theorems_used: steps
+                    .iter()
+                    .flat_map(|n| n.tactic.theorems_used.clone())
+                    .collect(),
+            },
+            state_before: head.state_before.clone(),
+            new_hypotheses: (tail.state_after.hypotheses.iter().enumerate())
+                .filter(|(_, h)| new_names.contains(h.name.as_str()))
+                .map(|(i, _)| i)
+                .collect(),
+            parent: head.parent,
+            depth: head.depth,
+            has_unsolved_spawned_goals: steps.iter().any(|n| n.has_unsolved_spawned_goals),
+            clause: head.clause.clone().or_else(|| tail.clause.clone()),
+            elab_time_ms: steps
+                .iter()
+                .filter_map(|n| n.elab_time_ms)
+                .reduce(|a, b| a + b),
+            ..tail.clone()
+        };
+        let Some(slot) = self.nodes.get_mut(tail.id as usize) else {
+            return;
+        };
+        *slot = merged;
+
+        let relink = |id: &mut NodeId| {
+            if *id == head.id {
+                *id = tail.id;
+            }
+        };
+        let parent = head.parent.and_then(|id| self.nodes.get_mut(id as usize));
+        parent
+            .into_iter()
+            .flat_map(|p| &mut p.children)
+            .for_each(relink);
+        self.root
+            .iter_mut()
+            .chain(&mut self.orphans)
+            .for_each(relink);
     }
 
     /// Check if the DAG is empty.
@@ -218,6 +684,25 @@ impl ProofDag {
         self.nodes.len()
     }
 
+    /// Goals left open at the leaves of the proof.
+    pub fn open_goal_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.is_leaf())
+            .map(|node| node.state_after.goals.len())
+            .sum()
+    }
+
+    /// Some tactic in the proof is `sorry` or `admit`.
+    pub fn contains_sorry(&self) -> bool {
+        self.nodes.iter().any(ProofDagNode::is_sorry)
+    }
+
+    /// Steps closed by automation tactics.
+    pub fn automation_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.is_automation()).count()
+    }
+
     /// Check if a node is the current node (closest to cursor).
     pub fn is_current(&self, node_id: NodeId) -> bool {
         self.current_node == Some(node_id)
@@ -225,21 +710,448 @@ impl ProofDag {
 }
 
 /// Depth-first iterator over proof DAG nodes.
+///
+/// A node reachable from several parents is yielded, and its subtree
+/// walked, only the first time it is reached; this also ends the walk on a
+/// cycle in malformed server data.
 struct DfsIterator<'a> {
     dag: &'a ProofDag,
     stack: Vec<NodeId>,
+    visited: HashSet<NodeId>,
+}
+
+impl<'a> DfsIterator<'a> {
+    fn new(dag: &'a ProofDag, stack: Vec<NodeId>) -> Self {
+        Self {
+            dag,
+            stack,
+            visited: HashSet::new(),
+        }
+    }
 }
 
 impl<'a> Iterator for DfsIterator<'a> {
     type Item = &'a ProofDagNode;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let id = self.stack.pop()?;
-        let node = self.dag.get(id)?;
-        // Push children in reverse order so first child is processed first
-        for &child_id in node.children.iter().rev() {
-            self.stack.push(child_id);
+        loop {
+            let id = self.stack.pop()?;
+            if !self.visited.insert(id) {
+                continue;
+            }
+            let node = self.dag.get(id)?;
+            // Push children in reverse order so first child is processed first
+            for &child_id in node.children.iter().rev() {
+                self.stack.push(child_id);
+            }
+            return Some(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> TaggedText {
+        TaggedText::Text {
+            text: s.to_string(),
         }
-        Some(node)
+    }
+
+    /// Hypotheses named in the target are flagged; substrings are not.
+    #[test]
+    fn test_mentioned_hypotheses() {
+        let hyp = |name: &str| HypothesisInfo {
+            name: name.to_string(),
+            type_: text("Prop"),
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: vec![GoalInfo {
+                type_: text("p h.1 ∧ f (hp + 1)"),
+                ..Default::default()
+            }],
+            hypotheses: vec![hyp("h"), hyp("hp"), hyp("q"), hyp("f'")],
+        };
+
+        let mentioned = state.mentioned_hypotheses();
+        assert!(mentioned.contains("h"));
+        assert!(mentioned.contains("hp"));
+        assert!(!mentioned.contains("q"));
+        assert!(!mentioned.contains("f'"), "primes are part of the name");
+    }
+
+    /// The initial state renders as the statement's binders and conclusion.
+    #[test]
+    fn test_signature() {
+        let hyp = |name: &str, ty: &str, is_instance| HypothesisInfo {
+            name: name.to_string(),
+            type_: text(ty),
+            is_instance,
+            ..Default::default()
+        };
+        let goal = |ty: &str| GoalInfo {
+            type_: text(ty),
+            ..Default::default()
+        };
+
+        let state = ProofState {
+            goals: vec![goal("P n")],
+            hypotheses: vec![hyp("n", "Nat", false)],
+        };
+        assert_eq!(state.signature().as_deref(), Some("(n : Nat) : P n"));
+
+        let state = ProofState {
+            goals: vec![goal("a * b = b * a")],
+            hypotheses: vec![
+                hyp("M", "Type", false),
+                hyp("inst", "CommMonoid M", true),
+                hyp("a", "M", false),
+                hyp("b", "M", false),
+            ],
+        };
+        assert_eq!(
+            state.signature().as_deref(),
+            Some("(M : Type) [inst : CommMonoid M] (a b : M) : a * b = b * a")
+        );
+        assert_eq!(ProofState::default().signature(), None);
+
+        let binder = |name: &str, ty: &str, binder_info| HypothesisInfo {
+            binder_info,
+            ..hyp(name, ty, false)
+        };
+        let state = ProofState {
+            goals: vec![goal("f a = f b")],
+            hypotheses: vec![
+                binder("α", "Type", BinderInfo::Implicit),
+                binder("β", "Type", BinderInfo::Implicit),
+                binder("f", "α → β", BinderInfo::StrictImplicit),
+                binder("a", "α", BinderInfo::Default),
+                binder("b", "α", BinderInfo::Default),
+            ],
+        };
+        assert_eq!(
+            state.signature().as_deref(),
+            Some("{α β : Type} ⦃f : α → β⦄ (a b : α) : f a = f b")
+        );
+    }
+
+    /// Dependency order lists a hypothesis after the ones its type mentions,
+    /// and leaves a cycle in declaration order.
+    #[test]
+    fn test_dependency_order() {
+        let hyp = |name: &str, ty: &str| HypothesisInfo {
+            name: name.to_string(),
+            type_: text(ty),
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![hyp("hp", "p h"), hyp("n", "Nat"), hyp("h", "P n")],
+        };
+        assert_eq!(state.dependency_order(), [1, 2, 0]);
+
+        let cyclic = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![hyp("a", "P b"), hyp("b", "Q a")],
+        };
+        assert_eq!(cyclic.dependency_order(), [0, 1]);
+    }
+
+    /// A term goal's range survives the trip from the server to the TUI.
+    #[test]
+    fn test_term_goal_range_roundtrip() {
+        let json = serde_json::json!({
+            "type": { "kind": "text", "text": "Nat" },
+            "username": null,
+            "id": "g1",
+            "range": {
+                "start": { "line": 2, "character": 4 },
+                "end": { "line": 2, "character": 9 }
+            }
+        });
+        let goal: GoalInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(goal.term_location().as_deref(), Some("term at 3:5"));
+
+        let forwarded: GoalInfo =
+            serde_json::from_str(&serde_json::to_string(&goal).unwrap()).unwrap();
+        assert_eq!(forwarded.range, goal.range);
+
+        let tactic_goal = GoalInfo::default();
+        assert!(serde_json::to_value(&tactic_goal).unwrap().get("range").is_none());
+    }
+
+    /// A newer server may add fields, leave some out, or change the shape
+    /// of informative ones; the DAG still parses.
+    #[test]
+    fn test_unknown_fields_still_parse() {
+        let json = serde_json::json!({
+            "nodes": [{
+                "id": 0,
+                "tactic": { "text": "intro n", "futureField": [1, 2] },
+                "stateBefore": {
+                    "hypotheses": [{
+                        "name": "n",
+                        "type": { "kind": "text", "text": "Nat" },
+                        "binderInfo": { "unexpected": true }
+                    }]
+                },
+                "elabTimeMs": "slow",
+                "futureField": "ignored"
+            }],
+            "root": 0,
+            "futureField": {}
+        });
+        let dag: ProofDag = serde_json::from_value(json).unwrap();
+        let node = dag.get(0).unwrap();
+        assert_eq!(node.tactic.text, "intro n");
+        assert_eq!(node.state_before.hypotheses[0].name, "n");
+        assert!(node.elab_time_ms.is_none());
+    }
+
+    /// On the statement only the expected type is known; a finished proof
+    /// has no goals either, but is past its first tactic.
+    #[test]
+    fn test_statement_goal_before_proof() {
+        let root = ProofDagNode {
+            position: Position::new(1, 2),
+            ..Default::default()
+        };
+        let dag = ProofDag {
+            nodes: vec![root],
+            root: Some(0),
+            current_node: Some(0),
+            initial_state: ProofState {
+                goals: vec![GoalInfo {
+                    type_: text("∀ n, n + 0 = n"),
+                    ..Default::default()
+                }],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        let on_statement = dag.statement_goal(Position::new(0, 10));
+        assert_eq!(
+            on_statement.map(|g| g.type_.to_plain_text()).as_deref(),
+            Some("∀ n, n + 0 = n")
+        );
+        assert!(dag.statement_goal(Position::new(1, 8)).is_none());
+    }
+
+    /// Case names label their goals; anonymous goals from `⟨?_, ?_⟩` are
+    /// numbered by component, and a hygienic name counts as anonymous.
+    #[test]
+    fn test_goal_labels() {
+        let goal = |id: &str, name: Option<&str>| GoalInfo {
+            type_: text("P"),
+            username: name.map_or(UserName::Anonymous, UserName::from_raw),
+            id: id.to_string(),
+            ..Default::default()
+        };
+        let mut node = ProofDagNode {
+            state_before: ProofState {
+                goals: vec![goal("g0", None), goal("g1", Some("other"))],
+                hypotheses: Vec::new(),
+            },
+            state_after: ProofState {
+                goals: vec![
+                    goal("g2", None),
+                    goal("g3", Some("w._@.Test._hyg.12")),
+                    goal("g1", Some("other")),
+                ],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+        node.tactic.text = "refine ⟨?_, ?_⟩".to_string();
+
+        assert_eq!(node.goal_label(0).as_deref(), Some("component 1 of ⟨⟩"));
+        assert_eq!(node.goal_label(1).as_deref(), Some("component 2 of ⟨⟩"));
+        assert_eq!(node.goal_label(2).as_deref(), Some("other"));
+
+        node.tactic.text = "constructor".to_string();
+        assert_eq!(node.goal_label(0), None);
+    }
+
+    /// A hypothesis no node depends on is unused; one a tactic uses is not.
+    #[test]
+    fn test_unused_hypotheses() {
+        let hyp = |name: &str| HypothesisInfo {
+            name: name.to_string(),
+            type_: text("Prop"),
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![hyp("hp"), hyp("hq")],
+        };
+        let node = ProofDagNode {
+            tactic: DagTacticInfo {
+                text: "exact hp".to_string(),
+                depends_on: vec!["hp".to_string()],
+                ..Default::default()
+            },
+            state_before: state.clone(),
+            ..Default::default()
+        };
+        let dag = ProofDag {
+            nodes: vec![node],
+            root: Some(0),
+            initial_state: state,
+            ..Default::default()
+        };
+
+        let unused = dag.unused_hypotheses();
+        assert!(unused.contains("hq"));
+        assert!(!unused.contains("hp"));
+
+        // Mentioned by the target or by another hypothesis's type
+        let typed = |name: &str, type_: &str| HypothesisInfo {
+            type_: text(type_),
+            ..hyp(name)
+        };
+        let dag = ProofDag {
+            initial_state: ProofState {
+                goals: vec![GoalInfo {
+                    type_: text("n + 0 = n"),
+                    ..Default::default()
+                }],
+                hypotheses: vec![typed("n", "Nat"), typed("m", "Nat"), typed("h", "m > 0")],
+            },
+            ..Default::default()
+        };
+        let unused = dag.unused_hypotheses();
+        assert!(!unused.contains("n"), "the target mentions `n`");
+        assert!(!unused.contains("m"), "`h : m > 0` mentions `m`");
+        assert!(unused.contains("h"));
+    }
+
+    /// A step shared by two branches is walked once, and a cycle ends the
+    /// walk instead of looping.
+    #[test]
+    fn test_dfs_visits_shared_node_once() {
+        let node = |id, children: Vec<NodeId>| ProofDagNode {
+            id,
+            children,
+            ..Default::default()
+        };
+        // 0 splits into 1 and 2, which both lead to 3; 3 points back to 0.
+        let dag = ProofDag {
+            nodes: vec![
+                node(0, vec![1, 2]),
+                node(1, vec![3]),
+                node(2, vec![3]),
+                node(3, vec![0]),
+            ],
+            root: Some(0),
+            orphans: vec![3],
+            ..Default::default()
+        };
+
+        let order: Vec<NodeId> = dag.dfs_iter().map(|node| node.id).collect();
+        assert_eq!(order, [0, 1, 3, 2]);
+        assert_eq!(dag.orphan_dfs_iter().count(), 4);
+    }
+
+    /// The Next slot holds the following step's state, and nothing at the
+    /// last tactic.
+    #[test]
+    fn test_next_state_at_last_tactic() {
+        let goal = |text: &str| ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Text {
+                    text: text.to_string(),
+                },
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        };
+        let dag = ProofDag {
+            nodes: vec![
+                ProofDagNode {
+                    children: vec![1],
+                    state_after: goal("p"),
+                    ..Default::default()
+                },
+                ProofDagNode {
+                    id: 1,
+                    parent: Some(0),
+                    state_after: goal("q"),
+                    ..Default::default()
+                },
+            ],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let next = dag.next_state(0).map(|s| s.goals[0].type_.to_plain_text());
+        assert_eq!(next.as_deref(), Some("q"));
+        assert!(dag.next_state(1).is_none(), "no tactic follows");
+    }
+
+    /// Three single-child steps in a row collapse into their last step,
+    /// which lists their tactics and the hypotheses they introduced; a run
+    /// ends at the current step, and the steps below a run move up.
+    #[test]
+    fn test_merge_single_child_chain() {
+        let hyp = |name: &str| HypothesisInfo {
+            name: name.to_string(),
+            type_: text("Nat"),
+            ..Default::default()
+        };
+        let step = |id: NodeId, name: &str, children: Vec<NodeId>| {
+            let names = ["a", "b", "c"];
+            let in_scope = (id as usize + 1).min(names.len());
+            ProofDagNode {
+                id,
+                tactic: DagTacticInfo {
+                    text: format!("intro {name}"),
+                    ..Default::default()
+                },
+                state_after: ProofState {
+                    hypotheses: names[..in_scope].iter().map(|n| hyp(n)).collect(),
+                    ..Default::default()
+                },
+                new_hypotheses: vec![id as usize],
+                parent: id.checked_sub(1),
+                depth: id as usize,
+                children,
+                ..Default::default()
+            }
+        };
+        let dag = ProofDag {
+            nodes: vec![
+                step(0, "a", vec![1]),
+                step(1, "b", vec![2]),
+                step(2, "c", vec![]),
+            ],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let merged = dag.merge_chains();
+        let boxes: Vec<&ProofDagNode> = merged.dfs_iter().collect();
+        assert_eq!(boxes.len(), 1);
+        assert_eq!(boxes[0].id, 2);
+        assert_eq!(merged.root, Some(2));
+        assert_eq!(boxes[0].tactic.text, "intro a; intro b; intro c");
+        assert_eq!(boxes[0].new_hypotheses, [0, 1, 2]);
+        assert_eq!(boxes[0].parent, None);
+
+        let at_cursor = ProofDag {
+            current_node: Some(1),
+            ..dag
+        };
+        let boxes: Vec<(String, usize)> = at_cursor
+            .merge_chains()
+            .dfs_iter()
+            .map(|node| (node.tactic.text.clone(), node.depth))
+            .collect();
+        assert_eq!(
+            boxes,
+            [("intro a; intro b".to_string(), 0), ("intro c".to_string(), 1)]
+        );
     }
 }
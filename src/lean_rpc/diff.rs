@@ -0,0 +1,241 @@
+//! Local diffing of proof states, for comparisons the server did not compute.
+
+use super::{DiffTag, HypothesisInfo, ProofState, SubexprInfo, TaggedText};
+
+/// Plain text leaf without any server-provided diff tags.
+fn plain(text: &TaggedText) -> TaggedText {
+    TaggedText::Text {
+        text: text.to_plain_text(),
+    }
+}
+
+/// Plain text wrapped in a single diff tag.
+fn tagged(text: &TaggedText, tag: DiffTag) -> TaggedText {
+    TaggedText::Tag {
+        info: SubexprInfo {
+            diff_status: Some(tag),
+            ..Default::default()
+        },
+        content: Box::new(plain(text)),
+    }
+}
+
+/// The hypothesis in `others` that is the same local as `h` of `side`. The
+/// fvar id survives renames by pretty-printer settings, so it decides first.
+/// Tactics like `rw … at h` make a new fvar under the old name, so without an
+/// id match the name decides, among those not matched by id themselves.
+fn counterpart<'a>(
+    h: &HypothesisInfo,
+    side: &[HypothesisInfo],
+    others: &'a [HypothesisInfo],
+) -> Option<&'a HypothesisInfo> {
+    let same_id = |a: &HypothesisInfo, b: &HypothesisInfo| !a.id.is_empty() && a.id == b.id;
+    others.iter().find(|o| same_id(o, h)).or_else(|| {
+        others
+            .iter()
+            .find(|o| o.name == h.name && !side.iter().any(|s| same_id(s, o)))
+    })
+}
+
+/// Which side of a comparison a state is annotated as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    /// The earlier state: items gone from the other are removed.
+    Before,
+    /// The later state: items new to the other are inserted.
+    After,
+}
+
+/// Annotate `state` with what changed relative to `other`.
+///
+/// Hypotheses without a counterpart (see [`counterpart`]) are marked removed
+/// or inserted by `side`, and goals by target; hypotheses whose type changed
+/// are tagged as changed. A later state is compared with the step it follows,
+/// the comparison the server's own diff tags describe, so types carrying them
+/// are kept; the earlier side may be any state, so its tags are replaced.
+fn annotate(state: &ProofState, other: &ProofState, side: Side) -> ProofState {
+    let retag = |text: &TaggedText, tag: Option<DiffTag>| match tag {
+        _ if side == Side::After && text.has_any_diff() => text.clone(),
+        Some(tag) => tagged(text, tag),
+        None => plain(text),
+    };
+
+    let hypotheses = state
+        .hypotheses
+        .iter()
+        .map(|h| {
+            let mut h = h.clone();
+            let tag = match counterpart(&h, &state.hypotheses, &other.hypotheses) {
+                None => {
+                    match side {
+                        Side::Before => h.is_removed = true,
+                        Side::After => h.is_inserted = true,
+                    }
+                    None
+                }
+                Some(o) if o.type_.to_plain_text() != h.type_.to_plain_text() => {
+                    Some(DiffTag::WasChanged)
+                }
+                Some(_) => None,
+            };
+            h.type_ = retag(&h.type_, tag);
+            h
+        })
+        .collect();
+
+    let goals = state
+        .goals
+        .iter()
+        .map(|g| {
+            let target = g.type_.to_plain_text();
+            let mut g = g.clone();
+            let is_new = !other
+                .goals
+                .iter()
+                .any(|o| o.type_.to_plain_text() == target);
+            let tag = match side {
+                Side::Before => {
+                    g.is_removed = is_new;
+                    None
+                }
+                Side::After => is_new.then_some(DiffTag::WasInserted),
+            };
+            g.type_ = retag(&g.type_, tag);
+            g
+        })
+        .collect();
+
+    ProofState { goals, hypotheses }
+}
+
+/// Annotate `before` with what changed on the way to `after`: removed
+/// hypotheses and goals, and changed hypothesis types.
+pub fn annotate_before(before: &ProofState, after: &ProofState) -> ProofState {
+    annotate(before, after, Side::Before)
+}
+
+/// Annotate `after` with what changed since `before`, the mirror of
+/// [`annotate_before`]: inserted hypotheses and goals, and changed hypothesis
+/// types.
+pub fn annotate_after(after: &ProofState, before: &ProofState) -> ProofState {
+    annotate(after, before, Side::After)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo};
+
+    fn text(s: &str) -> TaggedText {
+        TaggedText::Text {
+            text: s.to_string(),
+        }
+    }
+
+    fn hyp(name: &str, ty: &str) -> HypothesisInfo {
+        HypothesisInfo {
+            name: name.to_string(),
+            type_: text(ty),
+            ..Default::default()
+        }
+    }
+
+    fn goal(ty: &str) -> GoalInfo {
+        GoalInfo {
+            type_: text(ty),
+            ..Default::default()
+        }
+    }
+
+    /// Removed and changed items are annotated relative to the other state.
+    #[test]
+    fn test_annotate_before() {
+        let before = ProofState {
+            goals: vec![goal("a = b"), goal("b = c")],
+            hypotheses: vec![hyp("h", "a ≤ b"), hyp("x", "Nat"), hyp("n", "Nat")],
+        };
+        let after = ProofState {
+            goals: vec![goal("b = c")],
+            hypotheses: vec![hyp("h", "a < b"), hyp("n", "Nat")],
+        };
+
+        let diffed = annotate_before(&before, &after);
+
+        let h = &diffed.hypotheses[0];
+        assert!(!h.is_removed);
+        assert!(h.type_.has_any_diff(), "changed type should be tagged");
+        assert!(diffed.hypotheses[1].is_removed, "x is gone in after");
+        assert!(!diffed.hypotheses[2].is_removed);
+        assert!(!diffed.hypotheses[2].type_.has_any_diff());
+
+        assert!(diffed.goals[0].is_removed);
+        assert!(!diffed.goals[1].is_removed);
+    }
+
+    /// A hypothesis shown under another name but with the same fvar id is
+    /// the same local, and so is one `rw … at h` rebuilt under its name; a
+    /// new local shadowing a renamed one is inserted.
+    #[test]
+    fn test_renamed_hypothesis_matched_by_fvar() {
+        let with_id = |name, ty, id: &str| HypothesisInfo {
+            id: id.to_string(),
+            ..hyp(name, ty)
+        };
+        let before = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![
+                with_id("n", "Nat", "_uniq.12"),
+                with_id("h", "a + 0 = b", "_uniq.13"),
+            ],
+        };
+        let after = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![
+                with_id("n✝", "Nat", "_uniq.12"),
+                with_id("h", "a = b", "_uniq.14"),
+                with_id("n", "Nat", "_uniq.15"),
+            ],
+        };
+
+        let removed = annotate_before(&before, &after);
+        assert!(!removed.hypotheses[0].is_removed, "renamed, same fvar");
+        assert!(!removed.hypotheses[1].is_removed, "rewritten under its name");
+        assert!(removed.hypotheses[1].type_.has_any_diff());
+
+        let inserted = annotate_after(&after, &before);
+        assert!(!inserted.hypotheses[0].is_inserted);
+        assert!(!inserted.hypotheses[1].is_inserted);
+        assert!(inserted.hypotheses[1].type_.has_any_diff());
+        assert!(inserted.hypotheses[2].is_inserted, "new `n` shadows the old one");
+    }
+
+    /// The steps view keeps the server's subexpression diff tags, which
+    /// highlight more precisely than a whole changed type.
+    #[test]
+    fn test_annotate_after_keeps_server_tags() {
+        let server_tagged = TaggedText::Append {
+            items: vec![text("a "), tagged(&text("<"), DiffTag::WillChange), text(" b")],
+        };
+        let before = ProofState {
+            goals: vec![goal("a ≤ b")],
+            hypotheses: vec![hyp("h", "a ≤ b")],
+        };
+        let after = ProofState {
+            goals: vec![GoalInfo {
+                type_: server_tagged.clone(),
+                ..Default::default()
+            }],
+            hypotheses: vec![HypothesisInfo {
+                type_: server_tagged.clone(),
+                ..hyp("h", "")
+            }],
+        };
+
+        let inserted = annotate_after(&after, &before);
+        assert_eq!(inserted.hypotheses[0].type_, server_tagged);
+        assert_eq!(inserted.goals[0].type_, server_tagged);
+
+        let removed = annotate_before(&after, &before);
+        assert!(matches!(removed.goals[0].type_, TaggedText::Text { .. }));
+    }
+}
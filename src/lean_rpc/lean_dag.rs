@@ -15,58 +15,78 @@ use async_lsp::{
     lsp_types::{DidChangeTextDocumentParams, DidOpenTextDocumentParams, Position, Url},
     MainLoop,
 };
-use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::{
+    process::{ChildStdin, ChildStdout, Command},
+    sync::RwLock,
+};
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
 use super::{
-    base::{BaseLspClient, LeanService, LEAN_PP_OPTIONS},
-    ProofDag,
+    base::{pp_options, swap_server, BaseLspClient, LeanService},
+    GotoLocation, ProofDag, SymbolMatch,
 };
 use crate::error::LspError;
 
 /// LSP client for standalone mode using the lean-dag binary.
 pub struct LeanDagClient {
-    base: Arc<BaseLspClient>,
+    /// Connection to the server, replaced when it restarts.
+    base: RwLock<Arc<BaseLspClient>>,
 }
 
 impl LeanDagClient {
     /// Create a new lean-dag client, spawning the server process.
     pub async fn new() -> Result<Arc<Self>, LspError> {
-        let server_path = find_lean_dag_server()?;
+        let base = RwLock::new(start(false).await?);
+        Ok(Arc::new(Self { base }))
+    }
 
-        tracing::info!("[LeanDag] Starting server: {}", server_path.display());
+    async fn base(&self) -> Arc<BaseLspClient> {
+        self.base.read().await.clone()
+    }
 
-        let (stdin, stdout) = spawn_lean_dag_server(&server_path)?;
+    /// Restart the lean-dag server, with `pp.explicit` when `explicit` is
+    /// set, and reopen the `documents` on it.
+    pub async fn restart(
+        &self,
+        explicit: bool,
+        documents: impl FnOnce() -> Vec<DidOpenTextDocumentParams>,
+    ) -> Result<(), LspError> {
+        swap_server(&self.base, start(explicit).await?, documents).await
+    }
 
-        // Create main loop with our service
-        let (mainloop, socket) = MainLoop::new_client(|_| LeanService::new("LeanDag"));
+    /// Open a document in the server.
+    pub async fn did_open(&self, params: DidOpenTextDocumentParams) -> Result<(), LspError> {
+        self.base().await.did_open(params).await
+    }
 
-        // Run the mainloop in a background task
-        tokio::spawn(async move {
-            if let Err(e) = mainloop
-                .run_buffered(stdout.compat(), stdin.compat_write())
-                .await
-            {
-                tracing::error!("[LeanDag] MainLoop error: {:?}", e);
-            }
-        });
+    /// Update a document in the server.
+    pub async fn did_change(&self, params: DidChangeTextDocumentParams) -> Result<(), LspError> {
+        self.base().await.did_change(params).await
+    }
 
-        let base = Arc::new(BaseLspClient::new("LeanDag", socket));
+    /// Close a document in the server.
+    pub async fn did_close(&self, uri: &Url) -> Result<(), LspError> {
+        self.base().await.did_close(uri).await
+    }
 
-        // Initialize the LSP connection
-        base.initialize().await?;
+    /// Set the DAG mode for subsequent proof DAG requests.
+    pub async fn set_dag_mode(&self, mode: &'static str) {
+        self.base().await.set_dag_mode(mode).await;
+    }
 
-        Ok(Arc::new(Self { base }))
+    /// DAG mode proof DAG requests ask for.
+    pub async fn dag_mode(&self) -> &'static str {
+        self.base().await.dag_mode().await
     }
 
-    /// Open a document in the server.
-    pub async fn did_open(&self, params: DidOpenTextDocumentParams) -> Result<(), LspError> {
-        self.base.did_open(params).await
+    /// Version of a document as last opened or changed, if open.
+    pub async fn document_version(&self, uri: &Url) -> Option<u32> {
+        self.base().await.document_version(uri).await
     }
 
-    /// Update a document in the server.
-    pub async fn did_change(&self, params: DidChangeTextDocumentParams) -> Result<(), LspError> {
-        self.base.did_change(params).await
+    /// Wait until the server has elaborated a version of a document.
+    pub async fn wait_for_diagnostics(&self, uri: &Url, version: u32) -> Result<(), LspError> {
+        self.base().await.wait_for_diagnostics(uri, version).await
     }
 
     /// Get the proof DAG at a position.
@@ -76,8 +96,57 @@ impl LeanDagClient {
         position: Position,
         mode: &str,
     ) -> Result<Option<ProofDag>, LspError> {
-        self.base.get_proof_dag(uri, position, mode).await
+        self.base().await.get_proof_dag(uri, position, mode).await
     }
+
+    /// Find symbols in the workspace whose names match `query`.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolMatch>, LspError> {
+        self.base().await.workspace_symbols(query).await
+    }
+
+    /// Where the symbol at `position` is implemented.
+    pub async fn implementation(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Result<Option<GotoLocation>, LspError> {
+        self.base().await.implementation(uri, position).await
+    }
+
+    /// Replace the RPC session for a document with a fresh one.
+    pub async fn reset_session(&self, uri: &Url) -> Result<u64, LspError> {
+        self.base().await.reset_session(uri).await
+    }
+}
+
+/// Spawn the lean-dag server and initialize a connection to it.
+async fn start(explicit: bool) -> Result<Arc<BaseLspClient>, LspError> {
+    let server_path = find_lean_dag_server()?;
+
+    tracing::info!("[LeanDag] Starting server: {}", server_path.display());
+
+    let (stdin, stdout) = spawn_lean_dag_server(&server_path, explicit)?;
+
+    // Create main loop with our service
+    let (mainloop, socket) = MainLoop::new_client(|_| LeanService::new("LeanDag"));
+
+    // Run the mainloop in a background task
+    tokio::spawn(async move {
+        if let Err(e) = mainloop
+            .run_buffered(stdout.compat(), stdin.compat_write())
+            .await
+        {
+            tracing::error!("[LeanDag] MainLoop error: {:?}", e);
+        }
+    });
+
+    let base = Arc::new(BaseLspClient::new("LeanDag", socket));
+
+    // Initialize the LSP connection
+    base.initialize().await?;
+    base.spawn_keep_alive();
+
+    Ok(base)
 }
 
 /// Find the Lake project root by searching upward for `lakefile.lean`.
@@ -148,17 +217,21 @@ fn get_lean_dag_log_file() -> Option<File> {
     File::create(&log_path).ok()
 }
 
-/// Spawn the lean-dag server process.
+/// Spawn the lean-dag server process, showing implicit arguments when
+/// `explicit` is set.
 ///
 /// lean-dag handles its own environment discovery internally by calling
 /// `lake env printenv` at startup, so we can spawn it directly without
 /// wrapping in `lake env`.
-fn spawn_lean_dag_server(server_path: &PathBuf) -> Result<(ChildStdin, ChildStdout), LspError> {
+fn spawn_lean_dag_server(
+    server_path: &PathBuf,
+    explicit: bool,
+) -> Result<(ChildStdin, ChildStdout), LspError> {
     let server_str = server_path.display().to_string();
 
     // Build command-line arguments for pretty-printing options
     let mut args: Vec<String> = Vec::new();
-    for opt in LEAN_PP_OPTIONS {
+    for opt in pp_options(explicit) {
         args.push("-D".to_string());
         args.push(opt.to_string());
     }
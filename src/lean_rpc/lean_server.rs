@@ -15,12 +15,15 @@ use async_lsp::{
     lsp_types::{DidChangeTextDocumentParams, DidOpenTextDocumentParams, Position, Url},
     MainLoop,
 };
-use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::{
+    process::{ChildStdin, ChildStdout, Command},
+    sync::RwLock,
+};
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
 use super::{
-    base::{BaseLspClient, LeanService, LEAN_PP_OPTIONS},
-    ProofDag,
+    base::{pp_options, swap_server, BaseLspClient, LeanService},
+    GotoLocation, ProofDag, SymbolMatch,
 };
 use crate::error::LspError;
 
@@ -29,45 +32,64 @@ use crate::error::LspError;
 /// Users must add `import LeanDag` to their Lean files for the
 /// `LeanDag.getProofDag` RPC method to be available.
 pub struct LeanServerClient {
-    base: Arc<BaseLspClient>,
+    /// Connection to the server, replaced when it restarts.
+    base: RwLock<Arc<BaseLspClient>>,
 }
 
 impl LeanServerClient {
     /// Create a new lean server client, spawning `lake serve`.
     pub async fn new() -> Result<Arc<Self>, LspError> {
-        tracing::info!("[LeanServer] Starting lake serve");
-
-        let (stdin, stdout) = spawn_lake_serve()?;
-
-        // Create main loop with our service
-        let (mainloop, socket) = MainLoop::new_client(|_| LeanService::new("LeanServer"));
-
-        // Run the mainloop in a background task
-        tokio::spawn(async move {
-            if let Err(e) = mainloop
-                .run_buffered(stdout.compat(), stdin.compat_write())
-                .await
-            {
-                tracing::error!("[LeanServer] MainLoop error: {:?}", e);
-            }
-        });
-
-        let base = Arc::new(BaseLspClient::new("LeanServer", socket));
+        let base = RwLock::new(start(false).await?);
+        Ok(Arc::new(Self { base }))
+    }
 
-        // Initialize the LSP connection
-        base.initialize().await?;
+    async fn base(&self) -> Arc<BaseLspClient> {
+        self.base.read().await.clone()
+    }
 
-        Ok(Arc::new(Self { base }))
+    /// Restart `lake serve`, with `pp.explicit` when `explicit` is set, and
+    /// reopen the `documents` on it.
+    pub async fn restart(
+        &self,
+        explicit: bool,
+        documents: impl FnOnce() -> Vec<DidOpenTextDocumentParams>,
+    ) -> Result<(), LspError> {
+        swap_server(&self.base, start(explicit).await?, documents).await
     }
 
     /// Open a document in the server.
     pub async fn did_open(&self, params: DidOpenTextDocumentParams) -> Result<(), LspError> {
-        self.base.did_open(params).await
+        self.base().await.did_open(params).await
     }
 
     /// Update a document in the server.
     pub async fn did_change(&self, params: DidChangeTextDocumentParams) -> Result<(), LspError> {
-        self.base.did_change(params).await
+        self.base().await.did_change(params).await
+    }
+
+    /// Close a document in the server.
+    pub async fn did_close(&self, uri: &Url) -> Result<(), LspError> {
+        self.base().await.did_close(uri).await
+    }
+
+    /// Set the DAG mode for subsequent proof DAG requests.
+    pub async fn set_dag_mode(&self, mode: &'static str) {
+        self.base().await.set_dag_mode(mode).await;
+    }
+
+    /// DAG mode proof DAG requests ask for.
+    pub async fn dag_mode(&self) -> &'static str {
+        self.base().await.dag_mode().await
+    }
+
+    /// Version of a document as last opened or changed, if open.
+    pub async fn document_version(&self, uri: &Url) -> Option<u32> {
+        self.base().await.document_version(uri).await
+    }
+
+    /// Wait until the server has elaborated a version of a document.
+    pub async fn wait_for_diagnostics(&self, uri: &Url, version: u32) -> Result<(), LspError> {
+        self.base().await.wait_for_diagnostics(uri, version).await
     }
 
     /// Get the proof DAG at a position.
@@ -77,10 +99,57 @@ impl LeanServerClient {
         position: Position,
         mode: &str,
     ) -> Result<Option<ProofDag>, LspError> {
-        self.base.get_proof_dag(uri, position, mode).await
+        self.base().await.get_proof_dag(uri, position, mode).await
+    }
+
+    /// Find symbols in the workspace whose names match `query`.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolMatch>, LspError> {
+        self.base().await.workspace_symbols(query).await
+    }
+
+    /// Where the symbol at `position` is implemented.
+    pub async fn implementation(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Result<Option<GotoLocation>, LspError> {
+        self.base().await.implementation(uri, position).await
+    }
+
+    /// Replace the RPC session for a document with a fresh one.
+    pub async fn reset_session(&self, uri: &Url) -> Result<u64, LspError> {
+        self.base().await.reset_session(uri).await
     }
 }
 
+/// Spawn `lake serve` and initialize a connection to it.
+async fn start(explicit: bool) -> Result<Arc<BaseLspClient>, LspError> {
+    tracing::info!("[LeanServer] Starting lake serve");
+
+    let (stdin, stdout) = spawn_lake_serve(explicit)?;
+
+    // Create main loop with our service
+    let (mainloop, socket) = MainLoop::new_client(|_| LeanService::new("LeanServer"));
+
+    // Run the mainloop in a background task
+    tokio::spawn(async move {
+        if let Err(e) = mainloop
+            .run_buffered(stdout.compat(), stdin.compat_write())
+            .await
+        {
+            tracing::error!("[LeanServer] MainLoop error: {:?}", e);
+        }
+    });
+
+    let base = Arc::new(BaseLspClient::new("LeanServer", socket));
+
+    // Initialize the LSP connection
+    base.initialize().await?;
+    base.spawn_keep_alive();
+
+    Ok(base)
+}
+
 /// Get the lake serve log file path.
 fn get_lake_serve_log_file() -> Option<File> {
     let home = env::var("HOME").ok()?;
@@ -90,15 +159,22 @@ fn get_lake_serve_log_file() -> Option<File> {
     File::create(&log_path).ok()
 }
 
-/// Spawn the lake serve process.
-fn spawn_lake_serve() -> Result<(ChildStdin, ChildStdout), LspError>
-{
+/// Command running `lake serve`, its Lean server showing implicit arguments
+/// when `explicit` is set.
+fn lake_serve_command(explicit: bool) -> Command {
     let mut cmd = Command::new("lake");
     cmd.arg("serve").arg("--");
 
-    for opt in LEAN_PP_OPTIONS {
+    for opt in pp_options(explicit) {
         cmd.args(["-D", opt]);
     }
+    cmd
+}
+
+/// Spawn the lake serve process.
+fn spawn_lake_serve(explicit: bool) -> Result<(ChildStdin, ChildStdout), LspError>
+{
+    let mut cmd = lake_serve_command(explicit);
 
     cmd.env_remove("LEAN_PATH");
     cmd.env_remove("LEAN_SYSROOT");
@@ -126,3 +202,27 @@ fn spawn_lake_serve() -> Result<(ChildStdin, ChildStdout), LspError>
 
     Ok((stdin, stdout))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A restart for explicit goals passes `pp.explicit` to the server the
+    /// refetch goes to.
+    #[test]
+    fn test_explicit_option_on_command_line() {
+        let args = |explicit| -> Vec<String> {
+            lake_serve_command(explicit)
+                .as_std()
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(args(false), ["serve", "--", "-D", "pp.showLetValues=true"]);
+        assert_eq!(
+            args(true),
+            ["serve", "--", "-D", "pp.showLetValues=true", "-D", "pp.explicit=true"]
+        );
+    }
+}
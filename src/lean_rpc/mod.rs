@@ -1,13 +1,33 @@
 mod base;
 mod client;
 mod dag;
+mod diff;
 mod lean_dag;
 mod lean_server;
 
 use async_lsp::lsp_types::{Position, Url};
+pub use base::DAG_MODES;
 pub use client::RpcClient;
 pub use dag::{GoalInfo, HypothesisInfo, NodeId, ProofDag, ProofDagNode, ProofState};
-use serde::{Deserialize, Serialize};
+pub use diff::{annotate_after, annotate_before};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Deserialize a field that is only informative, falling back to its
+/// default when the server sends it in a shape this version does not know,
+/// rather than failing the whole response.
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+        warn!("Ignoring malformed field ({e}): {value}");
+        T::default()
+    }))
+}
 
 /// Pre-resolved `goto` location for navigation without RPC calls.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -16,6 +36,16 @@ pub struct GotoLocation {
     pub position: Position,
 }
 
+/// A symbol found by `workspace/symbol`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    pub name: String,
+    /// Enclosing namespace or structure, if the server reports one.
+    #[serde(default)]
+    pub container: Option<String>,
+    pub location: GotoLocation,
+}
+
 /// Pre-resolved `goto` locations for multiple navigation kinds.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -45,16 +75,23 @@ pub enum DiffTag {
 }
 
 /// Sub-expression semantic information from Lean's `SubexprInfo`.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SubexprInfo {
     /// Diff status for this sub expression.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub diff_status: Option<DiffTag>,
+    /// The remaining fields as sent (`subexprPos`, `info`, ...), kept for
+    /// inspecting the raw JSON.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 /// Tagged text preserving Lean's `CodeWithInfos` structure with typed info.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Deserialization never fails: an unrecognized structure degrades to a plain
+/// text leaf holding whatever text the value contains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(tag = "kind", rename_all = "lowercase")]
 #[allow(clippy::use_self)] // Self in enum variants doesn't work with serde
 pub enum TaggedText {
@@ -71,6 +108,37 @@ pub enum TaggedText {
     Append { items: Vec<TaggedText> },
 }
 
+impl<'de> Deserialize<'de> for TaggedText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(Self::from_value(&value))
+    }
+}
+
+/// Gather text from an arbitrary JSON value in document order, following
+/// only `text`, `content`, `items`/`append` and the content of a `tag` pair.
+/// Other keys such as `info` carry payloads, not goal text.
+fn collect_text(value: &Value, out: &mut String) {
+    match value {
+        Value::String(s) => out.push_str(s),
+        Value::Array(items) => items.iter().for_each(|item| collect_text(item, out)),
+        Value::Object(map) => {
+            if let Some(Value::String(s)) = map.get("text") {
+                out.push_str(s);
+                return;
+            }
+            if let Some(content) = map.get("tag").and_then(|tag| tag.get(1)) {
+                collect_text(content, out);
+            }
+            ["content", "items", "append"]
+                .into_iter()
+                .filter_map(|key| map.get(key))
+                .for_each(|v| collect_text(v, out));
+        }
+        _ => {}
+    }
+}
+
 impl Default for TaggedText {
     fn default() -> Self {
         Self::Text {
@@ -80,6 +148,34 @@ impl Default for TaggedText {
 }
 
 impl TaggedText {
+    /// Build from JSON in one pass. A node of unrecognized shape degrades
+    /// to a plain text leaf, leaving well-formed siblings structured.
+    fn from_value(value: &Value) -> Self {
+        let field = |key| value.get(key);
+        let parsed = match value.get("kind").and_then(Value::as_str) {
+            Some("text") => field("text")
+                .and_then(Value::as_str)
+                .map(|text| Self::Text { text: text.to_string() }),
+            Some("tag") => field("info")
+                .and_then(|info| SubexprInfo::deserialize(info).ok())
+                .zip(field("content"))
+                .map(|(info, content)| Self::Tag {
+                    info,
+                    content: Box::new(Self::from_value(content)),
+                }),
+            Some("append") => field("items").and_then(Value::as_array).map(|items| Self::Append {
+                items: items.iter().map(Self::from_value).collect(),
+            }),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| {
+            warn!("Unrecognized tagged text, falling back to plain text");
+            let mut text = String::new();
+            collect_text(value, &mut text);
+            Self::Text { text }
+        })
+    }
+
     /// Extract plain text from the tagged structure (flattens all tags).
     pub fn to_plain_text(&self) -> String {
         match self {
@@ -108,4 +204,61 @@ pub struct RpcConnectResponse {
 
 pub const RPC_CONNECT: &str = "$/lean/rpc/connect";
 pub const RPC_CALL: &str = "$/lean/rpc/call";
+pub const RPC_KEEP_ALIVE: &str = "$/lean/rpc/keepAlive";
 pub const GET_PROOF_DAG: &str = "LeanDag.getProofDag";
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Well-formed tagged text still parses into the structured form.
+    #[test]
+    fn test_tagged_text_well_formed() {
+        let value = json!({
+            "kind": "tag",
+            "info": { "diffStatus": "wasChanged" },
+            "content": { "kind": "text", "text": "n + 1" }
+        });
+        let parsed: TaggedText = serde_json::from_value(value).unwrap();
+        assert!(parsed.has_any_diff());
+        assert_eq!(parsed.to_plain_text(), "n + 1");
+    }
+
+    /// Malformed tagged text keeps whatever text was present.
+    #[test]
+    fn test_tagged_text_fallback() {
+        // Unknown kind wrapping items, and a tag missing its info
+        let value = json!({
+            "kind": "weird",
+            "items": [
+                { "kind": "text", "text": "a = " },
+                { "kind": "tag", "content": { "kind": "text", "text": "b" } }
+            ]
+        });
+        let parsed: TaggedText = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.to_plain_text(), "a = b");
+
+        // Nested malformed nodes only degrade locally
+        let value = json!({
+            "kind": "append",
+            "items": [
+                { "kind": "text", "text": "x ≤ " },
+                { "kind": "tag", "content": "y" }
+            ]
+        });
+        let parsed: TaggedText = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.to_plain_text(), "x ≤ y");
+
+        // Lean's own `CodeWithInfos` shape: info payloads stay out of the text
+        let value = json!({
+            "append": [
+                { "text": "n" },
+                { "tag": [{ "info": { "p": "Nat.add" }, "doc": "sum" }, { "text": " + 0" }] }
+            ]
+        });
+        let parsed: TaggedText = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.to_plain_text(), "n + 0");
+    }
+}
@@ -1,12 +1,18 @@
 mod error;
+mod export;
 mod lean_rpc;
 mod proxy;
 mod tui;
 mod tui_ipc;
 
-use std::{fs, path::PathBuf, process};
+use std::{fs, path::PathBuf, process, time::Duration};
 
 use clap::{Parser, Subcommand};
+use proxy::ServerCommand;
+use tui::{
+    app::{DiffMarkers, EnterKey, SelectionColors, ViewConfig},
+    KeymapFormat,
+};
 
 #[derive(Parser)]
 #[command(name = "lean-tui")]
@@ -26,20 +32,141 @@ enum Commands {
         /// methods directly.
         #[arg(long)]
         standalone: bool,
+        /// Language server to wrap instead of `lake serve`, e.g.
+        /// `--server-cmd "lean --server"`.
+        #[arg(long, value_name = "CMD", value_parser = ServerCommand::parse)]
+        server_cmd: Option<ServerCommand>,
     },
     /// Run TUI viewer (connects to proxy)
-    View,
+    View {
+        /// Summarize the proof status (complete, open goals, sorry, errors)
+        /// in a headline above the goals.
+        #[arg(long)]
+        headline: bool,
+        /// Render at most this many hypotheses per goal; press `e` to show
+        /// the rest.
+        #[arg(long, value_name = "N")]
+        max_hyps: Option<usize>,
+        /// Enable debugging aids: `R` shows the raw JSON of the selected
+        /// hypothesis or goal.
+        #[arg(long)]
+        debug: bool,
+        /// Markers in front of inserted, removed, and changed hypotheses in
+        /// the tree views: `git` (+ - ~), `brackets` ([+] [-] [~]), or
+        /// `arrows` (▲ ▼ ◆), optionally followed by the colors of inserted,
+        /// removed, and changed text, e.g. `--diff-markers git:cyan,magenta,yellow`.
+        #[arg(long, value_name = "STYLE", default_value = "git", value_parser = DiffMarkers::parse)]
+        diff_markers: DiffMarkers,
+        /// Background of the selection highlight, optionally followed by a
+        /// comma and its text color: a name (`blue`), an index (`24`) or
+        /// `#rrggbb`, e.g. `--selection-color "#304070,white"`.
+        #[arg(long, value_name = "COLORS", value_parser = SelectionColors::parse)]
+        selection_color: Option<SelectionColors>,
+        /// Start with the status bar hidden; press `b` to show it.
+        #[arg(long)]
+        hide_status_bar: bool,
+        /// Experimental: right-align goals and hypotheses, with markers on
+        /// the right, for right-to-left locales.
+        #[arg(long)]
+        rtl: bool,
+        /// What Enter does: `context` folds the selected proof step in the
+        /// steps sidebar and goes to the selection elsewhere; `goto` always
+        /// goes to the selection.
+        #[arg(long, value_name = "BEHAVIOR", default_value = "context", value_parser = EnterKey::parse)]
+        enter: EnterKey,
+        /// When the goals come back empty inside a definition, fetch them
+        /// again every this many seconds until they appear, in case Lean
+        /// was still elaborating.
+        #[arg(long, value_name = "SECS")]
+        refetch_every: Option<u64>,
+        /// Show in the status bar how long Lean took to elaborate the
+        /// document after the latest edit. Makes the proxy wait for every
+        /// edit's diagnostics.
+        #[arg(long)]
+        elaboration_time: bool,
+        /// Wait for the first goals, print them as one rendered frame of
+        /// plain text, and exit; fail if none arrive within 10 seconds.
+        #[arg(long)]
+        once: bool,
+        /// Append every distinct goal state received to this JSONL file,
+        /// with timestamps.
+        #[arg(long, value_name = "PATH")]
+        record: Option<PathBuf>,
+    },
+    /// Print the key bindings as a cheat sheet
+    #[command(hide = true)]
+    Keymap {
+        /// `text` for aligned tables, `json` for an array of
+        /// `{mode, bindings}` objects with `{key, action}` bindings.
+        #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = KeymapFormat::parse)]
+        format: KeymapFormat,
+        /// The `--enter` behavior to describe, as passed to `view`.
+        #[arg(long, value_name = "BEHAVIOR", default_value = "context", value_parser = EnterKey::parse)]
+        enter: EnterKey,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    // Init tracing to log file (separate files for proxy and TUI)
-    let log_filename = match &cli.command {
-        Commands::Proxy { .. } => "proxy.log",
-        Commands::View => "tui.log",
+    let result = match cli.command {
+        // Printed without starting logging, leaving the TUI's log alone
+        Commands::Keymap { format, enter } => {
+            print!("{}", tui::keymap(format, enter));
+            Ok(())
+        }
+        Commands::Proxy {
+            standalone,
+            server_cmd,
+        } => {
+            init_logging("proxy.log");
+            proxy::run(standalone, server_cmd.unwrap_or_default()).await
+        }
+        Commands::View {
+            headline,
+            max_hyps,
+            debug,
+            diff_markers,
+            selection_color,
+            hide_status_bar,
+            rtl,
+            enter,
+            refetch_every,
+            elaboration_time,
+            once,
+            record,
+        } => {
+            init_logging("tui.log");
+            let config = ViewConfig {
+                headline,
+                max_hyps,
+                debug,
+                diff_markers,
+                selection_colors: selection_color.unwrap_or_default(),
+                hide_status_bar,
+                rtl,
+                enter,
+                refetch_every: refetch_every.map(Duration::from_secs),
+                elaboration_time,
+            };
+            if once {
+                tui::run_once(config).await
+            } else {
+                tui::run(config, record).await
+            }
+        }
     };
+
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        process::exit(1);
+    }
+}
+
+/// Init tracing to a log file in the cache directory (separate files for
+/// proxy and TUI).
+fn init_logging(log_filename: &str) {
     let log_path = dirs::cache_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("lean-tui")
@@ -63,14 +190,4 @@ async fn main() {
             .pretty()
             .init();
     }
-
-    let result = match cli.command {
-        Commands::Proxy { standalone } => proxy::run(standalone).await,
-        Commands::View => tui::run().await,
-    };
-
-    if let Err(e) = result {
-        eprintln!("Error: {e}");
-        process::exit(1);
-    }
 }
@@ -0,0 +1,460 @@
+//! Source scan for the definitions enclosing a line.
+
+use std::{iter, mem};
+
+use crate::{
+    lean_rpc::ProofDag,
+    tui_ipc::{DefinitionInfo, Position},
+};
+
+/// Keywords that start a definition, after any modifiers.
+const DEFINITION_KINDS: &[&str] = &[
+    "theorem", "lemma", "def", "example", "instance", "abbrev", "opaque",
+];
+
+/// Modifiers that may precede a definition keyword.
+const MODIFIERS: &[&str] = &[
+    "private",
+    "protected",
+    "noncomputable",
+    "partial",
+    "unsafe",
+    "nonrec",
+];
+
+/// Keywords of declarations whose `where` lists fields rather than local
+/// definitions.
+const FIELD_KINDS: &[&str] = &["instance", "structure", "class"];
+
+/// Keywords that continue a definition even at its own indentation.
+const CONTINUATIONS: &[&str] = &["termination_by", "decreasing_by", "where", "deriving"];
+
+/// Definition header found on a line.
+struct Header {
+    kind: String,
+    name: String,
+    attributes: Vec<String>,
+    /// Text after the name, where the signature starts.
+    tail: String,
+}
+
+/// Split leading attributes off a line, e.g. `@[simp, ext] theorem` into
+/// `["simp", "ext"]` and `theorem`.
+fn parse_attributes(line: &str) -> Option<(Vec<String>, &str)> {
+    let (list, rest) = line.trim_start().strip_prefix("@[")?.split_once(']')?;
+    let attributes = list
+        .split(',')
+        .map(str::trim)
+        .filter(|attr| !attr.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((attributes, rest.trim_start()))
+}
+
+/// Take the next whitespace-separated word off the front of `rest`.
+fn next_word<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (word, after) = trimmed.split_at(end);
+    *rest = after;
+    (!word.is_empty()).then_some(word)
+}
+
+/// Parse a definition header, e.g. `private theorem foo (n : Nat) : ...`.
+fn parse_header(line: &str) -> Option<Header> {
+    let (attributes, mut rest) = parse_attributes(line).unwrap_or_else(|| (Vec::new(), line));
+    let mut word = next_word(&mut rest)?;
+    while MODIFIERS.contains(&word) {
+        word = next_word(&mut rest)?;
+    }
+    let kind = match word {
+        "let" => {
+            next_word(&mut rest).filter(|w| *w == "rec")?;
+            "let rec"
+        }
+        kind if DEFINITION_KINDS.contains(&kind) => kind,
+        _ => return None,
+    };
+    // Anonymous definitions, e.g. `instance : Foo`, go by their kind
+    let after_kind = rest;
+    let name = match next_word(&mut rest) {
+        Some(name) if !name.starts_with([':', '(', '{', '[', '⦃']) => name,
+        _ => {
+            rest = after_kind;
+            kind
+        }
+    };
+    Some(Header {
+        kind: kind.to_string(),
+        name: name.to_string(),
+        attributes,
+        tail: rest.to_string(),
+    })
+}
+
+/// Binders and type of a definition, e.g. `(n : Nat) : P n`: the words
+/// after its name up to `:=`, a `where` or the first match arm, read on
+/// from the header line through the lines indented deeper than it.
+fn read_signature<'a>(
+    tail: &'a str,
+    indent: usize,
+    following: impl Iterator<Item = &'a str>,
+) -> Option<String> {
+    let continued =
+        following.take_while(|line| line.trim().is_empty() || indentation(line) > indent);
+    let mut words = Vec::new();
+    'lines: for line in iter::once(tail).chain(continued) {
+        let code = line.split_once("--").map_or(line, |(code, _)| code);
+        if code.trim_start().starts_with('|') {
+            break;
+        }
+        for word in code.split_whitespace() {
+            if let Some((before, _)) = word.split_once(":=") {
+                words.extend(Some(before).filter(|before| !before.is_empty()));
+                break 'lines;
+            }
+            if word == "where" {
+                break 'lines;
+            }
+            words.push(word);
+        }
+    }
+    let signature = words.join(" ");
+    (!signature.is_empty()).then_some(signature)
+}
+
+/// Parse the header of the definition around `character` on a line that
+/// may hold several one-liners separated by `;`, e.g. `def a := 1; def b := 2`.
+/// A `;` between tactics starts no header, so the cursor after it stays in
+/// the definition before.
+fn parse_header_at(line: &str, character: u32) -> Option<Header> {
+    let cursor = line
+        .char_indices()
+        .nth(character as usize)
+        .map_or(line.len(), |(i, _)| i);
+    iter::once(0)
+        .chain(line.match_indices(';').map(|(i, _)| i + 1))
+        .filter(|&start| start <= cursor)
+        .rev()
+        .find_map(|start| parse_header(&line[start..]))
+}
+
+/// Whether a line starts a declaration whose `where` lists fields.
+fn declares_fields(line: &str) -> bool {
+    let (_, rest) = parse_attributes(line).unwrap_or_else(|| (Vec::new(), line));
+    rest.split_whitespace()
+        .find(|w| !MODIFIERS.contains(w))
+        .is_some_and(|w| FIELD_KINDS.contains(&w))
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Indentation of an open `where` block and of its local definitions, once
+/// the first one is seen.
+struct WhereBlock {
+    indent: usize,
+    member_indent: Option<usize>,
+}
+
+impl WhereBlock {
+    /// Parse a local definition of the block, e.g. `go (n : Nat) : Nat :=`.
+    fn parse_member(&mut self, indent: usize, trimmed: &str) -> Option<Header> {
+        let member_indent = *self.member_indent.get_or_insert(indent);
+        if indent != member_indent || !trimmed.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        {
+            return None;
+        }
+        let mut tail = trimmed;
+        let name = next_word(&mut tail)?;
+        Some(Header {
+            kind: "where".to_string(),
+            name: name.to_string(),
+            attributes: Vec::new(),
+            tail: tail.to_string(),
+        })
+    }
+}
+
+/// Definitions enclosing `position`, outermost first.
+///
+/// Nesting is judged by indentation: a definition ends at the next line
+/// indented no deeper than its header, unless that line continues it (a
+/// match arm, `where`, `termination_by`, ...). Local definitions of a
+/// `where` block count as nested in the definition the block belongs to;
+/// the `where` of a structure, class or instance lists fields instead.
+/// On the cursor's own line, the column picks between one-liners.
+/// Attributes on a line of their own go to the header that follows.
+pub fn find_definition_chain(content: &str, position: Position) -> Vec<DefinitionInfo> {
+    let mut chain: Vec<(usize, DefinitionInfo, String)> = Vec::new();
+    let mut where_block: Option<WhereBlock> = None;
+    // Indentation of the structure, class or instance being declared
+    let mut fields_header: Option<usize> = None;
+    let mut pending_attributes = Vec::new();
+    for (number, text) in (0..=position.line).zip(content.lines()) {
+        let trimmed = text.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with("--") {
+            continue;
+        }
+        if let Some((attributes, "")) = parse_attributes(trimmed) {
+            pending_attributes.extend(attributes);
+            continue;
+        }
+        let indent = indentation(text);
+        let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+        let continues = trimmed.starts_with('|') || CONTINUATIONS.contains(&first_word);
+        chain.retain(|(depth, ..)| *depth < indent || (*depth == indent && continues));
+
+        if where_block
+            .as_ref()
+            .is_some_and(|block| indent <= block.indent && !continues)
+        {
+            where_block = None;
+        }
+        if fields_header.is_some_and(|header| indent <= header && !continues) {
+            fields_header = None;
+        }
+        if declares_fields(trimmed) {
+            fields_header = Some(indent);
+        }
+        let opens_where = first_word == "where" || trimmed.ends_with(" where");
+        let header = if opens_where && fields_header.is_none() {
+            where_block = Some(WhereBlock {
+                indent,
+                member_indent: None,
+            });
+            None
+        } else {
+            match &mut where_block {
+                Some(block) if indent > block.indent => block.parse_member(indent, trimmed),
+                _ => None,
+            }
+        };
+        let header = header.or_else(|| {
+            if number == position.line {
+                parse_header_at(text, position.character)
+            } else {
+                parse_header(text)
+            }
+        });
+        let attributes = mem::take(&mut pending_attributes);
+        if let Some(header) = header {
+            chain.push((
+                indent,
+                DefinitionInfo {
+                    kind: Some(header.kind),
+                    name: header.name,
+                    line: Some(number),
+                    signature: None,
+                    attributes: [attributes, header.attributes].concat(),
+                },
+                header.tail,
+            ));
+        }
+    }
+    // Read only for the enclosing definitions, as a header may run on past
+    // the cursor
+    chain
+        .into_iter()
+        .map(|(indent, def, tail)| {
+            let following = content.lines().skip(def.line.map_or(0, |line| line as usize + 1));
+            DefinitionInfo {
+                signature: read_signature(&tail, indent, following),
+                ..def
+            }
+        })
+        .collect()
+}
+
+/// Name each side proof (orphan subtree) after the `where` or `let rec`
+/// clause it proves, so it does not show up as an anonymous orphan.
+pub fn label_side_proofs(dag: &mut ProofDag, content: &str) {
+    for &orphan in &dag.orphans {
+        let Some(node) = dag.nodes.get_mut(orphan as usize) else {
+            continue;
+        };
+        node.clause = find_definition_chain(content, node.position)
+            .into_iter()
+            .skip(1)
+            .next_back()
+            .map(|def| format!("{} {}", def.kind.unwrap_or_default(), def.name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::ProofDagNode;
+
+    /// A `let rec` inside a theorem yields both, outermost first.
+    #[test]
+    fn test_nested_definition_chain() {
+        let content = "\
+def unrelated : Nat := 0
+
+theorem outer (n : Nat) : n = n := by
+  let rec go : Nat → Nat
+    | 0 => 0
+    | k + 1 => go k
+  rfl
+";
+        let names = |line| -> Vec<(Option<String>, String)> {
+            find_definition_chain(content, Position::new(line, 0))
+                .into_iter()
+                .map(|d| (d.kind, d.name))
+                .collect()
+        };
+
+        assert_eq!(
+            names(5),
+            vec![
+                (Some("theorem".to_string()), "outer".to_string()),
+                (Some("let rec".to_string()), "go".to_string()),
+            ]
+        );
+        assert_eq!(
+            names(6),
+            vec![(Some("theorem".to_string()), "outer".to_string())]
+        );
+        assert_eq!(
+            names(0),
+            vec![(Some("def".to_string()), "unrelated".to_string())]
+        );
+    }
+
+    /// The signature runs from the name to `:=`, across the lines of a
+    /// multi-line header, and stops at the first match arm.
+    #[test]
+    fn test_signature_read_up_to_body() {
+        let content = "\
+theorem outer {α : Type} [Inhabited α]
+    (n : Nat) : -- the statement
+    n = n := by
+  let rec go : Nat → Nat
+    | 0 => 0
+    | k + 1 => go k
+  rfl
+
+instance : Inhabited Nat := ⟨0⟩
+def two:=2
+";
+        let signatures = |line| -> Vec<Option<String>> {
+            find_definition_chain(content, Position::new(line, 0))
+                .into_iter()
+                .map(|d| d.signature)
+                .collect()
+        };
+
+        assert_eq!(
+            signatures(4),
+            [
+                Some("{α : Type} [Inhabited α] (n : Nat) : n = n".to_string()),
+                Some(": Nat → Nat".to_string()),
+            ]
+        );
+        assert_eq!(signatures(8), [Some(": Inhabited Nat".to_string())]);
+        assert_eq!(signatures(9), [None]);
+    }
+
+    /// The `where` of an instance or structure lists fields, which are not
+    /// local definitions; a `where` clause after them is again.
+    #[test]
+    fn test_fields_after_where_are_not_definitions() {
+        let content = "\
+instance : Inhabited Point where
+  default := by
+    exact ⟨0, 0⟩
+
+structure Pos
+    (n : Nat) where
+  pos : 0 < n := by
+    omega
+
+def twice (n : Nat) : Nat := go n
+where
+  go (k : Nat) : Nat := k + k
+";
+        let names = |line| -> Vec<String> {
+            find_definition_chain(content, Position::new(line, 4))
+                .into_iter()
+                .map(|d| d.name)
+                .collect()
+        };
+
+        assert_eq!(names(2), ["instance"]);
+        assert!(names(7).is_empty(), "{:?}", names(7));
+        assert_eq!(names(11), ["twice", "go"]);
+    }
+
+    /// A tactic proof in a `where` clause labels its side proof.
+    #[test]
+    fn test_where_clause_labels_side_proof() {
+        let content = "\
+theorem main (n : Nat) : helper n = n := by
+  simp [helper]
+where
+  helper (k : Nat) : Nat := by
+    exact k
+";
+        let chain = find_definition_chain(content, Position::new(4, 4));
+        let names: Vec<&str> = chain.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["main", "helper"]);
+
+        let mut side_proof = ProofDagNode {
+            position: Position::new(4, 4),
+            ..Default::default()
+        };
+        side_proof.tactic.text = "exact k".to_string();
+        let mut dag = ProofDag {
+            nodes: vec![ProofDagNode::default(), side_proof],
+            root: Some(0),
+            orphans: vec![1],
+            ..Default::default()
+        };
+        label_side_proofs(&mut dag, content);
+
+        assert_eq!(dag.nodes[0].clause, None);
+        assert_eq!(dag.nodes[1].clause.as_deref(), Some("where helper"));
+        assert!(dag
+            .orphan_dfs_iter()
+            .any(|node| node.tactic.text == "exact k"));
+    }
+
+    /// With two one-liners on a line, the column decides which one encloses
+    /// the cursor; a `;` between tactics does not start another.
+    #[test]
+    fn test_one_liners_split_by_column() {
+        let content = "def a : Nat := 1; def b : Nat := by simp; exact 2\n";
+        let name = |character| {
+            find_definition_chain(content, Position::new(0, character))[0]
+                .name
+                .clone()
+        };
+
+        assert_eq!(name(5), "a");
+        assert_eq!(name(22), "b");
+        assert_eq!(name(45), "b");
+    }
+
+    /// Attributes inline or on the line above a header are kept with it.
+    #[test]
+    fn test_simp_attribute_extracted() {
+        let content = "\
+@[simp] theorem add_zero' (n : Nat) : n + 0 = n := by
+  rfl
+
+@[simp, norm_cast]
+theorem cast_id (n : Nat) : (n : Nat) = n := by
+  rfl
+";
+        let attributes = |line| {
+            find_definition_chain(content, Position::new(line, 2))[0]
+                .attributes
+                .clone()
+        };
+
+        assert_eq!(attributes(1), ["simp"]);
+        assert_eq!(attributes(5), ["simp", "norm_cast"]);
+    }
+}
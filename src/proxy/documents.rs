@@ -1,25 +1,52 @@
 //! Document content cache for tracking open documents.
 
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
 
 use async_lsp::{
     lsp_types::{
         notification::{Notification, PublishDiagnostics},
-        Position, PublishDiagnosticsParams, TextDocumentContentChangeEvent,
+        DiagnosticSeverity, DidOpenTextDocumentParams, Position, PublishDiagnosticsParams,
+        TextDocumentContentChangeEvent, TextDocumentItem, Url,
     },
     AnyNotification,
 };
 
-use super::lsp::ParsedNotification;
+use super::{
+    definitions::{find_definition_chain, label_side_proofs},
+    lexical::in_comment_or_string_outside_proof,
+    lsp::ParsedNotification,
+};
+use crate::{
+    lean_rpc::ProofDag,
+    tui_ipc::{DefinitionInfo, DocumentVersion},
+};
+
+/// Language of the documents the proxy reopens on a restarted server.
+const LANGUAGE_ID: &str = "lean4";
+
+/// Text of an open document and the version the editor gave it.
+struct Document {
+    content: String,
+    version: DocumentVersion,
+}
 
 pub struct DocumentCache {
-    documents: Mutex<HashMap<String, String>>,
+    documents: Mutex<HashMap<String, Document>>,
+    /// Documents opened so far, numbering the epochs of their versions.
+    opened: AtomicU32,
 }
 
 impl DocumentCache {
     pub fn new() -> Self {
         Self {
             documents: Mutex::new(HashMap::new()),
+            opened: AtomicU32::new(0),
         }
     }
 
@@ -29,49 +56,133 @@ impl DocumentCache {
             ParsedNotification::DidOpen(p) => {
                 let uri = p.text_document.uri.as_str();
                 tracing::debug!("DidOpen URI: {uri}");
-                self.update(uri, p.text_document.text.clone());
+                let version = DocumentVersion {
+                    epoch: self.opened.fetch_add(1, Ordering::Relaxed) + 1,
+                    version: p.text_document.version.cast_unsigned(),
+                };
+                self.update(uri, p.text_document.text.clone(), version);
             }
             ParsedNotification::DidChange(p) => {
                 let uri = p.text_document.uri.as_str();
                 tracing::debug!("DidChange URI: {uri}");
                 if let Some(content) = self.apply_changes(uri, &p.content_changes) {
-                    self.update(uri, content);
+                    let version = DocumentVersion {
+                        epoch: self.version(uri).map_or(0, |v| v.epoch),
+                        version: p.text_document.version.cast_unsigned(),
+                    };
+                    self.update(uri, content, version);
                 }
             }
             ParsedNotification::Other => {}
         }
     }
 
-    /// Handle server-to-client notifications (`PublishDiagnostics`).
-    pub fn handle_server_notification(notif: &AnyNotification) {
-        if notif.method == PublishDiagnostics::METHOD {
-            let Ok(p) = serde_json::from_value::<PublishDiagnosticsParams>(notif.params.clone())
-            else {
-                return;
-            };
-            tracing::debug!(
-                "PublishDiagnostics for {}: {} diagnostics",
-                p.uri,
-                p.diagnostics.len()
-            );
+    /// Handle server-to-client notifications (`PublishDiagnostics`),
+    /// returning the document they are for and how many are errors.
+    pub fn handle_server_notification(notif: &AnyNotification) -> Option<(Url, usize)> {
+        if notif.method != PublishDiagnostics::METHOD {
+            return None;
         }
+        let p = serde_json::from_value::<PublishDiagnosticsParams>(notif.params.clone()).ok()?;
+        tracing::debug!(
+            "PublishDiagnostics for {}: {} diagnostics",
+            p.uri,
+            p.diagnostics.len()
+        );
+        let errors = p
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+            .count();
+        Some((p.uri, errors))
+    }
+
+    /// Current text of a cached document.
+    pub fn content(&self, uri: &str) -> Option<String> {
+        self.documents
+            .lock()
+            .expect("lock poisoned")
+            .get(uri)
+            .map(|doc| doc.content.clone())
+    }
+
+    /// Every cached document as a `didOpen` of its latest version, to open
+    /// them again on a restarted server.
+    pub fn reopen_params(&self) -> Vec<DidOpenTextDocumentParams> {
+        let docs = self.documents.lock().expect("lock poisoned");
+        docs.iter()
+            .filter_map(|(uri, doc)| {
+                let version = doc.version.version.cast_signed();
+                let item = TextDocumentItem::new(
+                    Url::parse(uri).ok()?,
+                    LANGUAGE_ID.to_string(),
+                    version,
+                    doc.content.clone(),
+                );
+                Some(DidOpenTextDocumentParams {
+                    text_document: item,
+                })
+            })
+            .collect()
     }
 
-    fn update(&self, uri: &str, content: String) {
+    /// Version of a cached document as of the editor's latest edit.
+    pub fn version(&self, uri: &str) -> Option<DocumentVersion> {
         self.documents
             .lock()
             .expect("lock poisoned")
-            .insert(uri.to_string(), content);
+            .get(uri)
+            .map(|doc| doc.version)
+    }
+
+    /// Definitions enclosing `position` of a cached document, outermost
+    /// first.
+    pub fn definition_chain(&self, uri: &str, position: Position) -> Vec<DefinitionInfo> {
+        let docs = self.documents.lock().expect("lock poisoned");
+        docs.get(uri)
+            .map(|doc| find_definition_chain(&doc.content, position))
+            .unwrap_or_default()
+    }
+
+    /// Name the side proofs of a DAG fetched from a cached document.
+    pub fn annotate_dag(&self, uri: &str, dag: &mut ProofDag) {
+        let docs = self.documents.lock().expect("lock poisoned");
+        // Scanned in place, sparing a copy of the text on every fetch
+        if let Some(doc) = docs.get(uri) {
+            label_side_proofs(dag, &doc.content);
+        }
+        drop(docs);
     }
 
+    /// Whether `position` of a cached document is inside a comment or a
+    /// string literal outside any proof.
+    pub fn in_comment_or_string_outside_proof(&self, uri: &str, position: Position) -> bool {
+        let docs = self.documents.lock().expect("lock poisoned");
+        docs.get(uri)
+            .is_some_and(|doc| in_comment_or_string_outside_proof(&doc.content, position))
+    }
+
+    fn update(&self, uri: &str, content: String, version: DocumentVersion) {
+        self.documents
+            .lock()
+            .expect("lock poisoned")
+            .insert(uri.to_string(), Document { content, version });
+    }
+
+    /// New text of a document after `changes`, or `None` when they edit a
+    /// document the cache has not seen. A change without a range (full
+    /// document sync) replaces the text outright, so it needs no cached
+    /// copy.
     fn apply_changes(
         &self,
         uri: &str,
         changes: &[TextDocumentContentChangeEvent],
     ) -> Option<String> {
-        let docs = self.documents.lock().expect("lock poisoned");
-        let mut content = docs.get(uri)?.clone();
-        drop(docs);
+        let full_sync = changes.iter().rposition(|change| change.range.is_none());
+        let (mut content, changes) = match full_sync {
+            Some(i) => (changes[i].text.clone(), &changes[i + 1..]),
+            None => (self.content(uri)?, changes),
+        };
 
         for change in changes {
             let Some(range) = change.range else {
@@ -102,3 +213,78 @@ fn position_to_offset(content: &str, pos: Position) -> usize {
         .sum::<usize>()
         + pos.character as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use async_lsp::lsp_types::{
+        DidChangeTextDocumentParams, DidOpenTextDocumentParams, Range, TextDocumentItem,
+        VersionedTextDocumentIdentifier,
+    };
+
+    use super::*;
+
+    fn did_open(version: i32) -> ParsedNotification {
+        ParsedNotification::DidOpen(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                Url::parse("file:///tmp/Test.lean").unwrap(),
+                "lean4".to_string(),
+                version,
+                String::new(),
+            ),
+        })
+    }
+
+    fn did_change(changes: Vec<TextDocumentContentChangeEvent>) -> ParsedNotification {
+        ParsedNotification::DidChange(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+                version: 2,
+            },
+            content_changes: changes,
+        })
+    }
+
+    /// A full-sync change carries the whole text, so it fills the cache
+    /// even for a document never opened, and later ranged edits apply to
+    /// it.
+    #[test]
+    fn test_full_sync_change_updates_cache() {
+        let cache = DocumentCache::new();
+        let uri = "file:///tmp/Test.lean";
+        cache.handle_parsed_notification(&did_change(vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "theorem foo : True := trivial\n".to_string(),
+        }]));
+        assert_eq!(
+            cache.content(uri).as_deref(),
+            Some("theorem foo : True := trivial\n")
+        );
+
+        let edit = TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(0, 8), Position::new(0, 11))),
+            range_length: None,
+            text: "bar".to_string(),
+        };
+        cache.handle_parsed_notification(&did_change(vec![edit]));
+        assert_eq!(
+            cache.content(uri).as_deref(),
+            Some("theorem bar : True := trivial\n")
+        );
+    }
+
+    /// Reopening a document starts a new epoch, so its versions compare as
+    /// newer than those before it was closed.
+    #[test]
+    fn test_reopen_starts_new_epoch() {
+        let cache = DocumentCache::new();
+        let uri = "file:///tmp/Test.lean";
+        cache.handle_parsed_notification(&did_open(1));
+        cache.handle_parsed_notification(&did_change(Vec::new()));
+        let before = cache.version(uri).unwrap();
+        assert_eq!(before, DocumentVersion { epoch: 1, version: 2 });
+
+        cache.handle_parsed_notification(&did_open(1));
+        assert!(cache.version(uri).unwrap() > before);
+    }
+}
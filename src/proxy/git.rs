@@ -0,0 +1,56 @@
+//! Committed file contents, for comparing goals against `HEAD`.
+
+use std::path::Path;
+
+use async_lsp::lsp_types::Url;
+use tokio::process::Command;
+
+/// Prefix of the file name the `HEAD` copy of a document is opened under.
+const HEAD_COPY_PREFIX: &str = ".lean-tui-HEAD-";
+
+/// Text of a file as committed at `HEAD` of the Git repository holding it.
+pub async fn head_content(path: &Path) -> Result<String, String> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str()))
+    else {
+        return Err(format!("{} is not a file", path.display()));
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("HEAD:./{name}"))
+        .output()
+        .await
+        .map_err(|e| format!("could not run git: {e}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("{name} at HEAD is not UTF-8"))
+}
+
+/// URI to open the `HEAD` copy of a document under: a hidden file beside
+/// it, so imports resolve as they do for the working copy. Nothing is
+/// written there; the server gets the text with `didOpen`.
+pub fn head_copy_uri(uri: &Url) -> Option<Url> {
+    let path = uri.to_file_path().ok()?;
+    let name = path.file_name()?.to_str()?;
+    Url::from_file_path(path.with_file_name(format!("{HEAD_COPY_PREFIX}{name}"))).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `HEAD` copy sits beside the document; other schemes have none.
+    #[test]
+    fn test_head_copy_uri() {
+        let uri = Url::parse("file:///home/me/Proj/Basic.lean").unwrap();
+        assert_eq!(
+            head_copy_uri(&uri).unwrap().as_str(),
+            "file:///home/me/Proj/.lean-tui-HEAD-Basic.lean"
+        );
+
+        let library = Url::parse("lean4://library/Init/Prelude.lean").unwrap();
+        assert!(head_copy_uri(&library).is_none());
+    }
+}
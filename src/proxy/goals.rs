@@ -1,54 +1,170 @@
-use std::sync::Arc;
+use std::{future::Future, sync::Arc, time::Duration};
 
+use async_lsp::lsp_types::{Position, Url};
+use tokio::time::{sleep, Instant};
+
+use super::{
+    documents::DocumentCache,
+    limiter::{run_limited, FetchLimiter},
+};
 use crate::{
     lean_rpc::RpcClient,
-    tui_ipc::{CursorInfo, LspProxySocketEndpoint},
+    tui_ipc::{CursorInfo, DocumentVersion, LspProxySocketEndpoint},
 };
 
+/// A goal fetch waiting for a slot: the position and the document version
+/// the editor last sent.
+pub type GoalFetch = (Position, Option<DocumentVersion>);
+
+/// Longest wait for the RPC client to reach the editor's document version.
+const CATCH_UP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Pause between checks of the RPC client's document version.
+const CATCH_UP_POLL: Duration = Duration::from_millis(10);
+
 /// Spawn a task to fetch the proof DAG at the given cursor position.
+///
+/// The document's `version` is the one the goals must be fetched for. When
+/// the document already has the most fetches in flight, the request is
+/// parked for the next free slot instead. Once the fetch is done, the
+/// `documents` cache names the side proofs and supplies the timing of the
+/// latest edit.
 pub fn spawn_goal_fetch(
     cursor: &CursorInfo,
     socket_server: &Arc<LspProxySocketEndpoint>,
     rpc_client: &RpcClient,
+    limiter: &Arc<FetchLimiter<GoalFetch>>,
+    documents: &Arc<DocumentCache>,
+    version: Option<DocumentVersion>,
 ) {
+    let job = (cursor.position, version);
+    let Some(job) = limiter.start(cursor.uri.as_str(), job) else {
+        tracing::debug!(
+            "Goal fetch for {} parked until a slot frees",
+            cursor.uri.path()
+        );
+        return;
+    };
     let rpc_client = rpc_client.clone();
-    let socket_server = socket_server.clone();
+    let server = socket_server.clone();
+    let limiter = limiter.clone();
+    let documents = documents.clone();
     let uri = cursor.uri.clone();
-    let position = cursor.position;
-
-    tokio::spawn(async move {
-        tracing::info!(
-            "Fetching proof DAG for {}:{}:{}",
-            uri.path(),
-            position.line,
-            position.character
-        );
 
-        // Fetch proof DAG using the RPC client
-        let result = rpc_client.get_proof_dag(&uri, position, "tree").await;
-
-        match result {
-            Ok(Some(dag)) => {
-                tracing::debug!(
-                    "ProofDag: {} nodes, root={:?}, current={:?}",
-                    dag.nodes.len(),
-                    dag.root,
-                    dag.current_node
-                );
-                socket_server.broadcast_proof_dag(uri, position, Some(dag));
-            }
-            Ok(None) => {
-                tracing::debug!("LeanDag.getProofDag returned no data at this position");
-                socket_server.broadcast_proof_dag(uri, position, None);
-            }
-            Err(e) => {
-                tracing::warn!(
-                    "Could not fetch proof DAG at {uri}:{}:{}: {e}",
-                    position.line,
-                    position.character
-                );
-                socket_server.broadcast_error(e.to_string());
-            }
-        }
+    let task = tokio::spawn(async move {
+        run_limited(&limiter, uri.as_str(), job, |(position, version)| {
+            fetch_goals(&rpc_client, &server, &documents, uri.clone(), position, version)
+        })
+        .await;
+    });
+    socket_server.track_goal_fetch(task.abort_handle());
+}
+
+/// Fetch the proof DAG at a position and broadcast it.
+async fn fetch_goals(
+    rpc_client: &RpcClient,
+    server: &LspProxySocketEndpoint,
+    documents: &DocumentCache,
+    uri: Url,
+    position: Position,
+    expected_version: Option<DocumentVersion>,
+) {
+    tracing::info!(
+        "Fetching proof DAG for {}:{}:{}",
+        uri.path(),
+        position.line,
+        position.character
+    );
+
+    // Fetch proof DAG using the RPC client
+    let mode = rpc_client.dag_mode().await;
+    let expected = expected_version.map(|v| v.version);
+    let caught_up = catch_up(expected, || rpc_client.document_version(&uri)).await;
+    // Stamped in the editor's epoch; untracked documents go unstamped
+    let version = expected_version.map(|v| DocumentVersion {
+        version: caught_up,
+        ..v
     });
+    let result = rpc_client.get_proof_dag(&uri, position, mode).await;
+
+    match result {
+        Ok(Some(mut dag)) => {
+            documents.annotate_dag(uri.as_str(), &mut dag);
+            tracing::debug!(
+                "ProofDag: {} nodes, root={:?}, current={:?}",
+                dag.nodes.len(),
+                dag.root,
+                dag.current_node
+            );
+            server.broadcast_proof_dag(uri, position, version, Some(dag));
+        }
+        Ok(None) => {
+            tracing::debug!("LeanDag.getProofDag returned no data at this position");
+            server.broadcast_proof_dag(uri, position, version, None);
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Could not fetch proof DAG at {uri}:{}:{}: {e}",
+                position.line,
+                position.character
+            );
+            server.broadcast_error(e.to_string());
+        }
+    }
+}
+
+/// The RPC client's version of a document, once it reaches `expected`.
+///
+/// The proxy forwards `didChange` to the RPC client on a task of its own,
+/// so a request right after an edit can overtake it and fetch goals for the
+/// text before the edit. After [`CATCH_UP_TIMEOUT`] the fetch goes ahead
+/// with whatever version the client has.
+async fn catch_up<F, Fut>(expected: Option<u32>, mut current: F) -> u32
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<u32>>,
+{
+    let deadline = Instant::now() + CATCH_UP_TIMEOUT;
+    loop {
+        let version = current().await.unwrap_or_default();
+        let Some(expected) = expected.filter(|&expected| version < expected) else {
+            return version;
+        };
+        if Instant::now() >= deadline {
+            tracing::debug!("RPC client at v{version}, editor at v{expected}; fetching anyway");
+            return version;
+        }
+        sleep(CATCH_UP_POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+
+    /// A fetch asked for right after an edit waits until the RPC client has
+    /// the edit before it proceeds.
+    #[tokio::test]
+    async fn test_fetch_waits_for_editor_version() {
+        let client_version = Arc::new(AtomicU32::new(1));
+        let forwarded = client_version.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(30)).await;
+            forwarded.store(2, Ordering::SeqCst);
+        });
+
+        let current = || future::ready(Some(client_version.load(Ordering::SeqCst)));
+        let version = catch_up(Some(2), current).await;
+        assert_eq!(version, 2);
+
+        let started = Instant::now();
+        let version = catch_up(Some(1), || future::ready(Some(2))).await;
+        assert_eq!(version, 2, "a newer client version needs no wait");
+        assert!(started.elapsed() < CATCH_UP_POLL);
+    }
 }
@@ -1,21 +1,61 @@
-//! Lake serve child process management for the editor-facing LSP server.
+//! Lake serve (or `--server-cmd`) child process management for the
+//! editor-facing LSP server.
 
 use std::{
     env,
     fs::{self, File},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Stdio,
+    result::Result as StdResult,
 };
 
 use tokio::process::{ChildStdin, ChildStdout, Command};
 
 use crate::error::{Error, LspError, Result};
 
+/// Files that make a directory the root of a Lake project.
+const LAKEFILES: &[&str] = &["lakefile.lean", "lakefile.toml"];
+
 /// Lean pretty-printer options for the server.
 const LEAN_PP_OPTIONS: &[&str] = &[
     "pp.showLetValues=true", // Show full let-binding values (not ⋯)
 ];
 
+/// Language server the proxy spawns, `lake serve` unless overridden.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Default for ServerCommand {
+    fn default() -> Self {
+        Self {
+            program: "lake".to_string(),
+            args: vec!["serve".to_string()],
+        }
+    }
+}
+
+impl ServerCommand {
+    /// Command given on the command line, e.g. `lean --server`.
+    pub fn parse(line: &str) -> StdResult<Self, String> {
+        let mut words = line.split_whitespace().map(str::to_string);
+        let program = words
+            .next()
+            .ok_or_else(|| "server command must not be empty".to_string())?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+
+    /// `lake serve` passes options on to Lean only after `--`.
+    fn is_lake(&self) -> bool {
+        self.program.rsplit('/').next() == Some("lake")
+    }
+}
+
 /// Get the lake serve log file path, creating the directory if needed.
 fn get_lake_serve_log_file() -> Option<File> {
     let home = env::var("HOME").ok()?;
@@ -28,18 +68,41 @@ fn get_lake_serve_log_file() -> Option<File> {
     File::create(&log_path).ok()
 }
 
-/// Spawn lake serve for the editor-facing LSP connection.
-pub fn spawn_lake_serve() -> Result<(ChildStdin, ChildStdout)> {
+/// Root of the Lake project containing `dir`, searching upward.
+fn find_lake_project(dir: &Path) -> Result<&Path> {
+    dir.ancestors()
+        .find(|ancestor| LAKEFILES.iter().any(|name| ancestor.join(name).exists()))
+        .ok_or_else(|| {
+            Error::Lsp(LspError::NotALeanProject {
+                searched_from: dir.to_path_buf(),
+            })
+        })
+}
+
+/// Spawn the language server for the editor-facing LSP connection.
+pub fn spawn_lake_serve(server: &ServerCommand) -> Result<(ChildStdin, ChildStdout)> {
     // Log working directory for debugging
     if let Ok(cwd) = env::current_dir() {
         tracing::info!(
-            "Spawning lake serve from working directory: {}",
+            "Spawning {} {} from working directory: {}",
+            server.program,
+            server.args.join(" "),
             cwd.display()
         );
     }
 
-    let mut cmd = Command::new("lake");
-    cmd.arg("serve").arg("--");
+    // Without a lakefile, lake serve exits before speaking LSP
+    if server.is_lake() {
+        let cwd = env::current_dir()?;
+        let root = find_lake_project(&cwd)?;
+        tracing::info!("Lake project root: {}", root.display());
+    }
+
+    let mut cmd = Command::new(&server.program);
+    cmd.args(&server.args);
+    if server.is_lake() {
+        cmd.arg("--");
+    }
     for opt in LEAN_PP_OPTIONS {
         cmd.args(["-D", opt]);
     }
@@ -60,15 +123,56 @@ pub fn spawn_lake_serve() -> Result<(ChildStdin, ChildStdout)> {
     let stdin = child.stdin.take().ok_or_else(|| {
         Error::Lsp(LspError::RpcError {
             code: None,
-            message: "Failed to capture lake serve stdin".to_string(),
+            message: format!("Failed to capture {} stdin", server.program),
         })
     })?;
     let stdout = child.stdout.take().ok_or_else(|| {
         Error::Lsp(LspError::RpcError {
             code: None,
-            message: "Failed to capture lake serve stdout".to_string(),
+            message: format!("Failed to capture {} stdout", server.program),
         })
     })?;
 
     Ok((stdin, stdout))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    /// The first word is the program and the rest are its arguments;
+    /// an empty command is rejected.
+    #[test]
+    fn test_server_command_parsed() {
+        let server = ServerCommand::parse("  lean --server --threads=4 ").unwrap();
+        assert_eq!(server.program, "lean");
+        assert_eq!(server.args, ["--server", "--threads=4"]);
+        assert!(!server.is_lake());
+
+        assert!(ServerCommand::parse("/usr/bin/lake serve")
+            .unwrap()
+            .is_lake());
+        assert!(ServerCommand::parse("   ").is_err());
+    }
+
+    /// Outside a Lake project the lookup fails with `NotALeanProject`; a
+    /// lakefile in an ancestor makes a subdirectory count.
+    #[test]
+    fn test_not_a_lean_project() {
+        let dir = env::temp_dir().join(format!("lean-tui-no-project-{}", process::id()));
+        let nested = dir.join("src");
+        fs::create_dir_all(&nested).unwrap();
+
+        let err = find_lake_project(&nested).unwrap_err();
+        assert!(
+            matches!(err, Error::Lsp(LspError::NotALeanProject { .. })),
+            "{err}"
+        );
+
+        fs::write(dir.join("lakefile.toml"), "name = \"test\"\n").unwrap();
+        assert_eq!(find_lake_project(&nested).unwrap(), dir);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
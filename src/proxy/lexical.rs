@@ -0,0 +1,130 @@
+//! Source scan for comments and string literals outside proofs, where the
+//! server has no goals.
+
+use async_lsp::lsp_types::Position;
+
+/// Part of the source the scan is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Code,
+    LineComment,
+    /// Block comment, nested this deep.
+    BlockComment(usize),
+    Str,
+}
+
+/// Region after `c`, given the character `next` after it, and whether the
+/// two form one token (`--`, `/-`, `-/` or an escape).
+const fn step(region: Region, c: char, next: Option<char>) -> (Region, bool) {
+    match (region, c, next) {
+        (Region::Code, '-', Some('-')) => (Region::LineComment, true),
+        (Region::Code, '/', Some('-')) => (Region::BlockComment(1), true),
+        (Region::Code, '"', _) => (Region::Str, false),
+        (Region::LineComment, '\n', _) | (Region::Str, '"', _) => (Region::Code, false),
+        // The outermost block comment closes; the char literal `'"'` opens
+        // no string
+        (Region::BlockComment(1), '-', Some('/')) | (Region::Code, '\'', Some('"')) => {
+            (Region::Code, true)
+        }
+        (Region::BlockComment(depth), '/', Some('-')) => (Region::BlockComment(depth + 1), true),
+        (Region::BlockComment(depth), '-', Some('/')) => (Region::BlockComment(depth - 1), true),
+        (Region::Str, '\\', Some(_)) => (Region::Str, true),
+        _ => (region, false),
+    }
+}
+
+/// Position just after `c` at `at`, in UTF-16 code units like LSP positions.
+const fn advance(at: (u32, u32), c: char) -> (u32, u32) {
+    if c == '\n' {
+        (at.0 + 1, 0)
+    } else {
+        (at.0, at.1 + c.len_utf16() as u32)
+    }
+}
+
+/// Whether `c` can be part of an identifier or keyword.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '\'' | '.' | '!' | '?')
+}
+
+/// Whether `position` in `content` lies inside a comment (`--`, `/- -/`,
+/// nested or doc) or a string literal outside any proof. A cursor just
+/// before the opening `--` or `"` is still in code.
+///
+/// Between tactics the server still has goals, so a proof starts at `:=` or
+/// `by` and runs until a command starts flush left; a flush-left comment
+/// inside a proof ends it early.
+pub fn in_comment_or_string_outside_proof(content: &str, position: Position) -> bool {
+    let target = (position.line, position.character);
+    let mut region = Region::Code;
+    let mut in_proof = false;
+    let mut word = String::new();
+    let mut at = (0, 0);
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if at >= target {
+            return region != Region::Code && !in_proof;
+        }
+        if region == Region::Code {
+            if at.1 == 0 && !c.is_whitespace() {
+                in_proof = false;
+            }
+            if is_word_char(c) {
+                word.push(c);
+            } else {
+                in_proof |= word == "by" || (c == ':' && chars.peek() == Some(&'='));
+                word.clear();
+            }
+        }
+        at = advance(at, c);
+        let (next_region, pair) = step(region, c, chars.peek().copied());
+        if pair {
+            let Some(second) = chars.next() else {
+                break;
+            };
+            // On the second character of a token, either side counts
+            if at == target {
+                return (region != Region::Code || next_region != Region::Code) && !in_proof;
+            }
+            at = advance(at, second);
+        }
+        region = next_region;
+    }
+    region != Region::Code && !in_proof
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"/-- Doc. -/
+theorem foo : True := by -- close it
+  /- a /- nested -/ block -/ trivial
+#eval "a -- \"b\"" ++ s
+#eval "𝔽" ++ s
+"#;
+
+    /// Comments, nested block comments and strings are told from the code
+    /// around them, and those inside a proof count as code.
+    #[test]
+    fn test_comments_and_strings() {
+        let at = |line, character| {
+            in_comment_or_string_outside_proof(SOURCE, Position::new(line, character))
+        };
+
+        assert!(at(0, 5), "doc comment before the theorem");
+        assert!(!at(1, 0), "the theorem itself");
+
+        assert!(!at(1, 25), "just before `--`");
+        assert!(!at(1, 30), "comment after `by`");
+        assert!(!at(2, 10), "nested comment between tactics");
+
+        assert!(!at(3, 5), "`#eval` starts a new command");
+        assert!(at(3, 10), "`--` inside a string");
+        assert!(at(3, 13), "escaped quote inside a string");
+        assert!(!at(3, 21), "`++` after the string");
+
+        assert!(at(4, 9), "`𝔽` takes two UTF-16 units");
+        assert!(!at(4, 10), "after the closing quote");
+    }
+}
@@ -0,0 +1,186 @@
+//! Per-document cap on concurrent goal fetches.
+//!
+//! Holding an arrow key in the editor asks for goals at every position it
+//! passes. Only a few fetches per document run at once; requests beyond
+//! that are parked, and a newer one replaces the parked one, so the server
+//! only ever catches up with the latest cursor position.
+
+use std::{collections::HashMap, future::Future, sync::Mutex};
+
+/// Most goal fetches in flight for one document.
+pub const MAX_FETCHES_PER_URI: usize = 2;
+
+struct Slots<T> {
+    in_flight: usize,
+    /// Latest request waiting for a free slot.
+    parked: Option<T>,
+}
+
+pub struct FetchLimiter<T> {
+    limit: usize,
+    documents: Mutex<HashMap<String, Slots<T>>>,
+}
+
+impl<T> FetchLimiter<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            documents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Claim a slot for `uri` and hand `job` back to run now, or park it as
+    /// the document's next fetch, replacing the one parked before.
+    pub fn start(&self, uri: &str, job: T) -> Option<T> {
+        let mut documents = self.documents.lock().expect("lock poisoned");
+        let slots = documents.entry(uri.to_string()).or_insert(Slots {
+            in_flight: 0,
+            parked: None,
+        });
+        if slots.in_flight >= self.limit {
+            slots.parked = Some(job);
+            return None;
+        }
+        slots.in_flight += 1;
+        drop(documents);
+        Some(job)
+    }
+
+    /// After a fetch for `uri` finished, the parked job to run next in the
+    /// same slot. The slot is released when nothing is parked.
+    pub fn finish(&self, uri: &str) -> Option<T> {
+        let mut documents = self.documents.lock().expect("lock poisoned");
+        let slots = documents.get_mut(uri)?;
+        let next = slots.parked.take();
+        if next.is_none() {
+            slots.in_flight = slots.in_flight.saturating_sub(1);
+            if slots.in_flight == 0 {
+                documents.remove(uri);
+            }
+        }
+        next
+    }
+
+    /// Release the slot of an aborted fetch. The parked job goes with it,
+    /// since it would otherwise run after whatever the cursor asks for next.
+    fn abandon(&self, uri: &str) {
+        let mut documents = self.documents.lock().expect("lock poisoned");
+        if let Some(slots) = documents.get_mut(uri) {
+            slots.parked = None;
+            slots.in_flight = slots.in_flight.saturating_sub(1);
+            if slots.in_flight == 0 {
+                documents.remove(uri);
+            }
+        }
+    }
+}
+
+/// Releases the slot if the task running in it is aborted.
+struct SlotGuard<'a, T> {
+    limiter: &'a FetchLimiter<T>,
+    uri: &'a str,
+    released: bool,
+}
+
+impl<T> Drop for SlotGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.released {
+            self.limiter.abandon(self.uri);
+        }
+    }
+}
+
+/// Run `job`, then the jobs parked behind it, in a slot claimed by
+/// [`FetchLimiter::start`].
+pub async fn run_limited<T, F, Fut>(limiter: &FetchLimiter<T>, uri: &str, job: T, mut fetch: F)
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut guard = SlotGuard {
+        limiter,
+        uri,
+        released: false,
+    };
+    let mut job = job;
+    loop {
+        fetch(job).await;
+        match limiter.finish(uri) {
+            Some(next) => job = next,
+            None => break,
+        }
+    }
+    guard.released = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::{task::JoinSet, time::sleep};
+
+    use super::*;
+
+    const URI: &str = "file:///tmp/Test.lean";
+
+    /// Stand-in for the server, recording how many fetches overlap.
+    #[derive(Default)]
+    struct Probe {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+        fetched: Mutex<Vec<u32>>,
+    }
+
+    impl Probe {
+        async fn fetch(&self, position: u32) {
+            let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.fetched.lock().unwrap().push(position);
+        }
+    }
+
+    /// Ask for goals at `position` the way the proxy does.
+    fn request(
+        tasks: &mut JoinSet<()>,
+        limiter: &Arc<FetchLimiter<u32>>,
+        probe: &Arc<Probe>,
+        position: u32,
+    ) {
+        let Some(job) = limiter.start(URI, position) else {
+            return;
+        };
+        let (limiter, probe) = (limiter.clone(), probe.clone());
+        tasks.spawn(async move {
+            run_limited(&limiter, URI, job, |position| probe.fetch(position)).await;
+        });
+    }
+
+    /// Fifty rapid requests never run more than two fetches at once, and the
+    /// last position requested is fetched.
+    #[tokio::test]
+    async fn test_concurrent_fetches_capped() {
+        let limiter = Arc::new(FetchLimiter::new(MAX_FETCHES_PER_URI));
+        let probe = Arc::new(Probe::default());
+        let mut tasks = JoinSet::new();
+
+        for position in 0..50 {
+            request(&mut tasks, &limiter, &probe, position);
+            sleep(Duration::from_millis(1)).await;
+        }
+        while tasks.join_next().await.is_some() {}
+
+        assert!(probe.peak.load(Ordering::SeqCst) <= MAX_FETCHES_PER_URI);
+        let fetched = probe.fetched.lock().unwrap().clone();
+        assert!(fetched.contains(&49), "latest position fetched");
+        assert!(fetched.len() < 50, "parked requests coalesced");
+        assert!(limiter.documents.lock().unwrap().is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     result::Result as StdResult,
     sync::{Arc, OnceLock},
     task::{Context, Poll},
+    time::Instant,
 };
 
 use async_lsp::{
@@ -17,7 +18,10 @@ use async_lsp::{
 };
 use futures::Future;
 
-use super::{cursor::extract_cursor_from_request, documents::DocumentCache};
+use super::{
+    cursor::extract_cursor_from_request, documents::DocumentCache, goals::GoalFetch,
+    limiter::FetchLimiter,
+};
 use crate::{
     lean_rpc::RpcClient, proxy::goals::spawn_goal_fetch, tui_ipc::CursorInfo,
     tui_ipc::LspProxySocketEndpoint,
@@ -32,15 +36,63 @@ fn spawn_did_open(client: RpcClient, params: DidOpenTextDocumentParams) {
     });
 }
 
-/// Spawn async task to forward didChange to RPC client.
-fn spawn_did_change(client: RpcClient, params: DidChangeTextDocumentParams) {
+/// Spawn async task to forward didChange to RPC client. While a TUI shows
+/// elaboration timings, also time how long the server takes to elaborate
+/// the whole document after the edit, and broadcast it unless the editor
+/// has moved on.
+fn spawn_did_change(
+    client: RpcClient,
+    params: DidChangeTextDocumentParams,
+    document_cache: Arc<DocumentCache>,
+    socket_server: Arc<LspProxySocketEndpoint>,
+) {
     tokio::spawn(async move {
+        let uri = params.text_document.uri.clone();
+        let version = params.text_document.version.cast_unsigned();
+        let started = Instant::now();
         if let Err(e) = client.did_change(params).await {
             tracing::warn!("Failed to forward didChange to RPC client: {e}");
+            return;
+        }
+        if !socket_server.times_elaboration() {
+            return;
+        }
+        if let Err(e) = client.wait_for_diagnostics(&uri, version).await {
+            tracing::debug!("Could not time elaboration of {uri} v{version}: {e}");
+            return;
+        }
+        let ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        if document_cache
+            .version(uri.as_str())
+            .is_some_and(|current| current.version == version)
+        {
+            socket_server.broadcast_elaboration(uri, ms);
         }
     });
 }
 
+/// Broadcast that there are no goals when the cursor is in a comment or a
+/// string outside any proof, where the server has none, sparing it a fetch. Returns
+/// whether the fetch is skipped.
+fn skip_outside_code(
+    cursor: &CursorInfo,
+    document_cache: &DocumentCache,
+    socket_server: &LspProxySocketEndpoint,
+) -> bool {
+    let uri = cursor.uri.as_str();
+    if !document_cache.in_comment_or_string_outside_proof(uri, cursor.position) {
+        return false;
+    }
+    tracing::debug!("Cursor in a comment or string, skipping goal fetch");
+    socket_server.broadcast_proof_dag(
+        cursor.uri.clone(),
+        cursor.position,
+        document_cache.version(uri),
+        None,
+    );
+    true
+}
+
 /// Parsed notification variants for single-parse optimization.
 pub enum ParsedNotification {
     DidOpen(DidOpenTextDocumentParams),
@@ -61,6 +113,10 @@ impl ParsedNotification {
     }
 
     /// Extract cursor info from parsed notification.
+    ///
+    /// Only ranged changes locate the cursor. Editors using full document
+    /// sync send no range, so their cursor is tracked from requests
+    /// (hover, goals) alone.
     fn cursor_info(&self) -> Option<CursorInfo> {
         match self {
             Self::DidChange(params) => {
@@ -88,6 +144,8 @@ pub struct InterceptService<S> {
     pub document_cache: Arc<DocumentCache>,
     /// RPC client slot - set after client is initialized.
     pub rpc_client_slot: RpcClientSlot,
+    /// Cap on concurrent goal fetches per document.
+    pub fetch_limiter: Arc<FetchLimiter<GoalFetch>>,
 }
 
 impl<S: LspService> InterceptService<S> {
@@ -101,10 +159,26 @@ impl<S: LspService> InterceptService<S> {
         )
         .entered();
 
-        self.socket_server.broadcast_cursor(cursor.clone());
+        let mut with_definitions = cursor.clone();
+        with_definitions.definitions = self
+            .document_cache
+            .definition_chain(cursor.uri.as_str(), cursor.position);
+        self.socket_server.broadcast_cursor(with_definitions);
 
         if let Some(client) = self.rpc_client_slot.get() {
-            spawn_goal_fetch(cursor, &self.socket_server, client);
+            let uri = cursor.uri.as_str();
+            let version = self.document_cache.version(uri);
+            if skip_outside_code(cursor, &self.document_cache, &self.socket_server) {
+                return;
+            }
+            spawn_goal_fetch(
+                cursor,
+                &self.socket_server,
+                client,
+                &self.fetch_limiter,
+                &self.document_cache,
+                version,
+            );
         }
     }
 
@@ -116,7 +190,9 @@ impl<S: LspService> InterceptService<S> {
 
     fn handle_notification(&self, notif: &AnyNotification) {
         // Handle server-to-client notifications (`PublishDiagnostics`)
-        DocumentCache::handle_server_notification(notif);
+        if let Some((uri, errors)) = DocumentCache::handle_server_notification(notif) {
+            self.socket_server.broadcast_diagnostics(uri, errors);
+        }
 
         // Parse once, use for all purposes
         let parsed = ParsedNotification::from_any(notif);
@@ -139,7 +215,14 @@ impl<S: LspService> InterceptService<S> {
         };
         match parsed {
             ParsedNotification::DidOpen(params) => spawn_did_open(client, params.clone()),
-            ParsedNotification::DidChange(params) => spawn_did_change(client, params.clone()),
+            ParsedNotification::DidChange(params) => {
+                spawn_did_change(
+                    client,
+                    params.clone(),
+                    self.document_cache.clone(),
+                    self.socket_server.clone(),
+                );
+            }
             ParsedNotification::Other => {}
         }
     }
@@ -218,3 +301,37 @@ impl<S: LspService> LspService for DeferredService<S> {
             .emit(event)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_lsp::lsp_types::{Position, TextDocumentItem, Url};
+
+    use super::*;
+    use crate::tui_ipc::ServerMode;
+
+    /// A cursor inside a `--` comment before the theorem gets no goal
+    /// fetch; one in a comment between tactics or on a tactic does.
+    #[test]
+    fn test_fetch_skipped_in_comment() {
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        let cache = DocumentCache::new();
+        cache.handle_parsed_notification(&ParsedNotification::DidOpen(
+            DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "lean4".to_string(),
+                    version: 1,
+                    text: "-- why\ntheorem foo : True := by\n  -- how\n  trivial\n".to_string(),
+                },
+            },
+        ));
+        let server = LspProxySocketEndpoint::new(ServerMode::Library);
+        let cursor = |line, character| {
+            CursorInfo::new(uri.clone(), Position::new(line, character), "test")
+        };
+
+        assert!(skip_outside_code(&cursor(0, 4), &cache, &server));
+        assert!(!skip_outside_code(&cursor(2, 5), &cache, &server));
+        assert!(!skip_outside_code(&cursor(3, 4), &cache, &server));
+    }
+}
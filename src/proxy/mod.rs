@@ -8,16 +8,23 @@
 //! ```
 
 mod cursor;
+mod definitions;
 mod documents;
+mod git;
 mod goals;
 mod lake;
+mod lexical;
+mod limiter;
 mod lsp;
 
 use std::sync::{Arc, OnceLock};
 
 use async_lsp::MainLoop;
-use documents::DocumentCache;
+pub use documents::DocumentCache;
+pub use git::{head_content, head_copy_uri};
+pub use lake::ServerCommand;
 use lake::spawn_lake_serve;
+use limiter::{FetchLimiter, MAX_FETCHES_PER_URI};
 use lsp::{DeferredService, InterceptService, RpcClientSlot};
 use tokio::io::{stdin, stdout};
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
@@ -28,7 +35,7 @@ use crate::{
     tui_ipc::{CommandHandler, ServerMode, LspProxySocketEndpoint},
 };
 
-pub async fn run(standalone: bool) -> Result<()> {
+pub async fn run(standalone: bool, server: ServerCommand) -> Result<()> {
     let server_mode = if standalone {
         tracing::info!("Running in standalone mode (lean-dag binary)");
         ServerMode::Standalone
@@ -39,32 +46,17 @@ pub async fn run(standalone: bool) -> Result<()> {
 
     let socket_server = Arc::new(LspProxySocketEndpoint::new(server_mode));
     let document_cache = Arc::new(DocumentCache::new());
+    let fetch_limiter = Arc::new(FetchLimiter::new(MAX_FETCHES_PER_URI));
 
-    // Create RPC client based on mode
-    let rpc_client: Option<RpcClient> = match RpcClient::new(standalone).await {
-        Ok(client) => {
-            tracing::info!("RPC client initialized successfully");
-            Some(client)
-        }
-        Err(e) => {
-            tracing::warn!(
-                "Failed to initialize RPC client: {}. Proof DAG will be unavailable.",
-                e
-            );
-            None
-        }
-    };
-    let rpc_client_slot: RpcClientSlot = Arc::new(OnceLock::new());
-    if let Some(client) = rpc_client {
-        let _ = rpc_client_slot.set(client);
-    }
+    let rpc_client_slot = start_rpc_client(standalone).await;
 
-    // Spawn the editor-facing LSP server (lake serve)
-    let (child_stdin, child_stdout) = spawn_lake_serve()?;
+    // Spawn the editor-facing LSP server (lake serve by default)
+    let (child_stdin, child_stdout) = spawn_lake_serve(&server)?;
 
     let doc_cache_client = document_cache.clone();
     let socket_server_client = socket_server.clone();
     let rpc_client_slot_client = rpc_client_slot.clone();
+    let fetch_limiter_client = fetch_limiter.clone();
     let (mut client_mainloop, server_socket) = MainLoop::new_client(move |_| {
         {
             let service = DeferredService(None);
@@ -74,6 +66,7 @@ pub async fn run(standalone: bool) -> Result<()> {
                 socket_server,
                 document_cache: doc_cache_client,
                 rpc_client_slot: rpc_client_slot_client,
+                fetch_limiter: fetch_limiter_client,
             }
         }
     });
@@ -89,6 +82,7 @@ pub async fn run(standalone: bool) -> Result<()> {
                 socket_server,
                 document_cache: doc_cache_server,
                 rpc_client_slot: rpc_client_slot_server,
+                fetch_limiter,
             }
         }
     });
@@ -97,7 +91,12 @@ pub async fn run(standalone: bool) -> Result<()> {
     let cmd_rx = socket_server.start_listener();
 
     // Create command handler to process TUI commands
-    let (cmd_handler, cmd_tx) = CommandHandler::new(client_socket.clone());
+    let (cmd_handler, cmd_tx) = CommandHandler::new(
+        client_socket.clone(),
+        rpc_client_slot,
+        socket_server.clone(),
+        document_cache,
+    );
 
     // Forward commands from socket server to command handler
     tokio::spawn(async move {
@@ -144,3 +143,25 @@ pub async fn run(standalone: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Create the RPC client based on mode; the slot stays empty when it fails.
+async fn start_rpc_client(standalone: bool) -> RpcClientSlot {
+    let rpc_client: Option<RpcClient> = match RpcClient::new(standalone).await {
+        Ok(client) => {
+            tracing::info!("RPC client initialized successfully");
+            Some(client)
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Failed to initialize RPC client: {}. Proof DAG will be unavailable.",
+                e
+            );
+            None
+        }
+    };
+    let rpc_client_slot: RpcClientSlot = Arc::new(OnceLock::new());
+    if let Some(client) = rpc_client {
+        let _ = rpc_client_slot.set(client);
+    }
+    rpc_client_slot
+}
@@ -0,0 +1,274 @@
+//! Actions of the global keymap, shared by key handling and the command
+//! palette (`Ctrl-p`).
+
+use std::{fmt::Write, iter};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde_json::json;
+
+use super::{app::EnterKey, modes::DisplayMode};
+
+/// Something the user can ask the app to do, by key or from the palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    NextMode,
+    PrevMode,
+    GotoSelection,
+    CopySelection,
+    CopyGoalsAsComment,
+    CopyGoalsAsMarkdown,
+    ToggleComparison,
+    CompareWithHead,
+    ExportHtml,
+    ToggleExplicit,
+    CycleDagMode,
+    ToggleStatusBar,
+    TogglePresentation,
+    RawJson,
+    TaggedTextTree,
+    GotoImplementation,
+    FindSymbol,
+    ResetSession,
+    CancelFetch,
+    CommandPalette,
+    /// A key the current display mode handles, e.g. a filter toggle.
+    ModeKey(char),
+}
+
+impl Action {
+    /// Actions available in every display mode, in palette order.
+    pub const GLOBAL: &'static [Self] = &[
+        Self::NextMode,
+        Self::PrevMode,
+        Self::GotoSelection,
+        Self::CopySelection,
+        Self::CopyGoalsAsComment,
+        Self::CopyGoalsAsMarkdown,
+        Self::ToggleComparison,
+        Self::CompareWithHead,
+        Self::ExportHtml,
+        Self::ToggleExplicit,
+        Self::CycleDagMode,
+        Self::ToggleStatusBar,
+        Self::TogglePresentation,
+        Self::TaggedTextTree,
+        Self::GotoImplementation,
+        Self::FindSymbol,
+        Self::RawJson,
+        Self::ResetSession,
+        Self::CancelFetch,
+        Self::ToggleHelp,
+        Self::Quit,
+    ];
+
+    /// The global action bound to a key, if any.
+    pub const fn for_key(key: KeyEvent) -> Option<Self> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('l') => Some(Self::ResetSession),
+                KeyCode::Char('c') => Some(Self::CancelFetch),
+                KeyCode::Char('p') => Some(Self::CommandPalette),
+                _ => None,
+            };
+        }
+        let action = match key.code {
+            KeyCode::Char('q') => Self::Quit,
+            KeyCode::Char('?') => Self::ToggleHelp,
+            KeyCode::Char(']') => Self::NextMode,
+            KeyCode::Char('[') => Self::PrevMode,
+            KeyCode::Char('g') | KeyCode::Enter => Self::GotoSelection,
+            KeyCode::Char('y') => Self::CopySelection,
+            KeyCode::Char('C') => Self::CopyGoalsAsComment,
+            KeyCode::Char('M') => Self::CopyGoalsAsMarkdown,
+            KeyCode::Char('P') => Self::ToggleComparison,
+            KeyCode::Char('V') => Self::CompareWithHead,
+            KeyCode::Char('H') => Self::ExportHtml,
+            KeyCode::Char('X') => Self::ToggleExplicit,
+            KeyCode::Char('D') => Self::CycleDagMode,
+            KeyCode::Char('b') => Self::ToggleStatusBar,
+            KeyCode::F(2) => Self::TogglePresentation,
+            KeyCode::Char('R') => Self::RawJson,
+            KeyCode::Char('A') => Self::TaggedTextTree,
+            KeyCode::Char('I') => Self::GotoImplementation,
+            KeyCode::Char(':') => Self::FindSymbol,
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    /// Key the action is bound to, as shown in the palette.
+    pub fn key(self) -> String {
+        let key = match self {
+            Self::Quit => "q",
+            Self::ToggleHelp => "?",
+            Self::NextMode => "]",
+            Self::PrevMode => "[",
+            Self::GotoSelection => "g",
+            Self::CopySelection => "y",
+            Self::CopyGoalsAsComment => "C",
+            Self::CopyGoalsAsMarkdown => "M",
+            Self::ToggleComparison => "P",
+            Self::CompareWithHead => "V",
+            Self::ExportHtml => "H",
+            Self::ToggleExplicit => "X",
+            Self::CycleDagMode => "D",
+            Self::ToggleStatusBar => "b",
+            Self::TogglePresentation => "F2",
+            Self::RawJson => "R",
+            Self::TaggedTextTree => "A",
+            Self::GotoImplementation => "I",
+            Self::FindSymbol => ":",
+            Self::ResetSession => "C-l",
+            Self::CancelFetch => "C-c",
+            Self::CommandPalette => "C-p",
+            Self::ModeKey(c) => return c.to_string(),
+        };
+        key.to_string()
+    }
+
+    /// What the action does, for global actions; mode keys are described by
+    /// their mode.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::ToggleHelp => "toggle help",
+            Self::NextMode => "next display mode",
+            Self::PrevMode => "previous display mode",
+            Self::GotoSelection => "goto origin",
+            Self::CopySelection => "copy to clipboard",
+            Self::CopyGoalsAsComment => "copy goals as comment",
+            Self::CopyGoalsAsMarkdown => "copy goals as Markdown",
+            Self::ToggleComparison => "pin for comparison",
+            Self::CompareWithHead => "compare with git HEAD",
+            Self::ExportHtml => "open goals as HTML",
+            Self::ToggleExplicit => "toggle implicit args",
+            Self::CycleDagMode => "cycle DAG mode",
+            Self::ToggleStatusBar => "toggle status bar",
+            Self::TogglePresentation => "presentation mode",
+            Self::RawJson => "raw JSON (--debug)",
+            Self::TaggedTextTree => "tagged text tree",
+            Self::GotoImplementation => "go to implementation",
+            Self::FindSymbol => "go to symbol",
+            Self::ResetSession => "reset RPC session",
+            Self::CancelFetch => "cancel goal fetch",
+            Self::CommandPalette => "command palette",
+            Self::ModeKey(_) => "",
+        }
+    }
+}
+
+/// Output format of `lean-tui keymap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapFormat {
+    Text,
+    Json,
+}
+
+impl KeymapFormat {
+    /// Format named on the command line.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown keymap format `{name}` (text, json)")),
+        }
+    }
+}
+
+/// The bindings of one keymap section: global, or a display mode's.
+fn section_bindings(mode: Option<&DisplayMode>, enter: EnterKey) -> Vec<(String, &'static str)> {
+    let Some(mode) = mode else {
+        return Action::GLOBAL
+            .iter()
+            .chain([&Action::CommandPalette])
+            .map(|action| (action.key(), action.description()))
+            .chain([("Enter".to_string(), enter.description())])
+            .collect();
+    };
+    mode.keybindings()
+        .iter()
+        // With `--enter goto` a mode never sees Enter
+        .filter(|(key, _)| enter == EnterKey::Context || *key != "Enter")
+        .map(|&(key, description)| (key.to_string(), description))
+        .collect()
+}
+
+/// Every action with its key, global first and then per display mode, as
+/// aligned tables or a JSON array, for `lean-tui keymap`.
+pub fn keymap(format: KeymapFormat, enter: EnterKey) -> String {
+    let modes = DisplayMode::all();
+    let sections = iter::once(("Global", None))
+        .chain(modes.iter().map(|mode| (mode.name(), Some(mode))))
+        .map(|(name, mode)| (name, section_bindings(mode, enter)));
+    match format {
+        KeymapFormat::Text => {
+            let mut out = String::new();
+            for (name, bindings) in sections {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "{name}");
+                for (key, description) in bindings {
+                    let _ = writeln!(out, "{key:<8}{description}");
+                }
+            }
+            out
+        }
+        KeymapFormat::Json => {
+            let entries: Vec<_> = sections
+                .map(|(name, bindings)| {
+                    let bindings: Vec<_> = bindings
+                        .into_iter()
+                        .map(|(key, description)| json!({ "key": key, "action": description }))
+                        .collect();
+                    json!({ "mode": name, "bindings": bindings })
+                })
+                .collect();
+            format!("{:#}\n", json!(entries))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Both formats list the default bindings, including the palette's,
+    /// followed by a section per display mode.
+    #[test]
+    fn test_keymap_lists_default_bindings() {
+        let text = keymap(KeymapFormat::Text, EnterKey::Context);
+        assert!(text.starts_with("Global\n"), "{text}");
+        assert!(text.lines().any(|line| line == "q       quit"), "{text}");
+        assert!(text.contains("C-p     command palette"));
+        assert!(text.contains("Enter   fold step, else goto origin"));
+        for mode in DisplayMode::all() {
+            assert!(text.contains(&format!("\n\n{}\n", mode.name())), "{text}");
+        }
+
+        let json = keymap(KeymapFormat::Json, EnterKey::Context);
+        let json: Value = serde_json::from_str(&json).unwrap();
+        let sections = json.as_array().unwrap();
+        assert_eq!(sections.len(), DisplayMode::all().len() + 1);
+        assert_eq!(sections[0]["mode"], "Global");
+        let global = sections[0]["bindings"].as_array().unwrap();
+        assert_eq!(global.len(), Action::GLOBAL.len() + 2);
+        assert!(global.contains(&json!({ "key": "H", "action": "open goals as HTML" })));
+        assert!(sections[1..].iter().all(|s| !s["bindings"].as_array().unwrap().is_empty()));
+    }
+
+    /// With `--enter goto`, Enter is described as goto and no mode lists it.
+    #[test]
+    fn test_keymap_follows_enter_behavior() {
+        let text = keymap(KeymapFormat::Text, EnterKey::Goto);
+        let enter: Vec<_> = text.lines().filter(|line| line.starts_with("Enter ")).collect();
+        assert_eq!(enter, ["Enter   goto origin"]);
+
+        let text = keymap(KeymapFormat::Text, EnterKey::Context);
+        assert!(text.lines().any(|line| line == "Enter   fold"), "{text}");
+    }
+}
@@ -1,11 +1,16 @@
 //! Application state for the TUI.
 
-use std::{io::stdout, mem};
+use std::{
+    collections::HashMap,
+    io::stdout,
+    mem,
+    time::{Duration, Instant},
+};
 
 use async_lsp::lsp_types::Url;
 use crossterm::{
     clipboard::CopyToClipboard,
-    event::{Event, KeyCode, KeyEventKind},
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     ExecutableCommand,
 };
 use ratatui::{
@@ -16,34 +21,201 @@ use ratatui::{
 };
 
 use super::{
+    action::Action,
     modes::{
         BeforeAfterModeInput, DeductionTreeModeInput, DisplayMode, PlainListInput, StepsModeInput,
     },
+    recorder::Recorder,
     widgets::{welcome::WelcomeScreen, KeyMouseEvent, Selection},
 };
+pub use crate::tui::widgets::{diff_text::DiffMarkers, theme::SelectionColors};
 use crate::{
-    lean_rpc::{ProofDag, ProofState},
+    export::{html, lean_comment::goals_to_lean_comment, markdown::goals_to_markdown},
+    lean_rpc::{annotate_before, ProofDag, ProofState, TaggedText, DAG_MODES, RPC_CONNECT},
     tui::widgets::{
+        command_palette::{CommandPalette, CommandPaletteWidget, PaletteEntry},
+        diff_text::TaggedTextExt,
         help_menu::{HelpMenu, HelpMenuWidget},
+        raw_view::{RawView, RawViewWidget},
+        spinner::Spinner,
         status_bar::{StatusBar, StatusBarInput, StatusBarWidget},
+        symbol_search::{SymbolAction, SymbolSearch, SymbolSearchWidget},
         InteractiveStatefulWidget,
     },
-    tui_ipc::{socket_path, Command, CursorInfo, Message, Position, ServerMode},
+    tui_ipc::{
+        is_local_file, socket_path, uri_display_name, Command, CursorInfo, DocumentVersion,
+        Message, Position, ServerMode,
+    },
 };
 
-/// Information about the enclosing definition (theorem, lemma, def, etc.)
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct DefinitionInfo {
-    /// Kind of definition (theorem, lemma, def, example)
-    pub kind: Option<String>,
-    /// Name of the definition
-    pub name: String,
-    /// Line where the definition starts
-    pub line: Option<u32>,
+pub use crate::tui_ipc::DefinitionInfo;
+
+/// The definition name, prefixed by the definitions it is nested in when
+/// the source shows more than one.
+fn breadcrumb(def: &DefinitionInfo, chain: &[DefinitionInfo]) -> String {
+    if chain.len() < 2 {
+        return def.name.clone();
+    }
+    chain
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" › ")
+}
+
+/// `@[simp] ` when the source tags the definition, looked up in the chain by
+/// name since the DAG only knows the name.
+fn attribute_prefix(def: &DefinitionInfo, chain: &[DefinitionInfo]) -> String {
+    let attributes = chain
+        .iter()
+        .rev()
+        .find(|d| d.name == def.name || def.name.ends_with(&format!(".{}", d.name)))
+        .map_or(&def.attributes, |d| &d.attributes);
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!("@[{}] ", attributes.join(", "))
+    }
+}
+
+/// Options of the `view` command.
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ViewConfig {
+    /// Summarize the proof status above the goals.
+    pub headline: bool,
+    /// Most hypotheses rendered per goal before the rest collapse into one
+    /// line; `None` renders them all.
+    pub max_hyps: Option<usize>,
+    /// Enable debugging aids such as the raw JSON popup.
+    pub debug: bool,
+    /// Markers and colors of diffed items.
+    pub diff_markers: DiffMarkers,
+    /// Colors of the selection highlight.
+    pub selection_colors: SelectionColors,
+    /// Start with the status bar hidden, giving its row to the content.
+    pub hide_status_bar: bool,
+    /// Right-align goals and hypotheses for right-to-left locales.
+    pub rtl: bool,
+    /// What `Enter` does.
+    pub enter: EnterKey,
+    /// How often to ask again for goals that came back empty inside a
+    /// definition, in case elaboration had not finished; `None` never does.
+    pub refetch_every: Option<Duration>,
+    /// Show how long the server took over the document after the latest
+    /// edit, asking the proxy to time edits.
+    pub elaboration_time: bool,
+}
+
+/// What `Enter` does, chosen with `--enter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnterKey {
+    /// Whatever the focused pane uses it for, such as folding a proof step,
+    /// falling back to goto.
+    #[default]
+    Context,
+    /// Always go to the selection's origin.
+    Goto,
+}
+
+impl EnterKey {
+    /// Behavior named on the command line.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "context" => Ok(Self::Context),
+            "goto" => Ok(Self::Goto),
+            _ => Err(format!("unknown Enter behavior `{name}` (context, goto)")),
+        }
+    }
+
+    /// What the key does, for the keymap.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Context => "fold step, else goto origin",
+            Self::Goto => "goto origin",
+        }
+    }
+}
+
+/// One-line summary of where the proof stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofStatus {
+    Complete,
+    GoalsRemaining(usize),
+    ContainsSorry,
+    Errors,
+}
+
+impl ProofStatus {
+    /// Summarize a proof given the number of `errors` the server reports
+    /// for its document; errors outrank `sorry`, which outranks open goals.
+    pub fn of(dag: Option<&ProofDag>, errors: usize) -> Option<Self> {
+        if errors > 0 {
+            return Some(Self::Errors);
+        }
+        let dag = dag?;
+        Some(if dag.contains_sorry() {
+            Self::ContainsSorry
+        } else {
+            match dag.open_goal_count() {
+                0 => Self::Complete,
+                n => Self::GoalsRemaining(n),
+            }
+        })
+    }
+
+    /// Headline text and its color.
+    pub fn headline(self) -> (String, Color) {
+        match self {
+            Self::Complete => ("Complete ✓".to_string(), Color::Green),
+            Self::GoalsRemaining(1) => ("1 goal remaining".to_string(), Color::Yellow),
+            Self::GoalsRemaining(n) => (format!("{n} goals remaining"), Color::Yellow),
+            Self::ContainsSorry => ("contains sorry ⚠".to_string(), Color::Magenta),
+            Self::Errors => ("errors ✗".to_string(), Color::Red),
+        }
+    }
+}
+
+/// How long a transient message stays in the status line.
+const TRANSIENT_FOR: Duration = Duration::from_secs(2);
+
+/// Inactivity after which the screen is dimmed.
+const IDLE_DIM_AFTER: Duration = Duration::from_mins(5);
+
+/// Goals pinned at another location for comparison.
+struct PinnedState {
+    /// Short description of the pinned location (`file:line`).
+    label: String,
+    state: ProofState,
+}
+
+/// Trouble with the backend, as far as the proxy's messages tell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct BackendHealth {
+    /// The last RPC call failed to reach the server.
+    rpc_failing: bool,
+    /// No backend answers `LeanDag.getProofDag`: the proxy has no RPC
+    /// client, or the file lacks `import LeanDag` in library mode.
+    dag_missing: bool,
+}
+
+impl BackendHealth {
+    /// Learn from an error the proxy broadcast.
+    fn observe_error(&mut self, error: &str) {
+        if error.contains("RPC client unavailable") {
+            self.rpc_failing = true;
+            self.dag_missing = true;
+        } else if error.contains("No RPC method") {
+            self.dag_missing = true;
+        } else if error.contains(RPC_CONNECT) || error.contains("Lost connection") {
+            self.rpc_failing = true;
+        }
+    }
 }
 
 /// Application state.
 #[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct App {
     /// Current cursor position from editor.
     pub cursor: Option<CursorInfo>,
@@ -51,10 +223,18 @@ pub struct App {
     pub definition: Option<DefinitionInfo>,
     /// Current error message.
     pub error: Option<String>,
+    /// Outcome of the last command, cleared when the cursor moves.
+    status: Option<String>,
+    /// Short-lived notice shown over `status`, and when it was posted.
+    transient: Option<(String, Instant)>,
     /// Whether connected to proxy.
     pub connected: bool,
+    /// Whether the RPC session and DAG backend behind the proxy work.
+    health: BackendHealth,
     /// Whether app should exit.
     pub should_exit: bool,
+    /// Options given on the command line.
+    pub config: ViewConfig,
     /// Outgoing commands queue.
     outgoing_commands: Vec<Command>,
     /// Current display mode.
@@ -67,6 +247,55 @@ pub struct App {
     status_bar: StatusBar,
     /// Help menu overlay.
     help_menu: HelpMenu,
+    /// Go-to-symbol prompt and results.
+    symbol_search: SymbolSearch,
+    /// Command palette (`Ctrl-p`).
+    command_palette: CommandPalette,
+    /// Raw JSON of the selected item (`--debug` only).
+    raw_view: RawView,
+    /// Goals pinned for comparison, shown in place of the previous state.
+    comparison: Option<PinnedState>,
+    /// Whether state changed since the last draw.
+    dirty: bool,
+    /// Time of the last message or handled event.
+    last_activity: Option<Instant>,
+    /// Whether the screen is dimmed after inactivity.
+    idle: bool,
+    /// Most open goals seen since the definition changed.
+    max_goals_seen: usize,
+    /// Cursor position the displayed goals were fetched for.
+    fetched_at: Option<Position>,
+    /// Document and version the displayed goals were computed against.
+    shown_version: Option<(Url, DocumentVersion)>,
+    /// The last key was `]` or `[`, in case an `m` follows to make it an
+    /// outline motion.
+    bracket: Option<char>,
+    /// Cursor positions saved with `m<c>`, by register.
+    bookmarks: HashMap<char, (Url, Position)>,
+    /// `m` or `'` waiting for the register it applies to.
+    register_key: Option<char>,
+    /// Modes switched away from, resumed with their scroll and selection.
+    parked_modes: Vec<DisplayMode>,
+    /// Index into `DAG_MODES` of the level of detail goals are fetched at.
+    dag_mode: usize,
+    /// Whether goals are fetched with `pp.explicit`, showing `@f a b`.
+    pp_explicit: bool,
+    /// Presentation mode (`F2`): roomier goals without the surrounding chrome.
+    presentation: bool,
+    /// Whether `b` flipped the status bar from how the config shows it.
+    status_bar_toggled: bool,
+    /// Log of received goal states, with `--record`.
+    pub recorder: Option<Recorder>,
+    /// Animation shown while connecting or waiting for goals.
+    spinner: Spinner,
+    /// When the goals last came back empty inside a definition, until the
+    /// cursor moves; drives `--refetch-every`.
+    empty_goals_since: Option<Instant>,
+    /// Errors among each document's latest diagnostics.
+    diagnostic_errors: HashMap<Url, usize>,
+    /// How long each document took to elaborate after its latest edit,
+    /// with `--elaboration-time`.
+    elaboration_ms: HashMap<Url, u64>,
 }
 
 impl App {
@@ -99,61 +328,394 @@ impl App {
         mem::take(&mut self.outgoing_commands)
     }
 
+    /// Request a redraw on the next loop iteration.
+    pub const fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether a redraw is needed, clearing the flag.
+    pub const fn take_dirty(&mut self) -> bool {
+        mem::replace(&mut self.dirty, false)
+    }
+
+    /// Record activity: wakes the screen from idle and requests a redraw.
+    fn touch(&mut self) {
+        self.last_activity = Some(Instant::now());
+        self.idle = false;
+        self.dirty = true;
+    }
+
+    /// Show `message` in the status line for a moment.
+    fn notify(&mut self, message: impl Into<String>) {
+        self.transient = Some((message.into(), Instant::now()));
+        self.dirty = true;
+    }
+
+    /// Timed housekeeping, run whenever the event loop wakes: animate the
+    /// spinner while waiting, clear expired transient messages and dim the
+    /// screen once idle for long enough.
+    pub fn tick(&mut self, now: Instant) {
+        if self.is_waiting() && self.spinner.tick(now) {
+            self.dirty = true;
+        }
+        if self
+            .transient
+            .as_ref()
+            .is_some_and(|(_, posted)| now.duration_since(*posted) >= TRANSIENT_FOR)
+        {
+            self.transient = None;
+            self.dirty = true;
+        }
+        let inactive = self
+            .last_activity
+            .is_some_and(|last| now.duration_since(last) >= IDLE_DIM_AFTER);
+        if inactive && !self.idle {
+            self.idle = true;
+            self.dirty = true;
+        }
+        self.refetch_if_due(now);
+    }
+
+    /// When [`Self::tick`] next has work to do, if ever: the spinner's next
+    /// frame while waiting, a transient message expiring, the screen
+    /// dimming or a refetch falling due. The event loop sleeps until then.
+    pub fn next_deadline(&self, now: Instant) -> Option<Instant> {
+        let spinner = self
+            .is_waiting()
+            .then(|| self.spinner.next_frame_at().unwrap_or(now));
+        let transient = self.transient.as_ref().map(|(_, posted)| *posted + TRANSIENT_FOR);
+        let dim = self
+            .last_activity
+            .filter(|_| !self.idle)
+            .map(|last| last + IDLE_DIM_AFTER);
+        [spinner, transient, dim, self.refetch_at()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Whether the TUI waits for the proxy or for goals at the cursor.
+    fn is_waiting(&self) -> bool {
+        !self.connected || self.stale_indicator().is_some()
+    }
+
+    /// When goals that came back empty are next asked for again.
+    fn refetch_at(&self) -> Option<Instant> {
+        let every = self.config.refetch_every?;
+        self.cursor.as_ref()?;
+        Some(self.empty_goals_since? + every)
+    }
+
+    /// Ask again for goals that came back empty inside a definition, once
+    /// per `--refetch-every`, until they appear or the cursor moves.
+    fn refetch_if_due(&mut self, now: Instant) {
+        let (Some(at), Some(cursor)) = (self.refetch_at(), &self.cursor) else {
+            return;
+        };
+        if now < at {
+            return;
+        }
+        let refetch = Command::Refetch {
+            uri: cursor.uri.clone(),
+            position: cursor.position,
+        };
+        self.queue_command(refetch);
+        self.empty_goals_since = Some(now);
+    }
+
     /// Handle incoming message from proxy.
     pub fn handle_message(&mut self, msg: Message) {
+        self.touch();
         match msg {
             Message::Connected { server_mode } => {
                 self.connected = true;
                 self.server_mode = server_mode;
+                self.health = BackendHealth::default();
+                if self.config.elaboration_time {
+                    self.queue_command(Command::TimeElaboration { enabled: true });
+                }
             }
             Message::Cursor(cursor) => {
                 self.cursor = Some(cursor);
+                self.empty_goals_since = None;
                 self.connected = true;
                 self.error = None;
+                self.status = None;
             }
             Message::ProofDag {
-                uri: _,
-                position: _,
+                uri,
+                position,
+                version,
                 proof_dag,
             } => {
-                // Extract definition name from the ProofDag
-                let definition_name = proof_dag
+                if self.is_older(&uri, version) {
+                    return;
+                }
+                let current = proof_dag
                     .as_ref()
-                    .and_then(|dag| dag.definition_name.clone());
-
-                self.definition = definition_name.map(|name| DefinitionInfo {
-                    name,
-                    kind: None,
-                    line: None,
+                    .and_then(|dag| dag.get(dag.current_node?));
+                if let (Some(recorder), Some(node)) = (&mut self.recorder, current) {
+                    recorder.record(&uri, position, &node.state_after);
+                }
+                if let Some(version) = version {
+                    self.shown_version = Some((uri, version));
+                }
+                // Extract definition name from the ProofDag
+                let definition = proof_dag.as_ref().and_then(|dag| {
+                    dag.definition_name.clone().map(|name| DefinitionInfo {
+                        kind: None,
+                        name,
+                        line: None,
+                        signature: dag.initial_state.signature(),
+                        attributes: Vec::new(),
+                    })
                 });
+                if definition.as_ref().map(|d| &d.name) != self.definition.as_ref().map(|d| &d.name)
+                {
+                    self.max_goals_seen = 0;
+                }
+                self.definition = definition;
+                if proof_dag.is_some() && self.fetched_at.is_some_and(|at| at != position) {
+                    self.notify("Goals updated");
+                }
                 self.proof_dag = proof_dag;
+                self.fetched_at = Some(position);
+                self.max_goals_seen = self.max_goals_seen.max(self.proof_state().goals.len());
+                self.empty_goals_since = (self.definition.is_some()
+                    && self.proof_state().goals.is_empty())
+                .then(Instant::now);
                 self.connected = true;
+                self.health = BackendHealth::default();
                 self.error = None;
             }
+            Message::Comparison {
+                uri,
+                position,
+                revision,
+                proof_dag,
+            } => self.pin_comparison(&uri, position, revision, proof_dag.as_ref()),
             Message::Error { error } => {
+                self.health.observe_error(&error);
+                if error.to_lowercase().contains("symbol search") {
+                    self.symbol_search.search_failed();
+                }
                 self.error = Some(error);
                 self.connected = true;
             }
+            Message::Symbols { query, symbols } => {
+                self.symbol_search.set_results(query, symbols);
+            }
+            Message::Status { message } => {
+                self.status = Some(message);
+            }
+            Message::DagMode { mode } => {
+                self.dag_mode = DAG_MODES.iter().position(|&m| m == mode).unwrap_or(0);
+            }
+            Message::Explicit { explicit } => self.pp_explicit = explicit,
+            Message::Diagnostics { uri, errors } => {
+                self.diagnostic_errors.insert(uri, errors);
+            }
+            Message::Elaboration { uri, ms } => {
+                self.elaboration_ms.insert(uri, ms);
+            }
+        }
+    }
+
+    /// Show the goals fetched for a pinned position in place of the
+    /// previous state, or an error when there are none.
+    fn pin_comparison(
+        &mut self,
+        uri: &Url,
+        position: Position,
+        revision: Option<String>,
+        proof_dag: Option<&ProofDag>,
+    ) {
+        let state = proof_dag.and_then(|dag| {
+            dag.current_node
+                .and_then(|id| dag.get(id))
+                .map(|node| node.state_after.clone())
+        });
+        let filename = uri_display_name(uri).unwrap_or("?");
+        let mut label = format!("{filename}:{}", position.line + 1);
+        if let Some(revision) = revision {
+            label = format!("{label}@{revision}");
+        }
+        match state {
+            Some(state) => self.comparison = Some(PinnedState { label, state }),
+            None => self.error = Some(format!("No goals at pinned position {label}")),
+        }
+    }
+
+    /// Whether a broadcast is a fetch for an older version than the goals
+    /// shown, finished late. A reopened document starts a new epoch, so its
+    /// versions count again; untracked documents are never older.
+    fn is_older(&self, uri: &Url, version: Option<DocumentVersion>) -> bool {
+        matches!(
+            (&self.shown_version, version),
+            (Some((shown, v)), Some(version)) if shown == uri && version < *v
+        )
+    }
+
+    /// Ask the proxy to reconnect the RPC session for the current document.
+    fn reset_session(&mut self) {
+        if let Some(cursor) = &self.cursor {
+            self.queue_command(Command::ResetSession {
+                uri: cursor.uri.clone(),
+            });
+        }
+    }
+
+    /// Cycle the level of detail of the proof DAG and refetch the goals at
+    /// the cursor with it.
+    fn cycle_dag_mode(&mut self) {
+        let Some(cursor) = &self.cursor else {
+            return;
+        };
+        self.dag_mode = (self.dag_mode + 1) % DAG_MODES.len();
+        self.queue_command(Command::SetDagMode {
+            uri: cursor.uri.clone(),
+            position: cursor.position,
+            mode: DAG_MODES[self.dag_mode].to_string(),
+        });
+    }
+
+    /// Toggle `pp.explicit`, which restarts the server, and refetch the
+    /// goals at the cursor.
+    fn toggle_explicit(&mut self) {
+        let Some(cursor) = &self.cursor else {
+            return;
+        };
+        self.pp_explicit = !self.pp_explicit;
+        self.queue_command(Command::SetExplicit {
+            uri: cursor.uri.clone(),
+            position: cursor.position,
+            explicit: self.pp_explicit,
+        });
+    }
+
+    /// Pin the goals at the cursor for comparison, or clear the pin.
+    fn toggle_comparison(&mut self) {
+        if self.comparison.take().is_some() {
+            return;
+        }
+        if let Some(cursor) = &self.cursor {
+            self.queue_command(Command::CompareWith {
+                uri: cursor.uri.clone(),
+                position: cursor.position,
+            });
+        }
+    }
+
+    /// Pin the goals at the cursor in the file as committed at `HEAD`, or
+    /// clear the pin.
+    fn toggle_head_comparison(&mut self) {
+        if self.comparison.take().is_some() {
+            return;
+        }
+        if let Some(cursor) = &self.cursor {
+            self.queue_command(Command::CompareWithHead {
+                uri: cursor.uri.clone(),
+                position: cursor.position,
+            });
         }
     }
 
     /// Cycle to the next display mode.
     pub fn next_mode(&mut self) {
-        self.display_mode.next();
+        let next = self.display_mode.following();
+        self.switch_mode(']', next);
     }
 
     /// Cycle to the previous display mode.
     pub fn prev_mode(&mut self) {
-        self.display_mode.prev();
+        let prev = self.display_mode.preceding();
+        self.switch_mode('[', prev);
+    }
+
+    /// Switch to `mode` on `]`/`[`, remembering the key in case it starts
+    /// a `]m`/`[m` motion.
+    fn switch_mode(&mut self, bracket: char, mode: DisplayMode) {
+        self.resume_mode(mode);
+        self.bracket = Some(bracket);
+    }
+
+    /// Show the parked instance of `mode`'s kind if there is one, or else
+    /// `mode` itself, parking the mode left.
+    fn resume_mode(&mut self, mode: DisplayMode) {
+        let mode = match self
+            .parked_modes
+            .iter()
+            .position(|parked| parked.name() == mode.name())
+        {
+            Some(idx) => self.parked_modes.swap_remove(idx),
+            None => mode,
+        };
+        let left = mem::replace(&mut self.display_mode, mode);
+        self.parked_modes.push(left);
+    }
+
+    /// Save the cursor under `register` (`m`), or go back to the position
+    /// saved there (`'`).
+    fn use_bookmark(&mut self, prefix: char, register: char) {
+        if prefix == 'm' {
+            if let Some(cursor) = &self.cursor {
+                self.bookmarks
+                    .insert(register, (cursor.uri.clone(), cursor.position));
+                self.status = Some(format!("Bookmark {register} set"));
+            }
+            return;
+        }
+        match self.bookmarks.get(&register).cloned() {
+            Some((uri, position)) => self.navigate(uri, position),
+            None => self.status = Some(format!("No bookmark {register}")),
+        }
+    }
+
+    /// Move the editor to the next (or previous) structural step of the
+    /// proof: a top-level tactic or a `have`.
+    fn jump_outline(&mut self, forward: bool) {
+        let (Some(dag), Some(cursor)) = (&self.proof_dag, &self.cursor) else {
+            return;
+        };
+        let here = (cursor.position.line, cursor.position.character);
+        let outline = dag.outline();
+        let target = if forward {
+            outline.into_iter().find(|p| (p.line, p.character) > here)
+        } else {
+            outline.into_iter().rfind(|p| (p.line, p.character) < here)
+        };
+        match target {
+            Some(position) => {
+                let uri = cursor.uri.clone();
+                self.navigate(uri, position);
+            }
+            None => self.status = Some("No more structural steps".to_string()),
+        }
     }
 
     /// Navigate to where the selected item was introduced.
     /// - Hypotheses: go to binder location (where variable was bound)
     /// - Goals: go to tactic position (where goal was created)
+    /// - Steps: go to the tactic
     pub fn navigate_to_selection(&mut self, selection: Option<Selection>) {
-        let Some(cursor) = &self.cursor else {
-            return;
-        };
+        if let Some((uri, position)) = self.selection_location(selection) {
+            self.navigate(uri, position);
+        }
+    }
+
+    /// Ask the proxy to show where the symbol at the selected item's
+    /// location is implemented.
+    fn goto_implementation(&mut self) {
+        let selection = self.display_mode.current_selection();
+        if let Some((uri, position)) = self.selection_location(selection) {
+            self.queue_command(Command::GotoImplementation { uri, position });
+        }
+    }
+
+    /// Source location of the selected item, falling back to where the goals
+    /// were fetched.
+    fn selection_location(&self, selection: Option<Selection>) -> Option<(Url, Position)> {
+        let cursor = self.cursor.as_ref()?;
 
         let dag = self.proof_dag.as_ref();
         let fallback_pos = self.goals_position().unwrap_or(cursor.position);
@@ -170,16 +732,27 @@ impl App {
                 .and_then(|node| node.state_after.hypotheses.get(hyp_idx))
                 .and_then(|h| h.goto_locations.definition.as_ref())
                 .map(|loc| (loc.uri.clone(), loc.position)),
-            // Goals: use node's tactic position
-            Selection::Goal { node_id, .. } => dag
+            // Goals and steps: use node's tactic position
+            Selection::Goal { node_id, .. } | Selection::Node { node_id } => dag
                 .and_then(|d| d.get(node_id))
                 .map(|node| (cursor.uri.clone(), node.position)),
-            // Theorem: fallback to cursor position
-            Selection::Theorem => None,
+            // Theorem and the hypotheses summary: fallback to cursor position
+            Selection::AllHyps { .. } | Selection::Theorem => None,
         });
 
-        let (uri, position) = goto_location.unwrap_or_else(|| (cursor.uri.clone(), fallback_pos));
-        self.queue_command(Command::Navigate { uri, position });
+        Some(goto_location.unwrap_or_else(|| (cursor.uri.clone(), fallback_pos)))
+    }
+
+    /// Ask the editor to show a location. Locations outside local files
+    /// (e.g. a `lean4://` library document) cannot be opened and are only
+    /// reported.
+    fn navigate(&mut self, uri: Url, position: Position) {
+        if is_local_file(&uri) {
+            self.queue_command(Command::Navigate { uri, position });
+        } else {
+            let name = uri_display_name(&uri).unwrap_or("?");
+            self.notify(format!("{name} is not a local file"));
+        }
     }
 
     /// Get the text of the currently selected item (hypothesis or goal).
@@ -196,10 +769,19 @@ impl App {
                 .get(node_id)
                 .and_then(|node| node.state_after.hypotheses.get(hyp_idx))
                 .map(|h| format!("{} : {}", h.name, h.type_.to_plain_text())),
+            Selection::AllHyps { node_id } => dag.get(node_id).map(|node| {
+                node.state_after
+                    .hypotheses
+                    .iter()
+                    .map(|h| format!("{} : {}", h.name, h.type_.to_plain_text()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }),
             Selection::Goal { node_id, goal_idx } => dag
                 .get(node_id)
                 .and_then(|node| node.state_after.goals.get(goal_idx))
                 .map(|g| g.type_.to_plain_text()),
+            Selection::Node { node_id } => dag.get(node_id).map(|node| node.tactic.text.clone()),
             Selection::Theorem => dag
                 .initial_state
                 .goals
@@ -208,73 +790,159 @@ impl App {
         }
     }
 
+    /// Tagged text of the selected item's type.
+    fn get_selection_type(&self, selection: Option<Selection>) -> Option<&TaggedText> {
+        let dag = self.proof_dag.as_ref()?;
+        let text = match selection? {
+            Selection::InitialHyp { hyp_idx } => &dag.initial_state.hypotheses.get(hyp_idx)?.type_,
+            Selection::Hyp { node_id, hyp_idx } => {
+                &dag.get(node_id)?.state_after.hypotheses.get(hyp_idx)?.type_
+            }
+            Selection::Goal { node_id, goal_idx } => {
+                &dag.get(node_id)?.state_after.goals.get(goal_idx)?.type_
+            }
+            Selection::Theorem => &dag.initial_state.goals.first()?.type_,
+            Selection::AllHyps { .. } | Selection::Node { .. } => return None,
+        };
+        Some(text)
+    }
+
+    /// Pretty-printed `CodeWithInfos` JSON of the selected item's type.
+    fn get_selection_json(&self, selection: Option<Selection>) -> Option<String> {
+        serde_json::to_string_pretty(self.get_selection_type(selection)?).ok()
+    }
+
+    /// Show the raw JSON of the selected item, when debugging.
+    fn open_raw_view(&mut self) {
+        if !self.config.debug {
+            return;
+        }
+        match self.get_selection_json(self.display_mode.current_selection()) {
+            Some(json) => self.raw_view.open(&json),
+            None => self.status = Some("Nothing selected".to_string()),
+        }
+    }
+
+    /// Show the tagged text structure of the selected item, or of the first
+    /// goal when nothing is selected.
+    fn open_tree_view(&mut self) {
+        let lines = self
+            .get_selection_type(self.display_mode.current_selection())
+            .map(TaggedTextExt::to_tree_lines)
+            .or_else(|| Some(self.proof_state().goals.first()?.type_.to_tree_lines()));
+        match lines {
+            Some(lines) => self.raw_view.open_lines("Tagged text", lines),
+            None => self.status = Some("No goal".to_string()),
+        }
+    }
+
     /// Copy the selected item's text to the clipboard.
-    fn copy_selection_to_clipboard(&self) {
+    fn copy_selection_to_clipboard(&mut self) {
         let selection = self.display_mode.current_selection();
         if let Some(text) = self.get_selection_text(selection) {
             let _ = stdout().execute(CopyToClipboard::to_clipboard_from(text));
+            self.notify("Copied");
+        }
+    }
+
+    /// Copy the current goals to the clipboard as a Lean comment.
+    fn copy_goals_as_comment(&mut self) {
+        let comment = goals_to_lean_comment(&self.proof_state());
+        let _ = stdout().execute(CopyToClipboard::to_clipboard_from(comment));
+        self.notify("Copied goals as comment");
+    }
+
+    /// Copy the current goals as Markdown code fences.
+    fn copy_goals_as_markdown(&mut self) {
+        let markdown = goals_to_markdown(&self.proof_state());
+        let _ = stdout().execute(CopyToClipboard::to_clipboard_from(markdown));
+        self.notify("Copied goals as Markdown");
+    }
+
+    /// Open the current goals as HTML in the browser.
+    fn export_html(&mut self) {
+        if let Err(e) = html::open_in_browser(&self.proof_state()) {
+            self.error = Some(format!("HTML export failed: {e}"));
         }
     }
 
     /// Update all components with current state.
     pub fn update(&mut self) {
         self.update_display_mode();
+        let goal_progress = (self.max_goals_seen > 0)
+            .then(|| (self.proof_state().goals.len(), self.max_goals_seen));
+        let elaboration_ms = self.elaboration_ms();
         StatusBarWidget::update_state(
             &mut self.status_bar,
             StatusBarInput {
                 filters: self.display_mode.filters(),
                 keybindings: self.display_mode.keybindings(),
                 supported_filters: self.display_mode.supported_filters(),
+                goal_progress,
+                elaboration_ms,
             },
         );
     }
 
+    /// States for the previous and next columns, derived from the DAG.
+    ///
+    /// Both are filled whether or not their column is shown, so toggling a
+    /// column shows it right away.
+    fn temporal_states(&self) -> (Option<ProofState>, Option<ProofState>) {
+        let dag = self.proof_dag.as_ref();
+        let current_node = dag.and_then(|dag| dag.get(dag.current_node?));
+
+        // A pinned comparison replaces the previous state, diffed locally
+        let previous_state = self.comparison.as_ref().map_or_else(
+            || current_node.map(|n| n.state_before.clone()),
+            |pinned| Some(annotate_before(&pinned.state, &self.proof_state())),
+        );
+        let next_state = current_node
+            .and_then(|n| dag?.next_state(n.id))
+            .cloned();
+        (previous_state, next_state)
+    }
+
     fn update_display_mode(&mut self) {
         self.display_mode.update_open_goal_list(PlainListInput {
             state: self.proof_state(),
             definition: self.definition.clone(),
             error: self.error.clone(),
             proof_dag: self.proof_dag.clone(),
+            max_hyps: self.config.max_hyps,
+            diff_markers: self.config.diff_markers,
+            selection_colors: self.config.selection_colors,
+            presentation: self.presentation,
+            rtl: self.config.rtl,
         });
-        // Derive before/after states from the ProofDag
-        let current_node = self
-            .proof_dag
+        let current_state = self.proof_state();
+        let (previous_state, next_state) = self.temporal_states();
+        let previous_title = self
+            .comparison
             .as_ref()
-            .and_then(|dag| dag.current_node)
-            .and_then(|id| self.proof_dag.as_ref()?.get(id));
-
-        let previous_state = self
-            .display_mode
-            .show_previous()
-            .then(|| current_node.map(|n| n.state_before.clone()))
-            .flatten();
-
-        let next_state = self
-            .display_mode
-            .show_next()
-            .then(|| {
-                current_node
-                    .and_then(|n| n.children.first())
-                    .and_then(|&child_id| self.proof_dag.as_ref()?.get(child_id))
-                    .map(|child| child.state_after.clone())
-            })
-            .flatten();
+            .map(|pinned| format!("Pinned {}", pinned.label));
 
         self.display_mode.update_before_after(BeforeAfterModeInput {
             previous_state,
-            current_state: current_node
-                .map(|n| n.state_after.clone())
-                .unwrap_or_default(),
+            previous_title,
+            current_state,
             next_state,
             definition: self.definition.clone(),
             error: self.error.clone(),
             proof_dag: self.proof_dag.clone(),
+            max_hyps: self.config.max_hyps,
+            diff_markers: self.config.diff_markers,
+            selection_colors: self.config.selection_colors,
+            presentation: self.presentation,
+            rtl: self.config.rtl,
         });
         self.display_mode.update_steps(StepsModeInput {
             state: self.proof_state(),
             definition: self.definition.clone(),
             error: self.error.clone(),
             proof_dag: self.proof_dag.clone(),
+            selection_colors: self.config.selection_colors,
+            diff_markers: self.config.diff_markers,
         });
         self.display_mode
             .update_deduction_tree(DeductionTreeModeInput {
@@ -282,17 +950,45 @@ impl App {
                 definition: self.definition.clone(),
                 error: self.error.clone(),
                 proof_dag: self.proof_dag.clone(),
+                selection_colors: self.config.selection_colors,
             });
     }
 
+    /// Split the frame into the main area and the status bar row, if shown.
+    fn frame_areas(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if self.presentation || self.config.hide_status_bar != self.status_bar_toggled {
+            return (area, None);
+        }
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        (main_area, Some(status_area))
+    }
+
     /// Render the entire UI.
     pub fn render(&mut self, frame: &mut Frame) {
-        let [main_area, status_area] =
-            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let (main_area, status_area) = self.frame_areas(frame.area());
 
         self.render_main(frame, main_area);
-        frame.render_stateful_widget(StatusBarWidget, status_area, &mut self.status_bar);
+        if let Some(status_area) = status_area {
+            frame.render_stateful_widget(StatusBarWidget, status_area, &mut self.status_bar);
+        }
         frame.render_stateful_widget(HelpMenuWidget, frame.area(), &mut self.help_menu);
+        self.symbol_search.set_selection_colors(self.config.selection_colors);
+        self.command_palette.set_selection_colors(self.config.selection_colors);
+        frame.render_stateful_widget(SymbolSearchWidget, frame.area(), &mut self.symbol_search);
+        frame.render_stateful_widget(
+            CommandPaletteWidget,
+            frame.area(),
+            &mut self.command_palette,
+        );
+        frame.render_stateful_widget(RawViewWidget, frame.area(), &mut self.raw_view);
+
+        if self.idle {
+            let area = frame.area();
+            frame
+                .buffer_mut()
+                .set_style(area, Style::new().add_modifier(Modifier::DIM));
+        }
     }
 
     fn render_main(&mut self, frame: &mut Frame, area: Rect) {
@@ -304,15 +1000,28 @@ impl App {
             return;
         }
 
+        if self.presentation && self.connected {
+            self.render_content(frame, area);
+            return;
+        }
+
         let title = self.build_title();
         let mode_name = format!(" {} ", self.display_mode.name());
-        let backend = self.build_backend_display();
+        let mut backend = self.build_health_indicator();
+        backend.push_span(self.build_backend_display());
         let position_info = self.build_position_info();
+        let status = self
+            .transient
+            .as_ref()
+            .map(|(message, _)| message)
+            .or(self.status.as_ref())
+            .map_or_else(String::new, |status| format!(" {status} "));
 
         let block = Block::bordered()
             .title(title)
             .title_top(Line::from(mode_name).right_aligned())
-            .title_bottom(Line::from(backend).left_aligned())
+            .title_bottom(backend.left_aligned())
+            .title_bottom(Line::from(status).centered())
             .title_bottom(Line::from(position_info).right_aligned())
             .border_style(Style::new().fg(Color::Cyan));
 
@@ -321,20 +1030,139 @@ impl App {
 
         if !self.connected {
             frame.render_widget(
-                Paragraph::new(format!("Connecting to {}...", socket_path().display())),
+                Paragraph::new(format!(
+                    "{} Connecting to {}...",
+                    self.spinner.frame(),
+                    socket_path().display()
+                )),
                 inner,
             );
             return;
         }
 
-        self.display_mode.render(frame, inner);
+        self.render_content(frame, inner);
+    }
+
+    /// Render the headline, signature and goals inside the frame.
+    fn render_content(&mut self, frame: &mut Frame, area: Rect) {
+        let area = self.render_headline(frame, area);
+        let area = self.render_signature(frame, area);
+        if !self.render_statement_goal(frame, area) {
+            self.display_mode.render(frame, area);
+        }
+    }
+
+    /// Before the proof starts there are no goals to list, only the
+    /// statement's expected type. Returns whether that was rendered.
+    fn render_statement_goal(&self, frame: &mut Frame, area: Rect) -> bool {
+        let (Some(dag), Some(cursor)) = (&self.proof_dag, &self.cursor) else {
+            return false;
+        };
+        let Some(goal) = dag.statement_goal(cursor.position) else {
+            return false;
+        };
+        let lines = vec![
+            Line::styled(
+                "Statement — not yet in proof",
+                Style::new().fg(Color::DarkGray),
+            ),
+            Line::from(vec![
+                Span::styled("⊢ ", Style::new().fg(Color::Cyan)),
+                Span::raw(goal.type_.to_plain_text()),
+            ]),
+        ];
+        frame.render_widget(Paragraph::new(lines), area);
+        true
+    }
+
+    /// How long the cursor's document took to elaborate after its latest
+    /// edit, when the proxy timed it.
+    fn elaboration_ms(&self) -> Option<u64> {
+        let cursor = self.cursor.as_ref()?;
+        self.elaboration_ms.get(&cursor.uri).copied()
+    }
+
+    /// Where the proof stands, with the errors the server reports for the
+    /// cursor's document.
+    fn proof_status(&self) -> Option<ProofStatus> {
+        let errors = self
+            .cursor
+            .as_ref()
+            .and_then(|cursor| self.diagnostic_errors.get(&cursor.uri))
+            .copied()
+            .unwrap_or_default();
+        ProofStatus::of(self.proof_dag.as_ref(), errors)
+    }
+
+    /// Render the proof status headline on the first line, returning the rest.
+    fn render_headline(&self, frame: &mut Frame, area: Rect) -> Rect {
+        if !self.config.headline {
+            return area;
+        }
+        let Some(status) = self.proof_status() else {
+            return area;
+        };
+
+        let [header, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        let (text, color) = status.headline();
+        frame.render_widget(
+            Paragraph::new(text)
+                .centered()
+                .style(Style::new().fg(color).add_modifier(Modifier::BOLD)),
+            header,
+        );
+        rest
+    }
+
+    /// Render the definition's signature on the first line, returning the rest.
+    fn render_signature(&self, frame: &mut Frame, area: Rect) -> Rect {
+        let Some(def) = &self.definition else {
+            return area;
+        };
+        let Some(signature) = &def.signature else {
+            return area;
+        };
+
+        let [header, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        let line = Line::from(vec![
+            Span::styled(def.name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+            Span::raw(" "),
+            Span::styled(signature.clone(), Style::new().fg(Color::DarkGray)),
+        ]);
+        frame.render_widget(Paragraph::new(line), header);
+        rest
     }
 
     fn build_title(&self) -> String {
+        let mut title = self.build_location_title();
+        if self.pp_explicit {
+            title.push_str("[explicit] ");
+        }
+        match self.last_tactic() {
+            Some(tactic) => format!("{title}after: {tactic} "),
+            None => title,
+        }
+    }
+
+    /// Text of the tactic that produced the current goals, if the DAG knows it.
+    fn last_tactic(&self) -> Option<&str> {
+        let dag = self.proof_dag.as_ref()?;
+        let node = dag.get(dag.current_node?)?;
+        Some(node.tactic.text.as_str()).filter(|text| !text.is_empty())
+    }
+
+    fn build_location_title(&self) -> String {
         if let (Some(def), Some(cursor)) = (&self.definition, &self.cursor) {
             let filename = cursor.filename().unwrap_or("?");
             let kind = def.kind.as_deref().unwrap_or("proof");
-            format!(" {kind} {} ({}) ", def.name, filename)
+            format!(
+                " {}{kind} {} ({}) ",
+                attribute_prefix(def, &cursor.definitions),
+                breadcrumb(def, &cursor.definitions),
+                filename
+            )
         } else if let Some(cursor) = &self.cursor {
             let filename = cursor.filename().unwrap_or("?");
             format!(" lean-tui [{}] ({}) ", self.display_mode.name(), filename)
@@ -346,7 +1174,8 @@ impl App {
     fn build_position_info(&self) -> String {
         self.cursor.as_ref().map_or(String::new(), |cursor| {
             format!(
-                " {}:{} ({}) ",
+                " {}{}:{} ({}) ",
+                self.stale_indicator().unwrap_or_default(),
                 cursor.position.line + 1,
                 cursor.position.character + 1,
                 cursor.method
@@ -354,60 +1183,1062 @@ impl App {
         })
     }
 
-    fn build_backend_display(&self) -> String {
-        match self.server_mode {
-            Some(mode) => format!(" {} ", mode.display_name()),
-            None if self.proof_dag.is_some() => " Server ".to_string(),
-            None => String::new(),
+    /// Where the displayed goals came from, when the cursor has moved on
+    /// before they were refetched.
+    fn stale_indicator(&self) -> Option<String> {
+        let fetched_at = self.fetched_at?;
+        (fetched_at != self.cursor.as_ref()?.position)
+            .then(|| {
+                format!(
+                    "{} {}:{} ",
+                    self.spinner.frame(),
+                    fetched_at.line + 1,
+                    fetched_at.character + 1
+                )
+            })
+    }
+
+    /// One dot each for the proxy connection, the RPC session and the DAG
+    /// backend: green `●` when up, red `○` when down.
+    fn build_health_indicator(&self) -> Line<'static> {
+        let checks = [
+            self.connected,
+            !self.health.rpc_failing,
+            !self.health.dag_missing,
+        ];
+        let mut line = Line::from(" ");
+        for up in checks {
+            line.push_span(if up {
+                Span::styled("●", Style::new().fg(Color::Green))
+            } else {
+                Span::styled("○", Style::new().fg(Color::Red))
+            });
         }
+        line
+    }
+
+    fn build_backend_display(&self) -> String {
+        let backend = match self.server_mode {
+            Some(mode) => mode.display_name(),
+            None if self.proof_dag.is_some() => "Server",
+            None => return String::new(),
+        };
+        format!(" {backend} · {} ", DAG_MODES[self.dag_mode])
     }
 
-    /// Handle crossterm events.
+    /// Handle crossterm events. Only events that change something request a
+    /// redraw.
     pub fn handle_event(&mut self, event: &Event) {
-        match event {
+        let handled = match event {
             Event::Key(key) if key.kind == KeyEventKind::Press => {
-                if HelpMenuWidget::handle_event(&mut self.help_menu, *key) {
-                    return;
-                }
-                if !self.handle_global_key(key.code) {
-                    self.display_mode.handle_event(KeyMouseEvent::Key(*key));
-                }
-            }
-            Event::Mouse(mouse) => {
-                self.display_mode.handle_event(KeyMouseEvent::Mouse(*mouse));
+                HelpMenuWidget::handle_event(&mut self.help_menu, *key)
+                    || RawViewWidget::handle_event(&mut self.raw_view, *key)
+                    || self.handle_palette_key(*key)
+                    || self.handle_symbol_search_key(*key)
+                    || (self.display_mode.awaits_key()
+                        && self.display_mode.handle_event(KeyMouseEvent::Key(*key)))
+                    || self.handle_global_key(*key)
+                    || self.display_mode.handle_event(KeyMouseEvent::Key(*key))
             }
-            _ => {}
+            Event::Mouse(mouse) => self.display_mode.handle_event(KeyMouseEvent::Mouse(*mouse)),
+            Event::Resize(..) => true,
+            _ => false,
+        };
+        if handled {
+            self.touch();
         }
     }
 
-    fn handle_global_key(&mut self, code: KeyCode) -> bool {
-        match code {
-            KeyCode::Char('q') => {
-                self.should_exit = true;
-                true
-            }
-            KeyCode::Char('?') => {
-                self.help_menu.toggle();
-                true
+    /// Keys go to the symbol search while it is open.
+    fn handle_symbol_search_key(&mut self, key: KeyEvent) -> bool {
+        if !SymbolSearchWidget::handle_event(&mut self.symbol_search, key) {
+            return false;
+        }
+        match self.symbol_search.take_action() {
+            Some(SymbolAction::Search(query)) => self.queue_command(Command::FindSymbol { query }),
+            Some(SymbolAction::Goto(location)) => self.navigate(location.uri, location.position),
+            None => {}
+        }
+        true
+    }
+
+    /// Complete a two-key sequence: a bookmark register after `m` or `'`,
+    /// or `m` after a bracket.
+    fn handle_pending_prefix(&mut self, code: KeyCode) -> bool {
+        if let Some(prefix) = self.register_key.take() {
+            if let KeyCode::Char(register) = code {
+                self.use_bookmark(prefix, register);
             }
-            KeyCode::Char(']') => {
-                self.next_mode();
+            return true;
+        }
+        if let (KeyCode::Char('m'), Some(bracket)) = (code, self.bracket.take()) {
+            // Undo the mode switch of the bracket
+            let forward = bracket == ']';
+            let left = if forward {
+                self.display_mode.preceding()
+            } else {
+                self.display_mode.following()
+            };
+            self.resume_mode(left);
+            self.jump_outline(forward);
+            return true;
+        }
+        false
+    }
+
+    fn handle_global_key(&mut self, key: KeyEvent) -> bool {
+        let plain = !key.modifiers.contains(KeyModifiers::CONTROL);
+        if plain && self.handle_pending_prefix(key.code) {
+            return true;
+        }
+        if key.code == KeyCode::Enter
+            && self.config.enter == EnterKey::Context
+            && self.display_mode.handle_event(KeyMouseEvent::Key(key))
+        {
+            return true;
+        }
+        match key.code {
+            KeyCode::Char(prefix @ ('m' | '\'')) if plain => {
+                self.register_key = Some(prefix);
                 true
             }
-            KeyCode::Char('[') => {
-                self.prev_mode();
+            KeyCode::Char(c @ '1'..='9') if plain => {
+                // Numbers beyond the goal count are ignored
+                let goal_idx = c as usize - '1' as usize;
+                self.display_mode.select_goal(goal_idx);
                 true
             }
-            KeyCode::Char('g') | KeyCode::Enter => {
+            _ => Action::for_key(key).is_some_and(|action| self.perform(action)),
+        }
+    }
+
+    /// Run an action chosen by key or from the command palette. Returns
+    /// whether it was handled.
+    fn perform(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => self.should_exit = true,
+            Action::ToggleHelp => self.help_menu.toggle(),
+            Action::NextMode => self.next_mode(),
+            Action::PrevMode => self.prev_mode(),
+            Action::GotoSelection => {
                 let selection = self.display_mode.current_selection();
                 self.navigate_to_selection(selection);
-                true
             }
-            KeyCode::Char('y') => {
-                self.copy_selection_to_clipboard();
-                true
+            Action::CopySelection => self.copy_selection_to_clipboard(),
+            Action::CopyGoalsAsComment => self.copy_goals_as_comment(),
+            Action::CopyGoalsAsMarkdown => self.copy_goals_as_markdown(),
+            Action::ToggleComparison => self.toggle_comparison(),
+            Action::CompareWithHead => self.toggle_head_comparison(),
+            Action::ExportHtml => self.export_html(),
+            Action::ToggleExplicit => self.toggle_explicit(),
+            Action::CycleDagMode => self.cycle_dag_mode(),
+            Action::ToggleStatusBar => self.status_bar_toggled = !self.status_bar_toggled,
+            Action::TogglePresentation => self.presentation = !self.presentation,
+            Action::RawJson => self.open_raw_view(),
+            Action::TaggedTextTree => self.open_tree_view(),
+            Action::GotoImplementation => self.goto_implementation(),
+            Action::FindSymbol => self.symbol_search.open(),
+            Action::ResetSession => self.reset_session(),
+            Action::CancelFetch => self.queue_command(Command::CancelFetch),
+            Action::CommandPalette => self.open_command_palette(),
+            Action::ModeKey(c) => {
+                let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                return self.display_mode.handle_event(KeyMouseEvent::Key(key));
             }
-            _ => false,
         }
+        true
+    }
+
+    /// Offer the global actions and the current mode's own keys.
+    fn open_command_palette(&mut self) {
+        let mode_keys = self
+            .display_mode
+            .keybindings()
+            .iter()
+            .filter_map(|(key, description)| {
+                let mut chars = key.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    return None;
+                };
+                Some(PaletteEntry {
+                    action: Action::ModeKey(c),
+                    key: c.to_string(),
+                    description: format!("{description} ({})", self.display_mode.name()),
+                })
+            });
+        let entries = Action::GLOBAL
+            .iter()
+            .copied()
+            .map(PaletteEntry::global)
+            .chain(mode_keys)
+            .collect();
+        self.command_palette.open(entries);
+    }
+
+    /// Keys go to the command palette while it is open.
+    fn handle_palette_key(&mut self, key: KeyEvent) -> bool {
+        if !CommandPaletteWidget::handle_event(&mut self.command_palette, key) {
+            return false;
+        }
+        if let Some(action) = self.command_palette.take_action() {
+            self.perform(action);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo, ProofDagNode, TaggedText};
+
+    fn goal(text: &str) -> GoalInfo {
+        GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn app_with_state(state: ProofState) -> App {
+        app_with_node(ProofDagNode {
+            state_after: state,
+            ..Default::default()
+        })
+    }
+
+    fn app_with_node(node: ProofDagNode) -> App {
+        let dag = ProofDag {
+            nodes: vec![node],
+            root: Some(0),
+            current_node: Some(0),
+            ..Default::default()
+        };
+        let mut app = App::default();
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            Url::parse("file:///tmp/Test.lean").unwrap(),
+            Position::default(),
+            "test",
+        )));
+        app.handle_message(Message::ProofDag {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::default(),
+            version: None,
+            proof_dag: Some(dag),
+        });
+        app.update();
+        app
+    }
+
+    fn press(app: &mut App, c: char) {
+        app.handle_event(&Event::Key(KeyEvent::new(
+            KeyCode::Char(c),
+            KeyModifiers::NONE,
+        )));
+        app.update();
+    }
+
+    /// Events that change nothing do not request a redraw.
+    #[test]
+    fn test_noop_event_keeps_clean() {
+        let mut app = App::default();
+        assert!(!app.take_dirty());
+
+        app.handle_event(&Event::FocusGained);
+        assert!(!app.take_dirty());
+
+        app.handle_event(&Event::Resize(80, 24));
+        assert!(app.take_dirty());
+        assert!(!app.take_dirty(), "taking the flag clears it");
+    }
+
+    /// Pressing a number selects the matching goal's target.
+    #[test]
+    fn test_number_key_selects_goal() {
+        let mut app = app_with_state(ProofState {
+            goals: vec![goal("p"), goal("q")],
+            hypotheses: Vec::new(),
+        });
+
+        press(&mut app, '2');
+        assert_eq!(
+            app.display_mode.current_selection(),
+            Some(Selection::Goal {
+                node_id: 0,
+                goal_idx: 1
+            })
+        );
+
+        // Numbers beyond the goal count leave the selection alone
+        press(&mut app, '5');
+        assert_eq!(
+            app.display_mode.current_selection(),
+            Some(Selection::Goal {
+                node_id: 0,
+                goal_idx: 1
+            })
+        );
+    }
+
+    /// The header names the tactic that produced the current goals.
+    #[test]
+    fn test_title_shows_last_tactic() {
+        let mut node = ProofDagNode::default();
+        node.tactic.text = "intro h".to_string();
+        let app = app_with_node(node);
+        assert!(app.build_title().contains("after: intro h"));
+
+        assert!(!App::default().build_title().contains("after:"));
+    }
+
+    /// The goal gauge remembers the most goals seen, per definition.
+    #[test]
+    fn test_max_goals_resets_on_definition_change() {
+        let send = |app: &mut App, name: &str, goal_count: usize| {
+            let node = ProofDagNode {
+                state_after: ProofState {
+                    goals: (0..goal_count).map(|_| goal("p")).collect(),
+                    hypotheses: Vec::new(),
+                },
+                ..Default::default()
+            };
+            app.handle_message(Message::ProofDag {
+                uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+                position: Position::default(),
+                version: None,
+                proof_dag: Some(ProofDag {
+                    nodes: vec![node],
+                    root: Some(0),
+                    current_node: Some(0),
+                    definition_name: Some(name.to_string()),
+                    ..Default::default()
+                }),
+            });
+        };
+        let mut app = App::default();
+
+        send(&mut app, "foo", 3);
+        send(&mut app, "foo", 1);
+        assert_eq!(app.max_goals_seen, 3);
+
+        send(&mut app, "bar", 2);
+        assert_eq!(app.max_goals_seen, 2);
+    }
+
+    /// Each proof status wins over the ones below it.
+    #[test]
+    fn test_proof_status_variants() {
+        let leaf = |goals: usize, tactic: &str| {
+            let mut node = ProofDagNode {
+                state_after: ProofState {
+                    goals: (0..goals).map(|_| goal("p")).collect(),
+                    hypotheses: Vec::new(),
+                },
+                ..Default::default()
+            };
+            node.tactic.text = tactic.to_string();
+            node
+        };
+        let dag = |nodes| ProofDag {
+            nodes,
+            ..Default::default()
+        };
+
+        let complete = dag(vec![leaf(0, "simp")]);
+        let open = dag(vec![leaf(2, "constructor")]);
+        let sorry = dag(vec![leaf(0, "sorry")]);
+
+        assert_eq!(ProofStatus::of(None, 0), None);
+        assert_eq!(ProofStatus::of(Some(&complete), 0), Some(ProofStatus::Complete));
+        assert_eq!(ProofStatus::of(Some(&open), 0), Some(ProofStatus::GoalsRemaining(2)));
+        assert_eq!(ProofStatus::of(Some(&sorry), 0), Some(ProofStatus::ContainsSorry));
+        assert_eq!(ProofStatus::of(Some(&sorry), 1), Some(ProofStatus::Errors));
+    }
+
+    /// Only errors the server reports for the cursor's document count; a
+    /// failed command or another file's errors leave the status alone.
+    #[test]
+    fn test_proof_status_errors_from_document() {
+        let mut app = app_with_state(ProofState::default());
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        app.handle_message(Message::Error {
+            error: "HTML export failed: no browser".to_string(),
+        });
+        app.handle_message(Message::Diagnostics {
+            uri: Url::parse("file:///tmp/Other.lean").unwrap(),
+            errors: 3,
+        });
+        assert_eq!(app.proof_status(), Some(ProofStatus::Complete));
+
+        app.handle_message(Message::Diagnostics { uri: uri.clone(), errors: 1 });
+        assert_eq!(app.proof_status(), Some(ProofStatus::Errors));
+        app.handle_message(Message::Diagnostics { uri, errors: 0 });
+        assert_eq!(app.proof_status(), Some(ProofStatus::Complete));
+    }
+
+    /// The "all hypotheses" row comes first and copies every hypothesis.
+    #[test]
+    fn test_all_hyps_selection_copies_context() {
+        let hyp = |name: &str, ty: &str| HypothesisInfo {
+            name: name.to_string(),
+            type_: TaggedText::Text {
+                text: ty.to_string(),
+            },
+            ..Default::default()
+        };
+        let mut app = app_with_state(ProofState {
+            goals: vec![goal("a = b")],
+            hypotheses: vec![hyp("a", "Nat"), hyp("h", "a = b")],
+        });
+        // From the tableau to the plain list
+        app.next_mode();
+        app.update();
+
+        let selection = app.display_mode.current_selection();
+        assert_eq!(selection, Some(Selection::AllHyps { node_id: 0 }));
+        assert_eq!(
+            app.get_selection_text(selection).as_deref(),
+            Some("a : Nat\nh : a = b")
+        );
+    }
+
+    /// `Ctrl-c` asks to cancel the fetch; the reply keeps the goals shown.
+    #[test]
+    fn test_cancel_keeps_previous_goals() {
+        let mut app = app_with_state(ProofState {
+            goals: vec![goal("p")],
+            hypotheses: Vec::new(),
+        });
+        app.take_commands();
+
+        app.handle_event(&Event::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        )));
+        assert!(matches!(app.take_commands()[..], [Command::CancelFetch]));
+        assert!(!app.should_exit);
+
+        app.handle_message(Message::Status {
+            message: "Cancelled".to_string(),
+        });
+        assert_eq!(app.status.as_deref(), Some("Cancelled"));
+        assert_eq!(app.proof_state().goals.len(), 1);
+    }
+
+    /// Goals fetched for an older cursor position are flagged with theirs.
+    #[test]
+    fn test_stale_goals_indicator() {
+        let mut app = app_with_state(ProofState::default());
+        assert_eq!(app.build_position_info(), " 1:1 (test) ");
+
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            Url::parse("file:///tmp/Test.lean").unwrap(),
+            Position::new(4, 2),
+            "test",
+        )));
+        let frame = app.spinner.frame();
+        assert_eq!(app.build_position_info(), format!(" {frame} 1:1 5:3 (test) "));
+    }
+
+    /// Both temporal slots are filled on update, whichever columns are
+    /// shown.
+    #[test]
+    fn test_temporal_slots_prefetched() {
+        let mut app = App::default();
+        app.handle_message(Message::ProofDag {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::default(),
+            version: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![
+                    ProofDagNode {
+                        state_before: ProofState {
+                            goals: vec![goal("p ∧ q")],
+                            hypotheses: Vec::new(),
+                        },
+                        children: vec![1],
+                        ..Default::default()
+                    },
+                    ProofDagNode {
+                        state_after: ProofState {
+                            goals: vec![goal("q")],
+                            hypotheses: Vec::new(),
+                        },
+                        ..Default::default()
+                    },
+                ],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+        });
+        app.update();
+
+        let (previous, next) = app.temporal_states();
+        assert_eq!(previous.unwrap().goals[0].type_.to_plain_text(), "p ∧ q");
+        assert_eq!(next.unwrap().goals[0].type_.to_plain_text(), "q");
+    }
+
+    /// `P` pins the goals at the cursor; the reply replaces the previous
+    /// state, diffed against the live goals, and a reply without goals is
+    /// reported instead of pinned.
+    #[test]
+    fn test_pinned_comparison_annotated() {
+        let hyp = |ty: &str| HypothesisInfo {
+            name: "h".to_string(),
+            type_: TaggedText::Text {
+                text: ty.to_string(),
+            },
+            ..Default::default()
+        };
+        let mut app = app_with_state(ProofState {
+            goals: vec![goal("a = b")],
+            hypotheses: vec![hyp("0 < n")],
+        });
+        app.take_commands();
+        press(&mut app, 'P');
+        assert!(matches!(
+            app.take_commands()[..],
+            [Command::CompareWith { .. }]
+        ));
+
+        let comparison = |state: Option<ProofState>| Message::Comparison {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::new(4, 0),
+            revision: None,
+            proof_dag: state.map(|state| ProofDag {
+                nodes: vec![ProofDagNode {
+                    state_after: state,
+                    ..Default::default()
+                }],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+        };
+        app.handle_message(comparison(Some(ProofState {
+            goals: vec![goal("c = d")],
+            hypotheses: vec![hyp("n ≠ 0")],
+        })));
+        assert_eq!(
+            app.comparison.as_ref().map(|pinned| pinned.label.as_str()),
+            Some("Test.lean:5")
+        );
+        let previous = app.temporal_states().0.unwrap();
+        assert!(previous.goals[0].is_removed);
+        assert!(!previous.hypotheses[0].is_removed);
+        assert!(previous.hypotheses[0].type_.has_any_diff());
+
+        press(&mut app, 'P');
+        assert!(app.comparison.is_none());
+        app.handle_message(comparison(None));
+        assert!(app.comparison.is_none());
+        assert_eq!(app.error.as_deref(), Some("No goals at pinned position Test.lean:5"));
+    }
+
+    /// `V` asks for the goals at `HEAD`; once they arrive they replace the
+    /// previous state, with what the working copy no longer has marked
+    /// removed.
+    #[test]
+    fn test_head_comparison_diffed_locally() {
+        let mut app = app_with_state(ProofState {
+            goals: vec![goal("a = b")],
+            hypotheses: Vec::new(),
+        });
+        app.take_commands();
+        press(&mut app, 'V');
+        assert!(matches!(
+            app.take_commands()[..],
+            [Command::CompareWithHead { .. }]
+        ));
+
+        app.handle_message(Message::Comparison {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::default(),
+            revision: Some("HEAD".to_string()),
+            proof_dag: Some(ProofDag {
+                nodes: vec![ProofDagNode {
+                    state_after: ProofState {
+                        goals: vec![goal("a = b"), goal("c = d")],
+                        hypotheses: Vec::new(),
+                    },
+                    ..Default::default()
+                }],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+        });
+        assert_eq!(
+            app.comparison.as_ref().map(|pinned| pinned.label.as_str()),
+            Some("Test.lean:1@HEAD")
+        );
+        let (previous, _) = app.temporal_states();
+        let removed: Vec<bool> = previous.unwrap().goals.iter().map(|g| g.is_removed).collect();
+        assert_eq!(removed, [false, true]);
+
+        press(&mut app, 'V');
+        assert!(app.comparison.is_none());
+        assert!(app.take_commands().is_empty());
+    }
+
+    /// With `--debug`, `R` shows the selected goal's tagged text including
+    /// the `info` the server attached.
+    #[test]
+    fn test_raw_view_shows_info_json() {
+        let type_: TaggedText = serde_json::from_value(serde_json::json!({
+            "kind": "tag",
+            "info": { "subexprPos": "/1", "info": { "p": "7" } },
+            "content": { "kind": "text", "text": "p" }
+        }))
+        .unwrap();
+        let mut app = app_with_state(ProofState {
+            goals: vec![GoalInfo {
+                type_,
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        });
+        press(&mut app, '1');
+
+        press(&mut app, 'R');
+        assert_eq!(app.raw_view.text(), None, "only with --debug");
+
+        app.config.debug = true;
+        press(&mut app, 'R');
+        let json = app.raw_view.text().unwrap();
+        assert!(json.contains(r#""subexprPos": "/1""#), "{json}");
+        assert!(json.contains(r#""p": "7""#), "{json}");
+    }
+
+    /// `]m` jumps to the next `have` or top-level tactic, skipping nested
+    /// steps, and leaves the display mode alone.
+    #[test]
+    fn test_outline_motion_targets_structural_tactic() {
+        let step = |id: u32, line, character, text: &str| {
+            let mut node = ProofDagNode {
+                id,
+                position: Position::new(line, character),
+                ..Default::default()
+            };
+            node.tactic.text = text.to_string();
+            node
+        };
+        let mut app = App::default();
+        app.handle_message(Message::ProofDag {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::default(),
+            version: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![
+                    step(0, 1, 2, "constructor"),
+                    step(1, 2, 4, "simp"),
+                    step(2, 3, 4, "have h : p := hp"),
+                    step(3, 5, 2, "exact h"),
+                ],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+        });
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            Url::parse("file:///tmp/Test.lean").unwrap(),
+            Position::new(1, 2),
+            "test",
+        )));
+        let mode = app.display_mode.name();
+
+        press(&mut app, ']');
+        press(&mut app, 'm');
+        assert_eq!(app.display_mode.name(), mode);
+        assert!(matches!(
+            app.take_commands()[..],
+            [Command::Navigate { position, .. }] if position == Position::new(3, 4)
+        ));
+
+        // Nothing structural precedes the first tactic
+        press(&mut app, '[');
+        press(&mut app, 'm');
+        assert!(app.take_commands().is_empty());
+        assert_eq!(app.status.as_deref(), Some("No more structural steps"));
+    }
+
+    /// A selected proof step navigates to its tactic.
+    #[test]
+    fn test_selected_node_navigates_to_tactic() {
+        let mut app = app_with_node(ProofDagNode {
+            position: Position::new(4, 2),
+            ..Default::default()
+        });
+        app.take_commands();
+
+        app.navigate_to_selection(Some(Selection::Node { node_id: 0 }));
+        assert!(matches!(
+            app.take_commands()[..],
+            [Command::Navigate { position, .. }] if position == Position::new(4, 2)
+        ));
+    }
+
+    /// With the status bar hidden, the main area gets the full frame height.
+    #[test]
+    fn test_hidden_status_bar_frees_row() {
+        let mut app = App::default();
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(app.frame_areas(area).0.height, 23);
+
+        press(&mut app, 'b');
+        let (main_area, status_area) = app.frame_areas(area);
+        assert_eq!(main_area, area);
+        assert!(status_area.is_none());
+
+        app.config.hide_status_bar = true;
+        assert_eq!(app.frame_areas(area).0.height, 23, "b shows it again");
+    }
+
+    /// A mode switched away from and back to keeps its selected step.
+    #[test]
+    fn test_mode_switch_keeps_state() {
+        let mut app = app_with_node(ProofDagNode {
+            children: vec![1],
+            ..Default::default()
+        });
+        let mut dag = app.proof_dag.clone().unwrap();
+        dag.nodes.push(ProofDagNode {
+            id: 1,
+            parent: Some(0),
+            depth: 1,
+            ..Default::default()
+        });
+        app.proof_dag = Some(dag);
+        press(&mut app, '[');
+        assert_eq!(app.display_mode.name(), "Tactic tree");
+        press(&mut app, 'j');
+        let selected = app.display_mode.current_selection();
+        assert_eq!(selected, Some(Selection::Node { node_id: 1 }));
+
+        press(&mut app, ']');
+        press(&mut app, '[');
+        assert_eq!(app.display_mode.current_selection(), selected);
+    }
+
+    /// `X` asks for a restart with `pp.explicit`, shown in the title until a
+    /// failed restart turns it off again.
+    #[test]
+    fn test_toggle_explicit_sends_option() {
+        let mut app = app_with_state(ProofState::default());
+        app.take_commands();
+
+        press(&mut app, 'X');
+        assert!(matches!(
+            &app.take_commands()[..],
+            [Command::SetExplicit { explicit: true, .. }]
+        ));
+        assert!(app.build_title().contains("[explicit]"));
+
+        app.handle_message(Message::Explicit { explicit: false });
+        assert!(!app.build_title().contains("[explicit]"));
+    }
+
+    /// With `--elaboration-time`, every connection asks the proxy to time
+    /// edits, and the timing of the cursor's document reaches the status
+    /// bar; without it, nothing is asked for.
+    #[test]
+    fn test_elaboration_time_opt_in() {
+        let connected = Message::Connected { server_mode: None };
+        let mut app = app_with_state(ProofState::default());
+        app.handle_message(connected.clone());
+        assert!(!app
+            .take_commands()
+            .iter()
+            .any(|cmd| matches!(cmd, Command::TimeElaboration { .. })));
+
+        app.config.elaboration_time = true;
+        app.handle_message(connected);
+        assert!(matches!(
+            &app.take_commands()[..],
+            [Command::TimeElaboration { enabled: true }]
+        ));
+
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            uri.clone(),
+            Position::new(0, 0),
+            "test",
+        )));
+        app.handle_message(Message::Elaboration {
+            uri: Url::parse("file:///tmp/Other.lean").unwrap(),
+            ms: 300,
+        });
+        assert_eq!(app.elaboration_ms(), None);
+        app.handle_message(Message::Elaboration { uri, ms: 120 });
+        assert_eq!(app.elaboration_ms(), Some(120));
+    }
+
+    /// `D` asks for the next DAG mode, and a rejection resets it.
+    #[test]
+    fn test_cycle_dag_mode_sends_mode() {
+        let mut app = app_with_state(ProofState::default());
+        app.take_commands();
+
+        press(&mut app, 'D');
+        assert!(matches!(
+            &app.take_commands()[..],
+            [Command::SetDagMode { mode, .. }] if mode == "tactics-only"
+        ));
+        assert_eq!(app.build_backend_display(), " Server · tactics-only ");
+
+        app.handle_message(Message::DagMode {
+            mode: "tree".to_string(),
+        });
+        assert_eq!(app.build_backend_display(), " Server · tree ");
+    }
+
+    /// A file without `import LeanDag` turns the DAG backend dot red,
+    /// and the next goals turn it green again.
+    #[test]
+    fn test_health_indicator_degraded() {
+        let mut app = app_with_state(ProofState::default());
+        assert_eq!(app.build_health_indicator().to_string(), " ●●●");
+
+        app.handle_message(Message::Error {
+            error: "RPC error -32601: $/lean/rpc/call failed: No RPC method \
+                    'LeanDag.getProofDag' found"
+                .to_string(),
+        });
+        let indicator = app.build_health_indicator();
+        assert_eq!(indicator.to_string(), " ●●○");
+        assert_eq!(indicator.spans[3].style.fg, Some(Color::Red));
+
+        app.handle_message(Message::ProofDag {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::new(0, 0),
+            version: None,
+            proof_dag: None,
+        });
+        assert_eq!(app.build_health_indicator().to_string(), " ●●●");
+    }
+
+    /// A broadcast for an older document version than the one shown is
+    /// dropped, even when it arrives last.
+    #[test]
+    fn test_older_version_broadcast_dropped() {
+        let dag = |target: &str| ProofDag {
+            nodes: vec![ProofDagNode {
+                state_after: ProofState {
+                    goals: vec![goal(target)],
+                    hypotheses: Vec::new(),
+                },
+                ..Default::default()
+            }],
+            root: Some(0),
+            current_node: Some(0),
+            ..Default::default()
+        };
+        let broadcast = |epoch, version, target: &str| Message::ProofDag {
+            uri: Url::parse("file:///tmp/Test.lean").unwrap(),
+            position: Position::default(),
+            version: Some(DocumentVersion { epoch, version }),
+            proof_dag: Some(dag(target)),
+        };
+        let goal = |app: &App| app.proof_state().goals[0].type_.to_plain_text();
+        let mut app = App::default();
+
+        app.handle_message(broadcast(1, 3, "edited"));
+        app.handle_message(broadcast(1, 2, "stale"));
+        assert_eq!(goal(&app), "edited");
+
+        app.handle_message(broadcast(1, 4, "newer"));
+        assert_eq!(goal(&app), "newer");
+
+        // Closed and reopened: the editor counts versions from 1 again
+        app.handle_message(broadcast(2, 1, "reopened"));
+        assert_eq!(goal(&app), "reopened");
+        app.handle_message(broadcast(2, 2, "edited again"));
+        assert_eq!(goal(&app), "edited again");
+    }
+
+    /// `ma` saves the cursor and `'a` navigates back to it.
+    #[test]
+    fn test_bookmark_round_trip() {
+        let mut app = app_with_state(ProofState::default());
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            uri.clone(),
+            Position::new(7, 3),
+            "test",
+        )));
+        press(&mut app, 'm');
+        press(&mut app, 'a');
+
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            uri.clone(),
+            Position::new(20, 0),
+            "test",
+        )));
+        app.take_commands();
+        press(&mut app, '\'');
+        press(&mut app, 'a');
+        assert!(matches!(
+            &app.take_commands()[..],
+            [Command::Navigate { uri: target, position }]
+                if *target == uri && *position == Position::new(7, 3)
+        ));
+
+        press(&mut app, '\'');
+        press(&mut app, 'b');
+        assert_eq!(app.status.as_deref(), Some("No bookmark b"));
+    }
+
+    /// `A` opens the tagged text outline of the selected goal.
+    #[test]
+    fn test_tree_view_shows_structure() {
+        let type_ = TaggedText::Append {
+            items: vec![
+                TaggedText::Text {
+                    text: "p ".to_string(),
+                },
+                TaggedText::Text {
+                    text: "q".to_string(),
+                },
+            ],
+        };
+        let mut app = app_with_state(ProofState {
+            goals: vec![GoalInfo {
+                type_,
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        });
+
+        press(&mut app, 'A');
+        assert_eq!(
+            app.raw_view.text().as_deref(),
+            Some("Append\n  Text \"p \"\n  Text \"q\"")
+        );
+        press(&mut app, 'A');
+        assert_eq!(app.raw_view.text(), None);
+    }
+
+    /// Goals arriving for a new cursor position post a transient notice,
+    /// which the tick clears once it has been up long enough.
+    #[test]
+    fn test_transient_message_expires() {
+        let mut app = app_with_state(ProofState::default());
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        app.handle_message(Message::ProofDag {
+            uri,
+            position: Position::new(3, 0),
+            version: None,
+            proof_dag: Some(ProofDag::default()),
+        });
+        let posted = app.transient.as_ref().map(|(message, at)| (message.clone(), *at));
+        let (message, at) = posted.expect("toast posted");
+        assert_eq!(message, "Goals updated");
+
+        app.tick(at + TRANSIENT_FOR / 2);
+        assert!(app.transient.is_some());
+        app.tick(at + TRANSIENT_FOR);
+        assert!(app.transient.is_none());
+    }
+
+    /// Settled goals leave nothing to wake up for but dimming the screen;
+    /// waiting for goals wakes the loop for the spinner's next frame.
+    #[test]
+    fn test_next_deadline() {
+        let mut app = app_with_state(ProofState::default());
+        let now = Instant::now();
+        let last = app.last_activity.expect("messages handled");
+        assert_eq!(app.next_deadline(now), Some(last + IDLE_DIM_AFTER));
+
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            Url::parse("file:///tmp/Test.lean").unwrap(),
+            Position::new(4, 2),
+            "test",
+        )));
+        assert_eq!(app.next_deadline(now), Some(now), "first frame right away");
+        app.tick(now);
+        assert!(app.next_deadline(now).is_some_and(|at| at > now && at < now + TRANSIENT_FOR));
+    }
+
+    /// `I` asks the proxy for the implementation at the selected step's
+    /// tactic, rather than navigating there directly.
+    #[test]
+    fn test_implementation_key_sends_lookup() {
+        let mut app = app_with_node(ProofDagNode {
+            position: Position::new(4, 2),
+            state_after: ProofState {
+                goals: vec![goal("P")],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        });
+        press(&mut app, '1');
+        app.take_commands();
+
+        press(&mut app, 'I');
+        assert!(matches!(
+            &app.take_commands()[..],
+            [Command::GotoImplementation { position, .. }] if *position == Position::new(4, 2)
+        ));
+    }
+
+    /// An action picked from the palette runs like its key, and the
+    /// palette takes the keys typed into it.
+    #[test]
+    fn test_palette_runs_action() {
+        let mut app = app_with_state(ProofState::default());
+        app.handle_event(&Event::Key(KeyEvent::new(
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL,
+        )));
+        for c in "status".chars() {
+            press(&mut app, c);
+        }
+        assert!(!app.should_exit, "typed keys go to the palette");
+        assert!(!app.status_bar_toggled);
+
+        app.handle_event(&Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+        assert!(app.status_bar_toggled);
+    }
+
+    /// With `--refetch-every`, empty goals inside a definition are fetched
+    /// again once the interval has passed, and not when the cursor moved.
+    #[test]
+    fn test_empty_goals_refetched_after_interval() {
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        let mut app = App::default();
+        app.config.refetch_every = Some(Duration::from_secs(3));
+        let cursor = || Message::Cursor(CursorInfo::new(uri.clone(), Position::new(4, 2), "test"));
+        app.handle_message(cursor());
+        app.handle_message(Message::ProofDag {
+            uri: uri.clone(),
+            position: Position::new(4, 2),
+            version: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![ProofDagNode::default()],
+                root: Some(0),
+                current_node: Some(0),
+                definition_name: Some("foo".to_string()),
+                ..Default::default()
+            }),
+        });
+        let start = Instant::now();
+
+        app.tick(start);
+        assert!(app.take_commands().is_empty(), "not before the interval");
+        app.tick(start + Duration::from_secs(3));
+        let commands = app.take_commands();
+        assert!(
+            matches!(
+                commands.as_slice(),
+                [Command::Refetch { position, .. }] if *position == Position::new(4, 2)
+            ),
+            "{commands:?}"
+        );
+
+        app.handle_message(cursor());
+        app.tick(start + Duration::from_secs(10));
+        assert!(app.take_commands().is_empty(), "the cursor moved");
     }
 }
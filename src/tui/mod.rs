@@ -1,24 +1,46 @@
 //! TUI for displaying Lean proof goals.
 
+mod action;
 pub mod app;
 mod modes;
+mod paraphrase;
+mod recorder;
 mod widgets;
 
-use std::{io::stdout, time::Duration};
+use std::{
+    io::{self, stdout},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use app::App;
+pub use action::{keymap, KeymapFormat};
+use app::{App, ViewConfig};
+use recorder::spawn_recorder;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, EventStream},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
     ExecutableCommand,
 };
 use futures::StreamExt;
-use ratatui::prelude::*;
-use tokio::time::sleep;
+use ratatui::{backend::TestBackend, prelude::*};
+use tokio::time::{sleep_until, timeout};
+
+use crate::{
+    error::Result,
+    tui_ipc::{spawn_socket_handler, Message},
+};
+
+/// Size of the `--once` frame when the terminal size is unknown.
+const ONCE_SIZE: (u16, u16) = (100, 40);
 
-use crate::{error::Result, tui_ipc::spawn_socket_handler};
+/// Longest `--once` waits for goals before giving up.
+const ONCE_TIMEOUT: Duration = Duration::from_secs(10);
 
-pub async fn run() -> Result<()> {
+/// Run the viewer, appending the goal states it receives to `record` if
+/// given.
+pub async fn run(config: ViewConfig, record: Option<PathBuf>) -> Result<()> {
     enable_raw_mode()?;
     stdout()
         .execute(EnterAlternateScreen)?
@@ -27,12 +49,21 @@ pub async fn run() -> Result<()> {
 
     let mut socket = spawn_socket_handler();
     let mut app = App::default();
+    app.config = config;
+    app.recorder = record.map(spawn_recorder);
     let mut event_stream = EventStream::new();
+    app.mark_dirty();
 
     while !app.should_exit {
-        app.update();
-        terminal.draw(|frame| app.render(frame))?;
+        // Only redraw when something changed; idle cycles cost nothing
+        if app.take_dirty() {
+            app.update();
+            terminal.draw(|frame| app.render(frame))?;
+        }
 
+        // Sleep until an event arrives or the app has timed work to do
+        let deadline = app.next_deadline(Instant::now());
+        let wake = sleep_until(deadline.unwrap_or_else(Instant::now).into());
         tokio::select! {
             Some(msg) = socket.rx.recv() => {
                 app.handle_message(msg);
@@ -40,11 +71,13 @@ pub async fn run() -> Result<()> {
             Some(Ok(event)) = event_stream.next() => {
                 app.handle_event(&event);
             }
-            () = sleep(Duration::from_millis(50)) => {
-                for cmd in app.take_commands() {
-                    let _ = socket.tx.send(cmd).await;
-                }
-            }
+            () = wake, if deadline.is_some() => {}
+            // Neither the proxy nor the terminal can wake us again
+            else => break,
+        }
+        app.tick(Instant::now());
+        for cmd in app.take_commands() {
+            let _ = socket.tx.send(cmd).await;
         }
     }
 
@@ -55,3 +88,99 @@ pub async fn run() -> Result<()> {
 
     Ok(())
 }
+
+/// Connect, wait for the first goals (or error), print one frame as plain
+/// text and exit, for `--once`. Fails when nothing arrives within
+/// [`ONCE_TIMEOUT`], e.g. because the editor never moved the cursor.
+pub async fn run_once(config: ViewConfig) -> Result<()> {
+    let mut socket = spawn_socket_handler();
+    let mut app = App::default();
+    app.config = config;
+
+    let answer = async {
+        while let Some(msg) = socket.rx.recv().await {
+            let answered = matches!(msg, Message::ProofDag { .. } | Message::Error { .. });
+            app.handle_message(msg);
+            if answered {
+                break;
+            }
+        }
+    };
+    if timeout(ONCE_TIMEOUT, answer).await.is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("no goals from the proxy within {}s", ONCE_TIMEOUT.as_secs()),
+        )
+        .into());
+    }
+
+    let (width, height) = terminal::size().unwrap_or(ONCE_SIZE);
+    println!("{}", render_frame(&mut app, width, height)?);
+    Ok(())
+}
+
+/// One frame of `app` rendered off-screen, as rows of plain text.
+fn render_frame(app: &mut App, width: u16, height: u16) -> Result<String> {
+    app.update();
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|frame| app.render(frame))?;
+    let buffer = terminal.backend().buffer();
+    let rows: Vec<String> = (0..height)
+        .map(|y| {
+            let row: String = (0..width).map(|x| buffer[(x, y)].symbol()).collect();
+            row.trim_end().to_string()
+        })
+        .collect();
+    Ok(rows.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_lsp::lsp_types::Url;
+
+    use super::*;
+    use crate::{
+        lean_rpc::{GoalInfo, ProofDag, ProofDagNode, ProofState, TaggedText},
+        tui_ipc::{CursorInfo, Position},
+    };
+
+    /// The off-screen frame shows the goals received.
+    #[test]
+    fn test_frame_rendered_off_screen() {
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+        let goal = GoalInfo {
+            type_: TaggedText::Text {
+                text: "n + 0 = n".to_string(),
+            },
+            ..Default::default()
+        };
+        let node = ProofDagNode {
+            state_after: ProofState {
+                goals: vec![goal],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let mut app = App::default();
+        app.handle_message(Message::Cursor(CursorInfo::new(
+            uri.clone(),
+            Position::default(),
+            "test",
+        )));
+        app.handle_message(Message::ProofDag {
+            uri,
+            position: Position::default(),
+            version: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![node],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+        });
+
+        let frame = render_frame(&mut app, 80, 20).unwrap();
+        assert_eq!(frame.lines().count(), 20);
+        assert!(frame.contains("n + 0 = n"), "{frame}");
+    }
+}
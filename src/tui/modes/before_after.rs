@@ -1,5 +1,7 @@
 //! Before/After mode - three-column temporal comparison view.
 
+use std::{collections::HashSet, iter, rc::Rc};
+
 use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
@@ -8,32 +10,95 @@ use ratatui::{
 
 use super::Mode;
 use crate::{
-    lean_rpc::{ProofDag, ProofState},
+    lean_rpc::{ProofDag, ProofDagNode, ProofState},
     tui::{
         app::DefinitionInfo,
         widgets::{
+            diff_text::DiffMarkers,
             goals_column::{GoalsColumn, GoalsColumnState},
             hypothesis_indices,
             render_helpers::render_error,
             selection::SelectionState,
+            theme::SelectionColors,
             FilterToggle, HypothesisFilters, InteractiveComponent, KeyMouseEvent, Selection,
         },
     },
 };
 
+/// Narrowest area that still fits the states side by side; below it they
+/// are stacked top to bottom.
+const STACK_BELOW_WIDTH: u16 = 90;
+
+/// Shape of the change, e.g. "+3 hypotheses, -1 goal", from the diff flags
+/// of the current state and the removals marked in the previous one.
+fn diff_summary(current: &ProofState, previous: Option<&ProofState>) -> Option<String> {
+    let states = || iter::once(current).chain(previous);
+    let inserted_hyps = current.hypotheses.iter().filter(|h| h.is_inserted).count();
+    let removed_hyps = states()
+        .flat_map(|s| &s.hypotheses)
+        .filter(|h| h.is_removed)
+        .count();
+    let removed_goals = states()
+        .flat_map(|s| &s.goals)
+        .filter(|g| g.is_removed)
+        .count();
+
+    let count = |sign: char, n: usize, one: &str, many: &str| {
+        (n > 0).then(|| format!("{sign}{n} {}", if n == 1 { one } else { many }))
+    };
+    let parts: Vec<String> = [
+        count('+', inserted_hyps, "hypothesis", "hypotheses"),
+        count('-', removed_hyps, "hypothesis", "hypotheses"),
+        count('-', removed_goals, "goal", "goals"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Areas of the shown states, in previous/current/next order.
+fn state_areas(area: Rect, has_prev: bool, has_next: bool) -> Rc<[Rect]> {
+    if area.width < STACK_BELOW_WIDTH {
+        let count = 1 + u32::from(has_prev) + u32::from(has_next);
+        let constraints = (0..count).map(|_| Constraint::Ratio(1, count));
+        return Layout::vertical(constraints).split(area);
+    }
+
+    let constraints = match (has_prev, has_next) {
+        (true, true) => vec![
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+        ],
+        (true, false) => vec![Constraint::Percentage(35), Constraint::Percentage(65)],
+        (false, true) => vec![Constraint::Percentage(65), Constraint::Percentage(35)],
+        (false, false) => vec![Constraint::Percentage(100)],
+    };
+    Layout::horizontal(constraints).split(area)
+}
+
 /// Input for updating the Before/After mode.
 pub struct BeforeAfterModeInput {
     pub previous_state: Option<ProofState>,
+    /// Title for the previous column (defaults to "Previous").
+    pub previous_title: Option<String>,
     pub current_state: ProofState,
     pub next_state: Option<ProofState>,
     pub definition: Option<DefinitionInfo>,
     pub error: Option<String>,
     pub proof_dag: Option<ProofDag>,
+    pub max_hyps: Option<usize>,
+    pub diff_markers: DiffMarkers,
+    pub selection_colors: SelectionColors,
+    pub presentation: bool,
+    pub rtl: bool,
 }
 
 /// Before/After display mode - temporal comparison of goal states.
 pub struct BeforeAfterMode {
     previous_state: Option<ProofState>,
+    previous_title: Option<String>,
     current_state: ProofState,
     next_state: Option<ProofState>,
     definition: Option<DefinitionInfo>,
@@ -43,6 +108,10 @@ pub struct BeforeAfterMode {
     /// Name of the goal the cursor's tactic is working on.
     active_goal_name: Option<String>,
     filters: HypothesisFilters,
+    /// Hypotheses no tactic of the proof uses.
+    unused_hyps: HashSet<String>,
+    /// Goals the current tactic split one goal into.
+    split_goals: Vec<usize>,
     selection: SelectionState,
     show_previous: bool,
     show_next: bool,
@@ -55,6 +124,7 @@ impl Default for BeforeAfterMode {
     fn default() -> Self {
         Self {
             previous_state: None,
+            previous_title: None,
             current_state: ProofState::default(),
             next_state: None,
             definition: None,
@@ -62,6 +132,8 @@ impl Default for BeforeAfterMode {
             current_node_id: None,
             active_goal_name: None,
             filters: HypothesisFilters::default(),
+            unused_hyps: HashSet::new(),
+            split_goals: Vec::new(),
             selection: SelectionState::default(),
             show_previous: true, // Show previous column by default
             show_next: false,
@@ -77,38 +149,25 @@ impl BeforeAfterMode {
         self.filters
     }
 
-    /// Whether the previous column should be shown.
-    pub const fn show_previous(&self) -> bool {
-        self.show_previous
-    }
-
-    /// Whether the next column should be shown.
-    pub const fn show_next(&self) -> bool {
-        self.show_next
-    }
-
     fn selectable_items(&self) -> Vec<Selection> {
         let Some(node_id) = self.current_node_id else {
             return Vec::new();
         };
 
         // Hypotheses followed by goals
-        let hyp_items = hypothesis_indices(
-            self.current_state.hypotheses.len(),
-            self.filters.reverse_order,
-        )
-        .filter(|&hyp_idx| {
-            self.current_state
-                .hypotheses
-                .get(hyp_idx)
-                .is_some_and(|h| {
-                    (!self.filters.hide_instances || !h.is_instance)
-                        && (!self.filters.hide_inaccessible || !h.is_proof)
-                })
-        })
-        .map(move |hyp_idx| Selection::Hyp { node_id, hyp_idx });
+        let hyp_items = hypothesis_indices(&self.current_state, self.filters.hyp_order)
+            .into_iter()
+            .filter(|&hyp_idx| {
+                self.current_state
+                    .hypotheses
+                    .get(hyp_idx)
+                    .is_some_and(|h| self.filters.should_show(h))
+            })
+            .take(self.filters.hyp_cap().unwrap_or(usize::MAX))
+            .map(move |hyp_idx| Selection::Hyp { node_id, hyp_idx });
 
         let goal_items = (0..self.current_state.goals.len())
+            .take(self.filters.goal_cap())
             .map(move |goal_idx| Selection::Goal { node_id, goal_idx });
 
         hyp_items.chain(goal_items).collect()
@@ -123,16 +182,28 @@ impl InteractiveComponent for BeforeAfterMode {
         let state_changed = self.current_state.goals.len() != input.current_state.goals.len()
             || self.current_state.hypotheses.len() != input.current_state.hypotheses.len();
         self.previous_state = input.previous_state;
+        self.previous_title = input.previous_title;
         self.current_state = input.current_state;
         self.next_state = input.next_state;
         self.definition = input.definition;
         self.error = input.error;
+        self.filters.max_hyps = input.max_hyps;
+        self.filters.diff_markers = input.diff_markers;
+        self.filters.selection = input.selection_colors;
+        self.filters.presentation = input.presentation;
+        self.filters.rtl = input.rtl;
         let current_node_id = input.proof_dag.as_ref().and_then(|dag| dag.current_node);
         let current_node = current_node_id.and_then(|id| input.proof_dag.as_ref()?.get(id));
         self.current_node_id = current_node_id;
+        self.unused_hyps = input
+            .proof_dag
+            .as_ref()
+            .map(ProofDag::unused_hypotheses)
+            .unwrap_or_default();
         self.active_goal_name = current_node
             .and_then(|node| node.state_before.goals.first())
             .and_then(|g| g.username.as_str().map(String::from));
+        self.split_goals = current_node.map(ProofDagNode::split_goals).unwrap_or_default();
         if state_changed {
             self.selection.reset(self.selectable_items().len());
         }
@@ -163,7 +234,47 @@ impl InteractiveComponent for BeforeAfterMode {
                     true
                 }
                 KeyCode::Char('r') => {
-                    self.filters.toggle(FilterToggle::ReverseOrder);
+                    self.filters.toggle(FilterToggle::Order);
+                    true
+                }
+                KeyCode::Char('x') => {
+                    self.filters.toggle(FilterToggle::Diffs);
+                    true
+                }
+                KeyCode::Char('s') => {
+                    self.filters.toggle(FilterToggle::Sizes);
+                    true
+                }
+                KeyCode::Char('u') => {
+                    self.filters.toggle(FilterToggle::Used);
+                    true
+                }
+                KeyCode::Char('c') => {
+                    self.filters.toggle(FilterToggle::Collapse);
+                    true
+                }
+                KeyCode::Char('e') => {
+                    self.filters.toggle(FilterToggle::Expand);
+                    true
+                }
+                KeyCode::Char('w') => {
+                    self.filters.toggle(FilterToggle::Paraphrase);
+                    true
+                }
+                KeyCode::Char('B') => {
+                    self.filters.toggle(FilterToggle::Binders);
+                    true
+                }
+                KeyCode::Char('*') => {
+                    self.filters.toggle(FilterToggle::FirstGoal);
+                    true
+                }
+                KeyCode::Char('#') => {
+                    self.filters.toggle(FilterToggle::Boxes);
+                    true
+                }
+                KeyCode::Char('d') => {
+                    self.filters.toggle(FilterToggle::Unused);
                     true
                 }
                 KeyCode::Char('p') => {
@@ -188,22 +299,10 @@ impl InteractiveComponent for BeforeAfterMode {
 
         let content_area = render_error(frame, area, self.error.as_deref());
 
-        // Three-column layout
+        // Three columns, or three rows on narrow terminals
         let has_prev = self.previous_state.is_some() && self.show_previous;
         let has_next = self.next_state.is_some() && self.show_next;
-
-        let constraints = match (has_prev, has_next) {
-            (true, true) => vec![
-                Constraint::Percentage(30),
-                Constraint::Percentage(40),
-                Constraint::Percentage(30),
-            ],
-            (true, false) => vec![Constraint::Percentage(35), Constraint::Percentage(65)],
-            (false, true) => vec![Constraint::Percentage(65), Constraint::Percentage(35)],
-            (false, false) => vec![Constraint::Percentage(100)],
-        };
-
-        let columns = Layout::horizontal(constraints).split(content_area);
+        let columns = state_areas(content_area, has_prev, has_next);
         let mut col_idx = 0;
 
         let selection = self.current_selection();
@@ -213,14 +312,15 @@ impl InteractiveComponent for BeforeAfterMode {
             if self.show_previous {
                 frame.render_stateful_widget(
                     GoalsColumn::new(
-                        "Previous",
+                        self.previous_title.as_deref().unwrap_or("Previous"),
                         state,
                         self.filters,
                         None,
                         false,
                         None,
                         self.active_goal_name.as_deref(),
-                    ),
+                    )
+                    .unused_hypotheses(&self.unused_hyps),
                     columns[col_idx],
                     &mut self.previous_column_state,
                 );
@@ -229,16 +329,20 @@ impl InteractiveComponent for BeforeAfterMode {
         }
 
         // Current column (always shown)
+        let current_title = diff_summary(&self.current_state, self.previous_state.as_ref())
+            .map_or_else(|| "Current".to_string(), |s| format!("Current ({s})"));
         frame.render_stateful_widget(
             GoalsColumn::new(
-                "Current",
+                &current_title,
                 &self.current_state,
                 self.filters,
                 selection,
                 true,
                 self.current_node_id,
                 self.active_goal_name.as_deref(),
-            ),
+            )
+            .unused_hypotheses(&self.unused_hyps)
+            .split_goals(&self.split_goals),
             columns[col_idx],
             &mut self.current_column_state,
         );
@@ -260,7 +364,8 @@ impl InteractiveComponent for BeforeAfterMode {
                         false,
                         None,
                         self.active_goal_name.as_deref(),
-                    ),
+                    )
+                    .unused_hypotheses(&self.unused_hyps),
                     columns[col_idx],
                     &mut self.next_column_state,
                 );
@@ -279,13 +384,32 @@ impl Mode for BeforeAfterMode {
         ("i", "inst"),
         ("a", "access"),
         ("l", "let"),
-        ("r", "rev"),
+        ("r", "order"),
+        ("x", "diff"),
+        ("s", "size"),
+        ("u", "used"),
+        ("c", "collapse"),
+        ("e", "expand"),
+        ("w", "words"),
+        ("B", "binders"),
+        ("*", "first goal"),
+        ("#", "boxes"),
+        ("d", "unused"),
     ];
     const SUPPORTED_FILTERS: &'static [FilterToggle] = &[
         FilterToggle::Instances,
         FilterToggle::Inaccessible,
         FilterToggle::LetValues,
-        FilterToggle::ReverseOrder,
+        FilterToggle::Order,
+        FilterToggle::Diffs,
+        FilterToggle::Sizes,
+        FilterToggle::Used,
+        FilterToggle::Collapse,
+        FilterToggle::Paraphrase,
+        FilterToggle::Unused,
+        FilterToggle::Binders,
+        FilterToggle::FirstGoal,
+        FilterToggle::Boxes,
     ];
 
     fn current_selection(&self) -> Option<Selection> {
@@ -293,4 +417,92 @@ impl Mode for BeforeAfterMode {
             .current_selection(&self.selectable_items())
             .copied()
     }
+
+    fn select_goal(&mut self, goal_idx: usize) -> bool {
+        let Some(node_id) = self.current_node_id else {
+            return false;
+        };
+        self.selection
+            .select(Selection::Goal { node_id, goal_idx }, &self.selectable_items())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo, TaggedText};
+
+    fn state(target: &str) -> ProofState {
+        ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Text {
+                    text: target.to_string(),
+                },
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        }
+    }
+
+    /// Row of the first line containing `needle`.
+    fn row_of(terminal: &Terminal<TestBackend>, needle: &str) -> Option<u16> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height).find(|&y| {
+            let row: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            row.contains(needle)
+        })
+    }
+
+    /// Below the width threshold the three states are stacked top to bottom.
+    #[test]
+    fn test_narrow_terminal_stacks_states() {
+        let mut mode = BeforeAfterMode::default();
+        mode.update(BeforeAfterModeInput {
+            previous_state: Some(state("before = 0")),
+            previous_title: None,
+            current_state: state("now = 1"),
+            next_state: Some(state("after = 2")),
+            definition: None,
+            error: None,
+            proof_dag: None,
+            max_hyps: None,
+            diff_markers: DiffMarkers::default(),
+            selection_colors: SelectionColors::default(),
+            presentation: false,
+            rtl: false,
+        });
+        mode.show_next = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 30)).unwrap();
+        terminal.draw(|frame| mode.render(frame, frame.area())).unwrap();
+
+        let rows = ["before = 0", "now = 1", "after = 2"].map(|t| row_of(&terminal, t));
+        assert!(rows.iter().all(Option::is_some), "all states shown: {rows:?}");
+        assert!(rows[0] < rows[1] && rows[1] < rows[2], "stacked: {rows:?}");
+    }
+
+    /// The header summary counts inserted hypotheses and removed goals.
+    #[test]
+    fn test_diff_summary_counts_changes() {
+        let inserted = HypothesisInfo {
+            is_inserted: true,
+            ..Default::default()
+        };
+        let mut current = state("x = 1");
+        current.hypotheses = vec![inserted.clone(), inserted.clone(), inserted];
+        current.goals.push(GoalInfo {
+            is_removed: true,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            diff_summary(&current, None).as_deref(),
+            Some("+3 hypotheses, -1 goal")
+        );
+        assert_eq!(diff_summary(&state("x = 1"), None), None);
+    }
 }
@@ -1,5 +1,7 @@
 //! Deduction Tree mode - semantic tree visualization.
 
+use std::mem;
+
 use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
 use ratatui::{
     layout::Rect,
@@ -17,33 +19,47 @@ use crate::{
         widgets::{
             render_helpers::{render_error, render_no_goals},
             semantic_tableau::{
-                navigation::{find_nearest_in_direction, Direction},
+                navigation::{find_nearest_in_direction, step_in_order, Direction},
                 SemanticTableauLayout, SemanticTableauState,
             },
+            theme::SelectionColors,
             FilterToggle, HypothesisFilters, InteractiveComponent, KeyMouseEvent, Selection,
         },
     },
 };
 
+/// Columns scrolled per `<`/`>` press.
+const SCROLL_STEP: u16 = 8;
+
 /// Input for updating the Deduction Tree mode.
 pub struct DeductionTreeModeInput {
     pub state: ProofState,
     pub definition: Option<DefinitionInfo>,
     pub error: Option<String>,
     pub proof_dag: Option<ProofDag>,
+    pub selection_colors: SelectionColors,
 }
 
 /// Deduction Tree display mode - semantic tree visualization.
+#[allow(clippy::struct_excessive_bools)]
 pub struct SemanticTableau {
     state: ProofState,
     definition: Option<DefinitionInfo>,
     error: Option<String>,
     proof_dag: Option<ProofDag>,
+    /// `proof_dag` with single-child chains merged, while merging is on.
+    merged_dag: Option<ProofDag>,
     filters: HypothesisFilters,
     /// Flat index into `tree_selectable_items()`.
     selected_idx: Option<usize>,
     /// When true, tree renders top-down (parent above children).
     tree_top_down: bool,
+    /// Whether the minimap column is shown.
+    show_minimap: bool,
+    /// Whether the given pane is folded to a one-line count.
+    givens_collapsed: bool,
+    /// Whether runs of single-child steps share one box.
+    merge_chains: bool,
     /// State for the semantic tableau widget.
     tableau_state: SemanticTableauState,
 }
@@ -55,9 +71,13 @@ impl Default for SemanticTableau {
             definition: None,
             error: None,
             proof_dag: None,
+            merged_dag: None,
             filters: HypothesisFilters::default(),
             selected_idx: None,
             tree_top_down: true,
+            show_minimap: false,
+            givens_collapsed: false,
+            merge_chains: false,
             tableau_state: SemanticTableauState::default(),
         }
     }
@@ -68,9 +88,37 @@ impl SemanticTableau {
         self.filters
     }
 
-    /// Build list of all selectable items in the tree from `proof_dag`.
+    /// The DAG as drawn: with chains merged, while that is on.
+    fn tree_dag(&self) -> Option<&ProofDag> {
+        self.merged_dag.as_ref().or(self.proof_dag.as_ref())
+    }
+
+    fn update_merged_dag(&mut self) {
+        self.merged_dag = self
+            .proof_dag
+            .as_ref()
+            .filter(|_| self.merge_chains)
+            .map(ProofDag::merge_chains);
+    }
+
+    /// Merge or expand single-child chains, keeping the selection when its
+    /// box is still drawn.
+    fn toggle_merge_chains(&mut self) {
+        let selection = self.current_tree_selection();
+        self.merge_chains = !self.merge_chains;
+        self.update_merged_dag();
+        let kept = selection.is_some_and(|sel| self.select_by_selection(sel));
+        if !kept {
+            self.selected_idx = None;
+            if let Some(sel) = self.active_goal_selection() {
+                self.select_by_selection(sel);
+            }
+        }
+    }
+
+    /// Build list of all selectable items in the tree as drawn.
     fn tree_selectable_items(&self) -> Vec<Selection> {
-        let Some(dag) = &self.proof_dag else {
+        let Some(dag) = self.tree_dag() else {
             return Vec::new();
         };
         if dag.is_empty() {
@@ -86,8 +134,8 @@ impl SemanticTableau {
             }
         }
 
-        // For each node: new hypotheses and goals
-        for node in dag.dfs_iter() {
+        // For each node: new hypotheses and goals (main tree, then side proofs)
+        for node in dag.dfs_iter().chain(dag.orphan_dfs_iter()) {
             let node_id = node.id;
 
             // New hypotheses introduced by this step
@@ -112,11 +160,26 @@ impl SemanticTableau {
         self.selected_idx.and_then(|idx| items.get(idx).copied())
     }
 
-    fn select_by_selection(&mut self, sel: Selection) {
+    fn select_by_selection(&mut self, sel: Selection) -> bool {
         let items = self.tree_selectable_items();
-        if let Some(idx) = items.iter().position(|s| *s == sel) {
-            self.selected_idx = Some(idx);
+        let idx = items.iter().position(|s| *s == sel);
+        if idx.is_some() {
+            self.selected_idx = idx;
         }
+        idx.is_some()
+    }
+
+    /// Select the first item of a node, preferring its goals.
+    fn select_node(&mut self, node_id: u32) -> bool {
+        let items = self.tree_selectable_items();
+        let in_node = |sel: &&Selection| sel.node_id() == Some(node_id);
+        let target = items
+            .iter()
+            .filter(in_node)
+            .find(|sel| matches!(sel, Selection::Goal { .. }))
+            .or_else(|| items.iter().find(in_node))
+            .copied();
+        target.is_some_and(|sel| self.select_by_selection(sel))
     }
 
     /// Get the active goal selection (first goal of the current node).
@@ -156,13 +219,22 @@ impl SemanticTableau {
             "move_in_direction called"
         );
 
-        let result = find_nearest_in_direction(navigation_regions, current_sel, direction);
+        // Vertical moves fall back to reading order so navigation can cross
+        // between goals whose subtrees are laid out side by side
+        let result = find_nearest_in_direction(navigation_regions, current_sel, direction)
+            .or_else(|| {
+                (!direction.is_horizontal())
+                    .then(|| step_in_order(navigation_regions, &items, current_sel, direction))
+                    .flatten()
+            });
         debug!(?result, "Navigation result in move_in_direction");
 
         result.is_some_and(|sel| {
             let found_in_items = items.contains(&sel);
             debug!(?sel, found_in_items, "Attempting to select");
             self.select_by_selection(sel);
+            // Follow the new selection again after manual scrolling
+            self.tableau_state.proof.reset_scroll();
             true
         })
     }
@@ -189,7 +261,9 @@ impl InteractiveComponent for SemanticTableau {
         self.state = input.state;
         self.definition = input.definition;
         self.error = input.error;
+        self.filters.selection = input.selection_colors;
         self.proof_dag = input.proof_dag;
+        self.update_merged_dag();
 
         // Auto-select active goal when tree or goals change
         if tree_changed || state_changed {
@@ -212,10 +286,50 @@ impl InteractiveComponent for SemanticTableau {
                     self.tree_top_down = !self.tree_top_down;
                     true
                 }
+                KeyCode::Char('<') => {
+                    self.tableau_state.proof.scroll_left(SCROLL_STEP);
+                    true
+                }
+                KeyCode::Char('>') => {
+                    self.tableau_state.proof.scroll_right(SCROLL_STEP);
+                    true
+                }
+                KeyCode::Char('{') => {
+                    let page = self.tableau_state.proof.viewport_width();
+                    self.tableau_state.proof.scroll_left(page);
+                    true
+                }
+                KeyCode::Char('}') => {
+                    let page = self.tableau_state.proof.viewport_width();
+                    self.tableau_state.proof.scroll_right(page);
+                    true
+                }
+                KeyCode::Char('v') => {
+                    self.show_minimap = !self.show_minimap;
+                    true
+                }
+                KeyCode::Char('G') => {
+                    self.givens_collapsed = !self.givens_collapsed;
+                    true
+                }
+                KeyCode::Char('c') => {
+                    self.toggle_merge_chains();
+                    true
+                }
+                KeyCode::Char('T') => {
+                    self.filters.toggle(FilterToggle::TacticSummary);
+                    true
+                }
                 _ => false,
             },
             KeyMouseEvent::Mouse(mouse) => {
                 let is_click = mouse.kind == MouseEventKind::Down(MouseButton::Left);
+                let minimap_node = is_click
+                    .then(|| self.tableau_state.minimap.node_at(mouse.column, mouse.row))
+                    .flatten();
+                if let Some(node_id) = minimap_node {
+                    return self.select_node(node_id);
+                }
                 let clicked = is_click
                     .then(|| self.tableau_state.find_click_at(mouse.column, mouse.row))
                     .flatten();
@@ -236,20 +350,27 @@ impl InteractiveComponent for SemanticTableau {
             return;
         }
 
-        if let Some(ref dag) = self.proof_dag {
+        // The layout borrows the DAG while its state is rendered into
+        let mut tableau_state = mem::take(&mut self.tableau_state);
+        if let Some(dag) = self.tree_dag() {
             let widget = SemanticTableauLayout::new(
                 dag,
                 self.tree_top_down,
                 self.current_tree_selection(),
                 &self.state,
-            );
-            frame.render_stateful_widget(widget, content_area, &mut self.tableau_state);
+            )
+            .show_minimap(self.show_minimap)
+            .collapse_givens(self.givens_collapsed)
+            .summarize_tactics(self.filters.tactic_summary)
+            .selection_colors(self.filters.selection);
+            frame.render_stateful_widget(widget, content_area, &mut tableau_state);
         } else {
             frame.render_widget(
                 Paragraph::new("No proof steps available").style(Style::new().fg(Color::DarkGray)),
                 content_area,
             );
         }
+        self.tableau_state = tableau_state;
     }
 }
 
@@ -257,10 +378,24 @@ impl Mode for SemanticTableau {
     type Model = DeductionTreeModeInput;
 
     const NAME: &'static str = "Semantic tableau";
-    const KEYBINDINGS: &'static [(&'static str, &'static str)] = &[("hjkl", "nav")];
-    const SUPPORTED_FILTERS: &'static [FilterToggle] = &[];
+    const KEYBINDINGS: &'static [(&'static str, &'static str)] = &[
+        ("hjkl", "nav"),
+        ("<>{}", "scroll"),
+        ("v", "minimap"),
+        ("G", "givens"),
+        ("c", "merge chains"),
+        ("T", "one-line tactics"),
+    ];
+    const SUPPORTED_FILTERS: &'static [FilterToggle] = &[FilterToggle::TacticSummary];
 
     fn current_selection(&self) -> Option<Selection> {
         self.current_tree_selection()
     }
+
+    fn select_goal(&mut self, goal_idx: usize) -> bool {
+        let Some(node_id) = self.proof_dag.as_ref().and_then(|dag| dag.current_node) else {
+            return false;
+        };
+        self.select_by_selection(Selection::Goal { node_id, goal_idx })
+    }
 }
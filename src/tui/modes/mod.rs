@@ -5,8 +5,6 @@ pub mod deduction_tree;
 mod open_goal_list;
 mod steps_view;
 
-use std::mem::take;
-
 use before_after::BeforeAfterMode;
 pub use before_after::BeforeAfterModeInput;
 pub use deduction_tree::DeductionTreeModeInput;
@@ -30,6 +28,16 @@ pub trait Mode: InteractiveComponent<Input = Self::Model, Event = KeyMouseEvent>
     const SUPPORTED_FILTERS: &'static [FilterToggle];
 
     fn current_selection(&self) -> Option<Selection>;
+
+    /// Select the target of the goal at `goal_idx` in the current node.
+    /// Returns false when there is no such goal.
+    fn select_goal(&mut self, goal_idx: usize) -> bool;
+
+    /// Whether the mode is halfway through a two-key command, so the next
+    /// key goes to it before the global keymap.
+    fn awaits_key(&self) -> bool {
+        false
+    }
 }
 
 /// Display mode with embedded state.
@@ -48,24 +56,34 @@ impl Default for DisplayMode {
 }
 
 impl DisplayMode {
-    /// Cycle to the next display mode, preserving state.
-    pub fn next(&mut self) {
-        *self = match take(self) {
+    /// A fresh instance of the next display mode.
+    pub fn following(&self) -> Self {
+        match self {
             Self::OpenGoalList(_) => Self::BeforeAfter(BeforeAfterMode::default()),
             Self::BeforeAfter(_) => Self::StepsView(TacticTree::default()),
             Self::StepsView(_) => Self::DeductionTree(SemanticTableau::default()),
             Self::DeductionTree(_) => Self::OpenGoalList(PlainList::default()),
-        };
+        }
     }
 
-    /// Cycle to the previous display mode, preserving state.
-    pub fn prev(&mut self) {
-        *self = match take(self) {
+    /// A fresh instance of the previous display mode.
+    pub fn preceding(&self) -> Self {
+        match self {
             Self::OpenGoalList(_) => Self::DeductionTree(SemanticTableau::default()),
             Self::BeforeAfter(_) => Self::OpenGoalList(PlainList::default()),
             Self::StepsView(_) => Self::BeforeAfter(BeforeAfterMode::default()),
             Self::DeductionTree(_) => Self::StepsView(TacticTree::default()),
-        };
+        }
+    }
+
+    /// A fresh instance of every display mode, in cycle order.
+    pub fn all() -> [Self; 4] {
+        [
+            Self::OpenGoalList(PlainList::default()),
+            Self::BeforeAfter(BeforeAfterMode::default()),
+            Self::StepsView(TacticTree::default()),
+            Self::DeductionTree(SemanticTableau::default()),
+        ]
     }
 
     /// Get the display name of the current mode.
@@ -108,29 +126,33 @@ impl DisplayMode {
         }
     }
 
-    /// Get filters from active mode.
-    pub const fn filters(&self) -> HypothesisFilters {
+    /// Jump the selection to the target of the goal at `goal_idx`.
+    pub fn select_goal(&mut self, goal_idx: usize) -> bool {
         match self {
-            Self::OpenGoalList(m) => m.filters(),
-            Self::BeforeAfter(m) => m.filters(),
-            Self::StepsView(m) => m.filters(),
-            Self::DeductionTree(m) => m.filters(),
+            Self::OpenGoalList(m) => m.select_goal(goal_idx),
+            Self::BeforeAfter(m) => m.select_goal(goal_idx),
+            Self::StepsView(m) => m.select_goal(goal_idx),
+            Self::DeductionTree(m) => m.select_goal(goal_idx),
         }
     }
 
-    /// Whether to show the previous column (`BeforeAfter` mode only).
-    pub const fn show_previous(&self) -> bool {
+    /// Whether the active mode takes the next key first.
+    pub fn awaits_key(&self) -> bool {
         match self {
-            Self::BeforeAfter(m) => m.show_previous(),
-            _ => false,
+            Self::OpenGoalList(m) => m.awaits_key(),
+            Self::BeforeAfter(m) => m.awaits_key(),
+            Self::StepsView(m) => m.awaits_key(),
+            Self::DeductionTree(m) => m.awaits_key(),
         }
     }
 
-    /// Whether to show the next column (`BeforeAfter` mode only).
-    pub const fn show_next(&self) -> bool {
+    /// Get filters from active mode.
+    pub const fn filters(&self) -> HypothesisFilters {
         match self {
-            Self::BeforeAfter(m) => m.show_next(),
-            _ => false,
+            Self::OpenGoalList(m) => m.filters(),
+            Self::BeforeAfter(m) => m.filters(),
+            Self::StepsView(m) => m.filters(),
+            Self::DeductionTree(m) => m.filters(),
         }
     }
 
@@ -1,18 +1,25 @@
 //! Open Goal List mode - displays hypotheses and goal targets in a navigable
 //! list.
 
+use std::collections::HashSet;
+
 use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
-use ratatui::{layout::Rect, Frame};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    widgets::Paragraph,
+    Frame,
+};
 
 use super::Mode;
 use crate::{
-    lean_rpc::{ProofDag, ProofState},
+    lean_rpc::{ProofDag, ProofDagNode, ProofState},
     tui::{
         app::DefinitionInfo,
         widgets::{
-            hypothesis_indices, open_goal_list::OpenGoalList, render_helpers::render_error,
-            selection::SelectionState, FilterToggle, HypothesisFilters, InteractiveComponent,
-            KeyMouseEvent, Selection,
+            diff_text::DiffMarkers, hypothesis_indices, open_goal_list::OpenGoalList,
+            render_helpers::render_error, selection::SelectionState, theme::SelectionColors,
+            FilterToggle, HypothesisFilters, InteractiveComponent, KeyMouseEvent, Selection,
         },
     },
 };
@@ -23,6 +30,11 @@ pub struct PlainListInput {
     pub definition: Option<DefinitionInfo>,
     pub error: Option<String>,
     pub proof_dag: Option<ProofDag>,
+    pub max_hyps: Option<usize>,
+    pub diff_markers: DiffMarkers,
+    pub selection_colors: SelectionColors,
+    pub presentation: bool,
+    pub rtl: bool,
 }
 
 /// Open Goal List display mode - navigable list of open goals with hypotheses.
@@ -37,7 +49,15 @@ pub struct PlainList {
     definition: Option<DefinitionInfo>,
     error: Option<String>,
     filters: HypothesisFilters,
+    /// Hypotheses no tactic of the proof uses.
+    unused_hyps: HashSet<String>,
+    /// Goals the current tactic split one goal into.
+    split_goals: Vec<usize>,
     selection: SelectionState,
+    /// Goal shown on its own, hiding the others.
+    focused_goal: Option<usize>,
+    /// Term whose mentions `f` cycles through.
+    mention: Option<String>,
 }
 
 impl PlainList {
@@ -53,32 +73,114 @@ impl PlainList {
         let hyp_count = self.state.hypotheses.len();
         let goal_count = self.state.goals.len();
 
-        // All hypotheses (filtered) followed by all goals
-        let hyp_items = hypothesis_indices(hyp_count, self.filters.reverse_order)
+        // The hypotheses summary, all hypotheses (filtered), then all goals
+        let summary = (hyp_count > 0).then_some(Selection::AllHyps { node_id });
+        let hyp_items = hypothesis_indices(&self.state, self.filters.hyp_order)
+            .into_iter()
             .filter(|&i| self.should_show_hypothesis(i))
+            .take(self.filters.hyp_cap().unwrap_or(usize::MAX))
             .map(move |hyp_idx| Selection::Hyp { node_id, hyp_idx });
 
-        let goal_items = (0..goal_count).map(move |goal_idx| Selection::Goal { node_id, goal_idx });
+        let focused_goal = self.focused_goal;
+        let goal_items = (0..goal_count)
+            .filter(move |&goal_idx| focused_goal.is_none_or(|focused| focused == goal_idx))
+            .take(self.filters.goal_cap())
+            .map(move |goal_idx| Selection::Goal { node_id, goal_idx });
 
-        hyp_items.chain(goal_items).collect()
+        summary.into_iter().chain(hyp_items).chain(goal_items).collect()
     }
 
     fn should_show_hypothesis(&self, idx: usize) -> bool {
-        let Some(h) = self.state.hypotheses.get(idx) else {
+        self.state
+            .hypotheses
+            .get(idx)
+            .is_some_and(|h| self.filters.should_show(h))
+    }
+
+    /// Focus the selected goal, or return to the full list.
+    fn toggle_focus(&mut self) -> bool {
+        if self.focused_goal.take().is_some() {
+            self.selection.reset(self.selectable_items().len());
+            return true;
+        }
+        let Some(goal @ Selection::Goal { goal_idx, .. }) = self.current_selection() else {
             return false;
         };
-        // Apply filters based on hypothesis properties
-        if self.filters.hide_instances && h.is_instance {
+        self.focused_goal = Some(goal_idx);
+        // Keep the goal selected among the reduced items
+        self.selection.select(goal, &self.selectable_items())
+    }
+
+    /// Select the next goal mentioning the selected hypothesis, or the
+    /// metavariable in the selected goal, wrapping around.
+    fn cycle_mention(&mut self) -> bool {
+        let (Some(node_id), Some(selection)) = (self.current_node_id, self.current_selection())
+        else {
             return false;
-        }
-        // Note: hide_inaccessible checks name prefix, is_proof maps to that heuristic
-        if self.filters.hide_inaccessible && h.is_proof {
+        };
+        let (term, after) = match selection {
+            Selection::Hyp { hyp_idx, .. } => (self.state.hypotheses[hyp_idx].name.clone(), None),
+            Selection::Goal { goal_idx, .. } => {
+                let term = self.mention.clone().or_else(|| {
+                    first_metavariable(&self.state.goals[goal_idx].type_.to_plain_text())
+                });
+                let Some(term) = term else {
+                    return false;
+                };
+                (term, Some(goal_idx))
+            }
+            _ => return false,
+        };
+
+        let matching: Vec<usize> = (0..self.state.goals.len())
+            .filter(|&i| mentions(&self.state.goals[i].type_.to_plain_text(), &term))
+            .collect();
+        let next = matching
+            .iter()
+            .find(|&&i| after.is_none_or(|current| i > current))
+            .or_else(|| matching.first());
+        let Some(&goal_idx) = next else {
             return false;
-        }
-        true
+        };
+        self.mention = Some(term);
+        self.selection
+            .select(Selection::Goal { node_id, goal_idx }, &self.selectable_items())
+    }
+
+    /// Note the focused goal on the first line, returning the rest.
+    fn render_focus_header(&self, frame: &mut Frame, area: Rect) -> Rect {
+        let Some(goal_idx) = self.focused_goal else {
+            return area;
+        };
+        let [header, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        let text = format!(
+            "Focused on goal {} of {} (o to show all)",
+            goal_idx + 1,
+            self.state.goals.len()
+        );
+        frame.render_widget(Paragraph::new(text).fg(Color::DarkGray), header);
+        rest
     }
 }
 
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '\'' | '?' | '!')
+}
+
+/// Whether `text` mentions `term` as a whole identifier, not as part of a
+/// longer one.
+fn mentions(text: &str, term: &str) -> bool {
+    text.split(|c: char| !is_ident_char(c)).any(|word| word == term)
+}
+
+/// First metavariable (`?m.12`, `?a`) in a goal target.
+fn first_metavariable(text: &str) -> Option<String> {
+    text.split(|c: char| !is_ident_char(c))
+        .find(|word| word.len() > 1 && word.starts_with('?'))
+        .map(str::to_string)
+}
+
 impl InteractiveComponent for PlainList {
     type Input = PlainListInput;
     type Event = KeyMouseEvent;
@@ -92,14 +194,27 @@ impl InteractiveComponent for PlainList {
             || self.state.hypotheses.len() != input.state.hypotheses.len();
 
         self.current_node_id = current_node_id;
+        self.unused_hyps = input
+            .proof_dag
+            .as_ref()
+            .map(ProofDag::unused_hypotheses)
+            .unwrap_or_default();
         self.active_goal_name = current_node
             .and_then(|node| node.state_before.goals.first())
             .and_then(|g| g.username.as_str().map(String::from));
+        self.split_goals = current_node.map(ProofDagNode::split_goals).unwrap_or_default();
         self.state = input.state;
         self.definition = input.definition;
         self.error = input.error;
+        self.filters.max_hyps = input.max_hyps;
+        self.filters.diff_markers = input.diff_markers;
+        self.filters.selection = input.selection_colors;
+        self.filters.presentation = input.presentation;
+        self.filters.rtl = input.rtl;
 
         if state_changed {
+            self.focused_goal = None;
+            self.mention = None;
             self.selection.reset(self.selectable_items().len());
         }
     }
@@ -129,9 +244,52 @@ impl InteractiveComponent for PlainList {
                     true
                 }
                 KeyCode::Char('r') => {
-                    self.filters.toggle(FilterToggle::ReverseOrder);
+                    self.filters.toggle(FilterToggle::Order);
+                    true
+                }
+                KeyCode::Char('x') => {
+                    self.filters.toggle(FilterToggle::Diffs);
+                    true
+                }
+                KeyCode::Char('s') => {
+                    self.filters.toggle(FilterToggle::Sizes);
+                    true
+                }
+                KeyCode::Char('u') => {
+                    self.filters.toggle(FilterToggle::Used);
+                    true
+                }
+                KeyCode::Char('c') => {
+                    self.filters.toggle(FilterToggle::Collapse);
+                    true
+                }
+                KeyCode::Char('e') => {
+                    self.filters.toggle(FilterToggle::Expand);
+                    true
+                }
+                KeyCode::Char('w') => {
+                    self.filters.toggle(FilterToggle::Paraphrase);
+                    true
+                }
+                KeyCode::Char('B') => {
+                    self.filters.toggle(FilterToggle::Binders);
+                    true
+                }
+                KeyCode::Char('*') => {
+                    self.filters.toggle(FilterToggle::FirstGoal);
+                    true
+                }
+                KeyCode::Char('#') => {
+                    self.filters.toggle(FilterToggle::Boxes);
                     true
                 }
+                KeyCode::Char('d') => {
+                    self.filters.toggle(FilterToggle::Unused);
+                    true
+                }
+                KeyCode::Char('f') => self.cycle_mention(),
+                KeyCode::Char('o' | ' ') => self.toggle_focus(),
+                KeyCode::Esc if self.focused_goal.is_some() => self.toggle_focus(),
                 _ => false,
             },
             KeyMouseEvent::Mouse(mouse) => {
@@ -145,6 +303,7 @@ impl InteractiveComponent for PlainList {
         self.selection.clear_regions();
 
         let content_area = render_error(frame, area, self.error.as_deref());
+        let content_area = self.render_focus_header(frame, content_area);
 
         // Render open goal list and collect click regions
         let goal_list = OpenGoalList::new(
@@ -153,7 +312,11 @@ impl InteractiveComponent for PlainList {
             self.filters,
             self.current_node_id,
             self.active_goal_name.as_deref(),
-        );
+        )
+        .focused_goal(self.focused_goal)
+        .hyps_summary(true)
+        .unused_hypotheses(&self.unused_hyps)
+        .split_goals(&self.split_goals);
         let click_regions = goal_list.render_to_frame(frame, content_area);
 
         // Adjust click regions for error offset and add to selection
@@ -176,13 +339,38 @@ impl Mode for PlainList {
     type Model = PlainListInput;
 
     const NAME: &'static str = "Plain list";
-    const KEYBINDINGS: &'static [(&'static str, &'static str)] =
-        &[("i", "inst"), ("a", "access"), ("l", "let"), ("r", "rev")];
+    const KEYBINDINGS: &'static [(&'static str, &'static str)] = &[
+        ("i", "inst"),
+        ("a", "access"),
+        ("l", "let"),
+        ("r", "order"),
+        ("x", "diff"),
+        ("s", "size"),
+        ("u", "used"),
+        ("c", "collapse"),
+        ("o", "focus"),
+        ("f", "mentions"),
+        ("e", "expand"),
+        ("w", "words"),
+        ("B", "binders"),
+        ("*", "first goal"),
+        ("#", "boxes"),
+        ("d", "unused"),
+    ];
     const SUPPORTED_FILTERS: &'static [FilterToggle] = &[
         FilterToggle::Instances,
         FilterToggle::Inaccessible,
         FilterToggle::LetValues,
-        FilterToggle::ReverseOrder,
+        FilterToggle::Order,
+        FilterToggle::Diffs,
+        FilterToggle::Sizes,
+        FilterToggle::Used,
+        FilterToggle::Collapse,
+        FilterToggle::Paraphrase,
+        FilterToggle::Unused,
+        FilterToggle::Binders,
+        FilterToggle::FirstGoal,
+        FilterToggle::Boxes,
     ];
 
     fn current_selection(&self) -> Option<Selection> {
@@ -190,4 +378,171 @@ impl Mode for PlainList {
             .current_selection(&self.selectable_items())
             .copied()
     }
+
+    fn select_goal(&mut self, goal_idx: usize) -> bool {
+        let Some(node_id) = self.current_node_id else {
+            return false;
+        };
+        self.selection
+            .select(Selection::Goal { node_id, goal_idx }, &self.selectable_items())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, buffer::Cell, Terminal};
+
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo, ProofDagNode, TaggedText};
+
+    fn goal(text: &str) -> GoalInfo {
+        GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Focusing the second goal renders it alone.
+    #[test]
+    fn test_focus_shows_only_goal() {
+        let state = ProofState {
+            goals: vec![goal("first = 1"), goal("second = 2"), goal("third = 3")],
+            hypotheses: Vec::new(),
+        };
+        let dag = ProofDag {
+            nodes: vec![ProofDagNode::default()],
+            root: Some(0),
+            current_node: Some(0),
+            ..Default::default()
+        };
+        let mut mode = PlainList::default();
+        mode.update(PlainListInput {
+            state,
+            definition: None,
+            error: None,
+            proof_dag: Some(dag),
+            max_hyps: None,
+            diff_markers: DiffMarkers::default(),
+            selection_colors: SelectionColors::default(),
+            presentation: false,
+            rtl: false,
+        });
+
+        assert!(mode.select_goal(1));
+        let key = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE);
+        assert!(mode.handle_event(KeyMouseEvent::Key(key)));
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+        terminal.draw(|frame| mode.render(frame, frame.area())).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(Cell::symbol)
+            .collect();
+
+        assert!(text.contains("second = 2"));
+        assert!(!text.contains("first = 1"));
+        assert!(!text.contains("third = 3"));
+        assert!(text.contains("Focused on goal 2 of 3"));
+    }
+
+    /// `f` on a hypothesis cycles through the goals mentioning it.
+    #[test]
+    fn test_cycle_goals_mentioning_term() {
+        let state = ProofState {
+            goals: vec![goal("x + 1 = 2"), goal("xs = []"), goal("f x = y")],
+            hypotheses: vec![HypothesisInfo {
+                name: "x".to_string(),
+                type_: TaggedText::Text {
+                    text: "Nat".to_string(),
+                },
+                ..Default::default()
+            }],
+        };
+        let dag = ProofDag {
+            nodes: vec![ProofDagNode::default()],
+            root: Some(0),
+            current_node: Some(0),
+            ..Default::default()
+        };
+        let mut mode = PlainList::default();
+        mode.update(PlainListInput {
+            state,
+            definition: None,
+            error: None,
+            proof_dag: Some(dag),
+            max_hyps: None,
+            diff_markers: DiffMarkers::default(),
+            selection_colors: SelectionColors::default(),
+            presentation: false,
+            rtl: false,
+        });
+        let items = mode.selectable_items();
+        assert!(mode.selection.select(Selection::Hyp { node_id: 0, hyp_idx: 0 }, &items));
+
+        let mut press_f = || {
+            let key = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE);
+            assert!(mode.handle_event(KeyMouseEvent::Key(key)));
+            match mode.current_selection() {
+                Some(Selection::Goal { goal_idx, .. }) => goal_idx,
+                other => panic!("expected a goal, got {other:?}"),
+            }
+        };
+        assert_eq!(press_f(), 0);
+        assert_eq!(press_f(), 2);
+        assert_eq!(press_f(), 0, "wraps around, skipping `xs`");
+    }
+
+    /// `*` renders only the first goal, and again shows them all.
+    #[test]
+    fn test_first_goal_only() {
+        let state = ProofState {
+            goals: vec![goal("first = 1"), goal("second = 2")],
+            hypotheses: Vec::new(),
+        };
+        let dag = ProofDag {
+            nodes: vec![ProofDagNode::default()],
+            root: Some(0),
+            current_node: Some(0),
+            ..Default::default()
+        };
+        let mut mode = PlainList::default();
+        mode.update(PlainListInput {
+            state,
+            definition: None,
+            error: None,
+            proof_dag: Some(dag),
+            max_hyps: None,
+            diff_markers: DiffMarkers::default(),
+            selection_colors: SelectionColors::default(),
+            presentation: false,
+            rtl: false,
+        });
+        let render = |mode: &mut PlainList| -> String {
+            let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+            terminal.draw(|frame| mode.render(frame, frame.area())).unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(Cell::symbol)
+                .collect()
+        };
+        let star = KeyEvent::new(KeyCode::Char('*'), KeyModifiers::NONE);
+
+        assert!(mode.handle_event(KeyMouseEvent::Key(star)));
+        let text = render(&mut mode);
+        assert!(text.contains("first = 1"));
+        assert!(!text.contains("second = 2"));
+        assert_eq!(mode.selectable_items().len(), 1);
+
+        mode.handle_event(KeyMouseEvent::Key(star));
+        assert!(render(&mut mode).contains("second = 2"));
+    }
 }
@@ -1,6 +1,6 @@
 //! Steps mode - sidebar with proof steps plus hypotheses and goals sections.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, mem};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
@@ -10,10 +10,11 @@ use ratatui::{
 
 use super::Mode;
 use crate::{
-    lean_rpc::{ProofDag, ProofDagNode, ProofState},
+    lean_rpc::{annotate_after, ProofDag, ProofDagNode, ProofState},
     tui::{
         app::DefinitionInfo,
         widgets::{
+            diff_text::DiffMarkers,
             goal_section::{GoalSection, GoalSectionState},
             hyp_section::{HypSection, HypSectionState},
             hypothesis_indices,
@@ -21,7 +22,7 @@ use crate::{
             render_helpers::{render_error, render_no_goals},
             selection::SelectionState,
             tactic_row::divider,
-            theme::FocusedPane,
+            theme::{FocusedPane, SelectionColors},
             FilterToggle, HypothesisFilters, InteractiveComponent, InteractiveStatefulWidget,
             KeyMouseEvent, Selection,
         },
@@ -34,6 +35,31 @@ pub struct StepsModeInput {
     pub definition: Option<DefinitionInfo>,
     pub error: Option<String>,
     pub proof_dag: Option<ProofDag>,
+    pub selection_colors: SelectionColors,
+    pub diff_markers: DiffMarkers,
+}
+
+/// Sidebar width in sixteenths of the mode's width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SidebarRatio(u32);
+
+impl SidebarRatio {
+    const MIN: u32 = 2;
+    const MAX: u32 = 12;
+
+    const fn widen(self) -> Self {
+        Self(if self.0 < Self::MAX { self.0 + 1 } else { Self::MAX })
+    }
+
+    const fn narrow(self) -> Self {
+        Self(if self.0 > Self::MIN { self.0 - 1 } else { Self::MIN })
+    }
+}
+
+impl Default for SidebarRatio {
+    fn default() -> Self {
+        Self(6) // 3/8
+    }
 }
 
 /// Steps display mode - sidebar + hypotheses + goals.
@@ -52,8 +78,10 @@ pub struct TacticTree {
     selection: SelectionState,
     /// Which pane is currently focused for keyboard navigation.
     focused_pane: FocusedPane,
-    /// Last known sidebar viewport height (for scroll bounds).
-    sidebar_viewport_height: usize,
+    /// Sidebar share of the width, adjustable with `<`/`>`.
+    sidebar_ratio: SidebarRatio,
+    /// Whether `z` started a `zM`/`zR` fold command.
+    fold_prefix: bool,
 }
 
 impl TacticTree {
@@ -63,12 +91,13 @@ impl TacticTree {
         };
 
         // All hypotheses first, then all goals
-        let hyps = hypothesis_indices(self.state.hypotheses.len(), self.filters.reverse_order)
+        let hyps = hypothesis_indices(&self.state, self.filters.hyp_order)
+            .into_iter()
             .filter(|&i| {
-                self.state.hypotheses.get(i).is_some_and(|h| {
-                    (!self.filters.hide_instances || !h.is_instance)
-                        && (!self.filters.hide_inaccessible || !h.is_proof)
-                })
+                self.state
+                    .hypotheses
+                    .get(i)
+                    .is_some_and(|h| self.filters.should_show(h))
             })
             .map(move |hyp_idx| Selection::Hyp { node_id, hyp_idx });
 
@@ -88,9 +117,10 @@ impl TacticTree {
 
     fn layout_with_sidebar(&self, area: Rect) -> (Rect, Option<Rect>) {
         if self.has_steps() {
+            let SidebarRatio(sidebar_share) = self.sidebar_ratio;
             let [sidebar, main] = Layout::horizontal([
-                Constraint::Ratio(3, 8), // Sidebar takes 3/8 of width
-                Constraint::Ratio(5, 8), // Main content takes 5/8
+                Constraint::Ratio(sidebar_share, 16),
+                Constraint::Ratio(16 - sidebar_share, 16),
             ])
             .areas(area);
             (main, Some(sidebar))
@@ -115,7 +145,18 @@ impl TacticTree {
             .and_then(|dag| dag.current_node.and_then(|id| dag.get(id)))
     }
 
+    /// State the current step started from: its parent's goals, or its own
+    /// before-state at the root.
+    fn parent_state(&self) -> Option<&ProofState> {
+        let node = self.current_node()?;
+        let parent = node.parent.and_then(|id| self.proof_dag.as_ref()?.get(id));
+        Some(parent.map_or(&node.state_before, |parent| &parent.state_after))
+    }
+
     fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if mem::take(&mut self.fold_prefix) && self.handle_fold_all(key.code) {
+            return true;
+        }
         if self.handle_focus_switch(&key) {
             return true;
         }
@@ -164,7 +205,27 @@ impl TacticTree {
                 true
             }
             KeyCode::Char('r') => {
-                self.filters.toggle(FilterToggle::ReverseOrder);
+                self.filters.toggle(FilterToggle::Order);
+                true
+            }
+            KeyCode::Char('x') => {
+                self.filters.toggle(FilterToggle::Diffs);
+                true
+            }
+            KeyCode::Char('s') => {
+                self.filters.toggle(FilterToggle::Sizes);
+                true
+            }
+            KeyCode::Char('u') => {
+                self.filters.toggle(FilterToggle::Used);
+                true
+            }
+            KeyCode::Char('c') => {
+                self.filters.toggle(FilterToggle::Collapse);
+                true
+            }
+            KeyCode::Char('T') => {
+                self.filters.toggle(FilterToggle::TacticSummary);
                 true
             }
             _ => false,
@@ -180,12 +241,17 @@ impl TacticTree {
 
     fn handle_sidebar_navigation(&mut self, code: KeyCode) -> bool {
         match code {
+            KeyCode::Enter => self.sidebar_state.toggle_fold(),
+            KeyCode::Char('z') => {
+                self.fold_prefix = true;
+                true
+            }
             KeyCode::Char('j') | KeyCode::Down => {
-                self.sidebar_state.scroll_down(self.sidebar_viewport_height);
+                self.sidebar_state.select_next();
                 true
             }
             KeyCode::Char('k') | KeyCode::Up => {
-                self.sidebar_state.scroll_up();
+                self.sidebar_state.select_previous();
                 true
             }
             KeyCode::Char('h') | KeyCode::Left => {
@@ -196,10 +262,28 @@ impl TacticTree {
                 self.sidebar_state.scroll_right();
                 true
             }
+            KeyCode::Char('>') => {
+                self.sidebar_ratio = self.sidebar_ratio.widen();
+                true
+            }
+            KeyCode::Char('<') => {
+                self.sidebar_ratio = self.sidebar_ratio.narrow();
+                true
+            }
             _ => false,
         }
     }
 
+    /// Finish a `zM` (fold all) or `zR` (unfold all) command.
+    fn handle_fold_all(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('M') => self.sidebar_state.fold_all(),
+            KeyCode::Char('R') => self.sidebar_state.unfold_all(),
+            _ => return false,
+        }
+        true
+    }
+
     fn handle_selection_navigation(&mut self, code: KeyCode) -> bool {
         let items = self.selectable_items();
         match code {
@@ -235,11 +319,17 @@ impl InteractiveComponent for TacticTree {
     fn update(&mut self, input: Self::Input) {
         let state_changed = self.state.goals.len() != input.state.goals.len()
             || self.state.hypotheses.len() != input.state.hypotheses.len();
-        self.state = input.state;
         self.definition = input.definition;
         self.error = input.error;
+        self.filters.selection = input.selection_colors;
+        self.filters.diff_markers = input.diff_markers;
         self.current_node_id = input.proof_dag.as_ref().and_then(|dag| dag.current_node);
         self.proof_dag = input.proof_dag;
+        ProofStepsSidebar::update_state(&mut self.sidebar_state, self.proof_dag.clone());
+        self.state = match self.parent_state() {
+            Some(before) => annotate_after(&input.state, before),
+            None => input.state,
+        };
 
         if state_changed {
             self.selection.reset(self.selectable_items().len());
@@ -268,11 +358,11 @@ impl InteractiveComponent for TacticTree {
 
         // Render sidebar if present
         if let Some(sidebar_area) = sidebar {
-            ProofStepsSidebar::update_state(&mut self.sidebar_state, self.proof_dag.clone());
             self.sidebar_state
                 .set_focused(self.focused_pane == FocusedPane::Sidebar);
-            // Store viewport height for scroll bounds
-            self.sidebar_viewport_height = sidebar_area.height.saturating_sub(2) as usize;
+            self.sidebar_state
+                .set_summarize_tactics(self.filters.tactic_summary);
+            self.sidebar_state.set_selection_colors(self.filters.selection);
             frame.render_stateful_widget(ProofStepsSidebar, sidebar_area, &mut self.sidebar_state);
         }
 
@@ -328,6 +418,11 @@ impl InteractiveComponent for TacticTree {
         );
         self.goal_section_state
             .set_focused(self.focused_pane == FocusedPane::Goals);
+        self.goal_section_state
+            .set_diff_markers((!self.filters.hide_diffs).then_some(self.filters.diff_markers));
+        self.goal_section_state
+            .set_collapse_inactive(self.filters.collapse_inactive);
+        self.goal_section_state.set_selection_colors(self.filters.selection);
         frame.render_stateful_widget(GoalSection, layout.goals, &mut self.goal_section_state);
         for region in self.goal_section_state.click_regions() {
             self.selection.add_region(region.area, region.selection);
@@ -340,21 +435,266 @@ impl Mode for TacticTree {
     const NAME: &'static str = "Tactic tree";
     const KEYBINDINGS: &'static [(&'static str, &'static str)] = &[
         ("Tab", "pane"),
+        ("Enter", "fold"),
+        ("zM/zR", "fold/unfold all"),
+        ("<>", "width"),
         ("i", "inst"),
         ("a", "access"),
         ("l", "let"),
-        ("r", "rev"),
+        ("r", "order"),
+        ("x", "diff"),
+        ("s", "size"),
+        ("u", "used"),
+        ("c", "collapse"),
+        ("T", "one-line tactics"),
     ];
     const SUPPORTED_FILTERS: &'static [FilterToggle] = &[
         FilterToggle::Instances,
         FilterToggle::Inaccessible,
         FilterToggle::LetValues,
-        FilterToggle::ReverseOrder,
+        FilterToggle::Order,
+        FilterToggle::Diffs,
+        FilterToggle::Sizes,
+        FilterToggle::Used,
+        FilterToggle::Collapse,
+        FilterToggle::TacticSummary,
     ];
 
     fn current_selection(&self) -> Option<Selection> {
+        if self.focused_pane == FocusedPane::Sidebar {
+            return self
+                .sidebar_state
+                .selected_node()
+                .map(|node_id| Selection::Node { node_id });
+        }
         self.selection
             .current_selection(&self.selectable_items())
             .copied()
     }
+
+    fn select_goal(&mut self, goal_idx: usize) -> bool {
+        let Some(node_id) = self.current_node_id else {
+            return false;
+        };
+        let selected = self
+            .selection
+            .select(Selection::Goal { node_id, goal_idx }, &self.selectable_items());
+        if selected {
+            self.focused_pane = FocusedPane::Goals;
+        }
+        selected
+    }
+
+    fn awaits_key(&self) -> bool {
+        self.fold_prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::HypothesisInfo;
+
+    fn press(mode: &mut TacticTree, c: char) {
+        mode.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+
+    /// `<`/`>` on the focused sidebar resize it within bounds.
+    #[test]
+    fn test_sidebar_resize() {
+        let mut mode = TacticTree {
+            proof_dag: Some(ProofDag {
+                nodes: vec![ProofDagNode::default()],
+                root: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 160, 40);
+        let width = |mode: &TacticTree| mode.layout_with_sidebar(area).1.unwrap().width;
+
+        assert_eq!(width(&mode), 60);
+        press(&mut mode, '>');
+        assert_eq!(width(&mode), 70);
+
+        for _ in 0..20 {
+            press(&mut mode, '<');
+        }
+        assert_eq!(width(&mode), 20, "clamped to the minimum");
+    }
+
+    /// Stepping to a node that introduces a hypothesis marks it as added.
+    #[test]
+    fn test_new_hypothesis_marked_inserted() {
+        let hyp = |name: &str| HypothesisInfo {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let parent = ProofDagNode {
+            state_after: ProofState {
+                goals: Vec::new(),
+                hypotheses: vec![hyp("n")],
+            },
+            children: vec![1],
+            ..Default::default()
+        };
+        let child_state = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![hyp("n"), hyp("h")],
+        };
+        let child = ProofDagNode {
+            id: 1,
+            state_after: child_state.clone(),
+            parent: Some(0),
+            ..Default::default()
+        };
+
+        let mut mode = TacticTree::default();
+        mode.update(StepsModeInput {
+            state: child_state,
+            definition: None,
+            error: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![parent, child],
+                root: Some(0),
+                current_node: Some(1),
+                ..Default::default()
+            }),
+            selection_colors: SelectionColors::default(),
+            diff_markers: DiffMarkers::default(),
+        });
+
+        assert!(!mode.state.hypotheses[0].is_inserted);
+        assert!(mode.state.hypotheses[1].is_inserted, "h is new in this step");
+    }
+
+    /// `j` on the sidebar selects the next step, standing for its tactic.
+    #[test]
+    fn test_sidebar_selects_step() {
+        let root = ProofDagNode {
+            children: vec![1],
+            ..Default::default()
+        };
+        let child = ProofDagNode {
+            id: 1,
+            parent: Some(0),
+            depth: 1,
+            ..Default::default()
+        };
+        let mut mode = TacticTree::default();
+        mode.update(StepsModeInput {
+            state: ProofState::default(),
+            definition: None,
+            error: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![root, child],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+            selection_colors: SelectionColors::default(),
+            diff_markers: DiffMarkers::default(),
+        });
+
+        assert_eq!(mode.current_selection(), Some(Selection::Node { node_id: 0 }));
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), Some(Selection::Node { node_id: 1 }));
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), Some(Selection::Node { node_id: 1 }));
+    }
+
+    /// Enter on the focused sidebar folds the selected step instead of
+    /// navigating, and passes through on a step without substeps.
+    #[test]
+    fn test_enter_toggles_fold_in_sidebar() {
+        let root = ProofDagNode {
+            children: vec![1],
+            ..Default::default()
+        };
+        let child = ProofDagNode {
+            id: 1,
+            parent: Some(0),
+            depth: 1,
+            ..Default::default()
+        };
+        let mut mode = TacticTree::default();
+        mode.update(StepsModeInput {
+            state: ProofState::default(),
+            definition: None,
+            error: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![root, child],
+                root: Some(0),
+                current_node: Some(0),
+                ..Default::default()
+            }),
+            selection_colors: SelectionColors::default(),
+            diff_markers: DiffMarkers::default(),
+        });
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        assert!(mode.handle_key(enter), "the fold consumes Enter");
+        press(&mut mode, 'j');
+        assert_eq!(
+            mode.current_selection(),
+            Some(Selection::Node { node_id: 0 }),
+            "the substep is hidden"
+        );
+
+        assert!(mode.handle_key(enter));
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), Some(Selection::Node { node_id: 1 }));
+        assert!(!mode.handle_key(enter), "a leaf leaves Enter to goto");
+    }
+
+    /// `zM` folds every step with substeps, nested ones included, and `zR`
+    /// unfolds them all.
+    #[test]
+    fn test_fold_and_unfold_all() {
+        let step = |id: u32, parent: Option<u32>, children: Vec<u32>| ProofDagNode {
+            id,
+            parent,
+            children,
+            depth: id as usize,
+            ..Default::default()
+        };
+        let mut mode = TacticTree::default();
+        mode.update(StepsModeInput {
+            state: ProofState::default(),
+            definition: None,
+            error: None,
+            proof_dag: Some(ProofDag {
+                nodes: vec![
+                    step(0, None, vec![1]),
+                    step(1, Some(0), vec![2]),
+                    step(2, Some(1), vec![]),
+                ],
+                root: Some(0),
+                current_node: Some(2),
+                ..Default::default()
+            }),
+            selection_colors: SelectionColors::default(),
+            diff_markers: DiffMarkers::default(),
+        });
+        let node = |node_id| Some(Selection::Node { node_id });
+
+        press(&mut mode, 'z');
+        assert!(mode.awaits_key());
+        press(&mut mode, 'M');
+        assert!(!mode.awaits_key());
+        assert_eq!(mode.current_selection(), node(0), "moved out of the fold");
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), node(0), "all below hidden");
+
+        // Unfolding the root alone shows its child, itself still folded
+        mode.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        press(&mut mode, 'j');
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), node(1));
+
+        press(&mut mode, 'z');
+        press(&mut mode, 'R');
+        press(&mut mode, 'j');
+        assert_eq!(mode.current_selection(), node(2));
+    }
 }
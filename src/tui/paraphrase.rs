@@ -0,0 +1,191 @@
+//! Rule-based English paraphrase of simple goals, as a teaching aid, and the
+//! binder splitting of quantified goals.
+//!
+//! The rules work on the pretty-printed target and only recognize a few
+//! top-level shapes; any part they do not recognize is kept as Lean text.
+
+/// Binary connectives from loosest to tightest, with their phrasing.
+const CONNECTIVES: &[(&str, &str)] = &[
+    (" ↔ ", "if and only if"),
+    (" → ", "implies"),
+    (" ∨ ", "or"),
+    (" ∧ ", "and"),
+    (" = ", "equals"),
+    (" ≠ ", "does not equal"),
+    (" ≤ ", "is at most"),
+    (" < ", "is less than"),
+    (" ≥ ", "is at least"),
+    (" > ", "is greater than"),
+];
+
+/// Paraphrase a goal target, or `None` when no rule applies to it.
+pub fn paraphrase(target: &str) -> Option<String> {
+    let target = target.trim();
+    if let Some(body) = target.strip_prefix("∀ ") {
+        let (binders, body) = split_binders(body)?;
+        return Some(format!("for all {binders}, {}", phrase(body)));
+    }
+    if let Some(body) = target.strip_prefix("∃ ") {
+        let (binders, body) = split_binders(body)?;
+        return Some(format!("there exists {binders} such that {}", phrase(body)));
+    }
+    if let Some(negated) = target.strip_prefix('¬') {
+        return Some(format!("not {}", phrase(negated)));
+    }
+    for (op, words) in CONNECTIVES {
+        if let Some((lhs, rhs)) = split_top_level(target, op) {
+            return Some(format!("{} {words} {}", phrase(lhs), phrase(rhs)));
+        }
+    }
+    application(target)
+}
+
+/// Paraphrase of `text` if a rule applies, otherwise the text itself.
+fn phrase(text: &str) -> String {
+    paraphrase(text).unwrap_or_else(|| text.trim().to_string())
+}
+
+/// Split `x y, body` at the comma ending the binders.
+fn split_binders(text: &str) -> Option<(&str, &str)> {
+    split_top_level(text, ", ")
+}
+
+/// Split at the first `op` outside brackets. Binders extend to the end of
+/// the text, so an `op` after one belongs to its body and is not a split.
+fn split_top_level<'a>(text: &'a str, op: &str) -> Option<(&'a str, &'a str)> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' | '⟨' => depth += 1,
+            ')' | ']' | '}' | '⟩' => depth = depth.saturating_sub(1),
+            '∀' | '∃' | 'λ' if depth == 0 && i > 0 => return None,
+            _ if depth == 0 && text[i..].starts_with(op) => {
+                return Some((&text[..i], &text[i + op.len()..]));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Leading `∀`/`∃` binders of a target, one per group, and the body after
+/// them, e.g. `∀ (x : α) (y : β), P` as `["∀ (x : α)", "∀ (y : β)"]` and
+/// `P`. `None` when the target does not start with a quantifier.
+pub fn split_quantifiers(target: &str) -> Option<(Vec<String>, &str)> {
+    let mut binders = Vec::new();
+    let mut body = target.trim();
+    while let Some((quantifier, rest)) = strip_quantifier(body) {
+        let Some((groups, rest)) = split_binders(rest) else {
+            break;
+        };
+        binders.extend(
+            binder_groups(groups)
+                .into_iter()
+                .map(|group| format!("{quantifier} {group}")),
+        );
+        body = rest.trim();
+    }
+    (!binders.is_empty()).then_some((binders, body))
+}
+
+/// The quantifier a text starts with and the text after it.
+fn strip_quantifier(text: &str) -> Option<(&'static str, &str)> {
+    ["∀", "∃"]
+        .into_iter()
+        .find_map(|q| Some((q, text.strip_prefix(q)?.strip_prefix(' ')?)))
+}
+
+/// Bracketed binders each make a group, as do bare names unless a type is
+/// given for them all, as in `x y : ℕ`.
+fn binder_groups(text: &str) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' | '⦃' => depth += 1,
+            ')' | ']' | '}' | '⦄' => depth = depth.saturating_sub(1),
+            ' ' if depth == 0 => {
+                groups.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    groups.push(&text[start..]);
+    groups.retain(|group| !group.is_empty());
+    if groups.contains(&":") {
+        vec![text.trim()]
+    } else {
+        groups
+    }
+}
+
+/// `P x` as "P of x", when the head and arguments are plain names.
+fn application(text: &str) -> Option<String> {
+    let mut words = text.split(' ');
+    let head = words.next()?;
+    let args: Vec<&str> = words.collect();
+    let is_name = |word: &str| {
+        !word.is_empty()
+            && word
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '\''))
+    };
+    let applies = head.starts_with(char::is_alphabetic)
+        && !args.is_empty()
+        && is_name(head)
+        && args.iter().all(|arg| is_name(arg));
+    applies.then(|| format!("{head} of {}", args.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Equations read as "equals", with unrecognized sides kept as Lean.
+    #[test]
+    fn test_equality_rule() {
+        assert_eq!(paraphrase("a = b").as_deref(), Some("a equals b"));
+        assert_eq!(paraphrase("a + 0 = a").as_deref(), Some("a + 0 equals a"));
+        assert_eq!(paraphrase("a + 0"), None);
+    }
+
+    /// A universal statement names its binders and phrases its body.
+    #[test]
+    fn test_forall_rule() {
+        assert_eq!(paraphrase("∀ x, P x").as_deref(), Some("for all x, P of x"));
+        assert_eq!(
+            paraphrase("∀ (n : ℕ), n ≤ n + 1").as_deref(),
+            Some("for all (n : ℕ), n is at most n + 1")
+        );
+    }
+
+    /// Arrows read as "implies"; an arrow under a binder stays in its body.
+    #[test]
+    fn test_implication_rule() {
+        assert_eq!(paraphrase("A → B").as_deref(), Some("A implies B"));
+        assert_eq!(
+            paraphrase("p ∧ q → ∀ x, q → p").as_deref(),
+            Some("p and q implies for all x, q implies p")
+        );
+        assert_eq!(
+            paraphrase("(A → B) = C").as_deref(),
+            Some("(A → B) equals C")
+        );
+    }
+
+    /// Each binder group gets its own line, nested quantifiers included;
+    /// names sharing a type stay together.
+    #[test]
+    fn test_split_quantifiers() {
+        let (binders, body) = split_quantifiers("∀ (x : α) (y : β), P x y").unwrap();
+        assert_eq!(binders, ["∀ (x : α)", "∀ (y : β)"]);
+        assert_eq!(body, "P x y");
+
+        let (binders, body) = split_quantifiers("∀ x y : ℕ, ∃ z, x + y = z").unwrap();
+        assert_eq!(binders, ["∀ x y : ℕ", "∃ z"]);
+        assert_eq!(body, "x + y = z");
+
+        assert_eq!(split_quantifiers("a = b"), None);
+    }
+}
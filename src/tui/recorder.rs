@@ -0,0 +1,121 @@
+//! `--record`: appends every distinct goal state the TUI receives to a JSONL
+//! file, one timestamped record per line.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_lsp::lsp_types::Url;
+use serde_json::json;
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncWriteExt, BufWriter},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+use crate::{lean_rpc::ProofState, tui_ipc::Position};
+
+/// Turns goal updates into records for a writer task, so the UI never
+/// waits on the file.
+pub struct Recorder {
+    lines: UnboundedSender<String>,
+    /// Goals of the last record, to skip updates that change nothing.
+    last: Option<String>,
+}
+
+impl Recorder {
+    pub const fn new(lines: UnboundedSender<String>) -> Self {
+        Self { lines, last: None }
+    }
+
+    /// Record `state` unless its goals are those recorded last.
+    pub fn record(&mut self, uri: &Url, position: Position, state: &ProofState) {
+        let goals = serde_json::to_value(state).unwrap_or_default();
+        let key = goals.to_string();
+        if self.last.as_ref() == Some(&key) {
+            return;
+        }
+        self.last = Some(key);
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let record = json!({
+            "timestampMs": timestamp_ms,
+            "uri": uri,
+            "position": position,
+            "state": goals,
+        });
+        let _ = self.lines.send(record.to_string());
+    }
+}
+
+/// Start appending records to `path` in the background.
+pub fn spawn_recorder(path: PathBuf) -> Recorder {
+    let (tx, mut rx) = unbounded_channel::<String>();
+    tokio::spawn(async move {
+        if let Err(e) = write_records(&path, &mut rx).await {
+            tracing::error!("Recording to {} failed: {e}", path.display());
+        }
+    });
+    Recorder::new(tx)
+}
+
+async fn write_records(path: &Path, rx: &mut UnboundedReceiver<String>) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let mut writer = BufWriter::new(file);
+    while let Some(line) = rx.recv().await {
+        writer.write_all(line.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        // Flush once the burst of queued records is written
+        if rx.is_empty() {
+            writer.flush().await?;
+        }
+    }
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, TaggedText};
+
+    fn state(target: &str) -> ProofState {
+        ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Text {
+                    text: target.to_string(),
+                },
+                ..Default::default()
+            }],
+            hypotheses: Vec::new(),
+        }
+    }
+
+    /// Two distinct goal updates make two records; repeating one adds none.
+    #[test]
+    fn test_distinct_updates_recorded() {
+        let (tx, mut rx) = unbounded_channel();
+        let mut recorder = Recorder::new(tx);
+        let uri = Url::parse("file:///tmp/Test.lean").unwrap();
+
+        recorder.record(&uri, Position::new(1, 2), &state("P"));
+        recorder.record(&uri, Position::new(1, 4), &state("P"));
+        recorder.record(&uri, Position::new(2, 2), &state("Q"));
+
+        let mut records = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            records.push(serde_json::from_str::<Value>(&line).unwrap());
+        }
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["position"]["line"], 2);
+        assert!(records[0]["timestampMs"].is_u64());
+    }
+}
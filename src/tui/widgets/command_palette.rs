@@ -0,0 +1,259 @@
+//! Command palette (`Ctrl-p`): every action by name, filtered as you type.
+
+use std::iter;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, StatefulWidget, Widget},
+};
+
+use super::{theme::SelectionColors, InteractiveStatefulWidget, KeyEvent};
+use crate::tui::action::Action;
+
+/// Most entries shown at once.
+const MAX_VISIBLE_ENTRIES: usize = 12;
+
+/// One action offered by the palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub action: Action,
+    pub key: String,
+    pub description: String,
+}
+
+impl PaletteEntry {
+    /// Entry for a global action.
+    pub fn global(action: Action) -> Self {
+        Self {
+            action,
+            key: action.key(),
+            description: action.description().to_string(),
+        }
+    }
+}
+
+/// Whether the characters of `query` appear in order in `text`, ignoring
+/// case.
+fn fuzzy_matches(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| text.any(|t| t == q))
+}
+
+/// State for the command palette popup.
+#[derive(Default)]
+pub struct CommandPalette {
+    /// Entries to choose from, while open.
+    entries: Option<Vec<PaletteEntry>>,
+    query: String,
+    selected: usize,
+    action: Option<Action>,
+    selection_colors: SelectionColors,
+}
+
+impl CommandPalette {
+    /// Open with an empty query over `entries`.
+    pub fn open(&mut self, entries: Vec<PaletteEntry>) {
+        self.entries = Some(entries);
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Entries matching the query, in their original order.
+    pub fn visible(&self) -> Vec<&PaletteEntry> {
+        self.entries
+            .iter()
+            .flatten()
+            .filter(|entry| {
+                fuzzy_matches(&self.query, &entry.description) || entry.key == self.query
+            })
+            .collect()
+    }
+
+    /// Take the action chosen with the last key, if any.
+    pub const fn take_action(&mut self) -> Option<Action> {
+        self.action.take()
+    }
+
+    pub const fn set_selection_colors(&mut self, colors: SelectionColors) {
+        self.selection_colors = colors;
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let prompt = Line::from(vec![
+            Span::styled("> ", Style::new().fg(Color::Cyan)),
+            Span::raw(self.query.clone()),
+            Span::styled("█", Style::new().fg(Color::DarkGray)),
+        ]);
+        let visible = self.visible();
+        if visible.is_empty() {
+            let none = Line::styled("No matching actions", Style::new().fg(Color::DarkGray));
+            return vec![prompt, none];
+        }
+        let first = self.selected.saturating_sub(MAX_VISIBLE_ENTRIES - 1);
+        let entries = visible
+            .into_iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_VISIBLE_ENTRIES)
+            .map(|(i, entry)| {
+                self.selection_colors.highlight(entry_line(entry), i == self.selected)
+            });
+        iter::once(prompt).chain(entries).collect()
+    }
+}
+
+/// One entry: the description and, dimmed, its key.
+fn entry_line(entry: &PaletteEntry) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{:<8}", entry.key), Style::new().fg(Color::Cyan)),
+        Span::raw(entry.description.clone()),
+    ])
+}
+
+/// Widget for rendering the command palette popup.
+pub struct CommandPaletteWidget;
+
+impl StatefulWidget for CommandPaletteWidget {
+    type State = CommandPalette;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        if state.entries.is_none() {
+            return;
+        }
+
+        let lines = state.lines();
+        let width = area.width.saturating_sub(4).min(50);
+        let height = u16::try_from(lines.len())
+            .unwrap_or(u16::MAX)
+            .saturating_add(2);
+        // Kept inside `area` so short terminals cut the list instead
+        let popup_area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + area.height / 6,
+            width,
+            height,
+        )
+        .intersection(area);
+
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .title(" Commands ")
+            .border_style(Style::new().fg(Color::Cyan));
+        Paragraph::new(lines).block(block).render(popup_area, buf);
+    }
+}
+
+impl InteractiveStatefulWidget for CommandPaletteWidget {
+    type Input = ();
+    type Event = KeyEvent;
+
+    fn update_state(_state: &mut Self::State, _input: Self::Input) {}
+
+    /// While open, every key belongs to the popup; Ctrl chords it doesn't
+    /// bind are swallowed rather than typed.
+    fn handle_event(state: &mut Self::State, event: Self::Event) -> bool {
+        if state.entries.is_none() {
+            return false;
+        }
+
+        match event.code {
+            KeyCode::Esc => state.entries = None,
+            KeyCode::Enter => {
+                state.action = state
+                    .visible()
+                    .get(state.selected)
+                    .map(|entry| entry.action);
+                state.entries = None;
+            }
+            KeyCode::Down => {
+                let last = state.visible().len().saturating_sub(1);
+                state.selected = (state.selected + 1).min(last);
+            }
+            KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+            KeyCode::Backspace => {
+                state.query.pop();
+                state.selected = 0;
+            }
+            KeyCode::Char(_) if event.modifiers.contains(KeyModifiers::CONTROL) => {}
+            KeyCode::Char(c) => {
+                state.query.push(c);
+                state.selected = 0;
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(palette: &mut CommandPalette, keys: &str) {
+        for c in keys.chars() {
+            let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            CommandPaletteWidget::handle_event(palette, key);
+        }
+    }
+
+    /// The palette lists every global action, and typing narrows the list
+    /// to the fuzzy matches; Enter picks the first.
+    #[test]
+    fn test_palette_filters_actions() {
+        let mut palette = CommandPalette::default();
+        palette.open(
+            Action::GLOBAL
+                .iter()
+                .copied()
+                .map(PaletteEntry::global)
+                .collect(),
+        );
+
+        let listed: Vec<Action> = palette.visible().iter().map(|e| e.action).collect();
+        assert_eq!(listed, Action::GLOBAL);
+        assert!(listed.contains(&Action::Quit));
+        assert!(listed.contains(&Action::ExportHtml));
+
+        type_keys(&mut palette, "htm");
+        let listed: Vec<Action> = palette.visible().iter().map(|e| e.action).collect();
+        assert_eq!(listed, [Action::ExportHtml]);
+
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        CommandPaletteWidget::handle_event(&mut palette, enter);
+        assert_eq!(palette.take_action(), Some(Action::ExportHtml));
+        assert!(palette.visible().is_empty(), "closed after choosing");
+    }
+
+    /// On a terminal shorter than the list the popup is cut at the bottom,
+    /// and Ctrl chords are neither typed nor passed on.
+    #[test]
+    fn test_palette_small_terminal() {
+        let mut palette = CommandPalette::default();
+        palette.open(Action::GLOBAL.iter().copied().map(PaletteEntry::global).collect());
+
+        // Rendered into a taller buffer, so drawing past `area` would show
+        let area = Rect::new(0, 0, 80, 12);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 24));
+        CommandPaletteWidget.render(area, &mut buf, &mut palette);
+        let rows: Vec<String> = (0..buf.area.height)
+            .map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        let top = rows.iter().position(|row| row.contains('┌')).unwrap();
+        let bottom = rows.iter().position(|row| row.contains('└')).unwrap();
+        assert_eq!(top, 2);
+        assert_eq!(bottom + 1, usize::from(area.height));
+        assert!(rows[bottom + 1..].iter().all(|row| row.trim().is_empty()));
+
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert!(CommandPaletteWidget::handle_event(&mut palette, ctrl_x));
+        assert!(palette.query.is_empty());
+    }
+}
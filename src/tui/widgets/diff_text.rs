@@ -1,30 +1,138 @@
 //! Diff-aware text rendering.
 
+use std::str::FromStr;
+
 use ratatui::{
     style::{Color, Modifier, Style},
-    text::Span,
+    text::{Line, Span},
 };
 
+use super::theme::SelectionColors;
 use crate::lean_rpc::{DiffTag, TaggedText};
 
-pub const fn diff_tag_style(tag: DiffTag, base_style: Style) -> Style {
-    match tag {
-        DiffTag::WasChanged | DiffTag::WillChange => base_style.fg(Color::Yellow),
-        DiffTag::WasInserted | DiffTag::WillInsert => base_style.fg(Color::Green),
-        DiffTag::WasDeleted | DiffTag::WillDelete => {
-            base_style.fg(Color::Red).add_modifier(Modifier::DIM)
+/// Markers in front of diffed hypotheses, and the colors of diffed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffMarkers {
+    pub inserted: &'static str,
+    pub removed: &'static str,
+    pub changed: &'static str,
+    pub inserted_color: Color,
+    pub removed_color: Color,
+    pub changed_color: Color,
+}
+
+impl DiffMarkers {
+    pub const GIT: Self = Self {
+        inserted: "+",
+        removed: "-",
+        changed: "~",
+        inserted_color: Color::Green,
+        removed_color: Color::Red,
+        changed_color: Color::Yellow,
+    };
+    pub const BRACKETS: Self = Self {
+        inserted: "[+]",
+        removed: "[-]",
+        changed: "[~]",
+        ..Self::GIT
+    };
+    pub const ARROWS: Self = Self {
+        inserted: "▲",
+        removed: "▼",
+        changed: "◆",
+        ..Self::GIT
+    };
+
+    /// Preset named on the command line, optionally followed by a colon and
+    /// the inserted, removed and changed colors, e.g. `arrows:cyan,magenta,yellow`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, colors) = spec.split_once(':').unwrap_or((spec, ""));
+        let markers = match name {
+            "git" => Self::GIT,
+            "brackets" => Self::BRACKETS,
+            "arrows" => Self::ARROWS,
+            _ => {
+                return Err(format!(
+                    "unknown marker style `{name}` (git, brackets, arrows)"
+                ))
+            }
+        };
+        if colors.is_empty() {
+            return Ok(markers);
         }
+        let colors = colors
+            .split(',')
+            .map(|name| {
+                Color::from_str(name.trim()).map_err(|_| format!("unknown color `{}`", name.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let [inserted_color, removed_color, changed_color] = colors[..] else {
+            return Err("expected three colors: inserted, removed, changed".to_string());
+        };
+        Ok(Self {
+            inserted_color,
+            removed_color,
+            changed_color,
+            ..markers
+        })
+    }
+
+    /// Marker for an item, if it is part of a diff.
+    pub const fn marker(
+        self,
+        is_inserted: bool,
+        is_removed: bool,
+        is_changed: bool,
+    ) -> Option<&'static str> {
+        if is_removed {
+            Some(self.removed)
+        } else if is_inserted {
+            Some(self.inserted)
+        } else if is_changed {
+            Some(self.changed)
+        } else {
+            None
+        }
+    }
+
+    /// Width of the widest marker, which unmarked items are padded to.
+    pub fn width(self) -> usize {
+        [self.inserted, self.removed, self.changed]
+            .iter()
+            .map(|&marker| Span::raw(marker).width())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Style of a removed item.
+    pub const fn removed_style(self) -> Style {
+        Style::new()
+            .fg(self.removed_color)
+            .add_modifier(Modifier::CROSSED_OUT)
     }
 }
 
-pub const fn item_style(is_selected: bool, fg_color: Color) -> Style {
-    if is_selected {
-        Style::new().fg(fg_color).add_modifier(Modifier::UNDERLINED)
-    } else {
-        Style::new().fg(fg_color)
+impl Default for DiffMarkers {
+    fn default() -> Self {
+        Self::GIT
     }
 }
 
+/// Style of text tagged `tag`, in the colors of `markers`.
+pub const fn diff_tag_style(tag: DiffTag, base_style: Style, markers: DiffMarkers) -> Style {
+    match tag {
+        DiffTag::WasChanged | DiffTag::WillChange => base_style.fg(markers.changed_color),
+        DiffTag::WasInserted | DiffTag::WillInsert => base_style.fg(markers.inserted_color),
+        DiffTag::WasDeleted | DiffTag::WillDelete => base_style
+            .fg(markers.removed_color)
+            .add_modifier(Modifier::DIM),
+    }
+}
+
+pub fn item_style(is_selected: bool, fg_color: Color, selection: SelectionColors) -> Style {
+    selection.apply(Style::new().fg(fg_color), is_selected)
+}
+
 pub struct DiffState {
     pub is_inserted: bool,
     pub is_removed: bool,
@@ -34,39 +142,137 @@ pub struct DiffStyle {
     pub style: Style,
 }
 
-pub const fn diff_style(state: &DiffState, is_selected: bool, base_color: Color) -> DiffStyle {
+pub fn diff_style(
+    state: &DiffState,
+    is_selected: bool,
+    base_color: Color,
+    markers: DiffMarkers,
+    selection: SelectionColors,
+) -> DiffStyle {
     if state.is_inserted {
         DiffStyle {
-            style: item_style(is_selected, Color::Green),
+            style: item_style(is_selected, markers.inserted_color, selection),
         }
     } else if state.is_removed {
         DiffStyle {
-            style: item_style(is_selected, Color::Red).add_modifier(Modifier::CROSSED_OUT),
+            style: item_style(is_selected, markers.removed_color, selection)
+                .add_modifier(Modifier::CROSSED_OUT),
         }
     } else  {
         DiffStyle {
-            style: item_style(is_selected, base_color),
+            style: item_style(is_selected, base_color, selection),
         }
     } }
 
 pub trait TaggedTextExt {
-    fn to_spans(&self, base_style: Style) -> Vec<Span<'static>>;
+    /// Spans of the text, with diffed parts in the colors of `markers`.
+    fn to_spans(&self, base_style: Style, markers: DiffMarkers) -> Vec<Span<'static>>;
+
+    /// Like `to_spans`, but a single plain span when diffs are hidden, that
+    /// is without `markers`.
+    fn to_diff_spans(&self, base_style: Style, markers: Option<DiffMarkers>)
+        -> Vec<Span<'static>>;
+
+    /// The `Text`/`Tag`/`Append` structure as an indented outline, one node
+    /// per line, with tags showing their sub-expression position.
+    fn to_tree_lines(&self) -> Vec<Line<'static>>;
+}
+
+/// Append the outline of `text` at `depth` to `lines`.
+fn push_tree_lines(text: &TaggedText, depth: usize, lines: &mut Vec<Line<'static>>) {
+    let indent = Span::raw("  ".repeat(depth));
+    let kind = |name: &'static str| Span::styled(name, Style::new().fg(Color::Blue));
+    match text {
+        TaggedText::Text { text } => lines.push(Line::from(vec![
+            indent,
+            kind("Text "),
+            Span::styled(format!("{text:?}"), Style::new().fg(Color::White)),
+        ])),
+        TaggedText::Tag { info, content } => {
+            let mut spans = vec![indent, kind("Tag")];
+            if let Some(pos) = info.raw.get("subexprPos").and_then(|pos| pos.as_str()) {
+                spans.push(Span::styled(format!(" {pos}"), Style::new().fg(Color::Cyan)));
+            }
+            if let Some(tag) = info.diff_status {
+                spans.push(Span::styled(
+                    format!(" {tag:?}"),
+                    diff_tag_style(tag, Style::new(), DiffMarkers::GIT),
+                ));
+            }
+            lines.push(Line::from(spans));
+            push_tree_lines(content, depth + 1, lines);
+        }
+        TaggedText::Append { items } => {
+            lines.push(Line::from(vec![indent, kind("Append")]));
+            for item in items {
+                push_tree_lines(item, depth + 1, lines);
+            }
+        }
+    }
 }
 
 impl TaggedTextExt for TaggedText {
-    fn to_spans(&self, base_style: Style) -> Vec<Span<'static>> {
+    fn to_spans(&self, base_style: Style, markers: DiffMarkers) -> Vec<Span<'static>> {
         match self {
             Self::Text { text } => vec![Span::styled(text.clone(), base_style)],
             Self::Tag { info, content } => {
-                let style = info
-                    .diff_status
-                    .map_or(base_style, |tag| diff_tag_style(tag, base_style));
-                content.to_spans(style)
+                let style = info.diff_status.map_or(base_style, |tag| {
+                    diff_tag_style(tag, base_style, markers)
+                });
+                content.to_spans(style, markers)
             }
             Self::Append { items } => items
                 .iter()
-                .flat_map(|item| item.to_spans(base_style))
+                .flat_map(|item| item.to_spans(base_style, markers))
                 .collect(),
         }
     }
+
+    fn to_diff_spans(
+        &self,
+        base_style: Style,
+        markers: Option<DiffMarkers>,
+    ) -> Vec<Span<'static>> {
+        markers.map_or_else(
+            || vec![Span::styled(self.to_plain_text(), base_style)],
+            |markers| self.to_spans(base_style, markers),
+        )
+    }
+
+    fn to_tree_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        push_tree_lines(self, 0, &mut lines);
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::SubexprInfo;
+
+    /// Each node is a line, indented under the node that contains it.
+    #[test]
+    fn test_tree_lines_indent_children() {
+        let mut info = SubexprInfo::default();
+        info.raw.insert("subexprPos".to_string(), "/1".into());
+        let text = TaggedText::Append {
+            items: vec![
+                TaggedText::Text {
+                    text: "a + ".to_string(),
+                },
+                TaggedText::Tag {
+                    info,
+                    content: Box::new(TaggedText::Text {
+                        text: "b".to_string(),
+                    }),
+                },
+            ],
+        };
+        let lines: Vec<String> = text.to_tree_lines().iter().map(Line::to_string).collect();
+        assert_eq!(
+            lines,
+            ["Append", "  Text \"a + \"", "  Tag /1", "    Text \"b\""]
+        );
+    }
 }
@@ -16,8 +16,11 @@ use ratatui::{
 use crate::{
     lean_rpc::{GoalInfo, ProofState},
     tui::widgets::{
-        diff_text::TaggedTextExt, layout_metrics::LayoutMetrics, theme::Theme, ClickRegion,
-        Selection,
+        diff_text::{DiffMarkers, TaggedTextExt},
+        layout_metrics::LayoutMetrics,
+        render_helpers::{goal_marker, summarize, COLLAPSED_GOAL_CHARS},
+        theme::{SelectionColors, Theme},
+        ClickRegion, Selection,
     },
 };
 
@@ -36,6 +39,12 @@ pub struct GoalSectionState {
     vertical_scroll: usize,
     /// Whether this pane is currently focused.
     is_focused: bool,
+    /// Colors of diffed text, or `None` when diffs are hidden.
+    diff_markers: Option<DiffMarkers>,
+    /// Whether goals other than the active one are collapsed.
+    collapse_inactive: bool,
+    /// Colors of the selected goal.
+    selection_colors: SelectionColors,
 }
 
 /// Lay out information for tracking click regions in the goal section.
@@ -70,6 +79,20 @@ impl GoalSectionState {
     pub const fn set_focused(&mut self, focused: bool) {
         self.is_focused = focused;
     }
+
+    /// Set the colors of diffed text, or `None` to hide diffs.
+    pub const fn set_diff_markers(&mut self, markers: Option<DiffMarkers>) {
+        self.diff_markers = markers;
+    }
+
+    /// Set whether goals other than the active one are collapsed.
+    pub const fn set_collapse_inactive(&mut self, collapse: bool) {
+        self.collapse_inactive = collapse;
+    }
+
+    pub const fn set_selection_colors(&mut self, colors: SelectionColors) {
+        self.selection_colors = colors;
+    }
 }
 
 /// Widget for rendering the goal section.
@@ -137,10 +160,11 @@ impl StatefulWidget for GoalSection {
             .iter()
             .enumerate()
             .map(|(goal_idx, goal)| {
-                let is_selected = matches!(
+                let selected = matches!(
                     state.selection,
                     Some(Selection::Goal { goal_idx: gi, .. }) if gi == goal_idx
-                );
+                )
+                .then_some(state.selection_colors);
                 let is_spawned = goal
                     .username
                     .as_str()
@@ -149,7 +173,16 @@ impl StatefulWidget for GoalSection {
                     .username
                     .as_str()
                     .is_some_and(|name| state.active_goal_name.as_deref() == Some(name));
-                goal_row(goal, is_selected, is_spawned, is_active)
+                let collapsed = state.collapse_inactive && goal_idx > 0;
+                goal_row(
+                    goal,
+                    goal_idx,
+                    selected,
+                    is_spawned,
+                    is_active,
+                    state.diff_markers,
+                    collapsed,
+                )
             })
             .collect();
 
@@ -200,21 +233,25 @@ fn track_goal_click_regions(
     }
 }
 
+#[allow(clippy::fn_params_excessive_bools)]
 fn goal_row(
     goal: &GoalInfo,
-    is_selected: bool,
+    goal_idx: usize,
+    selected: Option<SelectionColors>,
     _is_spawned: bool,
     is_active: bool,
+    diff_markers: Option<DiffMarkers>,
+    collapsed: bool,
 ) -> Row<'static> {
     let base_color = if is_active {
         Theme::CURRENT_NODE_BORDER
     } else {
         Theme::INCOMPLETE_NODE_BORDER
     };
-    let style = if is_selected {
-        Style::new().bg(Theme::SELECTION_BG).fg(base_color)
-    } else {
-        Style::new().fg(base_color)
+    let style = Style::new().fg(base_color);
+    let highlight = |line| match selected {
+        Some(colors) => colors.highlight(line, true),
+        None => line,
     };
 
     // Column 1: case label (e.g. "Expected:" or "h.mpr:")
@@ -222,12 +259,23 @@ fn goal_row(
         .username
         .as_str()
         .map_or(String::new(), |n| format!("{n}: "));
-    let col1 = Cell::from(Line::from(vec![Span::styled(case_label, style)]));
+    let col1 = Cell::from(highlight(Line::styled(case_label, style)));
 
     // Column 2: goal type (with diff highlighting)
-    let mut spans = vec![Span::styled("⊢ ", style)];
-    spans.extend(goal.type_.to_spans(style));
-    let col2 = Cell::from(Text::from(Line::from(spans)));
+    let mut spans = vec![
+        Span::styled(goal_marker(goal_idx), style.fg(Theme::CURRENT_NODE_BORDER)),
+        Span::styled("⊢ ", style),
+    ];
+    if collapsed {
+        let summary = summarize(&goal.type_.to_plain_text(), COLLAPSED_GOAL_CHARS);
+        spans.push(Span::styled(summary, style.patch(Theme::DIM)));
+    } else {
+        spans.extend(goal.type_.to_diff_spans(style, diff_markers));
+    }
+    if let Some(location) = goal.term_location() {
+        spans.push(Span::styled(format!("  ({location})"), style.patch(Theme::DIM)));
+    }
+    let col2 = Cell::from(Text::from(highlight(Line::from(spans))));
 
     Row::new(vec![col1, col2])
 }
@@ -1,19 +1,28 @@
 //! Goals column widget for temporal comparison views (Before/After mode).
 
+use std::{collections::HashSet, mem};
+
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
-    style::{Modifier, Style},
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
 };
 
 use super::{
-    diff_text::TaggedTextExt, hypothesis_indices, ClickRegion, HypothesisFilters, Selection,
+    diff_text::{DiffMarkers, TaggedTextExt},
+    hyp_layer::type_size_span,
+    hypothesis_indices,
+    render_helpers::{goal_marker, hard_wrap, mirror_marker, summarize, COLLAPSED_GOAL_CHARS},
+    ClickRegion, HypothesisFilters, Selection,
 };
 use crate::{
     lean_rpc::{GoalInfo, HypothesisInfo, ProofState},
-    tui::widgets::theme::Theme,
+    tui::{
+        paraphrase::{paraphrase, split_quantifiers},
+        widgets::theme::{SelectionColors, Theme},
+    },
 };
 
 /// State for the goals column widget (render artifacts only).
@@ -38,6 +47,10 @@ pub struct GoalsColumn<'a> {
     is_current: bool,
     node_id: Option<u32>,
     active_goal_name: Option<&'a str>,
+    /// Hypotheses flagged unused when the filter is on.
+    unused: Option<&'a HashSet<String>>,
+    /// Goals that came from splitting one goal, linked by a connector.
+    split: &'a [usize],
 }
 
 impl<'a> GoalsColumn<'a> {
@@ -58,20 +71,25 @@ impl<'a> GoalsColumn<'a> {
             is_current,
             node_id,
             active_goal_name,
+            unused: None,
+            split: &[],
         }
     }
 
-    fn should_show_hypothesis(&self, idx: usize) -> bool {
-        let Some(h) = self.state.hypotheses.get(idx) else {
-            return false;
-        };
-        if self.filters.hide_instances && h.is_instance {
-            return false;
-        }
-        if self.filters.hide_inaccessible && h.is_proof {
-            return false;
-        }
-        true
+    /// Hypotheses to flag as unused.
+    pub const fn unused_hypotheses(self, names: &'a HashSet<String>) -> Self {
+        self.unused(Some(names))
+    }
+
+    const fn unused(mut self, names: Option<&'a HashSet<String>>) -> Self {
+        self.unused = names;
+        self
+    }
+
+    /// Goals to group as siblings from one split.
+    pub const fn split_goals(mut self, goal_indices: &'a [usize]) -> Self {
+        self.split = goal_indices;
+        self
     }
 }
 
@@ -79,12 +97,19 @@ impl StatefulWidget for GoalsColumn<'_> {
     type State = GoalsColumnState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        state.click_regions.clear();
-
         let block = create_border_block(self.title, self.is_current);
         let inner = block.inner(area);
         block.render(area, buf);
 
+        self.render_body(inner, buf, state);
+    }
+}
+
+impl GoalsColumn<'_> {
+    /// Render hypotheses, divider and goals without the surrounding border.
+    fn render_body(&self, inner: Rect, buf: &mut Buffer, state: &mut GoalsColumnState) {
+        state.click_regions.clear();
+
         if self.state.goals.is_empty() && self.state.hypotheses.is_empty() {
             render_empty_message(inner, buf, self.is_current);
             return;
@@ -94,124 +119,453 @@ impl StatefulWidget for GoalsColumn<'_> {
         let node_id = self.is_current.then_some(self.node_id).flatten();
         let selection = self.is_current.then_some(self.selection).flatten();
 
-        self.render_hypotheses(hyp_area, buf, state, selection, node_id);
+        let regions = &mut state.click_regions;
+        self.hypothesis_list().render(hyp_area, buf, selection, node_id, regions);
         render_divider(div_area, buf);
         self.render_goals(goal_area, buf, state, selection, node_id);
     }
-}
 
-impl GoalsColumn<'_> {
     fn compute_layout(&self, inner: Rect) -> [Rect; 3] {
-        let visible_hyp_count =
-            hypothesis_indices(self.state.hypotheses.len(), self.filters.reverse_order)
-                .filter(|&i| self.should_show_hypothesis(i))
-                .count();
+        let visible_hyp_rows = self.hypothesis_list().height(inner.width);
 
-        let hyp_height = visible_hyp_count.min(inner.height.saturating_sub(3) as usize / 2);
+        let hyp_height = visible_hyp_rows.min(inner.height.saturating_sub(3) as usize / 2);
+        // Hidden hypotheses reserve nothing, not even the divider
+        let div_height = u16::from(visible_hyp_rows > 0);
         let constraints = vec![
             Constraint::Length(hyp_height as u16),
-            Constraint::Length(1),
+            Constraint::Length(div_height),
             Constraint::Fill(1),
         ];
         Layout::vertical(constraints).areas(inner)
     }
 
-    fn render_hypotheses(
+    fn hypothesis_list(&self) -> HypothesisList<'_> {
+        HypothesisList::new(self.state, self.filters, self.unused)
+    }
+
+    fn render_goals(
         &self,
-        hyp_area: Rect,
+        goal_area: Rect,
         buf: &mut Buffer,
         state: &mut GoalsColumnState,
         selection: Option<Selection>,
         node_id: Option<u32>,
     ) {
-        let visible_hyps = hypothesis_indices(self.state.hypotheses.len(), self.filters.reverse_order)
-            .filter(|&i| self.should_show_hypothesis(i))
-            .take(hyp_area.height as usize);
-
-        for (row, hyp_idx) in visible_hyps.enumerate() {
-            let h = &self.state.hypotheses[hyp_idx];
-            let is_selected = matches!(selection, Some(Selection::Hyp { hyp_idx: sel, .. }) if sel == hyp_idx);
-            let line_area = Rect::new(hyp_area.x, hyp_area.y + row as u16, hyp_area.width, 1);
-
-            let line = render_hypothesis_line(h, is_selected);
-            Paragraph::new(line).render(line_area, buf);
+        // Presentation mode leaves a blank row below each goal, and a box
+        // adds a border row above and below
+        let gap = u16::from(self.filters.presentation);
+        let frame = 2 * u16::from(self.filters.boxed_goals);
+        let stride = 1 + gap + frame;
+        let visible_goals = self
+            .state
+            .goals
+            .iter()
+            .enumerate()
+            .take(self.filters.goal_cap())
+            .take(goal_area.height.div_ceil(stride) as usize);
+
+        let mut y = goal_area.y;
+        for (goal_idx, g) in visible_goals {
+            if y >= goal_area.bottom() {
+                break;
+            }
+            let is_selected = matches!(selection, Some(Selection::Goal { goal_idx: sel, .. }) if sel == goal_idx);
+            let is_active = self.active_goal_name.is_some_and(|name| g.username.as_str() == Some(name));
+            let lines = goal_rows(g, goal_idx, is_selected, is_active, self.filters, self.split);
+            let height = (lines.len() as u16 + frame).min(goal_area.bottom() - y);
+            let line_area = Rect::new(goal_area.x, y, goal_area.width, height);
+            let text_area = render_goal_box(g, goal_idx, is_active, self.filters, line_area, buf);
+            Paragraph::new(lines).alignment(goal_alignment(self.filters)).render(text_area, buf);
+            y += height + gap;
 
             if let Some(nid) = node_id {
                 state.click_regions.push(ClickRegion {
                     area: line_area,
-                    selection: Selection::Hyp { node_id: nid, hyp_idx },
+                    selection: Selection::Goal { node_id: nid, goal_idx },
                 });
             }
         }
     }
+}
 
-    fn render_goals(
+/// Hypotheses of a proof state passing the filters, one below the other,
+/// each wrapped over as many rows as it covers, then a row counting those
+/// the display cap hides.
+pub struct HypothesisList<'a> {
+    state: &'a ProofState,
+    filters: HypothesisFilters,
+    /// Hypotheses flagged unused when the filter is on.
+    unused: Option<&'a HashSet<String>>,
+    /// Hypotheses the target mentions, when they are to stand out.
+    used: HashSet<String>,
+    /// Hypotheses shown, up to the display cap.
+    shown: Vec<usize>,
+    /// How many more the cap hides.
+    hidden: usize,
+}
+
+impl<'a> HypothesisList<'a> {
+    pub fn new(
+        state: &'a ProofState,
+        filters: HypothesisFilters,
+        unused: Option<&'a HashSet<String>>,
+    ) -> Self {
+        let mut shown: Vec<usize> = hypothesis_indices(state, filters.hyp_order)
+            .into_iter()
+            .filter(|&i| state.hypotheses.get(i).is_some_and(|h| filters.should_show(h)))
+            .collect();
+        let cap = filters.hyp_cap().unwrap_or(usize::MAX);
+        let hidden = shown.len().saturating_sub(cap);
+        shown.truncate(cap);
+        let used = if filters.highlight_used {
+            state.mentioned_hypotheses()
+        } else {
+            HashSet::new()
+        };
+        Self {
+            state,
+            filters,
+            unused,
+            used,
+            shown,
+            hidden,
+        }
+    }
+
+    /// Rows the list covers at `width`, the row counting hidden hypotheses
+    /// included.
+    pub fn height(&self, width: u16) -> usize {
+        let rows: usize = self
+            .shown
+            .iter()
+            .map(|&hyp_idx| self.rows(hyp_idx, false, width).len())
+            .sum();
+        rows + usize::from(self.hidden > 0)
+    }
+
+    /// Screen rows of a hypothesis wrapped to `width`, flagged when it is
+    /// among the unused ones.
+    fn rows(&self, hyp_idx: usize, is_selected: bool, width: u16) -> Vec<Line<'static>> {
+        let h = &self.state.hypotheses[hyp_idx];
+        let filters = self.filters;
+        let selected = is_selected.then_some(filters.selection);
+        let is_used = self.used.contains(&h.name);
+        let show_diffs = (!filters.hide_diffs).then_some(filters.diff_markers);
+        let mut line = render_hypothesis_line(h, selected, is_used, show_diffs);
+        if filters.show_sizes {
+            line.push_span(type_size_span(h));
+        }
+        if filters.flag_unused && self.unused.is_some_and(|names| names.contains(&h.name)) {
+            line = line.patch_style(Modifier::DIM);
+            line.push_span(Span::styled(" unused", Theme::DIM));
+        }
+        let mut rows = hard_wrap(&line, width);
+        if filters.presentation {
+            rows.push(Line::default());
+        }
+        rows
+    }
+
+    /// Draw the list into `area`, cut at its bottom, registering a click
+    /// region per hypothesis drawn when there is a node to select on.
+    pub fn render(
         &self,
-        goal_area: Rect,
+        area: Rect,
         buf: &mut Buffer,
-        state: &mut GoalsColumnState,
         selection: Option<Selection>,
         node_id: Option<u32>,
+        click_regions: &mut Vec<ClickRegion>,
     ) {
-        let visible_goals = self.state.goals.iter().enumerate().take(goal_area.height as usize);
+        // The last row goes to the collapsed remainder
+        let rows = area.height.saturating_sub(u16::from(self.hidden > 0));
+        let mut y = 0;
+        let mut shown_count = 0;
+        for &hyp_idx in &self.shown {
+            if y >= rows {
+                break;
+            }
+            let is_selected =
+                matches!(selection, Some(Selection::Hyp { hyp_idx: sel, .. }) if sel == hyp_idx);
+            // A wrapped hypothesis claims as many rows as it covers
+            let lines = self.rows(hyp_idx, is_selected, area.width);
+            let height = u16::try_from(lines.len()).unwrap_or(u16::MAX).min(rows - y);
+            let hyp_rect = Rect::new(area.x, area.y + y, area.width, height);
+            Paragraph::new(lines)
+                .alignment(hypothesis_alignment(self.filters))
+                .render(hyp_rect, buf);
+
+            if let Some(node_id) = node_id {
+                click_regions.push(ClickRegion {
+                    area: hyp_rect,
+                    selection: Selection::Hyp { node_id, hyp_idx },
+                });
+            }
+            y += height;
+            shown_count += 1;
+        }
 
-        for (goal_idx, g) in visible_goals {
-            let is_selected = matches!(selection, Some(Selection::Goal { goal_idx: sel, .. }) if sel == goal_idx);
-            let is_active = self.active_goal_name.is_some_and(|name| g.username.as_str() == Some(name));
-            let line_area = Rect::new(goal_area.x, goal_area.y + goal_idx as u16, goal_area.width, 1);
+        let hidden = self.hidden + self.shown.len() - shown_count;
+        if y < area.height {
+            let more_area = Rect::new(area.x, area.y + y, area.width, 1);
+            render_more_hyps(hidden, more_area, buf);
+        }
+    }
+}
 
-            let line = render_goal_line(g, is_selected, is_active);
-            Paragraph::new(line).render(line_area, buf);
+/// The row counting the hypotheses the cap hides, if any.
+fn render_more_hyps(hidden: usize, area: Rect, buf: &mut Buffer) {
+    if hidden == 0 {
+        return;
+    }
+    let text = format!("… {hidden} more (press e to expand)");
+    Paragraph::new(Line::styled(text, Style::new().fg(Color::DarkGray))).render(area, buf);
+}
 
-            if let Some(nid) = node_id {
-                state.click_regions.push(ClickRegion {
-                    area: line_area,
-                    selection: Selection::Goal { node_id: nid, goal_idx },
-                });
-            }
+pub const fn hypothesis_alignment(filters: HypothesisFilters) -> Alignment {
+    if filters.rtl {
+        Alignment::Right
+    } else {
+        Alignment::Left
+    }
+}
+
+pub const fn goal_alignment(filters: HypothesisFilters) -> Alignment {
+    if filters.rtl {
+        Alignment::Right
+    } else if filters.presentation {
+        Alignment::Center
+    } else {
+        Alignment::Left
+    }
+}
+
+/// A goal's lines, mirrored for RTL and linked to the other goals of its
+/// `split`.
+pub fn goal_rows(
+    g: &GoalInfo,
+    goal_idx: usize,
+    is_selected: bool,
+    is_active: bool,
+    filters: HypothesisFilters,
+    split: &[usize],
+) -> Vec<Line<'static>> {
+    let mut lines = render_goal_lines(g, goal_idx, is_selected, is_active, filters);
+    if filters.rtl {
+        lines.iter_mut().for_each(mirror_marker);
+    }
+    link_split(split, goal_idx, &mut lines);
+    lines
+}
+
+/// Connector in front of a goal's lines linking the goals of a split:
+/// `┌` on the first, `├` between, `└` on the last, with `│` down the
+/// continuation lines, and blank padding beside the other goals.
+fn split_connector(split: &[usize], goal_idx: usize, first_line: bool) -> &'static str {
+    let (Some(&first), Some(&last)) = (split.first(), split.last()) else {
+        return "";
+    };
+    let inside = (first..=last).contains(&goal_idx);
+    match (split.contains(&goal_idx), first_line) {
+        (true, true) if goal_idx == first => "┌ ",
+        (true, true) if goal_idx == last => "└ ",
+        (true, true) => "├ ",
+        _ if inside && goal_idx != last => "│ ",
+        _ => "  ",
+    }
+}
+
+/// Put the split connector in front of each of a goal's lines.
+fn link_split(split: &[usize], goal_idx: usize, lines: &mut [Line<'static>]) {
+    if split.is_empty() {
+        return;
+    }
+    for (i, line) in lines.iter_mut().enumerate() {
+        let connector = split_connector(split, goal_idx, i == 0);
+        line.spans.insert(0, Span::styled(connector, Theme::DIM));
+    }
+}
+
+/// With boxed goals, draw the border around a goal's `area`, titled with
+/// its case, and return the inside left for its lines.
+pub fn render_goal_box(
+    g: &GoalInfo,
+    goal_idx: usize,
+    is_active: bool,
+    filters: HypothesisFilters,
+    area: Rect,
+    buf: &mut Buffer,
+) -> Rect {
+    if !filters.boxed_goals {
+        return area;
+    }
+    let block = goal_block(g, goal_idx, is_active);
+    let inner = block.inner(area);
+    block.render(area, buf);
+    inner
+}
+
+/// Border around one goal, titled `case name` or by its position.
+fn goal_block(g: &GoalInfo, goal_idx: usize, is_active: bool) -> Block<'static> {
+    let title = g.username.as_str().map_or_else(
+        || format!(" goal {} ", goal_idx + 1),
+        |name| format!(" case {name} "),
+    );
+    let border_style = if is_active {
+        Style::new().fg(Theme::CURRENT_NODE_BORDER)
+    } else {
+        Theme::DIM
+    };
+    Block::bordered()
+        .border_style(border_style)
+        .title(title)
+        .title_style(Style::new().fg(Theme::TITLE_GOAL))
+}
+
+/// A hypothesis, highlighted with the `selected` colors when it is selected.
+fn render_hypothesis_line(
+    h: &HypothesisInfo,
+    selected: Option<SelectionColors>,
+    is_used: bool,
+    markers: Option<DiffMarkers>,
+) -> Line<'static> {
+    let style = Style::default();
+
+    // Instances are dimmed so they stay separable from ordinary hypotheses
+    let (name_style, type_style) = match markers {
+        Some(markers) if h.is_removed => {
+            let removed = style.patch(markers.removed_style());
+            (removed, removed)
         }
+        _ if h.is_instance => (style.patch(Theme::INSTANCE), style.patch(Theme::INSTANCE)),
+        _ => (style.fg(Theme::HYP_NAME), style.fg(Theme::HYP_TYPE)),
+    };
+    // Hypotheses mentioned in the target stand out by name
+    let name_style = if is_used {
+        name_style.add_modifier(Modifier::BOLD)
+    } else {
+        name_style
+    };
+
+    let brackets = h.binder_brackets();
+    let mut spans = Vec::new();
+    if let Some((open, _)) = brackets {
+        spans.push(Span::styled(open, style));
+    }
+    spans.push(Span::styled(h.name.clone(), name_style));
+    spans.push(Span::styled(" : ", style));
+    spans.extend(h.type_.to_diff_spans(type_style, markers));
+    if let Some((_, close)) = brackets {
+        spans.push(Span::styled(close, style));
+    }
+    let line = Line::from(spans);
+    match selected {
+        Some(colors) => colors.highlight(line, true),
+        None => line,
     }
 }
 
-fn render_hypothesis_line(h: &HypothesisInfo, is_selected: bool) -> Line<'static> {
+fn render_goal_line(
+    g: &GoalInfo,
+    goal_idx: usize,
+    is_selected: bool,
+    is_active: bool,
+    filters: HypothesisFilters,
+) -> Line<'static> {
+    let markers = (!filters.hide_diffs).then_some(filters.diff_markers);
     let style = if is_selected {
-        Style::new().bg(Theme::SELECTION_BG)
+        filters.selection.style()
     } else {
         Style::default()
     };
 
+    let target_style = match markers {
+        Some(markers) if g.is_removed => style.patch(markers.removed_style()),
+        _ if is_active => style.fg(Theme::CURRENT_NODE_BORDER).add_modifier(Modifier::BOLD),
+        _ => style.fg(Theme::GOAL_TYPE),
+    };
+
+    // A boxed goal carries its case in the box title
+    let prefix = g
+        .username
+        .as_str()
+        .filter(|_| !filters.boxed_goals)
+        .map_or_else(|| "⊢ ".to_string(), |name| format!("case {name} ⊢ "));
+
     let mut spans = vec![
-        Span::styled(h.name.clone(), style.fg(Theme::HYP_NAME)),
-        Span::styled(" : ", style),
+        Span::styled(goal_marker(goal_idx), style.fg(Theme::CURRENT_NODE_BORDER)),
+        Span::styled(prefix, style),
     ];
-    spans.extend(h.type_.to_spans(style.fg(Theme::HYP_TYPE)));
-    Line::from(spans)
+    let paraphrased = filters
+        .paraphrase
+        .then(|| paraphrase(&g.type_.to_plain_text()))
+        .flatten();
+    if filters.collapse_inactive && goal_idx > 0 {
+        let summary = summarize(&g.type_.to_plain_text(), COLLAPSED_GOAL_CHARS);
+        spans.push(Span::styled(summary, style.patch(Theme::DIM)));
+    } else if let Some(text) = paraphrased {
+        spans.push(Span::styled(text, target_style));
+    } else {
+        spans.extend(g.type_.to_diff_spans(target_style, markers));
+    }
+    // Term goals are set apart from tactic goals by their source location
+    if let Some(location) = g.term_location() {
+        spans.push(Span::styled(format!("  ({location})"), style.patch(Theme::DIM)));
+    }
+    filters.selection.highlight(Line::from(spans), is_selected)
 }
 
-fn render_goal_line(g: &GoalInfo, is_selected: bool, is_active: bool) -> Line<'static> {
-    let style = if is_selected {
-        Style::new().bg(Theme::SELECTION_BG)
-    } else {
-        Style::default()
+/// The goal's line, or with `split_binders` the main goal's leading binders
+/// one per line followed by the indented body.
+fn render_goal_lines(
+    g: &GoalInfo,
+    goal_idx: usize,
+    is_selected: bool,
+    is_active: bool,
+    filters: HypothesisFilters,
+) -> Vec<Line<'static>> {
+    let line = render_goal_line(g, goal_idx, is_selected, is_active, filters);
+    if !filters.split_binders || filters.paraphrase || goal_idx > 0 {
+        return vec![line];
+    }
+    let target = g.type_.to_plain_text();
+    let Some((binders, body)) = split_quantifiers(&target) else {
+        return vec![line];
     };
-
+    // Keep the marker and `⊢` prefix, replacing the target
+    let mut head: Vec<Span<'static>> = line.spans.into_iter().take(2).collect();
+    let indent = " ".repeat(head.iter().map(Span::width).sum());
+    let style = head.last().map_or_else(Style::default, |span| span.style);
     let target_style = if is_active {
         style.fg(Theme::CURRENT_NODE_BORDER).add_modifier(Modifier::BOLD)
     } else {
         style.fg(Theme::GOAL_TYPE)
     };
-
-    let prefix = g.username.as_str().map_or_else(
-        || "⊢ ".to_string(),
-        |name| format!("case {name} ⊢ "),
-    );
-
-    let mut spans = vec![Span::styled(prefix, style)];
-    spans.extend(g.type_.to_spans(target_style));
-    Line::from(spans)
+    let last = binders.len() - 1;
+    let mut lines: Vec<Line<'static>> = binders
+        .into_iter()
+        .enumerate()
+        .map(|(i, binder)| {
+            let text = if i == last { format!("{binder},") } else { binder };
+            let mut spans = if i == 0 {
+                mem::take(&mut head)
+            } else {
+                vec![Span::styled(indent.clone(), style)]
+            };
+            spans.push(Span::styled(text, target_style));
+            Line::from(spans)
+        })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled(format!("{indent}  "), style),
+        Span::styled(body.to_string(), target_style),
+    ]));
+    lines
+        .into_iter()
+        .map(|line| filters.selection.highlight(line, is_selected))
+        .collect()
 }
 
-fn render_divider(div_area: Rect, buf: &mut Buffer) {
+pub fn render_divider(div_area: Rect, buf: &mut Buffer) {
     let divider = "─".repeat(div_area.width as usize);
     Paragraph::new(divider).style(Theme::DIM).render(div_area, buf);
 }
@@ -241,3 +595,373 @@ fn render_empty_message(area: Rect, buf: &mut Buffer, is_current: bool) {
     let msg = if is_current { "No goals" } else { "No data" };
     Paragraph::new(msg).style(Theme::DIM).render(area, buf);
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier};
+
+    use super::*;
+    use crate::{
+        lean_rpc::{ProofDagNode, TaggedText},
+        tui::widgets::render_helpers::{ACTIVE_GOAL_MARKER, INACTIVE_GOAL_MARKER},
+    };
+
+    fn hyp(name: &str, is_instance: bool) -> HypothesisInfo {
+        HypothesisInfo {
+            name: name.to_string(),
+            type_: TaggedText::Text {
+                text: "Monoid M".to_string(),
+            },
+            is_instance,
+            ..Default::default()
+        }
+    }
+
+    /// Instance hypotheses use the dim blue instance style for name and
+    /// type, a color apart from ordinary hypothesis names.
+    #[test]
+    fn test_instance_hypothesis_style() {
+        let markers = Some(DiffMarkers::default());
+        let line = render_hypothesis_line(&hyp("inst✝", true), None, false, markers);
+        assert_eq!(line.spans[1].style.fg, Theme::INSTANCE.fg);
+        assert_eq!(line.spans[3].style.fg, Theme::INSTANCE.fg);
+        assert_ne!(Theme::INSTANCE.fg, Some(Theme::HYP_NAME));
+
+        let line = render_hypothesis_line(&hyp("h", false), None, false, markers);
+        assert_eq!(line.spans[0].style.fg, Some(Theme::HYP_NAME));
+    }
+
+    /// A removed hypothesis takes the configured removed color, with no
+    /// marker in front.
+    #[test]
+    fn test_custom_diff_colors() {
+        let removed = HypothesisInfo {
+            is_removed: true,
+            ..hyp("h", false)
+        };
+        let markers = DiffMarkers {
+            removed_color: Color::Magenta,
+            ..DiffMarkers::default()
+        };
+        let line = render_hypothesis_line(&removed, None, false, Some(markers));
+        assert_eq!(line.spans[0].content, "h");
+        assert_eq!(line.spans[0].style.fg, Some(Color::Magenta));
+        assert!(line.spans[0].style.add_modifier.contains(Modifier::CROSSED_OUT));
+
+        let line = render_hypothesis_line(&removed, None, false, None);
+        assert_eq!(line.spans[0].style.fg, Some(Theme::HYP_NAME));
+    }
+
+    /// A goal whose hypotheses are all filtered out gives its rows to the goals.
+    #[test]
+    fn test_filtered_hyps_reserve_no_rows() {
+        let state = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![hyp("inst✝", true), hyp("inst✝¹", true)],
+        };
+        let filters = HypothesisFilters {
+            hide_instances: true,
+            ..Default::default()
+        };
+        let inner = Rect::new(0, 0, 40, 10);
+
+        let column = GoalsColumn::new("", &state, filters, None, true, None, None);
+        let [hyps, div, goals] = column.compute_layout(inner);
+        assert_eq!((hyps.height, div.height, goals.height), (0, 0, 10));
+
+        let column = GoalsColumn::new("", &state, HypothesisFilters::default(), None, true, None, None);
+        let [hyps, div, _] = column.compute_layout(inner);
+        assert_eq!((hyps.height, div.height), (2, 1));
+    }
+
+    /// Only the first goal carries the active marker; collapsing shortens the rest.
+    #[test]
+    fn test_active_goal_marker() {
+        let goal = GoalInfo {
+            type_: TaggedText::Text {
+                text: "a very long target that does not fit in a summary".to_string(),
+            },
+            ..Default::default()
+        };
+        let filters = HypothesisFilters::default();
+
+        let first = render_goal_line(&goal, 0, false, false, filters);
+        let second = render_goal_line(&goal, 1, false, false, filters);
+        assert_eq!(first.spans[0].content, ACTIVE_GOAL_MARKER);
+        assert_eq!(second.spans[0].content, INACTIVE_GOAL_MARKER);
+
+        let filters = HypothesisFilters {
+            collapse_inactive: true,
+            ..Default::default()
+        };
+        let first = render_goal_line(&goal, 0, false, false, filters);
+        let second = render_goal_line(&goal, 1, false, false, filters);
+        assert!(!first.spans[2].content.ends_with('…'));
+        assert!(second.spans[2].content.ends_with('…'));
+    }
+
+    /// With a cap of 3, ten hypotheses render as three plus a summary line.
+    #[test]
+    fn test_hypothesis_cap() {
+        let state = ProofState {
+            goals: Vec::new(),
+            hypotheses: (0..10).map(|i| hyp(&format!("h{i}"), false)).collect(),
+        };
+        let filters = HypothesisFilters {
+            max_hyps: Some(3),
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 40, 20);
+        let row_text = |buf: &Buffer, y: u16| -> String {
+            let text: String = (0..area.width).map(|x| buf[(x, y)].symbol()).collect();
+            text.trim_end().to_string()
+        };
+
+        let mut buf = Buffer::empty(area);
+        let column = GoalsColumn::new("", &state, filters, None, true, None, None);
+        column.render_body(area, &mut buf, &mut GoalsColumnState::default());
+        assert!(row_text(&buf, 2).starts_with("h2 :"));
+        assert_eq!(row_text(&buf, 3), "… 7 more (press e to expand)");
+        assert!(!row_text(&buf, 4).starts_with('h'));
+
+        let expanded = HypothesisFilters {
+            expand_hyps: true,
+            ..filters
+        };
+        let mut buf = Buffer::empty(area);
+        let column = GoalsColumn::new("", &state, expanded, None, true, None, None);
+        column.render_body(area, &mut buf, &mut GoalsColumnState::default());
+        assert!(row_text(&buf, 3).starts_with("h3 :"));
+    }
+
+    /// A hypothesis wrapping onto two rows pushes the next one's click
+    /// region down by two.
+    #[test]
+    fn test_wrapped_hypothesis_click_regions() {
+        let mut long = hyp("h", false);
+        long.type_ = TaggedText::Text {
+            text: "a + b + c + d = d + c + b + a".to_string(),
+        };
+        let state = ProofState {
+            goals: Vec::new(),
+            hypotheses: vec![long, hyp("inst", false)],
+        };
+        let area = Rect::new(0, 0, 20, 12);
+        let mut column_state = GoalsColumnState::default();
+
+        let column = GoalsColumn::new("", &state, HypothesisFilters::default(), None, true, Some(0), None);
+        column.render_body(area, &mut Buffer::empty(area), &mut column_state);
+
+        let regions = column_state.click_regions();
+        assert_eq!((regions[0].area.y, regions[0].area.height), (0, 2));
+        assert_eq!((regions[1].area.y, regions[1].area.height), (2, 1));
+        assert_eq!(
+            regions[1].selection,
+            Selection::Hyp {
+                node_id: 0,
+                hyp_idx: 1
+            }
+        );
+    }
+
+    /// Presentation mode spaces goals apart and centers them.
+    #[test]
+    fn test_presentation_spacing() {
+        let goal = |text: &str| GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: vec![goal("p"), goal("q")],
+            hypotheses: Vec::new(),
+        };
+        let area = Rect::new(0, 0, 20, 6);
+        let render = |filters: HypothesisFilters| -> Vec<String> {
+            let mut buf = Buffer::empty(area);
+            let column = GoalsColumn::new("", &state, filters, None, true, None, None);
+            column.render_body(area, &mut buf, &mut GoalsColumnState::default());
+            (0..area.height)
+                .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+                .collect()
+        };
+
+        let normal = render(HypothesisFilters::default());
+        assert!(normal[1].contains('q'), "{normal:?}");
+
+        let presentation = render(HypothesisFilters {
+            presentation: true,
+            ..Default::default()
+        });
+        assert!(presentation[1].trim().is_empty(), "{presentation:?}");
+        assert!(presentation[2].contains('q'), "{presentation:?}");
+        assert!(presentation[0].starts_with(' '), "centered: {presentation:?}");
+    }
+
+    /// RTL mode right-aligns lines and moves the goal marker to the right,
+    /// keeping the Lean text in order.
+    #[test]
+    fn test_rtl_right_aligns() {
+        let state = ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Text {
+                    text: "a → b".to_string(),
+                },
+                ..Default::default()
+            }],
+            hypotheses: vec![hyp("h", false)],
+        };
+        let filters = HypothesisFilters {
+            rtl: true,
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 24, 6);
+        let mut buf = Buffer::empty(area);
+        let column = GoalsColumn::new("", &state, filters, None, true, None, None);
+        column.render_body(area, &mut buf, &mut GoalsColumnState::default());
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(rows[0], format!("{:>24}", "h : Monoid M"));
+        let goal_row = rows.iter().find(|row| row.contains('⊢')).unwrap();
+        assert_eq!(goal_row, &format!("{:>24}", "⊢ a → b ◂"));
+    }
+
+    /// With binders split, a two-binder `∀` goal takes one line per binder
+    /// and one for the body; other goals keep their single line.
+    #[test]
+    fn test_binders_on_own_lines() {
+        let goal = |text: &str| GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        };
+        let filters = HypothesisFilters {
+            split_binders: true,
+            ..Default::default()
+        };
+        let text = |lines: &[Line]| -> Vec<String> {
+            lines.iter().map(|line| line.to_string().trim_end().to_string()).collect()
+        };
+
+        let target = goal("∀ (x : ℕ) (y : ℕ), x + y = y + x");
+        let lines = text(&render_goal_lines(&target, 0, false, false, filters));
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("⊢ ∀ (x : ℕ)"));
+        assert!(lines[1].trim_start().starts_with("∀ (y : ℕ),"));
+        assert_eq!(lines[2].trim_start(), "x + y = y + x");
+
+        let second = render_goal_lines(&goal("∀ x, P x"), 1, false, false, filters);
+        assert_eq!(second.len(), 1);
+    }
+
+    /// Instance hypotheses are wrapped in `[ ]`; explicit ones stay bare.
+    #[test]
+    fn test_binder_brackets() {
+        let line = render_hypothesis_line(&hyp("inst✝", true), None, false, None);
+        assert_eq!(line.to_string(), "[inst✝ : Monoid M]");
+
+        let line = render_hypothesis_line(&hyp("h", false), None, false, None);
+        assert_eq!(line.to_string(), "h : Monoid M");
+    }
+
+    /// A selected line takes the configured colors over its own.
+    #[test]
+    fn test_selection_colors_applied() {
+        let colors = SelectionColors::parse("magenta,white").unwrap();
+        let line = render_hypothesis_line(&hyp("h", false), Some(colors), true, None);
+        assert!(line.spans.iter().all(|span| {
+            span.style.bg == Some(Color::Magenta) && span.style.fg == Some(Color::White)
+        }));
+
+        let line = render_hypothesis_line(&hyp("h", false), None, true, None);
+        assert!(line.spans.iter().all(|span| span.style.bg.is_none()));
+        assert_eq!(SelectionColors::default().bg, Theme::SELECTION_BG);
+        assert!(SelectionColors::parse("no-such-color").is_err());
+    }
+
+    /// The two goals `constructor` split `A ∧ B` into are linked by a
+    /// connector; the goal left untouched is only indented.
+    #[test]
+    fn test_split_goals_grouped() {
+        let goal = |text: &str| GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        };
+        let node = ProofDagNode {
+            state_before: ProofState {
+                goals: vec![goal("A ∧ B"), goal("C")],
+                hypotheses: Vec::new(),
+            },
+            state_after: ProofState {
+                goals: vec![goal("A"), goal("B"), goal("C")],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let split = node.split_goals();
+        assert_eq!(split, [0, 1]);
+
+        let area = Rect::new(0, 0, 24, 4);
+        let mut buf = Buffer::empty(area);
+        let filters = HypothesisFilters::default();
+        let column = GoalsColumn::new("", &node.state_after, filters, None, true, None, None)
+            .split_goals(&split);
+        column.render_body(area, &mut buf, &mut GoalsColumnState::default());
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        let goal_rows: Vec<&str> = rows
+            .iter()
+            .map(|row| row.trim_end())
+            .filter(|row| row.contains('⊢'))
+            .collect();
+        assert_eq!(goal_rows.len(), 3, "{rows:?}");
+        assert!(goal_rows[0].starts_with("┌ ") && goal_rows[0].ends_with("⊢ A"), "{rows:?}");
+        assert!(goal_rows[1].starts_with("└ ") && goal_rows[1].ends_with("⊢ B"), "{rows:?}");
+        assert!(goal_rows[2].starts_with("  ") && goal_rows[2].ends_with("⊢ C"), "{rows:?}");
+    }
+
+    /// Boxed goals each sit in a bordered block titled with their position,
+    /// one below the other.
+    #[test]
+    fn test_boxed_goals() {
+        let goal = |text: &str| GoalInfo {
+            type_: TaggedText::Text {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        };
+        let state = ProofState {
+            goals: vec![goal("A"), goal("B")],
+            hypotheses: Vec::new(),
+        };
+        let filters = HypothesisFilters {
+            boxed_goals: true,
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 20, 8);
+        let mut buf = Buffer::empty(area);
+        let mut column_state = GoalsColumnState::default();
+        let column = GoalsColumn::new("", &state, filters, None, true, Some(0), None);
+        column.render_body(area, &mut buf, &mut column_state);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert!(rows[0].starts_with("┌ goal 1 "), "{rows:?}");
+        assert!(rows[1].starts_with('│') && rows[1].contains("⊢ A"), "{rows:?}");
+        assert!(rows[2].starts_with('└'), "{rows:?}");
+        assert!(rows[3].starts_with("┌ goal 2 "), "{rows:?}");
+        assert!(rows[4].contains("⊢ B"), "{rows:?}");
+
+        let regions = column_state.click_regions();
+        assert_eq!((regions[1].area.y, regions[1].area.height), (3, 3));
+    }
+}
@@ -9,15 +9,35 @@ use ratatui::{
     widgets::{Block, Clear, Paragraph, StatefulWidget, Widget},
 };
 
-use super::{InteractiveStatefulWidget, KeyEvent};
+use super::{status_bar::FILTER_PILLS, InteractiveStatefulWidget, KeyEvent};
 
 const KEYBINDINGS: &[(&str, &str)] = &[
     // Display modes
     ("[/]", "cycle display mode"),
     // Navigation
     ("j/k", "navigate up/down"),
+    ("1-9", "jump to goal N"),
+    ("]m/[m", "outline motion"),
     ("g", "goto origin"),
+    ("Enter", "fold step, else goto (--enter)"),
     ("y", "copy to clipboard"),
+    ("C", "copy goals as comment"),
+    ("M", "copy goals as Markdown"),
+    ("P", "pin for comparison"),
+    ("V", "compare with git HEAD"),
+    ("H", "open goals as HTML"),
+    ("X", "toggle implicit args"),
+    ("D", "cycle DAG mode"),
+    ("b", "toggle status bar"),
+    ("F2", "presentation mode"),
+    ("A", "tagged text tree"),
+    ("I", "go to implementation"),
+    ("m<c>/'<c>", "set/jump to bookmark"),
+    ("R", "raw JSON (--debug)"),
+    ("C-l", "reset RPC session"),
+    ("C-c", "cancel goal fetch"),
+    (":", "go to symbol"),
+    ("C-p", "command palette"),
     // Other
     ("?", "close help"),
     ("q", "quit"),
@@ -48,7 +68,7 @@ impl StatefulWidget for HelpMenuWidget {
 
         let width = 28u16;
         #[allow(clippy::cast_possible_truncation)]
-        let height = (KEYBINDINGS.len() as u16) + 2;
+        let height = ((KEYBINDINGS.len() + FILTER_PILLS.len() + 1) as u16) + 2;
         let x = area.width.saturating_sub(width + 1);
         let y = area.height.saturating_sub(height + 2);
         let popup_area = Rect::new(x, y, width, height);
@@ -60,14 +80,23 @@ impl StatefulWidget for HelpMenuWidget {
             .border_style(Style::new().fg(Color::Cyan));
 
         let key_style = Style::new().fg(Color::Cyan);
-        let help_lines: Vec<Line> = KEYBINDINGS
-            .iter()
-            .map(|(key, desc)| {
-                Line::from(vec![
-                    Span::styled(format!("{key:>6}"), key_style),
-                    Span::raw(format!("  {desc}")),
-                ])
-            })
+        let pill_style = Style::new().fg(Color::Green);
+        let key_lines = KEYBINDINGS.iter().map(|(key, desc)| {
+            Line::from(vec![
+                Span::styled(format!("{key:>6}"), key_style),
+                Span::raw(format!("  {desc}")),
+            ])
+        });
+        // Legend for the filter pills in the status bar
+        let pill_lines = FILTER_PILLS.iter().map(|(_, label, desc)| {
+            Line::from(vec![
+                Span::styled(format!("{label:>6}"), pill_style),
+                Span::raw(format!("  {desc}")),
+            ])
+        });
+        let help_lines: Vec<Line> = key_lines
+            .chain([Line::styled(" Filters", pill_style)])
+            .chain(pill_lines)
             .collect();
 
         Paragraph::new(help_lines)
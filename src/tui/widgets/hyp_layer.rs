@@ -11,7 +11,8 @@ use ratatui::{
 use crate::{
     lean_rpc::HypothesisInfo,
     tui::widgets::{
-        diff_text::{diff_style, DiffState, TaggedTextExt},
+        diff_text::{diff_style, DiffMarkers, DiffState, TaggedTextExt},
+        theme::{SelectionColors, Theme},
         ClickRegion, Selection,
     },
 };
@@ -22,6 +23,14 @@ pub struct HypLayerRenderContext<'a> {
     pub base_y: u16,
     pub area: Rect,
     pub depends_on: &'a HashSet<String>,
+    /// Hypotheses mentioned in the main goal's target.
+    pub used: &'a HashSet<String>,
+    /// Diff markers and colors, or `None` when diffs are hidden.
+    pub diff_markers: Option<DiffMarkers>,
+    /// Colors of the selected hypothesis.
+    pub selection_colors: SelectionColors,
+    /// Whether to append a type size indicator.
+    pub show_sizes: bool,
 }
 
 /// A layer of hypotheses.
@@ -72,7 +81,18 @@ impl HypLayer {
                     Some(Selection::Hyp { hyp_idx: hi, .. }) if hi == *hyp_idx
                 );
                 let is_dependency = ctx.depends_on.contains(&hyp.name);
-                render_hyp_line(hyp, is_selected, is_dependency)
+                let is_used = ctx.used.contains(&hyp.name);
+                let mut line = render_hyp_line(
+                    hyp,
+                    is_selected.then_some(ctx.selection_colors),
+                    is_dependency,
+                    is_used,
+                    ctx.diff_markers,
+                );
+                if ctx.show_sizes {
+                    line.push_span(type_size_span(hyp));
+                }
+                line
             })
             .collect()
     }
@@ -102,25 +122,57 @@ impl HypLayer {
 
 const DIM_GRAY: Style = Style::new().fg(Color::DarkGray);
 
-fn render_hyp_line(hyp: &HypothesisInfo, is_selected: bool, is_dependency: bool) -> Line<'static> {
+/// Bar glyphs for the type size indicator, smallest first.
+const SIZE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Type size indicator: a log-scaled bar and the character count, to spot
+/// hypotheses with huge types.
+pub fn type_size_span(hyp: &HypothesisInfo) -> Span<'static> {
+    let len = hyp.type_.to_plain_text().chars().count();
+    let bits = (usize::BITS - len.leading_zeros()) as usize;
+    let bar = SIZE_BARS[bits.saturating_sub(3).min(SIZE_BARS.len() - 1)];
+    Span::styled(format!("  {bar}{len}"), DIM_GRAY)
+}
+
+/// A hypothesis, highlighted with the `selected` colors when it is selected.
+fn render_hyp_line(
+    hyp: &HypothesisInfo,
+    selected: Option<SelectionColors>,
+    is_dependency: bool,
+    is_used: bool,
+    diff_markers: Option<DiffMarkers>,
+) -> Line<'static> {
+    let show_diffs = diff_markers.is_some();
+    let markers = diff_markers.unwrap_or_default();
+    let is_removed = show_diffs && hyp.is_removed;
+    let is_inserted = show_diffs && hyp.is_inserted;
     let state = DiffState {
-        is_inserted: false,
-        is_removed: hyp.is_removed,
+        is_inserted,
+        is_removed,
     };
-    let diff = diff_style(&state, is_selected, Color::White);
-
-    // Simple dimmed markers like before_after mode
-    let marker = match (is_dependency, hyp.is_removed, hyp.type_.has_any_diff()) {
-        (true, _, _) => Span::styled("*", DIM_GRAY),
-        (_, true, _) => Span::styled("-", DIM_GRAY),
-        (_, _, true) => Span::styled("~", DIM_GRAY),
-        _ => Span::styled(" ", DIM_GRAY),
+    let diff = diff_style(
+        &state,
+        selected.is_some(),
+        Color::White,
+        markers,
+        selected.unwrap_or_default(),
+    );
+
+    // Simple dimmed markers like before_after mode, padded to one width
+    let has_diff = show_diffs && hyp.type_.has_any_diff();
+    let marker = if is_dependency {
+        "*"
+    } else {
+        markers.marker(is_inserted, is_removed, has_diff).unwrap_or(" ")
     };
+    let marker = Span::styled(format!("{marker:<width$}", width = markers.width()), DIM_GRAY);
 
-    // Only underline when selected (not for dependencies)
-    // Dependencies get bold name only
-    let name_style = if is_dependency {
+    // Only highlight when selected (not for dependencies)
+    // Dependencies and hypotheses mentioned in the target get bold name only
+    let name_style = if is_dependency || is_used {
         diff.style.add_modifier(Modifier::BOLD)
+    } else if hyp.is_instance && !is_removed {
+        diff.style.patch(Theme::INSTANCE)
     } else {
         diff.style
     };
@@ -130,7 +182,77 @@ fn render_hyp_line(hyp: &HypothesisInfo, is_selected: bool, is_dependency: bool)
         Span::raw(" "),
         Span::styled(format!("{} : ", hyp.name), name_style),
     ];
-    // Type spans use diff.style which applies underline only when selected
-    spans.extend(hyp.type_.to_spans(diff.style));
+    // Type spans use diff.style which applies the highlight only when selected
+    spans.extend(hyp.type_.to_diff_spans(diff.style, diff_markers));
     Line::from(spans)
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Modifier;
+
+    use super::*;
+    use crate::lean_rpc::TaggedText;
+
+    /// With diffs hidden, a removed hypothesis renders without its marker.
+    #[test]
+    fn test_render_hyp_line_without_diffs() {
+        let hyp = HypothesisInfo {
+            name: "h".to_string(),
+            type_: TaggedText::Text {
+                text: "p".to_string(),
+            },
+            is_removed: true,
+            ..Default::default()
+        };
+
+        let line = render_hyp_line(&hyp, None, false, false, Some(DiffMarkers::default()));
+        assert_eq!(line.spans[0].content, "-");
+        assert!(line.spans[2].style.add_modifier.contains(Modifier::CROSSED_OUT));
+
+        let line = render_hyp_line(&hyp, None, false, false, None);
+        assert_eq!(line.spans[0].content, " ");
+        assert!(!line.spans[2].style.add_modifier.contains(Modifier::CROSSED_OUT));
+    }
+
+    /// Custom markers and colors render in place of the defaults, with
+    /// unmarked hypotheses padded to the marker width.
+    #[test]
+    fn test_custom_diff_markers() {
+        let hyp = |is_removed| HypothesisInfo {
+            name: "h".to_string(),
+            type_: TaggedText::Text {
+                text: "p".to_string(),
+            },
+            is_removed,
+            ..Default::default()
+        };
+        let markers = DiffMarkers::parse("brackets:green,magenta,yellow").unwrap();
+
+        let line = render_hyp_line(&hyp(true), None, false, false, Some(markers));
+        assert_eq!(line.spans[0].content, "[-]");
+        assert_eq!(line.spans[2].style.fg, Some(Color::Magenta));
+
+        let line = render_hyp_line(&hyp(false), None, false, false, Some(markers));
+        assert_eq!(line.spans[0].content, "   ");
+        let line = render_hyp_line(&hyp(false), None, true, false, Some(markers));
+        assert_eq!(line.spans[0].content, "*  ");
+
+        assert!(DiffMarkers::parse("git:green,red").is_err());
+        assert!(DiffMarkers::parse("stars").is_err());
+    }
+
+    /// The size indicator grows with the length of the type.
+    #[test]
+    fn test_type_size_span() {
+        let hyp = |ty: String| HypothesisInfo {
+            type_: TaggedText::Text { text: ty },
+            ..Default::default()
+        };
+        let small = type_size_span(&hyp("Nat".to_string()));
+        let large = type_size_span(&hyp("a + ".repeat(150)));
+
+        assert_eq!(small.content, "  ▁3");
+        assert_eq!(large.content, "  █600");
+    }
+}
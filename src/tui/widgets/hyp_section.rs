@@ -30,7 +30,10 @@ impl Default for HypLayer {
 #[derive(Default)]
 pub struct HypSectionState {
     layer: HypLayer,
+    filters: HypothesisFilters,
     depends_on: HashSet<String>,
+    /// Hypotheses mentioned in the main goal's target.
+    used: HashSet<String>,
     selection: Option<Selection>,
     click_regions: Vec<ClickRegion>,
     scroll_state: ScrollbarState,
@@ -53,14 +56,16 @@ impl HypSectionState {
         self.layer.set_node_id(node_id);
         self.depends_on = depends_on;
         self.selection = selection;
+        self.filters = filters;
+        self.used = if filters.highlight_used {
+            state.mentioned_hypotheses()
+        } else {
+            HashSet::new()
+        };
         let mut seen: HashSet<String> = HashSet::new();
 
         for (hyp_idx, h) in state.hypotheses.iter().enumerate() {
-            // Apply filters
-            if filters.hide_instances && h.is_instance {
-                continue;
-            }
-            if filters.hide_inaccessible && h.is_proof {
+            if !filters.should_show(h) {
                 continue;
             }
             if seen.insert(h.name.clone()) {
@@ -136,6 +141,10 @@ impl StatefulWidget for HypSection {
             base_y: inner.y,
             area: inner,
             depends_on: &state.depends_on,
+            used: &state.used,
+            diff_markers: (!state.filters.hide_diffs).then_some(state.filters.diff_markers),
+            selection_colors: state.filters.selection,
+            show_sizes: state.filters.show_sizes,
         };
         let lines = state.layer.render(&render_ctx, &mut state.click_regions);
         Paragraph::new(lines).render(inner, buf);
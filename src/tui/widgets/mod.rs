@@ -1,5 +1,6 @@
 //! Component-based UI architecture.
 
+pub mod command_palette;
 pub mod diff_text;
 pub mod goal_section;
 pub mod goals_column;
@@ -10,10 +11,13 @@ pub mod interactive_widget;
 pub mod layout_metrics;
 pub mod open_goal_list;
 pub mod proof_steps_sidebar;
+pub mod raw_view;
 pub mod render_helpers;
 pub mod selection;
 pub mod semantic_tableau;
+pub mod spinner;
 pub mod status_bar;
+pub mod symbol_search;
 pub mod tactic_row;
 pub mod theme;
 pub mod welcome;
@@ -23,6 +27,10 @@ use crossterm::event::MouseEvent;
 pub use interactive_widget::{InteractiveComponent, InteractiveStatefulWidget};
 pub use selection::{ClickRegion, Selection};
 
+use crate::lean_rpc::{HypothesisInfo, ProofState};
+use diff_text::DiffMarkers;
+use theme::SelectionColors;
+
 #[derive(Clone)]
 pub enum KeyMouseEvent {
     Key(KeyEvent),
@@ -35,7 +43,74 @@ pub struct HypothesisFilters {
     pub hide_instances: bool,
     pub hide_inaccessible: bool,
     pub hide_let_values: bool,
-    pub reverse_order: bool,
+    /// Order the hypotheses are listed in.
+    pub hyp_order: HypOrder,
+    /// Render diff markers and highlighting as plain text.
+    pub hide_diffs: bool,
+    /// Show a type size indicator next to each hypothesis.
+    pub show_sizes: bool,
+    /// Emphasize hypotheses mentioned in the main goal's target.
+    pub highlight_used: bool,
+    /// Collapse goals other than the active one to short summaries.
+    pub collapse_inactive: bool,
+    /// Most hypotheses rendered before the rest collapse into one line;
+    /// `None` renders them all.
+    pub max_hyps: Option<usize>,
+    /// Render every hypothesis despite `max_hyps`.
+    pub expand_hyps: bool,
+    /// Paraphrase simple goal targets in English.
+    pub paraphrase: bool,
+    /// Dim hypotheses no tactic uses and tag them "unused".
+    pub flag_unused: bool,
+    /// Put each leading quantifier binder of the main goal on its own line.
+    pub split_binders: bool,
+    /// Render only the first (active) goal.
+    pub first_goal_only: bool,
+    /// Draw each goal in its own bordered box titled with its case.
+    pub boxed_goals: bool,
+    /// Cut multi-line tactics to their first line in step titles.
+    pub tactic_summary: bool,
+    /// Markers and colors of diffed items.
+    pub diff_markers: DiffMarkers,
+    /// Colors of the selection highlight.
+    pub selection: SelectionColors,
+    /// Spread rows apart and center the goals, for screen sharing.
+    pub presentation: bool,
+    /// Right-align lines, with markers on the right, for right-to-left
+    /// locales.
+    pub rtl: bool,
+}
+
+/// Order of the hypotheses in a list, cycled with `r`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HypOrder {
+    /// As Lean declares them.
+    #[default]
+    Declaration,
+    /// Latest first.
+    Reversed,
+    /// Each after the hypotheses its type mentions.
+    Dependency,
+}
+
+impl HypOrder {
+    /// The order `r` switches to.
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Declaration => Self::Reversed,
+            Self::Reversed => Self::Dependency,
+            Self::Dependency => Self::Declaration,
+        }
+    }
+
+    /// Status bar pill label.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Declaration => "decl",
+            Self::Reversed => "rev",
+            Self::Dependency => "deps",
+        }
+    }
 }
 
 /// Filter toggles that modes can support.
@@ -44,7 +119,19 @@ pub enum FilterToggle {
     Instances,
     Inaccessible,
     LetValues,
-    ReverseOrder,
+    /// Cycles the hypothesis order rather than toggling.
+    Order,
+    Diffs,
+    Sizes,
+    Used,
+    Collapse,
+    Expand,
+    Paraphrase,
+    Unused,
+    Binders,
+    FirstGoal,
+    Boxes,
+    TacticSummary,
 }
 
 impl HypothesisFilters {
@@ -54,16 +141,74 @@ impl HypothesisFilters {
             FilterToggle::Instances => self.hide_instances = !self.hide_instances,
             FilterToggle::Inaccessible => self.hide_inaccessible = !self.hide_inaccessible,
             FilterToggle::LetValues => self.hide_let_values = !self.hide_let_values,
-            FilterToggle::ReverseOrder => self.reverse_order = !self.reverse_order,
+            FilterToggle::Order => self.hyp_order = self.hyp_order.next(),
+            FilterToggle::Diffs => self.hide_diffs = !self.hide_diffs,
+            FilterToggle::Sizes => self.show_sizes = !self.show_sizes,
+            FilterToggle::Used => self.highlight_used = !self.highlight_used,
+            FilterToggle::Collapse => self.collapse_inactive = !self.collapse_inactive,
+            FilterToggle::Expand => self.expand_hyps = !self.expand_hyps,
+            FilterToggle::Paraphrase => self.paraphrase = !self.paraphrase,
+            FilterToggle::Unused => self.flag_unused = !self.flag_unused,
+            FilterToggle::Binders => self.split_binders = !self.split_binders,
+            FilterToggle::FirstGoal => self.first_goal_only = !self.first_goal_only,
+            FilterToggle::Boxes => self.boxed_goals = !self.boxed_goals,
+            FilterToggle::TacticSummary => self.tactic_summary = !self.tactic_summary,
+        }
+    }
+
+    /// Whether a filter setting is active.
+    pub const fn is_enabled(self, filter: FilterToggle) -> bool {
+        match filter {
+            FilterToggle::Instances => self.hide_instances,
+            FilterToggle::Inaccessible => self.hide_inaccessible,
+            FilterToggle::LetValues => self.hide_let_values,
+            FilterToggle::Order => !matches!(self.hyp_order, HypOrder::Declaration),
+            FilterToggle::Diffs => self.hide_diffs,
+            FilterToggle::Sizes => self.show_sizes,
+            FilterToggle::Used => self.highlight_used,
+            FilterToggle::Collapse => self.collapse_inactive,
+            FilterToggle::Expand => self.expand_hyps,
+            FilterToggle::Paraphrase => self.paraphrase,
+            FilterToggle::Unused => self.flag_unused,
+            FilterToggle::Binders => self.split_binders,
+            FilterToggle::FirstGoal => self.first_goal_only,
+            FilterToggle::Boxes => self.boxed_goals,
+            FilterToggle::TacticSummary => self.tactic_summary,
+        }
+    }
+
+    /// Most hypotheses to render, unless expanded.
+    pub const fn hyp_cap(self) -> Option<usize> {
+        if self.expand_hyps {
+            None
+        } else {
+            self.max_hyps
         }
     }
+
+    /// Most goals to render.
+    pub const fn goal_cap(self) -> usize {
+        if self.first_goal_only {
+            1
+        } else {
+            usize::MAX
+        }
+    }
+
+    /// Whether a hypothesis passes the visibility filters.
+    /// The instance filter hides only type class instances, independently of
+    /// the inaccessible filter.
+    pub const fn should_show(self, h: &HypothesisInfo) -> bool {
+        (!self.hide_instances || !h.is_instance) && (!self.hide_inaccessible || !h.is_proof)
+    }
 }
 
-pub fn hypothesis_indices(len: usize, reverse: bool) -> Box<dyn Iterator<Item = usize>> {
-    let range = 0..len;
-    if reverse {
-        Box::new(range.rev())
-    } else {
-        Box::new(range)
+/// Indices of `state`'s hypotheses in the order they are listed.
+pub fn hypothesis_indices(state: &ProofState, order: HypOrder) -> Vec<usize> {
+    let range = 0..state.hypotheses.len();
+    match order {
+        HypOrder::Declaration => range.collect(),
+        HypOrder::Reversed => range.rev().collect(),
+        HypOrder::Dependency => state.dependency_order(),
     }
 }
@@ -1,15 +1,18 @@
+use std::collections::HashSet;
 
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::{Modifier, Style},
-    text::{Line, Span},
+    text::Line,
     widgets::{Paragraph, StatefulWidget, Widget},
     Frame,
 };
 
 use super::{
-    diff_text::TaggedTextExt, hypothesis_indices, ClickRegion, HypothesisFilters, Selection,
+    goals_column::{goal_alignment, goal_rows, render_divider, render_goal_box, HypothesisList},
+    render_helpers::hard_wrap,
+    ClickRegion, HypothesisFilters, Selection,
 };
 use crate::{lean_rpc::ProofState, tui::widgets::theme::Theme};
 
@@ -21,6 +24,14 @@ pub struct OpenGoalList<'a> {
     node_id: Option<u32>,
     /// Name of the goal the cursor's tactic is working on.
     active_goal_name: Option<&'a str>,
+    /// Only goal shown, if any.
+    focused_goal: Option<usize>,
+    /// Whether to show the selectable "all hypotheses" row.
+    hyps_summary: bool,
+    /// Hypotheses flagged unused when the filter is on.
+    unused: Option<&'a HashSet<String>>,
+    /// Goals that came from splitting one goal.
+    split: &'a [usize],
 }
 
 #[derive(Default)]
@@ -49,9 +60,41 @@ impl<'a> OpenGoalList<'a> {
             filters,
             node_id,
             active_goal_name,
+            focused_goal: None,
+            hyps_summary: false,
+            unused: None,
+            split: &[],
         }
     }
 
+    /// Show only one goal, using all the space below the hypotheses.
+    pub const fn focused_goal(mut self, goal_idx: Option<usize>) -> Self {
+        self.focused_goal = goal_idx;
+        self
+    }
+
+    /// Show a selectable row standing for all hypotheses above them.
+    pub const fn hyps_summary(mut self, show: bool) -> Self {
+        self.hyps_summary = show;
+        self
+    }
+
+    /// Hypotheses to flag as unused.
+    pub const fn unused_hypotheses(self, names: &'a HashSet<String>) -> Self {
+        self.unused(Some(names))
+    }
+
+    const fn unused(mut self, names: Option<&'a HashSet<String>>) -> Self {
+        self.unused = names;
+        self
+    }
+
+    /// Goals to group as siblings from one split.
+    pub const fn split_goals(mut self, goal_indices: &'a [usize]) -> Self {
+        self.split = goal_indices;
+        self
+    }
+
     /// Render using Frame (convenience method for non-stateful usage).
     pub fn render_to_frame(&self, frame: &mut Frame, area: Rect) -> Vec<ClickRegion> {
         let mut render_state = OpenGoalListState::default();
@@ -62,24 +105,19 @@ impl<'a> OpenGoalList<'a> {
                 self.filters,
                 self.node_id,
                 self.active_goal_name,
-            ),
+            )
+            .focused_goal(self.focused_goal)
+            .hyps_summary(self.hyps_summary)
+            .unused(self.unused)
+            .split_goals(self.split),
             area,
             &mut render_state,
         );
         render_state.click_regions
     }
 
-    fn should_show_hypothesis(&self, idx: usize) -> bool {
-        let Some(h) = self.state.hypotheses.get(idx) else {
-            return false;
-        };
-        if self.filters.hide_instances && h.is_instance {
-            return false;
-        }
-        if self.filters.hide_inaccessible && h.is_proof {
-            return false;
-        }
-        true
+    const fn has_hyps_summary(&self) -> bool {
+        self.hyps_summary && !self.state.hypotheses.is_empty()
     }
 }
 
@@ -90,80 +128,67 @@ impl StatefulWidget for OpenGoalList<'_> {
         state.click_regions.clear();
 
         if self.state.goals.is_empty() && self.state.hypotheses.is_empty() {
-            Paragraph::new("No goals")
-                .style(Theme::DIM)
-                .render(area, buf);
+            Paragraph::new("No goals").style(Theme::DIM).render(area, buf);
             return;
         }
 
-        // Count visible hypotheses
-        let visible_hyp_count =
-            hypothesis_indices(self.state.hypotheses.len(), self.filters.reverse_order)
-                .filter(|&i| self.should_show_hypothesis(i))
-                .count();
+        let hyps = HypothesisList::new(self.state, self.filters, self.unused);
+        let visible_hyp_rows = hyps.height(area.width) + usize::from(self.has_hyps_summary());
 
         // Layout: hypotheses, divider, goals
-        let hyp_height = visible_hyp_count.min(area.height.saturating_sub(3) as usize / 2);
+        let hyp_height = visible_hyp_rows.min(area.height.saturating_sub(3) as usize / 2);
         let constraints = vec![
             Constraint::Length(hyp_height as u16),
-            Constraint::Length(1), // divider
-            Constraint::Fill(1),   // goals
+            Constraint::Length(u16::from(visible_hyp_rows > 0)), // divider
+            Constraint::Fill(1),                                  // goals
         ];
         let [hyp_area, div_area, goal_area] = Layout::vertical(constraints).areas(area);
 
-        // Render hypotheses
-        let mut y = hyp_area.y;
-        for hyp_idx in hypothesis_indices(self.state.hypotheses.len(), self.filters.reverse_order) {
-            if !self.should_show_hypothesis(hyp_idx) {
-                continue;
-            }
-            if y >= hyp_area.bottom() {
-                break;
-            }
-
-            let h = &self.state.hypotheses[hyp_idx];
-            let is_selected = matches!(self.selection, Some(Selection::Hyp { hyp_idx: sel_idx, .. }) if sel_idx == hyp_idx);
-
-            let style = if is_selected {
-                Style::new().bg(Theme::SELECTION_BG)
-            } else {
-                Style::default()
-            };
-
-            // Format: "name : type" (with diff highlighting)
-            let mut spans = vec![
-                Span::styled(&h.name, style.fg(Theme::HYP_NAME)),
-                Span::styled(" : ", style),
-            ];
-            spans.extend(h.type_.to_spans(style.fg(Theme::HYP_TYPE)));
-            let line = Line::from(spans);
-
-            let line_area = Rect::new(hyp_area.x, y, hyp_area.width, 1);
-            Paragraph::new(line).render(line_area, buf);
+        let hyp_area = if self.has_hyps_summary() {
+            let [summary_area, rest] =
+                Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(hyp_area);
+            self.render_hyps_summary(summary_area, buf, state);
+            rest
+        } else {
+            hyp_area
+        };
+        hyps.render(hyp_area, buf, self.selection, self.node_id, &mut state.click_regions);
+        render_divider(div_area, buf);
+        self.render_goals(goal_area, buf, state);
+    }
+}
 
-            // Register click region
-            if let Some(nid) = self.node_id {
-                state.click_regions.push(ClickRegion {
-                    area: line_area,
-                    selection: Selection::Hyp {
-                        node_id: nid,
-                        hyp_idx,
-                    },
-                });
-            }
+impl OpenGoalList<'_> {
+    /// The row standing for every hypothesis, e.g. to copy them all at once.
+    fn render_hyps_summary(&self, area: Rect, buf: &mut Buffer, state: &mut OpenGoalListState) {
+        let is_selected = matches!(self.selection, Some(Selection::AllHyps { .. }));
+        let label_style = Style::new().fg(Theme::HYP_NAME).add_modifier(Modifier::DIM);
+        let label = Line::styled("— all hypotheses —", label_style);
+        Paragraph::new(self.filters.selection.highlight(label, is_selected)).render(area, buf);
 
-            y += 1;
+        if let Some(node_id) = self.node_id {
+            state.click_regions.push(ClickRegion {
+                area,
+                selection: Selection::AllHyps { node_id },
+            });
         }
+    }
 
-        // Render divider
-        let divider = "─".repeat(div_area.width as usize);
-        Paragraph::new(divider)
-            .style(Theme::DIM)
-            .render(div_area, buf);
+    fn render_goals(&self, goal_area: Rect, buf: &mut Buffer, state: &mut OpenGoalListState) {
+        // Presentation mode leaves a blank row below each goal, and a box
+        // adds a border row above and below
+        let gap = u16::from(self.filters.presentation);
+        let frame = 2 * u16::from(self.filters.boxed_goals);
+        let goals = self
+            .state
+            .goals
+            .iter()
+            .enumerate()
+            .filter(|(goal_idx, _)| self.focused_goal.is_none_or(|focused| focused == *goal_idx))
+            .take(self.filters.goal_cap());
 
-        // Render goals
-        y = goal_area.y;
-        for (goal_idx, g) in self.state.goals.iter().enumerate() {
+        let mut y = goal_area.y;
+        for (goal_idx, g) in goals {
             if y >= goal_area.bottom() {
                 break;
             }
@@ -172,33 +197,25 @@ impl StatefulWidget for OpenGoalList<'_> {
             let is_active = self
                 .active_goal_name
                 .is_some_and(|name| g.username.as_str() == Some(name));
+            let lines = goal_rows(g, goal_idx, is_selected, is_active, self.filters, self.split);
 
-            let style = if is_selected {
-                Style::new().bg(Theme::SELECTION_BG)
+            // A focused goal wraps over the whole goal area
+            let line_area = if self.focused_goal.is_some() {
+                goal_area
             } else {
-                Style::default()
+                let height = (lines.len() as u16 + frame).min(goal_area.bottom() - y);
+                Rect::new(goal_area.x, y, goal_area.width, height)
             };
-
-            // Highlight active goal
-            let target_style = if is_active {
-                style
-                    .fg(Theme::CURRENT_NODE_BORDER)
-                    .add_modifier(Modifier::BOLD)
+            let text_area = render_goal_box(g, goal_idx, is_active, self.filters, line_area, buf);
+            let lines: Vec<Line> = if self.focused_goal.is_some() {
+                lines.iter().flat_map(|line| hard_wrap(line, text_area.width)).collect()
             } else {
-                style.fg(Theme::GOAL_TYPE)
+                lines
             };
-
-            // Format: "⊢ type" or "case name ⊢ type"
-            let prefix = g
-                .username
-                .as_str()
-                .map_or_else(|| "⊢ ".to_string(), |name| format!("case {name} ⊢ "));
-            let mut spans = vec![Span::styled(prefix, style)];
-            spans.extend(g.type_.to_spans(target_style));
-            let line = Line::from(spans);
-
-            let line_area = Rect::new(goal_area.x, y, goal_area.width, 1);
-            Paragraph::new(line).render(line_area, buf);
+            Paragraph::new(lines)
+                .alignment(goal_alignment(self.filters))
+                .render(text_area, buf);
+            y += line_area.height + gap;
 
             // Register click region
             if let Some(nid) = self.node_id {
@@ -210,8 +227,57 @@ impl StatefulWidget for OpenGoalList<'_> {
                     },
                 });
             }
-
-            y += 1;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, HypothesisInfo, TaggedText};
+
+    /// The list shows the summary row, then an instance hypothesis in the
+    /// instance color, then the goal, each clickable.
+    #[test]
+    fn test_instance_hypothesis_in_list() {
+        let state = ProofState {
+            goals: vec![GoalInfo {
+                type_: TaggedText::Text {
+                    text: "a * 1 = a".to_string(),
+                },
+                ..Default::default()
+            }],
+            hypotheses: vec![HypothesisInfo {
+                name: "inst✝".to_string(),
+                type_: TaggedText::Text {
+                    text: "Monoid M".to_string(),
+                },
+                is_instance: true,
+                ..Default::default()
+            }],
+        };
+        let area = Rect::new(0, 0, 30, 8);
+        let mut buf = Buffer::empty(area);
+        let mut list_state = OpenGoalListState::default();
+        OpenGoalList::new(&state, None, HypothesisFilters::default(), Some(0), None)
+            .hyps_summary(true)
+            .render(area, &mut buf, &mut list_state);
+
+        let row = |y: u16| -> String { (0..area.width).map(|x| buf[(x, y)].symbol()).collect() };
+        assert!(row(0).starts_with("— all hypotheses —"));
+        assert!(row(1).starts_with("[inst✝ : Monoid M]"));
+        assert_eq!(buf[(1, 1)].fg, Theme::INSTANCE.fg.unwrap());
+        assert!(row(3).contains("a * 1 = a"));
+
+        let selections: Vec<Selection> =
+            list_state.click_regions().iter().map(|region| region.selection).collect();
+        assert_eq!(
+            selections,
+            [
+                Selection::AllHyps { node_id: 0 },
+                Selection::Hyp { node_id: 0, hyp_idx: 0 },
+                Selection::Goal { node_id: 0, goal_idx: 0 },
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Proof steps sidebar widget for the tactic tree view.
 
+use std::{collections::HashSet, iter};
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -13,7 +15,11 @@ use ratatui::{
 
 use crate::{
     lean_rpc::{ProofDag, ProofDagNode},
-    tui::widgets::{theme::Theme, InteractiveStatefulWidget},
+    tui::widgets::{
+        render_helpers::{tactic_text, timing_span},
+        theme::{SelectionColors, Theme},
+        InteractiveStatefulWidget,
+    },
 };
 
 // Tree drawing characters
@@ -33,15 +39,75 @@ pub struct ProofStepsSidebarState {
     is_focused: bool,
     /// Whether the user has taken manual control of scrolling.
     manual_scroll: bool,
+    /// Step picked with `j`/`k`, if it differs from the current one.
+    selected: Option<u32>,
     /// Previous current node ID, used to detect changes.
     prev_current_node: Option<u32>,
     /// Max content width (cached from last render).
     max_content_width: usize,
     /// Viewport width (cached from last render).
     viewport_width: usize,
+    /// Steps whose substeps are hidden.
+    folded: HashSet<u32>,
+    /// Whether multi-line tactics are cut to their first line.
+    summarize_tactics: bool,
+    /// Colors of the picked step.
+    selection_colors: SelectionColors,
 }
 
 impl ProofStepsSidebarState {
+    /// Steps in tree order, leaving out those inside folded steps.
+    fn visible_nodes(&self) -> Vec<&ProofDagNode> {
+        let Some(dag) = &self.proof_dag else {
+            return Vec::new();
+        };
+        let is_hidden = |node: &ProofDagNode| {
+            iter::successors(node.parent, |&id| dag.get(id)?.parent)
+                .any(|id| self.folded.contains(&id))
+        };
+        dag.dfs_iter().filter(|node| !is_hidden(node)).collect()
+    }
+
+    /// Fold or unfold the selected step. Returns false when it has no
+    /// substeps to hide.
+    pub fn toggle_fold(&mut self) -> bool {
+        let Some(id) = self.selected_node() else {
+            return false;
+        };
+        let has_children = self
+            .proof_dag
+            .as_ref()
+            .and_then(|dag| dag.get(id))
+            .is_some_and(|node| !node.children.is_empty());
+        if has_children && !self.folded.remove(&id) {
+            self.folded.insert(id);
+        }
+        has_children
+    }
+
+    /// Fold every step with substeps, moving a selection hidden by that to
+    /// its top-level step.
+    pub fn fold_all(&mut self) {
+        let Some(dag) = &self.proof_dag else {
+            return;
+        };
+        self.folded = dag
+            .nodes
+            .iter()
+            .filter(|node| !node.children.is_empty())
+            .map(|node| node.id)
+            .collect();
+        self.selected = self
+            .selected_node()
+            .and_then(|id| iter::successors(Some(id), |&id| dag.get(id)?.parent).last());
+        self.manual_scroll = false;
+    }
+
+    /// Unfold every step.
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+    }
+
     fn build_lines(&self) -> Vec<Line<'static>> {
         let Some(dag) = &self.proof_dag else {
             return vec![];
@@ -51,7 +117,8 @@ impl ProofStepsSidebarState {
         let mut active_levels: Vec<bool> = Vec::new();
         let mut lines = Vec::new();
 
-        for node in dag.dfs_iter() {
+        let selected = self.selected_node().filter(|_| self.is_focused);
+        for node in self.visible_nodes() {
             let is_current = dag.is_current(node.id);
 
             // Determine if this node is the last child of its parent
@@ -65,7 +132,13 @@ impl ProofStepsSidebarState {
 
             // Build the tree prefix
             let prefix = build_tree_prefix(&active_levels, node.depth, is_last_child);
-            lines.push(step_line(node, &prefix, is_current));
+            let mut line = step_line(node, &prefix, is_current, self.summarize_tactics);
+            if self.folded.contains(&node.id) {
+                let hidden = descendant_count(dag, node.id);
+                line.push_span(Span::styled(format!(" ▸ {hidden} hidden"), Theme::DIM));
+            }
+            let line = self.selection_colors.highlight(line, selected == Some(node.id));
+            lines.push(line);
 
             // For continuation lines, use the same prefix structure but with vertical lines
             let cont_prefix = build_continuation_prefix(&active_levels, node.depth, is_last_child);
@@ -81,15 +154,12 @@ impl ProofStepsSidebarState {
         lines
     }
 
-    /// Calculate scroll position to center the current step in the viewport.
+    /// Calculate scroll position to center the selected step in the viewport.
     fn calculate_centered_scroll(&self, viewport_height: usize) -> usize {
-        let Some(dag) = &self.proof_dag else {
-            return 0;
-        };
-
+        let target = self.selected_node();
         let mut current_line: usize = 0;
-        for node in dag.dfs_iter() {
-            if dag.is_current(node.id) {
+        for node in self.visible_nodes() {
+            if Some(node.id) == target {
                 break;
             }
             current_line += 1; // Main step line
@@ -111,12 +181,8 @@ impl ProofStepsSidebarState {
 
     /// Total number of lines in the proof steps view.
     fn total_lines(&self) -> usize {
-        let Some(dag) = &self.proof_dag else {
-            return 0;
-        };
-
         let mut count = 0;
-        for node in dag.dfs_iter() {
+        for node in self.visible_nodes() {
             count += 1;
             if !node.tactic.depends_on.is_empty() {
                 count += 1;
@@ -133,19 +199,43 @@ impl ProofStepsSidebarState {
         self.is_focused = focused;
     }
 
-    /// Scroll up by one line.
-    pub const fn scroll_up(&mut self) {
-        self.manual_scroll = true;
-        self.vertical_scroll = self.vertical_scroll.saturating_sub(1);
+    pub const fn set_summarize_tactics(&mut self, summarize: bool) {
+        self.summarize_tactics = summarize;
     }
 
-    /// Scroll down by one line.
-    pub fn scroll_down(&mut self, viewport_height: usize) {
-        self.manual_scroll = true;
-        let max_scroll = self.total_lines().saturating_sub(viewport_height);
-        if self.vertical_scroll < max_scroll {
-            self.vertical_scroll += 1;
-        }
+    pub const fn set_selection_colors(&mut self, colors: SelectionColors) {
+        self.selection_colors = colors;
+    }
+
+    /// Step `j`/`k` last picked, or the current step.
+    pub fn selected_node(&self) -> Option<u32> {
+        self.selected.or_else(|| self.proof_dag.as_ref()?.current_node)
+    }
+
+    /// Pick the step after (or before) the selected one in tree order,
+    /// scrolling it into the middle of the viewport.
+    fn select_by(&mut self, forward: bool) {
+        let order: Vec<u32> = self.visible_nodes().iter().map(|node| node.id).collect();
+        let idx = self
+            .selected_node()
+            .and_then(|id| order.iter().position(|&n| n == id));
+        let next = match idx {
+            Some(i) if forward => (i + 1).min(order.len().saturating_sub(1)),
+            Some(i) => i.saturating_sub(1),
+            None => 0,
+        };
+        self.selected = order.get(next).copied();
+        self.manual_scroll = false;
+    }
+
+    /// Select the next step.
+    pub fn select_next(&mut self) {
+        self.select_by(true);
+    }
+
+    /// Select the previous step.
+    pub fn select_previous(&mut self) {
+        self.select_by(false);
     }
 
     /// Scroll left by one column.
@@ -169,6 +259,18 @@ impl ProofStepsSidebarState {
     }
 }
 
+/// Steps below `id`, however deep.
+fn descendant_count(dag: &ProofDag, id: u32) -> usize {
+    let mut stack = vec![id];
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if seen.insert(id) {
+            stack.extend(dag.get(id).into_iter().flat_map(|node| &node.children));
+        }
+    }
+    seen.len() - 1
+}
+
 /// Update the `active_levels` vector for the current node.
 fn update_active_levels(active_levels: &mut Vec<bool>, depth: usize, is_last_child: bool) {
     active_levels.truncate(depth);
@@ -237,6 +339,7 @@ impl InteractiveStatefulWidget for ProofStepsSidebar {
         // Reset manual scroll when current node changes (re-center on new step)
         if new_current != state.prev_current_node {
             state.manual_scroll = false;
+            state.selected = None;
             state.prev_current_node = new_current;
         }
 
@@ -254,10 +357,15 @@ impl StatefulWidget for ProofStepsSidebar {
         } else {
             Theme::DIM
         };
-        let title = if state.is_focused {
-            "▶ Proof Steps "
+        let marker = if state.is_focused { "▶ " } else { " " };
+        let automation = state
+            .proof_dag
+            .as_ref()
+            .map_or(0, ProofDag::automation_count);
+        let title = if automation > 0 {
+            format!("{marker}Proof Steps · {} {automation} ", Theme::AUTOMATION_ICON)
         } else {
-            " Proof Steps "
+            format!("{marker}Proof Steps ")
         };
 
         let block = Block::default()
@@ -316,7 +424,12 @@ impl StatefulWidget for ProofStepsSidebar {
     }
 }
 
-fn step_line(node: &ProofDagNode, prefix: &str, is_current: bool) -> Line<'static> {
+fn step_line(
+    node: &ProofDagNode,
+    prefix: &str,
+    is_current: bool,
+    summarize_tactic: bool,
+) -> Line<'static> {
     let style = if is_current {
         Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)
     } else {
@@ -325,11 +438,23 @@ fn step_line(node: &ProofDagNode, prefix: &str, is_current: bool) -> Line<'stati
 
     let marker = if is_current { "▶ " } else { "  " };
 
-    Line::from(vec![
+    let mut spans = vec![
         Span::styled(marker, Style::new().fg(Color::Cyan)),
         Span::styled(prefix.to_string(), Theme::DIM),
-        Span::styled(node.tactic.text.clone(), style),
-    ])
+    ];
+    if node.is_automation() {
+        spans.push(Span::styled(
+            format!("{} ", Theme::AUTOMATION_ICON),
+            Style::new().fg(Theme::AUTOMATION),
+        ));
+    }
+    let tactic = tactic_text(&node.tactic.text, summarize_tactic).into_owned();
+    spans.push(Span::styled(tactic, style));
+    if let Some(ms) = node.elab_time_ms {
+        spans.push(Span::raw(" "));
+        spans.push(timing_span(ms));
+    }
+    Line::from(spans)
 }
 
 fn dependency_line(node: &ProofDagNode, prefix: &str) -> Option<Line<'static>> {
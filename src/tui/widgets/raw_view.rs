@@ -0,0 +1,106 @@
+//! Scrollable popup showing the raw JSON (`R`) or the tagged text tree (`A`)
+//! of the selected item.
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, StatefulWidget, Widget},
+};
+
+use super::{InteractiveStatefulWidget, KeyEvent};
+
+/// State for the raw JSON and tagged text popup.
+#[derive(Default)]
+pub struct RawView {
+    lines: Option<Vec<Line<'static>>>,
+    title: &'static str,
+    scroll: u16,
+}
+
+impl RawView {
+    /// Show `text` as raw JSON from the top.
+    pub fn open(&mut self, text: &str) {
+        let lines = text
+            .lines()
+            .map(|line| Line::raw(line.to_string()))
+            .collect();
+        self.open_lines("Raw JSON", lines);
+    }
+
+    /// Show `lines` under `title` from the top.
+    pub fn open_lines(&mut self, title: &'static str, lines: Vec<Line<'static>>) {
+        self.lines = Some(lines);
+        self.title = title;
+        self.scroll = 0;
+    }
+
+    /// The text shown, while open.
+    #[cfg(test)]
+    pub fn text(&self) -> Option<String> {
+        let lines = self.lines.as_ref()?;
+        Some(
+            lines
+                .iter()
+                .map(Line::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+/// Widget for rendering the raw JSON and tagged text popup.
+pub struct RawViewWidget;
+
+impl StatefulWidget for RawViewWidget {
+    type State = RawView;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let Some(lines) = &state.lines else {
+            return;
+        };
+
+        let popup_area = Rect::new(
+            area.x + area.width / 10,
+            area.y + area.height / 10,
+            area.width - area.width / 5,
+            area.height - area.height / 5,
+        );
+
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .title(format!(" {} (j/k scroll, Esc close) ", state.title))
+            .border_style(Style::new().fg(Color::Cyan));
+        Paragraph::new(lines.clone())
+            .block(block)
+            .scroll((state.scroll, 0))
+            .render(popup_area, buf);
+    }
+}
+
+impl InteractiveStatefulWidget for RawViewWidget {
+    type Input = ();
+    type Event = KeyEvent;
+
+    fn update_state(_state: &mut Self::State, _input: Self::Input) {}
+
+    /// While open, every key belongs to the popup.
+    fn handle_event(state: &mut Self::State, event: Self::Event) -> bool {
+        let Some(lines) = &state.lines else {
+            return false;
+        };
+
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q' | 'R' | 'A') => state.lines = None,
+            KeyCode::Char('j') | KeyCode::Down => {
+                let last_line = u16::try_from(lines.len().saturating_sub(1)).unwrap_or(u16::MAX);
+                state.scroll = (state.scroll + 1).min(last_line);
+            }
+            KeyCode::Char('k') | KeyCode::Up => state.scroll = state.scroll.saturating_sub(1),
+            _ => {}
+        }
+        true
+    }
+}
@@ -1,12 +1,18 @@
 //! Common render helper functions for modes.
 
+use std::{borrow::Cow, mem};
+
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     prelude::Stylize,
-    style::Color,
+    style::{Color, Style},
+    text::{Line, Span},
     widgets::Paragraph,
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::theme::Theme;
 
 /// Render an error message at the top of the area and return the remaining
 /// area. Returns the original area if there's no error.
@@ -28,3 +34,204 @@ pub fn render_error(frame: &mut Frame, area: Rect, error: Option<&str>) -> Rect
 pub fn render_no_goals(frame: &mut Frame, area: Rect) {
     frame.render_widget(Paragraph::new("No goals").fg(Color::DarkGray), area);
 }
+
+/// Marker in front of the active goal (the first one, which the next tactic
+/// works on).
+pub const ACTIVE_GOAL_MARKER: &str = "▸ ";
+
+/// Blank of the same width for the other goals.
+pub const INACTIVE_GOAL_MARKER: &str = "  ";
+
+/// Characters kept when a non-active goal is collapsed.
+pub const COLLAPSED_GOAL_CHARS: usize = 32;
+
+/// Marker for the goal at `goal_idx`.
+pub const fn goal_marker(goal_idx: usize) -> &'static str {
+    if goal_idx == 0 {
+        ACTIVE_GOAL_MARKER
+    } else {
+        INACTIVE_GOAL_MARKER
+    }
+}
+
+/// Elaboration time from which a tactic shows as slow.
+const SLOW_TACTIC_MS: u64 = 100;
+
+/// Elaboration time from which a tactic shows as very slow.
+const VERY_SLOW_TACTIC_MS: u64 = 1000;
+
+/// Elaboration time as `12ms` or `1.2s`, colored by how slow it is.
+pub fn timing_span(ms: u64) -> Span<'static> {
+    let (text, color) = if ms < SLOW_TACTIC_MS {
+        (format!("{ms}ms"), Theme::TIMING_FAST)
+    } else if ms < VERY_SLOW_TACTIC_MS {
+        (format!("{ms}ms"), Theme::TIMING_SLOW)
+    } else {
+        (
+            format!("{}.{}s", ms / 1000, ms % 1000 / 100),
+            Theme::TIMING_VERY_SLOW,
+        )
+    };
+    Span::styled(text, Style::new().fg(color))
+}
+
+/// Move the marker leading `line` to its end for right-to-left layouts,
+/// pointing the other way. Only the marker changes, never the Lean text.
+pub fn mirror_marker(line: &mut Line<'static>) {
+    if line.spans.is_empty() {
+        return;
+    }
+    let marker = line.spans.remove(0);
+    let text = format!(" {}", marker.content.trim_end().replace('▸', "◂"));
+    line.spans.push(Span::styled(text, marker.style));
+}
+
+/// Shorten text to at most `max_chars` characters, marking the cut with `…`.
+pub fn summarize(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    format!("{kept}…")
+}
+
+/// A tactic as shown in a step title: whole, or with `summarize` cut to its
+/// first line, marking the cut with `…`.
+pub fn tactic_text(text: &str, summarize: bool) -> Cow<'_, str> {
+    match text.split_once('\n') {
+        Some((first, _)) if summarize => Cow::Owned(format!("{}…", first.trim_end())),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// Wrap `line` into rows of at most `width` terminal columns.
+///
+/// Breaks at whitespace like ratatui's `Wrap`, but a token longer than a
+/// whole row (a huge numeral or identifier) is cut at the column boundary
+/// instead of overflowing. Widths are display widths, so wide characters
+/// take two columns and are never split across rows.
+pub fn hard_wrap(line: &Line<'_>, width: u16) -> Vec<Line<'static>> {
+    let mut wrapper = Wrapper {
+        width: usize::from(width.max(1)),
+        rows: vec![Vec::new()],
+        column: 0,
+    };
+    for span in &line.spans {
+        for piece in split_words(&span.content) {
+            wrapper.push(piece, span.style);
+        }
+    }
+    wrapper
+        .rows
+        .into_iter()
+        .map(|spans| Line::from(spans).style(line.style))
+        .collect()
+}
+
+/// Split text into alternating runs of whitespace and non-whitespace.
+fn split_words(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut prev_kind = None;
+    for (i, c) in text.char_indices() {
+        let is_space = c.is_whitespace();
+        if prev_kind.is_some_and(|prev| prev != is_space) {
+            pieces.push(&text[start..i]);
+            start = i;
+        }
+        prev_kind = Some(is_space);
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+struct Wrapper {
+    width: usize,
+    rows: Vec<Vec<Span<'static>>>,
+    column: usize,
+}
+
+impl Wrapper {
+    fn push(&mut self, piece: &str, style: Style) {
+        let len = piece.width();
+        if self.column + len <= self.width {
+            self.append(piece.to_string(), len, style);
+        } else if piece.trim().is_empty() {
+            // Whitespace at a break is dropped
+            self.new_row();
+        } else if len <= self.width {
+            self.new_row();
+            self.append(piece.to_string(), len, style);
+        } else {
+            self.push_chars(piece, style);
+        }
+    }
+
+    /// Cut an over-long token into row-sized chunks.
+    fn push_chars(&mut self, token: &str, style: Style) {
+        let mut chunk = String::new();
+        let mut len = 0;
+        for c in token.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if self.column + len + char_width > self.width && self.column + len > 0 {
+                self.append(mem::take(&mut chunk), len, style);
+                self.new_row();
+                len = 0;
+            }
+            chunk.push(c);
+            len += char_width;
+        }
+        self.append(chunk, len, style);
+    }
+
+    fn append(&mut self, text: String, len: usize, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(row) = self.rows.last_mut() {
+            row.push(Span::styled(text, style));
+        }
+        self.column += len;
+    }
+
+    fn new_row(&mut self) {
+        self.rows.push(Vec::new());
+        self.column = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 500-character token is split into full rows instead of overflowing.
+    #[test]
+    fn test_long_token_hard_wrapped() {
+        let token = "9".repeat(500);
+        let line = Line::from(vec![Span::raw("⊢ x = "), Span::raw(token.clone())]);
+
+        let rows = hard_wrap(&line, 40);
+
+        assert!(rows.iter().all(|row| row.width() <= 40));
+        assert_eq!(rows[0].to_string(), format!("⊢ x = {}", &token[..34]));
+        let joined: String = rows.iter().map(ToString::to_string).collect();
+        assert_eq!(joined, format!("⊢ x = {token}"));
+        assert_eq!(rows.len(), 13);
+    }
+
+    /// Wide characters count two columns each and stay whole at a break.
+    #[test]
+    fn test_wide_token_wrapped_by_display_width() {
+        let token = "全".repeat(20);
+
+        let rows = hard_wrap(&Line::raw(token.clone()), 9);
+
+        assert!(rows.iter().all(|row| row.width() <= 9));
+        assert_eq!(rows[0].to_string(), "全".repeat(4));
+        let joined: String = rows.iter().map(ToString::to_string).collect();
+        assert_eq!(joined, token);
+        assert_eq!(rows.len(), 5);
+    }
+}
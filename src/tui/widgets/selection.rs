@@ -10,12 +10,29 @@ pub enum Selection {
     InitialHyp { hyp_idx: usize },
     /// Hypothesis at a proof step (`node_id` indexes into `ProofDag`).
     Hyp { node_id: u32, hyp_idx: usize },
+    /// Every hypothesis at a proof step at once.
+    AllHyps { node_id: u32 },
     /// Goal at a proof step.
     Goal { node_id: u32, goal_idx: usize },
+    /// A proof step itself, standing for its tactic.
+    Node { node_id: u32 },
     /// The theorem conclusion.
     Theorem,
 }
 
+impl Selection {
+    /// Proof step this selection belongs to, if any.
+    pub const fn node_id(self) -> Option<u32> {
+        match self {
+            Self::Hyp { node_id, .. }
+            | Self::AllHyps { node_id }
+            | Self::Goal { node_id, .. }
+            | Self::Node { node_id } => Some(node_id),
+            Self::InitialHyp { .. } | Self::Theorem => None,
+        }
+    }
+}
+
 /// A clickable region mapped to a selection.
 #[derive(Debug, Clone)]
 pub struct ClickRegion {
@@ -66,6 +83,15 @@ impl SelectionState {
         });
     }
 
+    /// Select a specific item. Returns false if it is not in the list.
+    pub fn select(&mut self, target: Selection, items: &[Selection]) -> bool {
+        let Some(idx) = items.iter().position(|item| *item == target) else {
+            return false;
+        };
+        self.selected_index = Some(idx);
+        true
+    }
+
     /// Get currently selected item from a list.
     pub fn current_selection<'a>(&self, items: &'a [Selection]) -> Option<&'a Selection> {
         self.selected_index.and_then(|i| items.get(i))
@@ -9,7 +9,10 @@ use ratatui::{
 };
 
 use super::{ClickRegion, Selection};
-use crate::{lean_rpc::HypothesisInfo, tui::widgets::theme::Theme};
+use crate::{
+    lean_rpc::HypothesisInfo,
+    tui::widgets::theme::{SelectionColors, Theme},
+};
 
 /// State for the given pane.
 #[derive(Default)]
@@ -33,10 +36,17 @@ impl GivenPaneState {
     }
 }
 
+/// Rows of the bordered bar of givens.
+const EXPANDED_HEIGHT: u16 = 3;
+
 /// Given pane widget - displays initial hypotheses.
 pub struct GivenPane<'a> {
     hypotheses: &'a [HypothesisInfo],
     selection: Option<Selection>,
+    /// Show a one-line count instead of the givens.
+    collapsed: bool,
+    /// Colors of the selected given.
+    selection_colors: SelectionColors,
 }
 
 impl<'a> GivenPane<'a> {
@@ -44,8 +54,37 @@ impl<'a> GivenPane<'a> {
         Self {
             hypotheses,
             selection,
+            collapsed: false,
+            selection_colors: SelectionColors::DEFAULT,
+        }
+    }
+
+    pub const fn collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
+    }
+
+    /// Rows the pane takes in the tableau.
+    pub const fn height(&self) -> u16 {
+        if self.collapsed {
+            1
+        } else {
+            EXPANDED_HEIGHT
         }
     }
+
+    fn render_collapsed(&self, area: Rect, buf: &mut Buffer) {
+        let count = self.hypotheses.iter().filter(|h| !h.is_proof).count();
+        let noun = if count == 1 { "given" } else { "givens" };
+        Paragraph::new(format!(" {count} {noun}, press G to expand"))
+            .style(Style::new().fg(Theme::PROOF_HYP_FG))
+            .render(area, buf);
+    }
 }
 
 impl StatefulWidget for GivenPane<'_> {
@@ -53,6 +92,10 @@ impl StatefulWidget for GivenPane<'_> {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         state.click_regions.clear();
+        if self.collapsed {
+            self.render_collapsed(area, buf);
+            return;
+        }
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -86,6 +129,7 @@ impl StatefulWidget for GivenPane<'_> {
         }
 
         let mut x_offset = inner.x;
+        let shown = visible_hyps.len().min(5);
         let hyp_spans: Vec<Span> = visible_hyps
             .iter()
             .take(5)
@@ -103,12 +147,12 @@ impl StatefulWidget for GivenPane<'_> {
                 );
 
                 let (fg, bg) = hyp_style_colors(h.is_proof);
-                let mut style = Style::new().fg(fg).bg(bg);
-                if is_selected {
-                    style = style.add_modifier(Modifier::UNDERLINED);
-                }
+                let style = self
+                    .selection_colors
+                    .apply(Style::new().fg(fg).bg(bg), is_selected);
 
-                let truncated_type = truncate_str(&h.type_.to_plain_text(), 20);
+                let max_chars = text_budget(inner.width, shown, h.name.chars().count() + 5);
+                let truncated_type = truncate_str(&h.type_.to_plain_text(), max_chars);
                 let text = format!(" {}: {} ", h.name, truncated_type);
                 let text_width = text.chars().count() as u16;
 
@@ -139,6 +183,18 @@ impl StatefulWidget for GivenPane<'_> {
     }
 }
 
+/// Fewest characters of a hypothesis type or goal shown, however narrow the
+/// node.
+const MIN_TEXT_CHARS: usize = 8;
+
+/// Characters available to each of `count` items sharing a line of `width`
+/// columns, after `overhead` columns of names and separators.
+pub fn text_budget(width: u16, count: usize, overhead: usize) -> usize {
+    (usize::from(width) / count.max(1))
+        .saturating_sub(overhead)
+        .max(MIN_TEXT_CHARS)
+}
+
 /// Truncate a string to max length with ellipsis.
 pub fn truncate_str(s: &str, max: usize) -> String {
     if s.chars().count() <= max {
@@ -159,3 +215,35 @@ pub const fn hyp_style_colors(is_proof: bool) -> (Color, Color) {
         (Theme::DATA_HYP_FG, Theme::DATA_HYP_BG)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::TaggedText;
+
+    /// Collapsing the bar leaves one row with the number of givens.
+    #[test]
+    fn test_collapsed_height() {
+        let hypotheses: Vec<HypothesisInfo> = ["n", "m", "h"]
+            .into_iter()
+            .map(|name| HypothesisInfo {
+                name: name.to_string(),
+                type_: TaggedText::Text {
+                    text: "Nat".to_string(),
+                },
+                ..Default::default()
+            })
+            .collect();
+        let pane = || GivenPane::new(&hypotheses, None);
+        assert_eq!(pane().height(), EXPANDED_HEIGHT);
+        assert_eq!(pane().collapsed(true).height(), 1);
+
+        let area = Rect::new(0, 0, 40, 1);
+        let mut buf = Buffer::empty(area);
+        pane()
+            .collapsed(true)
+            .render(area, &mut buf, &mut GivenPaneState::default());
+        let row: String = (0..40).map(|x| buf[(x, 0)].symbol()).collect();
+        assert_eq!(row.trim(), "3 givens, press G to expand");
+    }
+}
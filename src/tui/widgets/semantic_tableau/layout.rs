@@ -8,11 +8,15 @@ use ratatui::{
 
 use super::{
     given_pane::{GivenPane, GivenPaneState},
+    minimap::{Minimap, MinimapState, MINIMAP_WIDTH},
     proof_pane::{ProofPane, ProofPaneState},
     theorem_pane::{TheoremPane, TheoremPaneState},
     Selection,
 };
-use crate::lean_rpc::{ProofDag, ProofState};
+use crate::{
+    lean_rpc::{ProofDag, ProofState},
+    tui::widgets::theme::SelectionColors,
+};
 
 /// Combined state for the semantic tableau layout.
 #[derive(Default)]
@@ -23,6 +27,8 @@ pub struct SemanticTableauState {
     pub proof: ProofPaneState,
     /// State for the theorem pane.
     pub theorem: TheoremPaneState,
+    /// State for the minimap column.
+    pub minimap: MinimapState,
 }
 
 impl SemanticTableauState {
@@ -41,12 +47,21 @@ impl SemanticTableauState {
 }
 
 /// Semantic tableau layout widget - combines given, proof, and theorem panes.
+#[allow(clippy::struct_excessive_bools)]
 pub struct SemanticTableauLayout<'a> {
     dag: &'a ProofDag,
     top_down: bool,
     selection: Option<Selection>,
     /// Current proof state from LSP (may differ from node's `state_after`).
     current_state: &'a ProofState,
+    /// Whether to show the minimap beside the proof pane.
+    show_minimap: bool,
+    /// Whether the given pane is folded to one line.
+    givens_collapsed: bool,
+    /// Whether multi-line tactics are titled with their first line.
+    summarize_tactics: bool,
+    /// Colors of the selected row in the minimap.
+    selection_colors: SelectionColors,
 }
 
 impl<'a> SemanticTableauLayout<'a> {
@@ -61,23 +76,66 @@ impl<'a> SemanticTableauLayout<'a> {
             top_down,
             selection,
             current_state,
+            show_minimap: false,
+            givens_collapsed: false,
+            summarize_tactics: false,
+            selection_colors: SelectionColors::DEFAULT,
         }
     }
+
+    pub const fn show_minimap(mut self, show: bool) -> Self {
+        self.show_minimap = show;
+        self
+    }
+
+    pub const fn collapse_givens(mut self, collapsed: bool) -> Self {
+        self.givens_collapsed = collapsed;
+        self
+    }
+
+    pub const fn summarize_tactics(mut self, summarize: bool) -> Self {
+        self.summarize_tactics = summarize;
+        self
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
+    }
 }
 
 impl StatefulWidget for SemanticTableauLayout<'_> {
     type State = SemanticTableauState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let (given_area, proof_area, theorem_area) = compute_layout(area, self.top_down);
+        let given_widget = GivenPane::new(&self.dag.initial_state.hypotheses, self.selection)
+            .collapsed(self.givens_collapsed)
+            .selection_colors(self.selection_colors);
+        let (given_area, proof_area, theorem_area) =
+            compute_layout(area, self.top_down, given_widget.height());
 
         // Render given pane
-        let given_widget = GivenPane::new(&self.dag.initial_state.hypotheses, self.selection);
         given_widget.render(given_area, buf, &mut state.given);
 
+        let proof_area = if self.show_minimap {
+            let [proof_area, minimap_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(MINIMAP_WIDTH)])
+                    .areas(proof_area);
+            let selected_node = self.selection.and_then(Selection::node_id);
+            Minimap::new(self.dag, selected_node)
+                .selection_colors(self.selection_colors)
+                .render(minimap_area, buf, &mut state.minimap);
+            proof_area
+        } else {
+            state.minimap = MinimapState::default();
+            proof_area
+        };
+
         // Render proof pane with actual current state
         let proof_widget =
-            ProofPane::new(self.dag, self.top_down, self.selection, self.current_state);
+            ProofPane::new(self.dag, self.top_down, self.selection, self.current_state)
+                .summarize_tactics(self.summarize_tactics)
+                .selection_colors(self.selection_colors);
         proof_widget.render(proof_area, buf, &mut state.proof);
 
         // Render theorem pane with the top-level theorem (initial goal)
@@ -88,17 +146,19 @@ impl StatefulWidget for SemanticTableauLayout<'_> {
             .first()
             .map(|g| g.type_.to_plain_text())
             .unwrap_or_default();
-        let theorem_widget = TheoremPane::new(&theorem_goal, self.selection);
+        let theorem_widget = TheoremPane::new(&theorem_goal, self.selection)
+            .automation_count(self.dag.automation_count())
+            .selection_colors(self.selection_colors);
         theorem_widget.render(theorem_area, buf, &mut state.theorem);
     }
 }
 
 /// Compute layout areas for the three panes.
-fn compute_layout(area: Rect, top_down: bool) -> (Rect, Rect, Rect) {
+fn compute_layout(area: Rect, top_down: bool, given_height: u16) -> (Rect, Rect, Rect) {
     if top_down {
         // Top-down: Given at top, Proof in middle, Theorem at bottom
         Layout::vertical([
-            Constraint::Length(3),
+            Constraint::Length(given_height),
             Constraint::Fill(1),
             Constraint::Length(3),
         ])
@@ -107,7 +167,7 @@ fn compute_layout(area: Rect, top_down: bool) -> (Rect, Rect, Rect) {
     } else {
         // Bottom-up: Given at top, Theorem below it, Proof at bottom
         let [given, theorem, proof] = Layout::vertical([
-            Constraint::Length(3),
+            Constraint::Length(given_height),
             Constraint::Length(3),
             Constraint::Fill(1),
         ])
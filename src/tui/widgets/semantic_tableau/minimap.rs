@@ -0,0 +1,168 @@
+//! Minimap - one row per proof node, showing the whole tree shape at a glance.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, StatefulWidget, Widget},
+};
+
+use crate::{
+    lean_rpc::{NodeId, ProofDag, ProofDagNode},
+    tui::widgets::theme::{SelectionColors, Theme},
+};
+
+/// Width of the minimap column, including its border.
+pub const MINIMAP_WIDTH: u16 = 10;
+
+/// State for the minimap, used to map clicks back to nodes.
+#[derive(Default)]
+pub struct MinimapState {
+    /// Inner area of the last render.
+    area: Rect,
+    /// Index of the node drawn on the first row.
+    offset: usize,
+    /// Nodes in display order (main tree, then side proofs).
+    nodes: Vec<NodeId>,
+}
+
+impl MinimapState {
+    /// Find the node drawn at a screen position.
+    pub fn node_at(&self, x: u16, y: u16) -> Option<NodeId> {
+        let inside = x >= self.area.x
+            && x < self.area.x + self.area.width
+            && y >= self.area.y
+            && y < self.area.y + self.area.height;
+        if !inside {
+            return None;
+        }
+        self.nodes
+            .get(self.offset + usize::from(y - self.area.y))
+            .copied()
+    }
+}
+
+/// Minimap widget, colored by completion status.
+pub struct Minimap<'a> {
+    dag: &'a ProofDag,
+    /// Node containing the current selection.
+    selected_node: Option<NodeId>,
+    /// Colors of the selected node's row.
+    selection_colors: SelectionColors,
+}
+
+impl<'a> Minimap<'a> {
+    pub const fn new(dag: &'a ProofDag, selected_node: Option<NodeId>) -> Self {
+        Self {
+            dag,
+            selected_node,
+            selection_colors: SelectionColors::DEFAULT,
+        }
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
+    }
+
+    /// Glyph and color for a node, matching the tree's border colors.
+    fn marker(&self, node: &ProofDagNode) -> (&'static str, Color) {
+        if self.dag.is_current(node.id) {
+            ("●", Theme::CURRENT_NODE_BORDER)
+        } else if node.has_unsolved_spawned_goals || (node.is_leaf() && !node.is_complete()) {
+            ("□", Theme::INCOMPLETE_NODE_BORDER)
+        } else if node.is_leaf() {
+            ("■", Theme::COMPLETED_NODE_BORDER)
+        } else {
+            ("·", Theme::TACTIC_BORDER)
+        }
+    }
+}
+
+impl StatefulWidget for Minimap<'_> {
+    type State = MinimapState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .border_style(Style::new().fg(Theme::BORDER));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        state.area = inner;
+        state.nodes = self
+            .dag
+            .dfs_iter()
+            .chain(self.dag.orphan_dfs_iter())
+            .map(|node| node.id)
+            .collect();
+
+        // Keep the current node in view
+        let rows = usize::from(inner.height);
+        let anchor = self
+            .dag
+            .current_node
+            .and_then(|id| state.nodes.iter().position(|&n| n == id))
+            .unwrap_or(0);
+        state.offset = anchor
+            .saturating_sub(rows / 2)
+            .min(state.nodes.len().saturating_sub(rows));
+
+        let max_indent = inner.width.saturating_sub(1);
+        for (row, &node_id) in state.nodes.iter().skip(state.offset).take(rows).enumerate() {
+            let Some(node) = self.dag.get(node_id) else {
+                continue;
+            };
+            let y = inner.y + u16::try_from(row).unwrap_or(u16::MAX);
+            let indent = u16::try_from(node.depth)
+                .unwrap_or(u16::MAX)
+                .min(max_indent);
+            let (glyph, color) = self.marker(node);
+
+            if self.selected_node == Some(node_id) {
+                buf.set_style(
+                    Rect::new(inner.x, y, inner.width, 1),
+                    self.selection_colors.style(),
+                );
+            }
+            buf.set_string(inner.x + indent, y, glyph, Style::new().fg(color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clicking a row maps back to the node drawn on it.
+    #[test]
+    fn test_click_maps_to_node() {
+        let node = |id, children: Vec<NodeId>, parent| ProofDagNode {
+            id,
+            children,
+            parent,
+            depth: usize::from(parent.is_some()),
+            ..Default::default()
+        };
+        let dag = ProofDag {
+            nodes: vec![
+                node(0, vec![1, 2], None),
+                node(1, vec![], Some(0)),
+                node(2, vec![], Some(0)),
+            ],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let area = Rect::new(20, 3, MINIMAP_WIDTH, 5);
+        let mut buf = Buffer::empty(area);
+        let mut state = MinimapState::default();
+        Minimap::new(&dag, None).render(area, &mut buf, &mut state);
+
+        // Left border takes the first column
+        assert_eq!(state.node_at(21, 3), Some(0));
+        assert_eq!(state.node_at(21, 5), Some(2));
+        assert_eq!(state.node_at(21, 6), None, "below the last node");
+        assert_eq!(state.node_at(5, 3), None, "outside the minimap");
+    }
+}
@@ -3,6 +3,7 @@
 mod canvas;
 pub mod given_pane;
 pub mod layout;
+pub mod minimap;
 pub mod navigation;
 pub mod proof_pane;
 pub mod state_node;
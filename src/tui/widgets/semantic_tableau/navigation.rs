@@ -4,7 +4,7 @@
 
 use tracing::debug;
 
-use super::{tree_layout::TreeLayout, Selection};
+use super::{proof_pane::cell_selection, tree_layout::TreeLayout, Selection};
 use crate::lean_rpc::ProofDag;
 
 /// Region for keyboard navigation - uses virtual coordinates (i32).
@@ -97,6 +97,18 @@ pub fn build_navigation_regions(dag: &ProofDag, layout: &TreeLayout) -> Vec<Navi
             "Processing node for navigation"
         );
 
+        // A node drawn as a single cell has one region, the cell
+        if !pos.detailed {
+            regions.push(NavigationRegion {
+                x: pos.x,
+                y: pos.y,
+                width: 1,
+                height: 1,
+                selection: cell_selection(node),
+            });
+            continue;
+        }
+
         // New hypotheses in this node
         for (i, &hyp_idx) in node.new_hypotheses.iter().enumerate() {
             let hyp_width = 15u16;
@@ -217,6 +229,25 @@ pub fn find_nearest_in_direction(
     result
 }
 
+/// Step to the neighbouring item in reading order, skipping items that have
+/// no navigation region.
+///
+/// Used when spatial navigation finds nothing, so `j`/`k` past the last item
+/// of one goal continue into the next goal instead of stopping.
+pub fn step_in_order(
+    regions: &[NavigationRegion],
+    order: &[Selection],
+    current: Selection,
+    direction: Direction,
+) -> Option<Selection> {
+    let idx = order.iter().position(|s| *s == current)?;
+    let navigable = |sel: &&Selection| regions.iter().any(|r| r.selection == **sel);
+    match direction {
+        Direction::Down | Direction::Right => order[idx + 1..].iter().find(navigable).copied(),
+        Direction::Up | Direction::Left => order[..idx].iter().rev().find(navigable).copied(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -442,4 +473,45 @@ mod tests {
             "Left from second goal should go to first goal"
         );
     }
+
+    /// Moving down past one goal's target continues into the next goal.
+    #[test]
+    fn test_step_into_next_goal() {
+        let region = |x, y, selection| NavigationRegion {
+            x,
+            y,
+            width: 15,
+            height: 1,
+            selection,
+        };
+        let first_goal = Selection::Goal {
+            node_id: 1,
+            goal_idx: 0,
+        };
+        let next_hyp = Selection::Hyp {
+            node_id: 2,
+            hyp_idx: 0,
+        };
+        // Second goal's subtree sits to the side, not below the first goal
+        let regions = vec![region(0, 5, first_goal), region(40, 2, next_hyp)];
+        let order = [Selection::InitialHyp { hyp_idx: 0 }, first_goal, next_hyp];
+
+        assert_eq!(
+            find_nearest_in_direction(&regions, first_goal, Direction::Down),
+            None
+        );
+        assert_eq!(
+            step_in_order(&regions, &order, first_goal, Direction::Down),
+            Some(next_hyp)
+        );
+        assert_eq!(
+            step_in_order(&regions, &order, next_hyp, Direction::Up),
+            Some(first_goal)
+        );
+        // Items without a region are skipped
+        assert_eq!(
+            step_in_order(&regions, &order, first_goal, Direction::Up),
+            None
+        );
+    }
 }
@@ -1,9 +1,11 @@
 //! Proof pane - scrollable proof tree visualization widget.
 
+use std::mem;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget, Widget},
 };
 
@@ -11,10 +13,13 @@ use super::{
     canvas::VirtualCanvas,
     navigation::{build_navigation_regions, NavigationRegion},
     state_node::{StateNode, StateNodeState},
-    tree_layout::{calculate_tree_layout, NodePosition, TreeLayout},
+    tree_layout::{calculate_tree_layout, NodePosition, TreeLayout, ORPHAN_LABEL},
     ClickRegion, Selection,
 };
-use crate::lean_rpc::{ProofDag, ProofState};
+use crate::{
+    lean_rpc::{NodeId, ProofDag, ProofDagNode, ProofState},
+    tui::widgets::theme::SelectionColors,
+};
 
 /// State for the proof pane widget.
 #[derive(Default)]
@@ -23,9 +28,13 @@ pub struct ProofPaneState {
     scroll_y: i32,
     manual_scroll: bool,
     prev_current_node: Option<u32>,
+    /// Node the layout of a large tree is detailed around.
+    lod_focus: Option<NodeId>,
     layout: TreeLayout,
     content_width: i32,
     content_height: i32,
+    /// Width of the pane at the last render, for scroll bounds.
+    viewport_width: u16,
     pub click_regions: Vec<ClickRegion>,
     pub navigation_regions: Vec<NavigationRegion>,
 }
@@ -44,20 +53,25 @@ impl ProofPaneState {
         self.scroll_y = (self.scroll_y + 1).min(max);
     }
 
-    #[allow(dead_code)]
-    pub fn scroll_left(&mut self) {
+    /// Scroll left by `columns`, taking over from auto-centering.
+    pub fn scroll_left(&mut self, columns: u16) {
         self.manual_scroll = true;
-        self.scroll_x = (self.scroll_x - 1).max(0);
+        self.scroll_x = (self.scroll_x - i32::from(columns)).max(0);
     }
 
-    #[allow(dead_code)]
-    pub fn scroll_right(&mut self, viewport_width: u16) {
+    /// Scroll right by `columns`, bounded by the content width.
+    pub fn scroll_right(&mut self, columns: u16) {
         self.manual_scroll = true;
-        let max = (self.content_width - i32::from(viewport_width)).max(0);
-        self.scroll_x = (self.scroll_x + 1).min(max);
+        let max = (self.content_width - i32::from(self.viewport_width)).max(0);
+        self.scroll_x = (self.scroll_x + i32::from(columns)).min(max);
     }
 
-    #[allow(dead_code)]
+    /// Width of the pane at the last render.
+    pub const fn viewport_width(&self) -> u16 {
+        self.viewport_width
+    }
+
+    /// Return to following the selection.
     pub const fn reset_scroll(&mut self) {
         self.manual_scroll = false;
     }
@@ -94,6 +108,10 @@ pub struct ProofPane<'a> {
     /// Current proof state from LSP (used to override current node's state if
     /// different).
     current_state: &'a ProofState,
+    /// Whether multi-line tactics are titled with their first line.
+    summarize_tactics: bool,
+    /// Colors of the selected goal or hypothesis.
+    selection_colors: SelectionColors,
 }
 
 impl<'a> ProofPane<'a> {
@@ -108,8 +126,20 @@ impl<'a> ProofPane<'a> {
             top_down,
             selection,
             current_state,
+            summarize_tactics: false,
+            selection_colors: SelectionColors::DEFAULT,
         }
     }
+
+    pub const fn summarize_tactics(mut self, summarize: bool) -> Self {
+        self.summarize_tactics = summarize;
+        self
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
+    }
 }
 
 impl StatefulWidget for ProofPane<'_> {
@@ -118,6 +148,7 @@ impl StatefulWidget for ProofPane<'_> {
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         state.click_regions.clear();
         state.navigation_regions.clear();
+        state.viewport_width = area.width;
 
         if self.dag.is_empty() || self.dag.root.is_none() {
             Paragraph::new("No proof steps")
@@ -126,8 +157,7 @@ impl StatefulWidget for ProofPane<'_> {
             return;
         }
 
-        // Calculate layout
-        state.layout = calculate_tree_layout(self.dag, self.top_down);
+        self.update_layout(state, area);
         state.content_width = state.layout.content_width;
         state.content_height = state.layout.content_height;
 
@@ -163,6 +193,14 @@ impl StatefulWidget for ProofPane<'_> {
             let Some(node) = self.dag.get(pos.node_id) else {
                 continue;
             };
+            if !pos.detailed {
+                self.render_cell(buf, render_area, node);
+                state.click_regions.push(ClickRegion {
+                    area: render_area,
+                    selection: cell_selection(node),
+                });
+                continue;
+            }
             if render_area.width < 3 || render_area.height < 3 {
                 continue;
             }
@@ -181,17 +219,119 @@ impl StatefulWidget for ProofPane<'_> {
                 self.selection,
                 self.top_down,
                 override_state,
-            );
+            )
+            .summarize_tactic(self.summarize_tactics)
+            .selection_colors(self.selection_colors);
             let mut node_state = StateNodeState::default();
             node_widget.render(render_area, buf, &mut node_state);
             state.click_regions.extend(node_state.click_regions);
         }
 
+        render_connectors(buf, area, &canvas, &state.layout);
+        render_orphan_label(buf, area, &canvas, &state.layout);
+
         // Scrollbars
         render_scrollbars(buf, area, &canvas);
     }
 }
 
+impl ProofPane<'_> {
+    /// Lay the tree out, in full only around the focus in large trees.
+    /// Scrolled by hand, the focus stays where it was in the viewport.
+    fn update_layout(&self, state: &mut ProofPaneState, area: Rect) {
+        let previous = mem::take(&mut state.layout);
+        let focus = lod_focus(state, &previous, self.dag, self.selection, area);
+        state.lod_focus = focus;
+        state.layout =
+            calculate_tree_layout(self.dag, self.top_down, self.summarize_tactics, focus);
+
+        if !state.manual_scroll {
+            return;
+        }
+        let moved =
+            focus.and_then(|id| Some((previous.find_node(id)?, state.layout.find_node(id)?)));
+        if let Some((old, new)) = moved {
+            state.scroll_x += new.x - old.x;
+            state.scroll_y += new.y - old.y;
+        }
+    }
+
+    /// Draw a node far from the focus of a large tree as a single cell.
+    fn render_cell(&self, buf: &mut Buffer, area: Rect, node: &ProofDagNode) {
+        let selected = self.selection.and_then(Selection::node_id) == Some(node.id);
+        let style = if selected {
+            self.selection_colors.apply(Style::new().fg(Color::Cyan), true)
+        } else if self.dag.is_current(node.id) {
+            Style::new().fg(Color::Cyan)
+        } else {
+            Style::new().fg(Color::DarkGray)
+        };
+        buf[(area.x, area.y)].set_char('▪').set_style(style);
+    }
+}
+
+/// What picking a node drawn as a single cell selects: its first goal, its
+/// first new hypothesis when it closes its goal, or else the step itself.
+pub fn cell_selection(node: &ProofDagNode) -> Selection {
+    if !node.state_after.goals.is_empty() {
+        Selection::Goal {
+            node_id: node.id,
+            goal_idx: 0,
+        }
+    } else if let Some(&hyp_idx) = node.new_hypotheses.first() {
+        Selection::Hyp {
+            node_id: node.id,
+            hyp_idx,
+        }
+    } else {
+        Selection::Node { node_id: node.id }
+    }
+}
+
+/// Draw the connectors of branching nodes cell by cell, so that they stay
+/// aligned with the boxes when partly scrolled out of view.
+fn render_connectors(buf: &mut Buffer, area: Rect, canvas: &VirtualCanvas, layout: &TreeLayout) {
+    let style = Style::new().fg(Color::DarkGray);
+    let cells = layout
+        .connectors
+        .iter()
+        .flat_map(|(x, y, glyphs)| (*x..).zip(glyphs.chars()).map(|(cx, glyph)| (cx, *y, glyph)));
+    for (x, y, glyph) in cells {
+        if let Some(cell) = canvas.clip_to_viewport(x, y, 1, 1, area) {
+            buf[(cell.x, cell.y)].set_char(glyph).set_style(style);
+        }
+    }
+}
+
+/// Label the side proof section, and each side proof of a named clause,
+/// when they are in view.
+fn render_orphan_label(buf: &mut Buffer, area: Rect, canvas: &VirtualCanvas, layout: &TreeLayout) {
+    let Some((x, y)) = layout.orphan_label else {
+        return;
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let width = ORPHAN_LABEL.chars().count() as u16;
+    if let Some(label_area) = canvas.clip_to_viewport(x, y, width, 1, area) {
+        Paragraph::new(ORPHAN_LABEL)
+            .style(
+                Style::new()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .render(label_area, buf);
+    }
+
+    for (x, y, clause) in &layout.clause_labels {
+        #[allow(clippy::cast_possible_truncation)]
+        let width = clause.chars().count() as u16;
+        if let Some(label_area) = canvas.clip_to_viewport(*x, *y, width, 1, area) {
+            Paragraph::new(clause.as_str())
+                .style(Style::new().fg(Color::Magenta))
+                .render(label_area, buf);
+        }
+    }
+}
+
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 fn render_scrollbars(buf: &mut Buffer, area: Rect, canvas: &VirtualCanvas) {
     if canvas.needs_vertical_scroll(area) && area.height > 2 {
@@ -227,6 +367,63 @@ fn render_scrollbars(buf: &mut Buffer, area: Rect, canvas: &VirtualCanvas) {
     }
 }
 
+/// Node the layout is detailed around: the selected one, else the current
+/// one, else the root.
+fn focus_node(dag: &ProofDag, selection: Option<Selection>) -> Option<NodeId> {
+    selection
+        .and_then(Selection::node_id)
+        .or(dag.current_node)
+        .or(dag.root)
+}
+
+/// Node the layout of a large tree is detailed around, given the
+/// `previous` layout. Following the selection, it moves to the selected node
+/// only once that is drawn as a single cell, so moving among full boxes keeps
+/// the layout; scrolled by hand, it stays while in view and is otherwise the
+/// node nearest the middle of the viewport.
+fn lod_focus(
+    state: &ProofPaneState,
+    previous: &TreeLayout,
+    dag: &ProofDag,
+    selection: Option<Selection>,
+    area: Rect,
+) -> Option<NodeId> {
+    let kept = state.lod_focus.and_then(|id| previous.find_node(id));
+    let focus = if state.manual_scroll {
+        let (left, top) = (state.scroll_x, state.scroll_y);
+        let (width, height) = (i32::from(area.width), i32::from(area.height));
+        // Distance along one axis from `mid` to a span of `len` from `start`
+        let gap = |start: i32, len: u16, mid: i32| {
+            (start - mid).max(mid - start - i32::from(len)).max(0)
+        };
+        let in_view = |pos: &&NodePosition| {
+            gap(pos.x, pos.width, left + width / 2) < width / 2
+                && gap(pos.y, pos.height, top + height / 2) < height / 2
+        };
+        kept.filter(in_view)
+            .or_else(|| {
+                previous.nodes.iter().min_by_key(|pos| {
+                    gap(pos.x, pos.width, left + width / 2)
+                        + gap(pos.y, pos.height, top + height / 2)
+                })
+            })
+            .map(|pos| pos.node_id)
+    } else {
+        let selected = focus_node(dag, selection);
+        let shown = selected
+            .and_then(|id| previous.find_node(id))
+            .is_some_and(|pos| pos.detailed);
+        if shown {
+            kept.map(|pos| pos.node_id)
+        } else {
+            selected
+        }
+    };
+    focus
+        .filter(|&id| dag.get(id).is_some())
+        .or_else(|| focus_node(dag, selection))
+}
+
 fn find_scroll_target<'a>(
     layout: &'a TreeLayout,
     dag: &ProofDag,
@@ -247,3 +444,100 @@ fn find_scroll_target<'a>(
         .and_then(|id| layout.find_node(id))
         .or_else(|| dag.root.and_then(|id| layout.find_node(id)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, TaggedText};
+
+    /// Scrolling right stops once the right edge of the content is in view.
+    #[test]
+    fn test_scroll_right_bounded() {
+        let mut state = ProofPaneState {
+            content_width: 100,
+            viewport_width: 40,
+            ..Default::default()
+        };
+
+        state.scroll_right(10);
+        assert_eq!(state.scroll_x, 10);
+        assert!(state.manual_scroll);
+
+        for _ in 0..10 {
+            state.scroll_right(10);
+        }
+        assert_eq!(state.scroll_x, 60);
+
+        state.scroll_left(100);
+        assert_eq!(state.scroll_x, 0);
+    }
+
+    /// In a large tree, moving the selection among full boxes keeps the
+    /// layout, and scrolling by hand details the nodes in view.
+    #[test]
+    fn test_detail_follows_viewport() {
+        let count = 1000;
+        let nodes = (0..count)
+            .map(|id| ProofDagNode {
+                id,
+                parent: id.checked_sub(1),
+                children: if id + 1 < count { vec![id + 1] } else { vec![] },
+                state_after: ProofState {
+                    goals: vec![GoalInfo {
+                        type_: TaggedText::Text {
+                            text: format!("p {id}"),
+                        },
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+        let dag = ProofDag {
+            nodes,
+            root: Some(0),
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 60, 20);
+        let mut buf = Buffer::empty(area);
+        let mut state = ProofPaneState::default();
+        let state_after = ProofState::default();
+        let mut render = |state: &mut ProofPaneState, node_id| {
+            let selection = Some(Selection::Goal {
+                node_id,
+                goal_idx: 0,
+            });
+            ProofPane::new(&dag, true, selection, &state_after).render(area, &mut buf, state);
+        };
+
+        render(&mut state, 500);
+        assert_eq!(state.lod_focus, Some(500));
+        let y = state.layout.find_node(501).unwrap().y;
+        render(&mut state, 501);
+        assert_eq!(state.lod_focus, Some(500));
+        assert_eq!(state.layout.find_node(501).unwrap().y, y);
+
+        for _ in 0..state.scroll_y {
+            state.scroll_up();
+        }
+        render(&mut state, 501);
+        assert!(state.layout.find_node(0).unwrap().detailed);
+        assert!(!state.click_regions.is_empty());
+        assert!(
+            state.click_regions.iter().all(|r| r.area.width > 1),
+            "no single cells in view"
+        );
+    }
+
+    /// A step with no goals and no new hypotheses drawn as a single cell
+    /// still selects itself.
+    #[test]
+    fn test_cell_without_goals_selectable() {
+        let node = ProofDagNode {
+            id: 3,
+            ..Default::default()
+        };
+        assert_eq!(cell_selection(&node), Selection::Node { node_id: 3 });
+    }
+}
@@ -6,16 +6,19 @@ use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
+    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
 };
 
 use super::{
-    given_pane::{hyp_style_colors, truncate_str},
+    given_pane::{hyp_style_colors, text_budget, truncate_str},
     ClickRegion, Selection,
 };
 use crate::{
     lean_rpc::{ProofDagNode, ProofState},
-    tui::widgets::theme::Theme,
+    tui::widgets::{
+        render_helpers::{hard_wrap, tactic_text, timing_span},
+        theme::{SelectionColors, Theme},
+    },
 };
 
 /// State for a single state node widget.
@@ -33,6 +36,10 @@ pub struct StateNode<'a> {
     top_down: bool,
     /// Override state from LSP (for current node).
     override_state: Option<&'a ProofState>,
+    /// Whether a multi-line tactic is titled with its first line only.
+    summarize_tactic: bool,
+    /// Colors of the selected goal or hypothesis.
+    selection_colors: SelectionColors,
 }
 
 impl<'a> StateNode<'a> {
@@ -49,9 +56,21 @@ impl<'a> StateNode<'a> {
             selection,
             top_down,
             override_state,
+            summarize_tactic: false,
+            selection_colors: SelectionColors::DEFAULT,
         }
     }
 
+    pub const fn summarize_tactic(mut self, summarize: bool) -> Self {
+        self.summarize_tactic = summarize;
+        self
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
+    }
+
     /// Determine if the node should show as complete.
     fn is_effective_complete(&self) -> bool {
         self.override_state
@@ -77,22 +96,32 @@ impl<'a> StateNode<'a> {
 
     /// Build the title string for the node.
     fn build_title(&self) -> String {
+        let icon = if self.node.is_automation() {
+            format!("{} ", Theme::AUTOMATION_ICON)
+        } else {
+            String::new()
+        };
+        let tactic = tactic_text(&self.node.tactic.text, self.summarize_tactic);
         if self.node.children.len() > 1 {
-            format!(
-                " {} [{}→] ",
-                self.node.tactic.text,
-                self.node.children.len()
-            )
+            format!(" {icon}{tactic} [{}→] ", self.node.children.len())
         } else {
-            format!(" {} ", self.node.tactic.text)
+            format!(" {icon}{tactic} ")
         }
     }
 
-    /// Build the hypothesis line (horizontal layout).
-    fn build_hyps_line(&self) -> Option<Line<'static>> {
+    /// Elaboration time for the top right of the border, when known.
+    fn timing_title(&self) -> Option<Line<'static>> {
+        let ms = self.node.elab_time_ms?;
+        Some(Line::from(vec![Span::raw(" "), timing_span(ms), Span::raw(" ")]).right_aligned())
+    }
+
+    /// Build the hypothesis line (horizontal layout), sharing `width` columns
+    /// between the new hypotheses.
+    fn build_hyps_line(&self, width: u16) -> Option<Line<'static>> {
         if self.node.new_hypotheses.is_empty() {
             return None;
         }
+        let count = self.node.new_hypotheses.len();
 
         let spans: Vec<Span> = self
             .node
@@ -106,11 +135,13 @@ impl<'a> StateNode<'a> {
                     Some(Selection::Hyp { node_id, hyp_idx: hi }) if node_id == self.node.id && hi == hyp_idx
                 );
                 let (fg, bg) = hyp_style_colors(h.is_proof);
-                let style = Style::new()
-                    .fg(fg)
-                    .bg(bg)
-                    .add_modifier(if selected { Modifier::UNDERLINED } else { Modifier::empty() });
-                let text = format!(" {}: {} ", h.name, truncate_str(&h.type_.to_plain_text(), 20));
+                let style = self
+                    .selection_colors
+                    .apply(Style::new().fg(fg).bg(bg), selected);
+                // " name: type " plus the gap to the next hypothesis
+                let max_chars = text_budget(width, count, h.name.chars().count() + 5);
+                let type_text = truncate_str(&h.type_.to_plain_text(), max_chars);
+                let text = format!(" {}: {type_text} ", h.name);
                 let mut result = Vec::new();
                 if i > 0 {
                     result.push(Span::raw(" "));
@@ -124,8 +155,8 @@ impl<'a> StateNode<'a> {
         Some(Line::from(spans))
     }
 
-    /// Build the goals line.
-    fn build_goals_line(&self) -> Line<'static> {
+    /// Build the goals line, sharing `width` columns between the goals.
+    fn build_goals_line(&self, width: u16) -> Line<'static> {
         if self.is_effective_complete() {
             return Line::from(vec![Span::styled(
                 "✓ Goal completed",
@@ -136,18 +167,20 @@ impl<'a> StateNode<'a> {
         }
 
         let mut spans: Vec<Span> = Vec::new();
+        let mut width = width;
         if self.node.is_leaf() {
             spans.push(Span::styled(
                 "⋯ ",
                 Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
             ));
+            width = width.saturating_sub(2);
         }
 
         // Use override state if provided
         if let Some(state) = self.override_state {
-            self.append_goal_spans_from_state(&mut spans, state);
+            self.append_goal_spans_from_state(&mut spans, state, width);
         } else {
-            self.append_goal_spans_from_node(&mut spans);
+            self.append_goal_spans_from_node(&mut spans, width);
         }
 
         if spans.is_empty() {
@@ -157,7 +190,13 @@ impl<'a> StateNode<'a> {
     }
 
     /// Append goal spans from override state.
-    fn append_goal_spans_from_state(&self, spans: &mut Vec<Span<'static>>, state: &ProofState) {
+    fn append_goal_spans_from_state(
+        &self,
+        spans: &mut Vec<Span<'static>>,
+        state: &ProofState,
+        width: u16,
+    ) {
+        let count = state.goals.len();
         for (goal_idx, g) in state.goals.iter().enumerate() {
             if goal_idx > 0 {
                 spans.push(Span::styled(" │ ", Style::new().fg(Color::DarkGray)));
@@ -167,28 +206,29 @@ impl<'a> StateNode<'a> {
                 self.selection,
                 Some(Selection::Goal { node_id, goal_idx: gi }) if node_id == self.node.id && gi == goal_idx
             );
-            let underline = if selected {
-                Modifier::UNDERLINED
-            } else {
-                Modifier::empty()
-            };
-            let goal_type = truncate_str(&g.type_.to_plain_text(), 35);
+            // "name: ⊢ type" plus the " │ " separator
+            let name_width = g.username.as_str().map_or(0, |name| name.chars().count() + 2);
+            let goal_type = truncate_str(
+                &g.type_.to_plain_text(),
+                text_budget(width, count, name_width + 5),
+            );
 
             if let Some(name) = g.username.as_str() {
                 spans.push(Span::styled(
                     format!("{name}: "),
-                    Style::new().fg(Color::Cyan).add_modifier(underline),
+                    self.selection_colors.apply(Style::new().fg(Color::Cyan), selected),
                 ));
             }
             spans.push(Span::styled(
                 format!("⊢ {goal_type}"),
-                Style::new().fg(Theme::GOAL_FG).add_modifier(underline),
+                self.selection_colors.apply(Style::new().fg(Theme::GOAL_FG), selected),
             ));
         }
     }
 
     /// Append goal spans from node's `state_after`.
-    fn append_goal_spans_from_node(&self, spans: &mut Vec<Span<'static>>) {
+    fn append_goal_spans_from_node(&self, spans: &mut Vec<Span<'static>>, width: u16) {
+        let count = self.node.state_after.goals.len();
         for (goal_idx, g) in self.node.state_after.goals.iter().enumerate() {
             if goal_idx > 0 {
                 spans.push(Span::styled(" │ ", Style::new().fg(Color::DarkGray)));
@@ -198,22 +238,23 @@ impl<'a> StateNode<'a> {
                 self.selection,
                 Some(Selection::Goal { node_id, goal_idx: gi }) if node_id == self.node.id && gi == goal_idx
             );
-            let underline = if selected {
-                Modifier::UNDERLINED
-            } else {
-                Modifier::empty()
-            };
-            let goal_type = truncate_str(&g.type_.to_plain_text(), 35);
+            // "name: ⊢ type" plus the " │ " separator
+            let label = self.node.goal_label(goal_idx);
+            let name_width = label.as_ref().map_or(0, |name| name.chars().count() + 2);
+            let goal_type = truncate_str(
+                &g.type_.to_plain_text(),
+                text_budget(width, count, name_width + 5),
+            );
 
-            if let Some(name) = g.username.as_str() {
+            if let Some(name) = label {
                 spans.push(Span::styled(
                     format!("{name}: "),
-                    Style::new().fg(Color::Cyan).add_modifier(underline),
+                    self.selection_colors.apply(Style::new().fg(Color::Cyan), selected),
                 ));
             }
             spans.push(Span::styled(
                 format!("⊢ {goal_type}"),
-                Style::new().fg(Theme::GOAL_FG).add_modifier(underline),
+                self.selection_colors.apply(Style::new().fg(Theme::GOAL_FG), selected),
             ));
         }
     }
@@ -279,6 +320,8 @@ impl StatefulWidget for StateNode<'_> {
         let title_style = Style::new()
             .fg(if self.is_current {
                 Color::White
+            } else if self.node.is_automation() {
+                Theme::AUTOMATION
             } else {
                 Color::Gray
             })
@@ -290,11 +333,14 @@ impl StatefulWidget for StateNode<'_> {
 
         let arrow = if self.top_down { "▼" } else { "▲" };
 
-        let block = Block::default()
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
             .title(Span::styled(title, title_style))
             .title_bottom(Span::styled(format!(" {arrow} "), border_style));
+        if let Some(timing) = self.timing_title() {
+            block = block.title(timing);
+        }
 
         let inner = block.inner(area);
         block.render(area, buf);
@@ -304,8 +350,8 @@ impl StatefulWidget for StateNode<'_> {
         }
 
         // Build content lines
-        let hyps_line = self.build_hyps_line();
-        let goals_line = self.build_goals_line();
+        let hyps_line = self.build_hyps_line(inner.width);
+        let goals_line = self.build_goals_line(inner.width);
 
         let lines: Vec<Line> = match (self.top_down, hyps_line) {
             (true, Some(h)) => vec![h, goals_line],
@@ -313,11 +359,127 @@ impl StatefulWidget for StateNode<'_> {
             (_, None) => vec![goals_line],
         };
 
-        Paragraph::new(lines)
-            .wrap(Wrap { trim: true })
-            .render(inner, buf);
+        let rows: Vec<Line> = lines
+            .iter()
+            .flat_map(|line| hard_wrap(line, inner.width))
+            .collect();
+        Paragraph::new(rows).render(inner, buf);
 
         // Build click regions
         state.click_regions = self.build_click_regions(inner);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, TaggedText};
+
+    /// Wider nodes cut goal text later than narrow ones.
+    #[test]
+    fn test_wider_node_truncates_less() {
+        let node = ProofDagNode {
+            state_after: ProofState {
+                goals: vec![GoalInfo {
+                    type_: TaggedText::Text {
+                        text: "a + b + c + d + e + f + g + h + i + j + k + l + m + n = 0".repeat(2),
+                    },
+                    ..Default::default()
+                }],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let widget = StateNode::new(&node, false, None, true, None);
+
+        let narrow = widget.build_goals_line(30).width();
+        let wide = widget.build_goals_line(90).width();
+        assert!(narrow <= 30, "narrow line fits its box: {narrow}");
+        assert!(wide > narrow);
+        assert!(wide <= 90);
+    }
+
+    /// A selected goal takes the configured selection colors.
+    #[test]
+    fn test_selection_colors_applied() {
+        let node = ProofDagNode {
+            state_after: ProofState {
+                goals: vec![GoalInfo {
+                    type_: TaggedText::Text {
+                        text: "p".to_string(),
+                    },
+                    ..Default::default()
+                }],
+                hypotheses: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let colors = SelectionColors::parse("magenta,white").unwrap();
+        let selection = Some(Selection::Goal {
+            node_id: 0,
+            goal_idx: 0,
+        });
+
+        let line = StateNode::new(&node, false, selection, true, None)
+            .selection_colors(colors)
+            .build_goals_line(40);
+        let goal = line.spans.iter().find(|span| span.content.contains('⊢')).unwrap();
+        assert_eq!((goal.style.bg, goal.style.fg), (Some(Color::Magenta), Some(Color::White)));
+
+        let line = StateNode::new(&node, false, None, true, None)
+            .selection_colors(colors)
+            .build_goals_line(40);
+        assert!(line.spans.iter().all(|span| span.style.bg.is_none()));
+    }
+
+    /// An `omega` step is titled with the automation icon; others are not.
+    #[test]
+    fn test_automation_tactic_annotated() {
+        let mut node = ProofDagNode::default();
+        node.tactic.text = "omega".to_string();
+        let title = StateNode::new(&node, false, None, true, None).build_title();
+        assert_eq!(title, format!(" {} omega ", Theme::AUTOMATION_ICON));
+
+        node.tactic.text = "intro n".to_string();
+        let title = StateNode::new(&node, false, None, true, None).build_title();
+        assert!(!title.contains(Theme::AUTOMATION_ICON));
+    }
+
+    /// With the summary on, a multi-line tactic is titled with its first
+    /// line and an ellipsis.
+    #[test]
+    fn test_multi_line_tactic_summarized() {
+        let mut node = ProofDagNode::default();
+        node.tactic.text = "calc a = b := by simp\n  _ = c := by ring".to_string();
+
+        let full = StateNode::new(&node, false, None, true, None).build_title();
+        assert!(full.contains('\n'));
+
+        let title = StateNode::new(&node, false, None, true, None)
+            .summarize_tactic(true)
+            .build_title();
+        assert_eq!(title, " calc a = b := by simp… ");
+    }
+
+    /// A tactic that took seconds to elaborate is annotated in red.
+    #[test]
+    fn test_slow_tactic_timing_red() {
+        let mut node = ProofDagNode {
+            elab_time_ms: Some(2345),
+            ..Default::default()
+        };
+        let title = StateNode::new(&node, false, None, true, None)
+            .timing_title()
+            .unwrap();
+        let timing = &title.spans[1];
+        assert_eq!(timing.content, "2.3s");
+        assert_eq!(timing.style.fg, Some(Theme::TIMING_VERY_SLOW));
+
+        node.elab_time_ms = Some(12);
+        let title = StateNode::new(&node, false, None, true, None)
+            .timing_title()
+            .unwrap();
+        assert_eq!(title.spans[1].content, "12ms");
+        assert_eq!(title.spans[1].style.fg, Some(Theme::TIMING_FAST));
+    }
+}
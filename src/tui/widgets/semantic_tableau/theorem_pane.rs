@@ -5,11 +5,14 @@ use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
+    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
 };
 
 use super::{ClickRegion, Selection};
-use crate::tui::widgets::theme::Theme;
+use crate::tui::widgets::{
+    render_helpers::hard_wrap,
+    theme::{SelectionColors, Theme},
+};
 
 /// State for the theorem pane.
 #[derive(Default)]
@@ -37,11 +40,30 @@ impl TheoremPaneState {
 pub struct TheoremPane<'a> {
     goal: &'a str,
     selection: Option<Selection>,
+    /// Steps of the proof closed by automation, counted in the title.
+    automation_count: usize,
+    /// Colors of the theorem when selected.
+    selection_colors: SelectionColors,
 }
 
 impl<'a> TheoremPane<'a> {
     pub const fn new(goal: &'a str, selection: Option<Selection>) -> Self {
-        Self { goal, selection }
+        Self {
+            goal,
+            selection,
+            automation_count: 0,
+            selection_colors: SelectionColors::DEFAULT,
+        }
+    }
+
+    pub const fn automation_count(mut self, count: usize) -> Self {
+        self.automation_count = count;
+        self
+    }
+
+    pub const fn selection_colors(mut self, colors: SelectionColors) -> Self {
+        self.selection_colors = colors;
+        self
     }
 }
 
@@ -58,27 +80,33 @@ impl StatefulWidget for TheoremPane<'_> {
             selection: Selection::Theorem,
         });
 
-        let block = Block::default()
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(Style::new().fg(Color::Magenta))
             .title(Span::styled(
                 " THEOREM ",
                 Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
             ));
+        if self.automation_count > 0 {
+            block = block.title(
+                Line::from(format!(
+                    " {} {} by automation ",
+                    Theme::AUTOMATION_ICON,
+                    self.automation_count
+                ))
+                .right_aligned()
+                .style(Style::new().fg(Theme::AUTOMATION)),
+            );
+        }
 
         let inner = block.inner(area);
         block.render(area, buf);
 
-        let mut style = Style::new().fg(Theme::GOAL_FG);
-        if is_selected {
-            style = style.add_modifier(Modifier::UNDERLINED);
-        }
+        let style = self
+            .selection_colors
+            .apply(Style::new().fg(Theme::GOAL_FG), is_selected);
 
-        Paragraph::new(Line::from(vec![Span::styled(
-            format!("⊢ {}", self.goal),
-            style,
-        )]))
-        .wrap(Wrap { trim: true })
-        .render(inner, buf);
+        let line = Line::from(vec![Span::styled(format!("⊢ {}", self.goal), style)]);
+        Paragraph::new(hard_wrap(&line, inner.width)).render(inner, buf);
     }
 }
@@ -1,6 +1,11 @@
 //! Tree layout calculation for the semantic tableau.
 
-use crate::lean_rpc::{NodeId, ProofDag, ProofDagNode};
+use std::mem;
+
+use crate::{
+    lean_rpc::{NodeId, ProofDag, ProofDagNode},
+    tui::widgets::render_helpers::tactic_text,
+};
 
 pub const MIN_NODE_WIDTH: u16 = 25;
 pub const MAX_NODE_WIDTH: u16 = 60;
@@ -12,6 +17,11 @@ pub struct NodePosition {
     pub y: i32,
     pub width: u16,
     pub height: u16,
+    /// Whether this node belongs to a side proof (an orphan subtree).
+    pub is_orphan: bool,
+    /// Whether the node is drawn as a full box rather than a single cell,
+    /// being near the focus of a large tree.
+    pub detailed: bool,
 }
 
 #[derive(Debug, Default)]
@@ -19,6 +29,12 @@ pub struct TreeLayout {
     pub nodes: Vec<NodePosition>,
     pub content_width: i32,
     pub content_height: i32,
+    /// Position of the "Side proofs" section label, if there are orphans.
+    pub orphan_label: Option<(i32, i32)>,
+    /// Clause names above the side proofs that have one, e.g. `where helper`.
+    pub clause_labels: Vec<(i32, i32, String)>,
+    /// Rows joining a branching node to its children, e.g. `┌──┴──┐`.
+    pub connectors: Vec<(i32, i32, String)>,
 }
 
 impl TreeLayout {
@@ -27,10 +43,114 @@ impl TreeLayout {
     }
 }
 
+/// Below this many nodes every node is drawn as a full box.
+const LOD_MIN_NODES: usize = 200;
+
+/// Steps from the focused node within which the nodes of a large tree keep
+/// their full box; the others shrink to a single cell.
+const LOD_RADIUS: u32 = 12;
+
 /// Configuration for tree layout direction and data source.
 struct TreeLayoutConfig<'a> {
     dag: &'a ProofDag,
     top_down: bool,
+    /// Whether multi-line tactics are titled with their first line.
+    summarize_tactics: bool,
+    /// Whether each node, by id, is drawn as a full box.
+    detailed: Vec<bool>,
+    /// Width and height of each node's subtree, by id, measured once.
+    subtree_sizes: Vec<Option<(i32, i32)>>,
+}
+
+impl<'a> TreeLayoutConfig<'a> {
+    fn new(
+        dag: &'a ProofDag,
+        top_down: bool,
+        summarize_tactics: bool,
+        focus: Option<NodeId>,
+    ) -> Self {
+        let mut config = Self {
+            dag,
+            top_down,
+            summarize_tactics,
+            detailed: detailed_nodes(dag, focus),
+            subtree_sizes: vec![None; dag.nodes.len()],
+        };
+        for &id in dag.root.iter().chain(&dag.orphans) {
+            config.measure(id);
+        }
+        config
+    }
+
+    fn is_detailed(&self, node_id: NodeId) -> bool {
+        self.detailed.get(node_id as usize).copied().unwrap_or(true)
+    }
+
+    /// Width and height of a node's own box.
+    fn box_size(&self, node_id: NodeId, node: &ProofDagNode) -> (u16, u16) {
+        if self.is_detailed(node_id) {
+            (node_content_width(node, self.summarize_tactics), node_height(node))
+        } else {
+            (1, 1)
+        }
+    }
+
+    /// Subtree dimensions (width, height), as measured by [`Self::measure`].
+    fn subtree_size(&self, node_id: NodeId) -> (i32, i32) {
+        self.subtree_sizes
+            .get(node_id as usize)
+            .copied()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// Calculate subtree dimensions (width, height), visiting each node once.
+    fn measure(&mut self, node_id: NodeId) -> (i32, i32) {
+        if let Some(Some(size)) = self.subtree_sizes.get(node_id as usize) {
+            return *size;
+        }
+        let Some(node) = self.dag.get(node_id) else {
+            return (0, 0);
+        };
+
+        let (node_w, h) = self.box_size(node_id, node);
+        let (node_w, h) = (i32::from(node_w), i32::from(h));
+        let size = if node.children.is_empty() {
+            (node_w, h)
+        } else {
+            let (total_w, max_child_h) = node.children.iter().fold((0, 0), |(tw, mh), &cid| {
+                let (cw, ch) = self.measure(cid);
+                (tw + cw, mh.max(ch))
+            });
+            (total_w.max(node_w), h + connector_rows(node) + max_child_h)
+        };
+        self.subtree_sizes[node_id as usize] = Some(size);
+        size
+    }
+}
+
+/// Whether each node, by id, is drawn as a full box: all of them in small
+/// trees, and those within [`LOD_RADIUS`] steps of `focus` in large ones.
+fn detailed_nodes(dag: &ProofDag, focus: Option<NodeId>) -> Vec<bool> {
+    let Some(focus) = focus.filter(|_| dag.nodes.len() >= LOD_MIN_NODES) else {
+        return vec![true; dag.nodes.len()];
+    };
+    let mut detailed = vec![false; dag.nodes.len()];
+    let mut frontier = vec![focus];
+    for _ in 0..=LOD_RADIUS {
+        let mut next = Vec::new();
+        for id in frontier {
+            let Some(node) = dag.get(id) else {
+                continue;
+            };
+            if mem::replace(&mut detailed[id as usize], true) {
+                continue;
+            }
+            next.extend(node.parent.iter().chain(&node.children));
+        }
+        frontier = next;
+    }
+    detailed
 }
 
 /// Placement target for a node in the layout grid.
@@ -49,11 +169,11 @@ pub const fn node_height(node: &ProofDagNode) -> u16 {
 }
 
 /// Calculate minimum width needed for a node's content.
-pub fn node_content_width(node: &ProofDagNode) -> u16 {
+pub fn node_content_width(node: &ProofDagNode, summarize_tactic: bool) -> u16 {
     let mut max_width: usize = 0;
 
     // Tactic title width (with " tactic [N→] " format)
-    let title_width = node.tactic.text.len() + 8;
+    let title_width = tactic_text(&node.tactic.text, summarize_tactic).len() + 8;
     max_width = max_width.max(title_width);
 
     // Hypothesis widths: " name: type "
@@ -79,92 +199,146 @@ pub fn node_content_width(node: &ProofDagNode) -> u16 {
     width.clamp(MIN_NODE_WIDTH, MAX_NODE_WIDTH)
 }
 
+/// Rows between a node with several children and the children, for the
+/// connector.
+const CONNECTOR_ROWS: i32 = 1;
+
+/// Connector rows above a node's children.
+const fn connector_rows(node: &ProofDagNode) -> i32 {
+    if node.children.len() > 1 {
+        CONNECTOR_ROWS
+    } else {
+        0
+    }
+}
+
+/// Connector from a parent's center to its children's centers, as the
+/// leftmost column and the glyphs from there.
+fn connector_row(parent_x: i32, child_xs: &[i32], top_down: bool) -> Option<(i32, String)> {
+    let (&first, &last) = (child_xs.first()?, child_xs.last()?);
+    let (left, middle, right, stem, cross) = if top_down {
+        ('┌', '┬', '┐', '┴', '┼')
+    } else {
+        ('└', '┴', '┘', '┬', '┼')
+    };
+    let glyphs = (first..=last)
+        .map(|x| match (x == parent_x, child_xs.contains(&x)) {
+            (true, true) => cross,
+            (true, false) => stem,
+            (false, true) if x == first => left,
+            (false, true) if x == last => right,
+            (false, true) => middle,
+            (false, false) => '─',
+        })
+        .collect();
+    Some((first, glyphs))
+}
+
+/// Center column of a laid out node's box.
+fn center_x(pos: &NodePosition) -> i32 {
+    pos.x + i32::from(pos.width) / 2
+}
+
 /// Gap between main tree and orphan nodes.
 const ORPHAN_GAP: i32 = 4;
 
-/// Calculate tree layout with actual content dimensions.
-pub fn calculate_tree_layout(dag: &ProofDag, top_down: bool) -> TreeLayout {
+/// Label shown above the orphan subtrees.
+pub const ORPHAN_LABEL: &str = "Side proofs";
+
+/// Calculate tree layout with actual content dimensions. In large trees
+/// only the nodes near `focus` get their full box.
+pub fn calculate_tree_layout(
+    dag: &ProofDag,
+    top_down: bool,
+    summarize_tactics: bool,
+    focus: Option<NodeId>,
+) -> TreeLayout {
     let mut layout = TreeLayout::default();
 
     let Some(root_id) = dag.root else {
         return layout;
     };
 
-    let (w, h) = subtree_size(dag, root_id);
+    let config = TreeLayoutConfig::new(dag, top_down, summarize_tactics, focus);
+    let (w, h) = config.subtree_size(root_id);
     layout.content_width = w;
     layout.content_height = h;
 
-    let config = TreeLayoutConfig { dag, top_down };
     let root_placement = NodePlacement {
         node_id: root_id,
         x: 0,
         y: 0,
         available_h: h,
     };
-    position_nodes(&config, root_placement, &mut layout.nodes);
-
-    // Position orphan nodes to the right of the main tree
-    if !dag.orphans.is_empty() {
-        let orphan_x = layout.content_width + ORPHAN_GAP;
-        let mut orphan_y = 0i32;
-
-        for &orphan_id in &dag.orphans {
-            if let Some(node) = dag.get(orphan_id) {
-                let node_w = node_content_width(node);
-                let node_h = node_height(node);
-
-                layout.nodes.push(NodePosition {
-                    node_id: orphan_id,
-                    x: orphan_x,
-                    y: orphan_y,
-                    width: node_w,
-                    height: node_h,
-                });
-
-                orphan_y += i32::from(node_h) + 1;
-                layout.content_width = layout.content_width.max(orphan_x + i32::from(node_w));
-                layout.content_height = layout.content_height.max(orphan_y);
-            }
-        }
-    }
+    position_nodes(&config, root_placement, &mut layout);
+
+    position_orphans(&config, &mut layout);
 
     layout
 }
 
-/// Calculate subtree dimensions (width, height).
-fn subtree_size(dag: &ProofDag, node_id: NodeId) -> (i32, i32) {
-    let Some(node) = dag.get(node_id) else {
-        return (0, 0);
-    };
-
-    let h = i32::from(node_height(node));
-    let node_w = i32::from(node_content_width(node));
-
-    if node.children.is_empty() {
-        return (node_w, h);
+/// Lay out orphan subtrees in a labeled section to the right of the main tree,
+/// stacked vertically.
+fn position_orphans(config: &TreeLayoutConfig<'_>, layout: &mut TreeLayout) {
+    let dag = config.dag;
+    if !dag.orphans.iter().any(|&id| dag.get(id).is_some()) {
+        return;
     }
 
-    let (total_w, max_child_h) = node.children.iter().fold((0, 0), |(tw, mh), &cid| {
-        let (cw, ch) = subtree_size(dag, cid);
-        (tw + cw, mh.max(ch))
-    });
+    let orphan_x = layout.content_width + ORPHAN_GAP;
+    layout.orphan_label = Some((orphan_x, 0));
+    #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+    let label_w = ORPHAN_LABEL.chars().count() as i32;
+    layout.content_width = layout.content_width.max(orphan_x + label_w);
+
+    // Leave the first row for the label
+    let mut orphan_y = 1i32;
+    for &orphan_id in &dag.orphans {
+        let Some(orphan) = dag.get(orphan_id) else {
+            continue;
+        };
+        if let Some(clause) = &orphan.clause {
+            layout
+                .clause_labels
+                .push((orphan_x, orphan_y, clause.clone()));
+            #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+            let clause_w = clause.chars().count() as i32;
+            layout.content_width = layout.content_width.max(orphan_x + clause_w);
+            orphan_y += 1;
+        }
+        let (w, h) = config.subtree_size(orphan_id);
+        let start = layout.nodes.len();
+        let placement = NodePlacement {
+            node_id: orphan_id,
+            x: orphan_x,
+            y: orphan_y,
+            available_h: h,
+        };
+        position_nodes(config, placement, layout);
+        for pos in &mut layout.nodes[start..] {
+            pos.is_orphan = true;
+        }
 
-    (total_w.max(node_w), h + max_child_h)
+        orphan_y += h + 1;
+        layout.content_width = layout.content_width.max(orphan_x + w);
+        layout.content_height = layout.content_height.max(orphan_y);
+    }
 }
 
-/// Position nodes recursively.
+/// Position nodes recursively, with the connectors of branching nodes.
 fn position_nodes(
     config: &TreeLayoutConfig<'_>,
     placement: NodePlacement,
-    out: &mut Vec<NodePosition>,
+    layout: &mut TreeLayout,
 ) {
     let Some(node) = config.dag.get(placement.node_id) else {
         return;
     };
 
-    let box_h = i32::from(node_height(node));
-    let (subtree_w, _) = subtree_size(config.dag, placement.node_id);
-    let node_w = node_content_width(node);
+    let (node_w, node_h) = config.box_size(placement.node_id, node);
+    let box_h = i32::from(node_h);
+    let (subtree_w, _) = config.subtree_size(placement.node_id);
+    let detailed = config.is_detailed(placement.node_id);
 
     let node_y = if config.top_down {
         placement.y
@@ -172,33 +346,217 @@ fn position_nodes(
         placement.y + placement.available_h - box_h
     };
 
-    out.push(NodePosition {
+    // A full box spans its subtree; a single cell sits over its middle
+    let (x, width) = if detailed {
+        let width = u16::try_from(subtree_w).unwrap_or(node_w).max(node_w);
+        (placement.x, width)
+    } else {
+        (placement.x + subtree_w / 2, 1)
+    };
+    let pos = NodePosition {
         node_id: placement.node_id,
-        x: placement.x,
+        x,
         y: node_y,
-        width: u16::try_from(subtree_w).unwrap_or(node_w).max(node_w),
-        height: node_height(node),
-    });
-
-    if !node.children.is_empty() {
-        let child_h = placement.available_h - box_h;
-        let child_y = if config.top_down {
-            placement.y + box_h
+        width,
+        height: node_h,
+        is_orphan: false,
+        detailed,
+    };
+    layout.nodes.push(pos);
+
+    if node.children.is_empty() {
+        return;
+    }
+    let gap = connector_rows(node);
+    let child_h = placement.available_h - box_h - gap;
+    let child_y = if config.top_down {
+        placement.y + box_h + gap
+    } else {
+        placement.y
+    };
+    let mut cx = placement.x;
+    let mut child_xs = Vec::with_capacity(node.children.len());
+
+    for &cid in &node.children {
+        let (cw, ch) = config.subtree_size(cid);
+        let child_placement = NodePlacement {
+            node_id: cid,
+            x: cx,
+            y: child_y,
+            available_h: child_h.min(ch),
+        };
+        let first = layout.nodes.len();
+        position_nodes(config, child_placement, layout);
+        child_xs.extend(layout.nodes.get(first).map(center_x));
+        cx += cw;
+    }
+
+    if gap > 0 {
+        let connector_y = if config.top_down {
+            node_y + box_h
         } else {
-            placement.y
+            node_y - gap
         };
-        let mut cx = placement.x;
-
-        for &cid in &node.children {
-            let (cw, ch) = subtree_size(config.dag, cid);
-            let child_placement = NodePlacement {
-                node_id: cid,
-                x: cx,
-                y: child_y,
-                available_h: child_h.min(ch),
-            };
-            position_nodes(config, child_placement, out);
-            cx += cw;
+        if let Some((x, glyphs)) = connector_row(center_x(&pos), &child_xs, config.top_down) {
+            layout.connectors.push((x, connector_y, glyphs));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::lean_rpc::{GoalInfo, ProofState, TaggedText};
+
+    fn node(id: NodeId, parent: Option<NodeId>, children: Vec<NodeId>) -> ProofDagNode {
+        ProofDagNode {
+            id,
+            children,
+            parent,
+            ..Default::default()
+        }
+    }
+
+    /// Orphans are laid out in the side proof section, not in the main tree.
+    #[test]
+    fn test_orphan_in_side_section() {
+        let dag = ProofDag {
+            nodes: vec![
+                node(0, None, vec![1]),
+                node(1, Some(0), vec![]),
+                node(2, None, vec![3]),
+                node(3, Some(2), vec![]),
+            ],
+            root: Some(0),
+            orphans: vec![2],
+            ..Default::default()
+        };
+
+        let layout = calculate_tree_layout(&dag, true, false, None);
+        let main_width = TreeLayoutConfig::new(&dag, true, false, None).subtree_size(0).0;
+
+        for id in [0, 1] {
+            let pos = layout.find_node(id).expect("main tree node laid out");
+            assert!(!pos.is_orphan, "node {id} should be in the main tree");
+            assert!(pos.x < main_width);
+        }
+        // The whole orphan subtree is laid out, to the right of the main tree
+        for id in [2, 3] {
+            let pos = layout.find_node(id).expect("orphan node laid out");
+            assert!(pos.is_orphan, "node {id} should be in the side section");
+            assert!(pos.x >= main_width + ORPHAN_GAP);
+        }
+        assert_eq!(layout.orphan_label, Some((main_width + ORPHAN_GAP, 0)));
+    }
+
+    /// A node with two children gets a connector on the row between it and
+    /// them, reaching from one child's center to the other's.
+    #[test]
+    fn test_branch_connector_between_parent_and_children() {
+        let dag = ProofDag {
+            nodes: vec![
+                node(0, None, vec![1, 2]),
+                node(1, Some(0), vec![]),
+                node(2, Some(0), vec![]),
+            ],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let layout = calculate_tree_layout(&dag, true, false, None);
+        let parent = layout.find_node(0).unwrap();
+        let (left, right) = (layout.find_node(1).unwrap(), layout.find_node(2).unwrap());
+        let [(x, y, glyphs)] = layout.connectors.as_slice() else {
+            panic!("one connector expected: {:?}", layout.connectors);
+        };
+
+        assert_eq!(*y, parent.y + i32::from(parent.height));
+        assert_eq!(*y + 1, left.y);
+        assert_eq!(*x, center_x(left));
+        #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+        let end = x + glyphs.chars().count() as i32 - 1;
+        assert_eq!(end, center_x(right));
+        assert!(glyphs.starts_with('┌') && glyphs.ends_with('┐'), "{glyphs}");
+        assert!(glyphs.contains('┴'), "parent stem: {glyphs}");
+
+        let bottom_up = calculate_tree_layout(&dag, false, false, None);
+        let parent = bottom_up.find_node(0).unwrap();
+        let child = bottom_up.find_node(1).unwrap();
+        let (_, y, glyphs) = &bottom_up.connectors[0];
+        assert_eq!(*y, parent.y - 1);
+        assert_eq!(*y, child.y + i32::from(child.height));
+        assert!(glyphs.starts_with('└'), "{glyphs}");
+    }
+
+    /// A linear chain has no connector, so it takes no extra rows.
+    #[test]
+    fn test_single_child_no_connector() {
+        let dag = ProofDag {
+            nodes: vec![node(0, None, vec![1]), node(1, Some(0), vec![])],
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let layout = calculate_tree_layout(&dag, true, false, None);
+        assert!(layout.connectors.is_empty());
+        let parent = layout.find_node(0).unwrap();
+        assert_eq!(
+            layout.find_node(1).unwrap().y,
+            parent.y + i32::from(parent.height)
+        );
+    }
+
+    /// A 1000-step proof lays out quickly, with full boxes only around the
+    /// focus and single cells elsewhere.
+    #[test]
+    fn test_deep_tree_layout_culled() {
+        let count = 1000;
+        let nodes = (0..count)
+            .map(|id| {
+                let children = if id + 1 < count { vec![id + 1] } else { vec![] };
+                ProofDagNode {
+                    state_after: ProofState {
+                        goals: vec![GoalInfo {
+                            type_: TaggedText::Text {
+                                text: format!("p {id}"),
+                            },
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                    ..node(id, id.checked_sub(1), children)
+                }
+            })
+            .collect();
+        let dag = ProofDag {
+            nodes,
+            root: Some(0),
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let layout = calculate_tree_layout(&dag, true, false, Some(500));
+        assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+
+        assert_eq!(layout.nodes.len(), 1000);
+        let detailed: Vec<_> = layout.nodes.iter().filter(|pos| pos.detailed).collect();
+        #[allow(clippy::cast_possible_truncation)]
+        let radius = LOD_RADIUS as usize;
+        assert_eq!(detailed.len(), 2 * radius + 1);
+        assert!(detailed.iter().all(|pos| pos.node_id.abs_diff(500) <= LOD_RADIUS));
+        let far = layout.find_node(0).unwrap();
+        assert_eq!((far.width, far.height), (1, 1));
+        assert!(layout.content_height < 1000 + 3 * 100);
+
+        // A small tree is drawn in full wherever the focus is
+        let small = ProofDag {
+            nodes: dag.nodes[..50].to_vec(),
+            root: Some(0),
+            ..Default::default()
+        };
+        let layout = calculate_tree_layout(&small, true, false, Some(0));
+        assert!(layout.nodes.iter().all(|pos| pos.detailed));
+    }
+}
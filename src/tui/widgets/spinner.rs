@@ -0,0 +1,69 @@
+//! Spinner shown while the TUI waits: connecting to the proxy or for goals
+//! at a new cursor position.
+
+use std::time::{Duration, Instant};
+
+/// Braille frames, one per step.
+const FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each frame is shown for.
+const FRAME_FOR: Duration = Duration::from_millis(100);
+
+/// Animation state, advanced by the app's tick.
+#[derive(Debug, Default)]
+pub struct Spinner {
+    frame: usize,
+    /// When the current frame was first shown.
+    shown_at: Option<Instant>,
+}
+
+impl Spinner {
+    /// Step the animation once the frame has been shown for long enough.
+    /// Returns whether the visible frame changed.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let due = self.next_frame_at().is_none_or(|at| now >= at);
+        if due {
+            if self.shown_at.is_some() {
+                self.frame = (self.frame + 1) % FRAMES.len();
+            }
+            self.shown_at = Some(now);
+        }
+        due
+    }
+
+    /// When the next frame is due, or `None` before the first tick.
+    pub fn next_frame_at(&self) -> Option<Instant> {
+        self.shown_at.map(|at| at + FRAME_FOR)
+    }
+
+    /// The frame to draw now.
+    pub fn frame(&self) -> &'static str {
+        FRAMES[self.frame]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each frame stays up for its time, then the next one shows, wrapping
+    /// around at the end.
+    #[test]
+    fn test_spinner_advances_on_tick() {
+        let mut spinner = Spinner::default();
+        let first = spinner.frame();
+        let start = Instant::now();
+
+        spinner.tick(start);
+        assert_eq!(spinner.frame(), first);
+        assert!(!spinner.tick(start + FRAME_FOR / 2));
+        assert!(spinner.tick(start + FRAME_FOR));
+        assert_eq!(spinner.frame(), FRAMES[1]);
+        assert_eq!(spinner.next_frame_at(), Some(start + 2 * FRAME_FOR));
+
+        for i in 2..=FRAMES.len() {
+            spinner.tick(start + FRAME_FOR * u32::try_from(i).unwrap());
+        }
+        assert_eq!(spinner.frame(), first);
+    }
+}
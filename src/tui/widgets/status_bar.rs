@@ -2,19 +2,26 @@
 
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Paragraph, StatefulWidget, Widget},
 };
 
-use super::{FilterToggle, HypothesisFilters, InteractiveStatefulWidget};
+use super::{
+    render_helpers::timing_span, FilterToggle, HypothesisFilters, InteractiveStatefulWidget,
+};
 
 /// Input for updating status bar state.
 pub struct StatusBarInput {
     pub filters: HypothesisFilters,
     pub keybindings: &'static [(&'static str, &'static str)],
     pub supported_filters: &'static [FilterToggle],
+    /// Open goals now and the most seen during this proof.
+    pub goal_progress: Option<(usize, usize)>,
+    /// How long the server took over the cursor's document after its
+    /// latest edit, with `--elaboration-time`.
+    pub elaboration_ms: Option<u64>,
 }
 
 /// State for the status bar widget.
@@ -23,6 +30,8 @@ pub struct StatusBar {
     filters: HypothesisFilters,
     keybindings: &'static [(&'static str, &'static str)],
     supported_filters: &'static [FilterToggle],
+    goal_progress: Option<(usize, usize)>,
+    elaboration_ms: Option<u64>,
 }
 
 /// Widget for rendering the status bar.
@@ -72,16 +81,30 @@ impl StatefulWidget for StatusBarWidget {
             Span::raw(": copy"),
         ];
 
-        let filter_status = build_filter_status(state.filters, state.supported_filters);
-        let filter_span = (!filter_status.is_empty())
-            .then(|| Span::styled(format!(" [{filter_status}]"), Style::new().fg(Color::Green)));
-
-        let spans: Vec<Span> = global_spans
-            .chain(mode_spans)
-            .chain(nav_spans)
-            .chain(filter_span)
-            .collect();
-        Paragraph::new(Line::from(spans)).render(area, buf);
+        let spans: Vec<Span> = global_spans.chain(mode_spans).chain(nav_spans).collect();
+
+        // Filter pills on the right; only the active ones when space is short
+        let mut pills = build_filter_pills(state.filters, state.supported_filters, false);
+        if spans_width(&pills) > area.width / 3 {
+            pills = build_filter_pills(state.filters, state.supported_filters, true);
+        }
+        let mut progress = state.goal_progress.map_or_else(Vec::new, |(open, max)| {
+            vec![Span::styled(
+                format!(" {open}/{max} goals"),
+                Style::new().fg(Color::DarkGray),
+            )]
+        });
+        progress.extend(elaboration_spans(state.elaboration_ms));
+        let [hints_area, progress_area, pills_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(spans_width(&progress)),
+            Constraint::Length(spans_width(&pills)),
+        ])
+        .areas(area);
+
+        Paragraph::new(Line::from(spans)).render(hints_area, buf);
+        Paragraph::new(Line::from(progress)).render(progress_area, buf);
+        Paragraph::new(Line::from(pills)).render(pills_area, buf);
     }
 }
 
@@ -93,18 +116,106 @@ impl InteractiveStatefulWidget for StatusBarWidget {
         state.filters = input.filters;
         state.keybindings = input.keybindings;
         state.supported_filters = input.supported_filters;
+        state.goal_progress = input.goal_progress;
+        state.elaboration_ms = input.elaboration_ms;
     }
 }
 
-fn build_filter_status(filters: HypothesisFilters, supported: &[FilterToggle]) -> String {
-    [
-        (FilterToggle::Instances, filters.hide_instances, 'i'),
-        (FilterToggle::Inaccessible, filters.hide_inaccessible, 'a'),
-        (FilterToggle::LetValues, filters.hide_let_values, 'l'),
-        (FilterToggle::ReverseOrder, filters.reverse_order, 'r'),
-    ]
-    .into_iter()
-    .filter(|(toggle, _, _)| supported.contains(toggle))
-    .filter_map(|(_, enabled, c)| enabled.then_some(c))
-    .collect()
+/// Whole-document elaboration time, worded so it is not read as the cost
+/// of the tactic under the cursor.
+fn elaboration_spans(ms: Option<u64>) -> Vec<Span<'static>> {
+    let faint = Style::new().fg(Color::DarkGray);
+    ms.map_or_else(Vec::new, |ms| {
+        vec![
+            Span::styled(" document elaborated in ", faint),
+            timing_span(ms),
+            Span::styled(" after last edit", faint),
+        ]
+    })
+}
+
+/// Filter pills: toggle, label, and legend description for the help menu.
+pub const FILTER_PILLS: &[(FilterToggle, &str, &str)] = &[
+    (FilterToggle::Instances, "inst", "instances hidden"),
+    (FilterToggle::Inaccessible, "acc", "inaccessible hidden"),
+    (FilterToggle::LetValues, "let", "let values hidden"),
+    (FilterToggle::Order, "rev", "reversed or dependency order"),
+    (FilterToggle::Diffs, "diff", "diffs hidden"),
+    (FilterToggle::Sizes, "size", "type sizes shown"),
+    (FilterToggle::Used, "used", "used hyps marked"),
+    (FilterToggle::Collapse, "fold", "other goals folded"),
+    (FilterToggle::Paraphrase, "eng", "goals in English"),
+    (FilterToggle::Unused, "unus", "unused hyps flagged"),
+    (FilterToggle::Binders, "bind", "binders on own lines"),
+    (FilterToggle::FirstGoal, "1st", "only the first goal"),
+    (FilterToggle::Boxes, "box", "goals in boxes"),
+    (FilterToggle::TacticSummary, "1ln", "tactics on one line"),
+];
+
+const PILL_ACTIVE: Style = Style::new().fg(Color::Black).bg(Color::Green);
+const PILL_INACTIVE: Style = Style::new().fg(Color::DarkGray);
+
+/// Pills for the supported filters, active ones highlighted. In compact
+/// mode only the active pills are shown.
+fn build_filter_pills(
+    filters: HypothesisFilters,
+    supported: &[FilterToggle],
+    compact: bool,
+) -> Vec<Span<'static>> {
+    FILTER_PILLS
+        .iter()
+        .filter(|(toggle, _, _)| supported.contains(toggle))
+        .map(|&(toggle, label, _)| {
+            let active = filters.is_enabled(toggle);
+            match toggle {
+                FilterToggle::Order if active => (active, filters.hyp_order.label()),
+                _ => (active, label),
+            }
+        })
+        .filter(|&(active, _)| active || !compact)
+        .flat_map(|(active, label)| {
+            let style = if active { PILL_ACTIVE } else { PILL_INACTIVE };
+            [Span::raw(" "), Span::styled(format!(" {label} "), style)]
+        })
+        .collect()
+}
+
+fn spans_width(spans: &[Span]) -> u16 {
+    u16::try_from(spans.iter().map(Span::width).sum::<usize>()).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::widgets::HypOrder;
+
+    /// Each active filter renders as one highlighted pill.
+    #[test]
+    fn test_active_filter_pills() {
+        let filters = HypothesisFilters {
+            hide_instances: true,
+            hyp_order: HypOrder::Reversed,
+            hide_diffs: true,
+            ..Default::default()
+        };
+        let supported = [
+            FilterToggle::Instances,
+            FilterToggle::Inaccessible,
+            FilterToggle::Order,
+            FilterToggle::Diffs,
+        ];
+
+        let pills = build_filter_pills(filters, &supported, false);
+        let active: Vec<_> = pills
+            .iter()
+            .filter(|span| span.style == PILL_ACTIVE)
+            .map(|span| span.content.trim())
+            .collect();
+        assert_eq!(active, ["inst", "rev", "diff"]);
+        assert!(pills.iter().any(|span| span.content.trim() == "acc"));
+
+        let compact = build_filter_pills(filters, &supported, true);
+        assert_eq!(compact.iter().filter(|s| s.style == PILL_ACTIVE).count(), 3);
+        assert!(!compact.iter().any(|span| span.content.trim() == "acc"));
+    }
 }
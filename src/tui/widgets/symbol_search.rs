@@ -0,0 +1,279 @@
+//! Symbol search prompt (`:`) and its results popup.
+
+use std::mem;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, StatefulWidget, Widget},
+};
+
+use super::{theme::SelectionColors, InteractiveStatefulWidget, KeyEvent};
+use crate::lean_rpc::{GotoLocation, SymbolMatch};
+
+/// Most result rows shown at once.
+const MAX_VISIBLE_RESULTS: usize = 10;
+
+/// What the app should do after a key was handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolAction {
+    /// Ask the server for symbols matching the query.
+    Search(String),
+    /// Jump to the chosen symbol.
+    Goto(GotoLocation),
+}
+
+#[derive(Default)]
+enum Phase {
+    #[default]
+    Closed,
+    /// Typing the query.
+    Prompt(String),
+    /// Query sent, waiting for matches.
+    Searching(String),
+    Results {
+        query: String,
+        symbols: Vec<SymbolMatch>,
+        selected: usize,
+    },
+}
+
+/// State for the symbol search popup.
+#[derive(Default)]
+pub struct SymbolSearch {
+    phase: Phase,
+    action: Option<SymbolAction>,
+    selection_colors: SelectionColors,
+}
+
+impl SymbolSearch {
+    /// Open an empty prompt.
+    pub fn open(&mut self) {
+        self.phase = Phase::Prompt(String::new());
+    }
+
+    pub const fn is_open(&self) -> bool {
+        !matches!(self.phase, Phase::Closed)
+    }
+
+    /// Show the matches for the query being searched; stale answers are
+    /// dropped.
+    pub fn set_results(&mut self, query: String, symbols: Vec<SymbolMatch>) {
+        if matches!(&self.phase, Phase::Searching(q) if *q == query) {
+            self.phase = Phase::Results {
+                query,
+                symbols,
+                selected: 0,
+            };
+        }
+    }
+
+    /// Back to the prompt with the query kept, when the search it sent
+    /// failed.
+    pub fn search_failed(&mut self) {
+        if let Phase::Searching(query) = &mut self.phase {
+            self.phase = Phase::Prompt(mem::take(query));
+        }
+    }
+
+    /// Take the action requested by the last key, if any.
+    pub const fn take_action(&mut self) -> Option<SymbolAction> {
+        self.action.take()
+    }
+
+    pub const fn set_selection_colors(&mut self, colors: SelectionColors) {
+        self.selection_colors = colors;
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let dim = Style::new().fg(Color::DarkGray);
+        match &self.phase {
+            Phase::Closed => Vec::new(),
+            Phase::Prompt(query) => vec![Line::from(vec![
+                Span::styled(":", Style::new().fg(Color::Cyan)),
+                Span::raw(query.clone()),
+                Span::styled("█", dim),
+            ])],
+            Phase::Searching(query) => vec![Line::styled(format!("Searching '{query}'..."), dim)],
+            Phase::Results { query, symbols, .. } if symbols.is_empty() => {
+                vec![Line::styled(format!("No symbols match '{query}'"), dim)]
+            }
+            Phase::Results {
+                symbols, selected, ..
+            } => {
+                let first = selected.saturating_sub(MAX_VISIBLE_RESULTS - 1);
+                symbols
+                    .iter()
+                    .enumerate()
+                    .skip(first)
+                    .take(MAX_VISIBLE_RESULTS)
+                    .map(|(i, symbol)| {
+                        self.selection_colors.highlight(result_line(symbol), i == *selected)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// One match: the symbol name and, dimmed, its namespace.
+fn result_line(symbol: &SymbolMatch) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        symbol.name.clone(),
+        Style::new().add_modifier(Modifier::BOLD),
+    )];
+    if let Some(container) = &symbol.container {
+        spans.push(Span::styled(
+            format!("  {container}"),
+            Style::new().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Widget for rendering the symbol search popup.
+pub struct SymbolSearchWidget;
+
+impl StatefulWidget for SymbolSearchWidget {
+    type State = SymbolSearch;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        if !state.is_open() {
+            return;
+        }
+
+        let lines = state.lines();
+        let width = area.width.saturating_sub(4).min(60);
+        let height = u16::try_from(lines.len())
+            .unwrap_or(u16::MAX)
+            .saturating_add(2);
+        // Kept inside `area` so short terminals cut the list instead
+        let popup_area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + area.height / 4,
+            width,
+            height,
+        )
+        .intersection(area);
+
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .title(" Go to symbol ")
+            .border_style(Style::new().fg(Color::Cyan));
+        Paragraph::new(lines).block(block).render(popup_area, buf);
+    }
+}
+
+impl InteractiveStatefulWidget for SymbolSearchWidget {
+    type Input = ();
+    type Event = KeyEvent;
+
+    fn update_state(_state: &mut Self::State, _input: Self::Input) {}
+
+    /// While open, every key belongs to the popup; Ctrl and Alt chords are
+    /// swallowed rather than typed.
+    fn handle_event(state: &mut Self::State, event: Self::Event) -> bool {
+        let chord = event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if event.code == KeyCode::Esc {
+            let was_open = state.is_open();
+            state.phase = Phase::Closed;
+            return was_open;
+        }
+
+        match &mut state.phase {
+            Phase::Closed => return false,
+            Phase::Prompt(_) | Phase::Results { .. } if chord => {}
+            Phase::Prompt(query) => match event.code {
+                KeyCode::Char(c) => query.push(c),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Enter if !query.is_empty() => {
+                    let query = query.clone();
+                    state.action = Some(SymbolAction::Search(query.clone()));
+                    state.phase = Phase::Searching(query);
+                }
+                _ => {}
+            },
+            Phase::Searching(_) => {}
+            Phase::Results {
+                symbols, selected, ..
+            } => match event.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    *selected = (*selected + 1).min(symbols.len().saturating_sub(1));
+                }
+                KeyCode::Char('k') | KeyCode::Up => *selected = selected.saturating_sub(1),
+                KeyCode::Enter => {
+                    state.action = symbols
+                        .get(*selected)
+                        .map(|symbol| SymbolAction::Goto(symbol.location.clone()));
+                    state.phase = Phase::Closed;
+                }
+                _ => {}
+            },
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_lsp::lsp_types::{Position, Url};
+
+    use super::*;
+
+    fn press(search: &mut SymbolSearch, code: KeyCode, modifiers: KeyModifiers) {
+        SymbolSearchWidget::handle_event(search, KeyEvent::new(code, modifiers));
+    }
+
+    /// A failed search goes back to the prompt, and Ctrl chords type nothing.
+    #[test]
+    fn test_failed_search_reopens_prompt() {
+        let mut search = SymbolSearch::default();
+        search.open();
+        press(&mut search, KeyCode::Char('x'), KeyModifiers::CONTROL);
+        press(&mut search, KeyCode::Char('f'), KeyModifiers::NONE);
+        press(&mut search, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(search.take_action(), Some(SymbolAction::Search("f".to_string())));
+
+        search.search_failed();
+        assert!(matches!(&search.phase, Phase::Prompt(query) if query == "f"));
+    }
+
+    /// Results taller than the terminal are cut at its bottom.
+    #[test]
+    fn test_popup_small_terminal() {
+        let mut search = SymbolSearch {
+            phase: Phase::Searching("f".to_string()),
+            ..Default::default()
+        };
+        let symbols = (0..MAX_VISIBLE_RESULTS)
+            .map(|i| SymbolMatch {
+                name: format!("f{i}"),
+                container: Some("Foo".to_string()),
+                location: GotoLocation {
+                    uri: Url::parse("file:///Foo.lean").unwrap(),
+                    position: Position::new(0, 0),
+                },
+            })
+            .collect();
+        search.set_results("f".to_string(), symbols);
+
+        // Rendered into a taller buffer, so drawing past `area` would show
+        let area = Rect::new(0, 0, 80, 12);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 16));
+        SymbolSearchWidget.render(area, &mut buf, &mut search);
+        let rows: Vec<String> = (0..buf.area.height)
+            .map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+
+        assert!(rows[3].contains("Go to symbol"));
+        assert!(rows[11].contains('└') && rows[11].contains('┘'));
+        assert!(rows[10].contains("f6"));
+        assert!(!rows.iter().any(|row| row.contains("f7")));
+        assert!(rows[12..].iter().all(|row| row.trim().is_empty()));
+    }
+}
@@ -1,6 +1,11 @@
 //! Centralized theme and styling constants.
 
-use ratatui::style::{Color, Modifier, Style};
+use std::str::FromStr;
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Line,
+};
 
 /// Theme constants for consistent styling across components.
 pub struct Theme;
@@ -18,12 +23,15 @@ impl Theme {
     pub const TITLE_HYPOTHESIS: Color = Color::Blue;
     pub const TITLE_GOAL: Color = Color::Cyan;
 
-    // Selection highlighting
-    pub const SELECTION_BG: Color = Color::Rgb(50, 50, 80);
+    // Selection highlighting, unless `--selection-color` picks another
+    pub const SELECTION_BG: Color = Color::Rgb(40, 80, 160);
 
     // Hypothesis colors
     pub const HYP_NAME: Color = Color::Blue;
     pub const HYP_TYPE: Color = Color::White;
+    // Type class instances: a dim blue of its own, apart from `HYP_NAME`
+    // even where the terminal ignores the DIM modifier
+    pub const INSTANCE: Style = Style::new().fg(Color::Rgb(80, 100, 150));
 
     // Goal colors
     pub const GOAL_TYPE: Color = Color::Cyan;
@@ -42,11 +50,83 @@ impl Theme {
     pub const INCOMPLETE_NODE_BORDER: Color = Color::Yellow;
     pub const COMPLETED_NODE_BORDER: Color = Color::Green;
 
+    // Automation tactics (omega, decide, norm_num, ...)
+    pub const AUTOMATION: Color = Color::Magenta;
+    pub const AUTOMATION_ICON: &str = "🔨";
+
+    // Elaboration times, by how slow the tactic was
+    pub const TIMING_FAST: Color = Color::Green;
+    pub const TIMING_SLOW: Color = Color::Yellow;
+    pub const TIMING_VERY_SLOW: Color = Color::Red;
+
     // Semantic tableau - goals
     pub const GOAL_FG: Color = Color::Rgb(200, 140, 140);
     pub const COMPLETED_GOAL_FG: Color = Color::Green;
 }
 
+/// Colors of the selection highlight, chosen with `--selection-color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionColors {
+    pub bg: Color,
+    /// Text color of the selected item; `None` keeps the item's own colors.
+    pub fg: Option<Color>,
+}
+
+impl Default for SelectionColors {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl SelectionColors {
+    pub const DEFAULT: Self = Self {
+        bg: Theme::SELECTION_BG,
+        fg: None,
+    };
+
+    /// Colors named on the command line: a background, optionally followed
+    /// by a comma and a foreground, e.g. `blue` or `#304070,white`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let color = |name: &str| {
+            Color::from_str(name.trim()).map_err(|_| format!("unknown color `{}`", name.trim()))
+        };
+        let (bg, fg) = match spec.split_once(',') {
+            Some((bg, fg)) => (color(bg)?, Some(color(fg)?)),
+            None => (color(spec)?, None),
+        };
+        Ok(Self { bg, fg })
+    }
+
+    /// Style patched over a selected item.
+    pub const fn style(self) -> Style {
+        Style {
+            fg: self.fg,
+            ..Style::new().bg(self.bg)
+        }
+    }
+
+    /// `style`, with these colors patched over when it is selected.
+    pub fn apply(self, style: Style, is_selected: bool) -> Style {
+        if is_selected {
+            style.patch(self.style())
+        } else {
+            style
+        }
+    }
+
+    /// `line`, highlighted when it is selected. The colors are patched
+    /// over each span too, so a configured foreground wins over span colors.
+    pub fn highlight(self, mut line: Line<'static>, is_selected: bool) -> Line<'static> {
+        if !is_selected {
+            return line;
+        }
+        for span in &mut line.spans {
+            span.style = span.style.patch(self.style());
+        }
+        line.patch_style(self.style())
+    }
+}
+
 /// Which pane is currently focused for keyboard navigation.
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum FocusedPane {
@@ -3,6 +3,9 @@ mod protocol;
 mod proxy_endpoint;
 mod tui_endpoint;
 
-pub use protocol::{socket_path, Command, CursorInfo, Message, Position, ServerMode};
+pub use protocol::{
+    is_local_file, socket_path, uri_display_name, Command, CursorInfo, DefinitionInfo,
+    DocumentVersion, Message, Position, ServerMode,
+};
 pub use proxy_endpoint::{CommandHandler, LspProxySocketEndpoint};
 pub use tui_endpoint::spawn_socket_handler;
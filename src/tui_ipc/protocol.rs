@@ -1,9 +1,10 @@
-use std::path::PathBuf;
+use std::{error::Error as StdError, fmt, io, path::PathBuf};
 
 pub use async_lsp::lsp_types::{Position, Url};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-use crate::lean_rpc::ProofDag;
+use crate::lean_rpc::{ProofDag, SymbolMatch};
 
 /// Returns the path to the UNIX socket for IPC.
 pub fn socket_path() -> PathBuf {
@@ -12,12 +13,119 @@ pub fn socket_path() -> PathBuf {
         .join("lean-tui/lean-tui.sock")
 }
 
+/// Version of the framing and message format, bumped whenever a proxy and
+/// TUI from different builds could not understand each other.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Largest frame body accepted, so a corrupt length cannot exhaust memory.
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Why a frame could not be read or written.
+#[derive(Debug)]
+pub enum FrameError {
+    Io(io::Error),
+    /// The other side was built with another protocol version.
+    VersionMismatch { ours: u8, theirs: u8 },
+    /// The announced body length exceeds `MAX_FRAME_LEN`.
+    TooLarge(usize),
+    /// The body is not a message this side knows. The frame was consumed
+    /// whole, so the stream is still in sync.
+    Malformed(serde_json::Error),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::VersionMismatch { ours, theirs } => write!(
+                f,
+                "Protocol version mismatch: this side speaks v{ours}, the other v{theirs}. \
+                 Run the proxy and the TUI from the same lean-tui build."
+            ),
+            Self::TooLarge(len) => write!(f, "Frame of {len} bytes exceeds the limit"),
+            Self::Malformed(e) => write!(f, "Malformed frame: {e}"),
+        }
+    }
+}
+
+impl StdError for FrameError {}
+
+/// Write `value` as one frame: the protocol version byte, the body length as
+/// a big-endian `u32`, then the JSON body.
+pub async fn write_frame<W, T>(writer: &mut W, value: &T) -> Result<(), FrameError>
+where
+    W: AsyncWrite + Unpin + Send,
+    T: Serialize + Sync,
+{
+    let body = serde_json::to_vec(value).map_err(FrameError::Malformed)?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|_| body.len() <= MAX_FRAME_LEN)
+        .ok_or(FrameError::TooLarge(body.len()))?;
+    let mut frame = Vec::with_capacity(body.len() + 5);
+    frame.push(PROTOCOL_VERSION);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&body);
+    writer.write_all(&frame).await.map_err(FrameError::Io)
+}
+
+/// Read one frame written by [`write_frame`], or `None` when the other side
+/// closed the stream between frames.
+///
+/// Not cancel safe: a frame read halfway is lost, so call it from a task of
+/// its own rather than a `select!` branch.
+pub async fn read_frame<R, T>(reader: &mut R) -> Result<Option<T>, FrameError>
+where
+    R: AsyncRead + Unpin + Send,
+    T: DeserializeOwned,
+{
+    let version = match reader.read_u8().await {
+        Ok(version) => version,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(FrameError::Io(e)),
+    };
+    if version != PROTOCOL_VERSION {
+        return Err(FrameError::VersionMismatch {
+            ours: PROTOCOL_VERSION,
+            theirs: version,
+        });
+    }
+    let len = reader.read_u32().await.map_err(FrameError::Io)? as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(FrameError::TooLarge(len));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await.map_err(FrameError::Io)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(FrameError::Malformed)
+}
+
+/// Information about the enclosing definition (theorem, lemma, def, etc.)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefinitionInfo {
+    /// Kind of definition (theorem, lemma, def, example)
+    pub kind: Option<String>,
+    /// Name of the definition
+    pub name: String,
+    /// Line where the definition starts
+    pub line: Option<u32>,
+    /// Binders and statement, e.g. `(n : Nat) : P n`
+    pub signature: Option<String>,
+    /// Attributes the definition is tagged with, e.g. `simp`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
 /// Cursor location with document URI and trigger method.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CursorInfo {
     pub uri: Url,
     pub position: Position,
     pub method: String,
+    /// Definitions enclosing the cursor, outermost first.
+    #[serde(default)]
+    pub definitions: Vec<DefinitionInfo>,
 }
 
 impl CursorInfo {
@@ -26,14 +134,39 @@ impl CursorInfo {
             uri,
             position,
             method: method.to_string(),
+            definitions: Vec::new(),
         }
     }
 
     pub fn filename(&self) -> Option<&str> {
-        self.uri.path_segments()?.next_back()
+        uri_display_name(&self.uri)
     }
 }
 
+/// Short name for a document: the last path segment of a hierarchical URI
+/// (`file://`, `lean4://`), or the whole path of an opaque one
+/// (`untitled:Untitled-1`).
+pub fn uri_display_name(uri: &Url) -> Option<&str> {
+    uri.path_segments().map_or_else(
+        || Some(uri.path()).filter(|path| !path.is_empty()),
+        |mut segments| segments.rfind(|s| !s.is_empty()),
+    )
+}
+
+/// Whether the editor can open the URI as a local file.
+pub fn is_local_file(uri: &Url) -> bool {
+    uri.scheme() == "file"
+}
+
+/// Version of a document, ordered across reopens. Editors start counting
+/// again at 1 when a document is reopened, so `epoch` counts the times the
+/// proxy saw it opened and is compared first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DocumentVersion {
+    pub epoch: u32,
+    pub version: u32,
+}
+
 /// Server mode for RPC communication.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ServerMode {
@@ -66,13 +199,58 @@ pub enum Message {
     ProofDag {
         uri: Url,
         position: Position,
+        /// Document version the DAG was computed against, if the proxy
+        /// tracks the document.
+        #[serde(default)]
+        version: Option<DocumentVersion>,
         /// Contains all proof steps, tree structure, and state info.
         #[serde(default)]
         proof_dag: Option<ProofDag>,
     },
+    /// Proof DAG fetched for a pinned comparison position.
+    Comparison {
+        uri: Url,
+        position: Position,
+        /// Git revision the goals were computed at, when not the working
+        /// copy.
+        #[serde(default)]
+        revision: Option<String>,
+        #[serde(default)]
+        proof_dag: Option<ProofDag>,
+    },
     Error {
         error: String,
     },
+    /// Workspace symbols matching a search query.
+    Symbols {
+        query: String,
+        symbols: Vec<SymbolMatch>,
+    },
+    /// Outcome of a command that produces no data, e.g. a session reset.
+    Status {
+        message: String,
+    },
+    /// DAG mode now in use, sent when the server rejected the one asked for.
+    DagMode {
+        mode: String,
+    },
+    /// Whether goals now show implicit arguments, sent once a restart for
+    /// `pp.explicit` finished or failed.
+    Explicit {
+        explicit: bool,
+    },
+    /// Number of errors among the diagnostics the server last published
+    /// for a document.
+    Diagnostics {
+        uri: Url,
+        errors: usize,
+    },
+    /// Milliseconds the server took to elaborate a document after its
+    /// latest edit, sent only while a TUI asked for timings.
+    Elaboration {
+        uri: Url,
+        ms: u64,
+    },
 }
 
 /// Commands sent from TUI to proxy.
@@ -80,4 +258,108 @@ pub enum Message {
 #[serde(tag = "type")]
 pub enum Command {
     Navigate { uri: Url, position: Position },
+    /// Fetch the goals at another location (possibly in another file) to
+    /// compare against.
+    CompareWith { uri: Url, position: Position },
+    /// Fetch the goals at the same position in the file as committed at
+    /// `HEAD`, to compare the working copy against.
+    CompareWithHead { uri: Url, position: Position },
+    /// Drop the RPC session for a document and connect a fresh one.
+    ResetSession { uri: Url },
+    /// Search workspace symbols by name.
+    FindSymbol { query: String },
+    /// Abort the goal fetch in flight.
+    CancelFetch,
+    /// Fetch the goals at a position again, e.g. after elaboration caught
+    /// up.
+    Refetch { uri: Url, position: Position },
+    /// Look up where the symbol at a position is implemented and show it in
+    /// the editor.
+    GotoImplementation { uri: Url, position: Position },
+    /// Ask for the proof DAG at another level of detail and refetch the
+    /// goals at a position.
+    SetDagMode {
+        uri: Url,
+        position: Position,
+        mode: String,
+    },
+    /// Restart the server with `pp.explicit` on or off and refetch the
+    /// goals at a position.
+    SetExplicit {
+        uri: Url,
+        position: Position,
+        explicit: bool,
+    },
+    /// Have the proxy time how long the server takes over each edit, for
+    /// as long as this TUI stays connected.
+    TimeElaboration { enabled: bool },
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+
+    fn cursor(uri: &str) -> CursorInfo {
+        CursorInfo::new(Url::parse(uri).unwrap(), Position::default(), "test")
+    }
+
+    /// A file URI shows its file name.
+    #[test]
+    fn test_filename_of_file_uri() {
+        let cursor = cursor("file:///home/me/Proj/Basic.lean");
+        assert_eq!(cursor.filename(), Some("Basic.lean"));
+        assert!(is_local_file(&cursor.uri));
+    }
+
+    /// Other schemes get a readable name and are not treated as local files.
+    #[test]
+    fn test_filename_of_non_file_uri() {
+        let library = cursor("lean4://library/Init/Prelude.lean");
+        assert_eq!(library.filename(), Some("Prelude.lean"));
+        assert!(!is_local_file(&library.uri));
+
+        let untitled = cursor("untitled:Untitled-1");
+        assert_eq!(untitled.filename(), Some("Untitled-1"));
+        assert!(!is_local_file(&untitled.uri));
+    }
+
+    /// A frame carries a message through intact, followed by a clean end of
+    /// stream.
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let (mut proxy, mut tui) = duplex(64);
+        let sent = Message::Status {
+            message: "line one\nline two".repeat(20),
+        };
+        let writer = tokio::spawn(async move {
+            write_frame(&mut proxy, &sent).await.unwrap();
+        });
+
+        let received: Option<Message> = read_frame(&mut tui).await.unwrap();
+        writer.await.unwrap();
+        assert!(matches!(
+            received,
+            Some(Message::Status { message }) if message == "line one\nline two".repeat(20)
+        ));
+        assert!(read_frame::<_, Message>(&mut tui).await.unwrap().is_none());
+    }
+
+    /// A frame from another protocol version is refused instead of parsed.
+    #[tokio::test]
+    async fn test_frame_version_mismatch() {
+        let (mut proxy, mut tui) = duplex(64);
+        proxy
+            .write_all(&[PROTOCOL_VERSION + 1, 0, 0, 0, 2, b'{', b'}'])
+            .await
+            .unwrap();
+
+        let result = read_frame::<_, Message>(&mut tui).await;
+        assert!(matches!(
+            result,
+            Err(FrameError::VersionMismatch { ours, theirs })
+                if ours == PROTOCOL_VERSION && theirs == PROTOCOL_VERSION + 1
+        ));
+    }
 }
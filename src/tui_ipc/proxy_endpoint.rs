@@ -1,19 +1,33 @@
 
-use std::fs;
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
 
 use async_lsp::{
-    lsp_types::{Position, Range, ShowDocumentParams, Url},
+    lsp_types::{
+        DidOpenTextDocumentParams, Position, Range, ShowDocumentParams, TextDocumentItem, Url,
+    },
     ClientSocket, LanguageClient,
 };
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::{UnixListener, UnixStream},
+    io::BufReader,
+    net::{unix::OwnedReadHalf, UnixListener, UnixStream},
     sync::{broadcast, mpsc},
+    task::AbortHandle,
 };
 
-use super::protocol::{socket_path, Command, CursorInfo, Message, ServerMode};
-use crate::lean_rpc::ProofDag;
-
+use super::protocol::{
+    read_frame, socket_path, write_frame, Command, CursorInfo, DocumentVersion, FrameError, Message,
+    ServerMode,
+};
+use crate::{
+    lean_rpc::{ProofDag, RpcClient, DAG_MODES},
+    proxy::{head_content, head_copy_uri, DocumentCache},
+};
 
 /// UNIX socket server that broadcasts messages to TUI clients.
 pub struct LspProxySocketEndpoint {
@@ -21,6 +35,10 @@ pub struct LspProxySocketEndpoint {
     msg_sender: broadcast::Sender<Message>,
     /// Server mode for RPC communication.
     server_mode: ServerMode,
+    /// Goal fetch in flight, so the TUI can cancel it.
+    goal_fetch: Mutex<Option<AbortHandle>>,
+    /// Connected TUI clients that asked for elaboration timings.
+    elaboration_viewers: Arc<AtomicUsize>,
 }
 
 impl LspProxySocketEndpoint {
@@ -30,18 +48,42 @@ impl LspProxySocketEndpoint {
         Self {
             msg_sender,
             server_mode,
+            goal_fetch: Mutex::new(None),
+            elaboration_viewers: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Whether any connected TUI shows elaboration timings, so edits are
+    /// worth timing.
+    pub fn times_elaboration(&self) -> bool {
+        self.elaboration_viewers.load(Ordering::Relaxed) > 0
+    }
+
+    /// Remember the goal fetch now in flight, replacing the previous one.
+    pub fn track_goal_fetch(&self, handle: AbortHandle) {
+        *self.goal_fetch.lock().expect("lock poisoned") = Some(handle);
+    }
+
+    /// Abort the goal fetch in flight. Returns false if none was running.
+    pub fn cancel_goal_fetch(&self) -> bool {
+        let Some(handle) = self.goal_fetch.lock().expect("lock poisoned").take() else {
+            return false;
+        };
+        let was_running = !handle.is_finished();
+        handle.abort();
+        was_running
+    }
+
     /// Start the socket listener.
     /// Returns a receiver for commands from TUI clients.
     pub fn start_listener(&self) -> mpsc::Receiver<Command> {
         let (cmd_tx, cmd_rx) = mpsc::channel::<Command>(16);
         let msg_sender = self.msg_sender.clone();
         let server_mode = self.server_mode;
+        let elaboration_viewers = self.elaboration_viewers.clone();
 
         tokio::spawn(async move {
-            run_listener(msg_sender, cmd_tx, server_mode).await;
+            run_listener(msg_sender, cmd_tx, server_mode, elaboration_viewers).await;
         });
 
         cmd_rx
@@ -64,11 +106,13 @@ impl LspProxySocketEndpoint {
         &self,
         uri: Url,
         position: super::Position,
+        version: Option<DocumentVersion>,
         proof_dag: Option<ProofDag>,
     ) {
         self.send(Message::ProofDag {
             uri,
             position,
+            version,
             proof_dag,
         });
     }
@@ -77,6 +121,16 @@ impl LspProxySocketEndpoint {
     pub fn broadcast_error(&self, error: String) {
         self.send(Message::Error { error });
     }
+
+    /// Broadcast a document's error count to all connected clients.
+    pub fn broadcast_diagnostics(&self, uri: Url, errors: usize) {
+        self.send(Message::Diagnostics { uri, errors });
+    }
+
+    /// Broadcast how long the server took over a document's latest edit.
+    pub fn broadcast_elaboration(&self, uri: Url, ms: u64) {
+        self.send(Message::Elaboration { uri, ms });
+    }
 }
 
 /// Run the UNIX socket listener.
@@ -84,6 +138,7 @@ async fn run_listener(
     msg_sender: broadcast::Sender<Message>,
     cmd_tx: mpsc::Sender<Command>,
     server_mode: ServerMode,
+    elaboration_viewers: Arc<AtomicUsize>,
 ) {
     let path = socket_path();
 
@@ -112,7 +167,8 @@ async fn run_listener(
             Ok((stream, _)) => {
                 let msg_rx = msg_sender.subscribe();
                 let cmd_tx = cmd_tx.clone();
-                tokio::spawn(handle_client(stream, msg_rx, cmd_tx, server_mode));
+                let viewers = elaboration_viewers.clone();
+                tokio::spawn(handle_client(stream, msg_rx, cmd_tx, server_mode, viewers));
             }
             Err(e) => {
                 tracing::error!("Accept error: {e}");
@@ -127,46 +183,105 @@ async fn handle_client(
     mut msg_rx: broadcast::Receiver<Message>,
     cmd_tx: mpsc::Sender<Command>,
     server_mode: ServerMode,
+    elaboration_viewers: Arc<AtomicUsize>,
 ) {
     let (reader, mut writer) = stream.into_split();
-    let mut lines = BufReader::new(reader).lines();
 
     // Send Connected message immediately with server mode
-    if let Ok(json) = serde_json::to_string(&Message::Connected {
+    let connected = Message::Connected {
         server_mode: Some(server_mode),
-    }) {
-        let _ = writer.write_all(format!("{json}\n").as_bytes()).await;
+    };
+    if write_frame(&mut writer, &connected).await.is_err() {
+        return;
     }
 
+    // Frames are read in a task of their own, as reading is not cancel safe
+    let mut commands = tokio::spawn(forward_commands(
+        BufReader::new(reader),
+        cmd_tx,
+        ElaborationViewer::new(elaboration_viewers),
+    ));
     loop {
         tokio::select! {
             // Send messages to TUI
             msg_result = msg_rx.recv() => {
                 let Ok(msg) = msg_result else { break };
-                let json = match serde_json::to_string(&msg) {
-                    Ok(j) => j,
-                    Err(e) => {
+                match write_frame(&mut writer, &msg).await {
+                    Ok(()) => {}
+                    Err(FrameError::Malformed(e)) => {
                         tracing::warn!("Failed to serialize message: {e}");
-                        continue;
                     }
-                };
-                if writer.write_all(format!("{json}\n").as_bytes()).await.is_err() {
-                    break;
+                    Err(_) => break,
                 }
             }
-            // Receive commands from TUI
-            line_result = lines.next_line() => {
-                match line_result {
-                    Ok(Some(line)) => {
-                        if let Ok(cmd) = serde_json::from_str::<Command>(&line) {
-                            if cmd_tx.send(cmd).await.is_err() {
-                                break;
-                            }
-                        }
-                    }
-                    Ok(None) | Err(_) => break,
-                }
+            // TUI disconnected or spoke another protocol version
+            _ = &mut commands => break,
+        }
+    }
+    commands.abort();
+}
+
+/// One TUI client's share of the count of clients showing elaboration
+/// timings, given back when the client disconnects.
+struct ElaborationViewer {
+    viewers: Arc<AtomicUsize>,
+    enabled: bool,
+}
+
+impl ElaborationViewer {
+    const fn new(viewers: Arc<AtomicUsize>) -> Self {
+        Self {
+            viewers,
+            enabled: false,
+        }
+    }
+
+    fn set(&mut self, enabled: bool) {
+        if enabled == self.enabled {
+            return;
+        }
+        self.enabled = enabled;
+        if enabled {
+            self.viewers.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.viewers.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for ElaborationViewer {
+    fn drop(&mut self) {
+        self.set(false);
+    }
+}
+
+/// Pass commands from one TUI client on until it disconnects. Whether the
+/// client wants elaboration timings is kept here, so it lapses with the
+/// connection.
+async fn forward_commands(
+    mut reader: BufReader<OwnedReadHalf>,
+    cmd_tx: mpsc::Sender<Command>,
+    mut viewer: ElaborationViewer,
+) {
+    loop {
+        let cmd = match read_frame::<_, Command>(&mut reader).await {
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => break,
+            Err(FrameError::Malformed(e)) => {
+                tracing::warn!("Ignoring unknown command: {e}");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("Dropping TUI client: {e}");
+                break;
             }
+        };
+        if let Command::TimeElaboration { enabled } = cmd {
+            viewer.set(enabled);
+            continue;
+        }
+        if cmd_tx.send(cmd).await.is_err() {
+            break;
         }
     }
 }
@@ -177,13 +292,30 @@ pub struct CommandHandler {
     rx: mpsc::Receiver<Command>,
     /// Socket to send LSP requests to the editor.
     socket: ClientSocket,
+    /// RPC client for fetches requested by the TUI (set once initialized).
+    rpc_client: Arc<OnceLock<RpcClient>>,
+    /// Socket server for replying to TUI clients.
+    socket_server: Arc<LspProxySocketEndpoint>,
+    /// Documents the editor has open, reopened on a restarted server.
+    documents: Arc<DocumentCache>,
 }
 
 impl CommandHandler {
     /// Create a new command handler and return a sender for commands.
-    pub fn new(socket: ClientSocket) -> (Self, mpsc::Sender<Command>) {
+    pub fn new(
+        socket: ClientSocket,
+        rpc_client: Arc<OnceLock<RpcClient>>,
+        socket_server: Arc<LspProxySocketEndpoint>,
+        documents: Arc<DocumentCache>,
+    ) -> (Self, mpsc::Sender<Command>) {
         let (tx, rx) = mpsc::channel(16);
-        let handler = Self { rx, socket };
+        let handler = Self {
+            rx,
+            socket,
+            rpc_client,
+            socket_server,
+            documents,
+        };
         (handler, tx)
     }
 
@@ -204,31 +336,380 @@ impl CommandHandler {
                 );
                 self.send_show_document(uri, position).await;
             }
+            Command::CompareWith { uri, position } => {
+                tracing::info!(
+                    "Compare request: {uri}:{}:{}",
+                    position.line,
+                    position.character
+                );
+                self.spawn_comparison_fetch(uri, position);
+            }
+            Command::CompareWithHead { uri, position } => {
+                tracing::info!(
+                    "HEAD comparison request: {uri}:{}:{}",
+                    position.line,
+                    position.character
+                );
+                self.spawn_head_comparison_fetch(uri, position);
+            }
+            Command::ResetSession { uri } => {
+                tracing::info!("Session reset request: {uri}");
+                self.spawn_session_reset(uri);
+            }
+            Command::GotoImplementation { uri, position } => {
+                tracing::info!(
+                    "Implementation request: {uri}:{}:{}",
+                    position.line,
+                    position.character
+                );
+                self.spawn_implementation_lookup(uri, position);
+            }
+            Command::FindSymbol { query } => {
+                tracing::info!("Symbol search request: {query}");
+                self.spawn_symbol_search(query);
+            }
+            Command::CancelFetch => {
+                // The TUI keeps the goals it has; only the pending answer is lost
+                let message = if self.socket_server.cancel_goal_fetch() {
+                    "Cancelled"
+                } else {
+                    "No fetch in progress"
+                };
+                tracing::info!("Cancel request: {message}");
+                self.socket_server.send(Message::Status {
+                    message: message.to_string(),
+                });
+            }
+            Command::Refetch { uri, position } => {
+                tracing::info!("Refetch request: {uri}:{}", position.line);
+                self.spawn_refetch(uri, position);
+            }
+            Command::SetDagMode {
+                uri,
+                position,
+                mode,
+            } => {
+                tracing::info!("DAG mode {mode} request");
+                self.spawn_dag_mode_refetch(uri, position, &mode);
+            }
+            Command::SetExplicit {
+                uri,
+                position,
+                explicit,
+            } => {
+                tracing::info!("pp.explicit={explicit} request");
+                self.spawn_explicit_restart(uri, position, explicit);
+            }
+            // Counted per connection before commands reach the handler
+            Command::TimeElaboration { .. } => {}
         }
     }
 
+    /// Fetch the goals at a position again with the current options.
+    fn spawn_refetch(&self, uri: Url, position: Position) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for refetch".to_string());
+            return;
+        };
+        let server = self.socket_server.clone();
+        let task = tokio::spawn(async move {
+            let mode = rpc_client.dag_mode().await;
+            match rpc_client.get_proof_dag(&uri, position, mode).await {
+                Ok(proof_dag) => server.broadcast_proof_dag(uri, position, None, proof_dag),
+                Err(e) => tracing::warn!("Could not refetch goals: {e}"),
+            }
+        });
+        self.socket_server.track_goal_fetch(task.abort_handle());
+    }
+
+    /// Switch the DAG mode and refetch the goals at the cursor with it. A
+    /// mode the server rejects falls back to the default one.
+    fn spawn_dag_mode_refetch(&self, uri: Url, position: Position, mode: &str) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for refetch".to_string());
+            return;
+        };
+        let Some(&mode) = DAG_MODES.iter().find(|&&known| known == mode) else {
+            self.socket_server.broadcast_error(format!("Unknown DAG mode {mode}"));
+            return;
+        };
+        let server = self.socket_server.clone();
+        let task = tokio::spawn(async move {
+            rpc_client.set_dag_mode(mode).await;
+            let result = match rpc_client.get_proof_dag(&uri, position, mode).await {
+                Err(e) if mode != DAG_MODES[0] => {
+                    tracing::warn!("Server rejected DAG mode {mode}: {e}");
+                    rpc_client.set_dag_mode(DAG_MODES[0]).await;
+                    server.send(Message::DagMode {
+                        mode: DAG_MODES[0].to_string(),
+                    });
+                    server.send(Message::Status {
+                        message: format!("DAG mode {mode} unsupported, using {}", DAG_MODES[0]),
+                    });
+                    rpc_client.get_proof_dag(&uri, position, DAG_MODES[0]).await
+                }
+                result => result,
+            };
+            match result {
+                Ok(proof_dag) => server.broadcast_proof_dag(uri, position, None, proof_dag),
+                Err(e) => {
+                    tracing::warn!("Could not refetch goals: {e}");
+                    server.broadcast_error(e.to_string());
+                }
+            }
+        });
+        self.socket_server.track_goal_fetch(task.abort_handle());
+    }
+
+    /// Restart the server with `pp.explicit` on or off, since Lean reads
+    /// pretty-printer options only at startup, and refetch the goals at the
+    /// cursor from it.
+    fn spawn_explicit_restart(&self, uri: Url, position: Position, explicit: bool) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for refetch".to_string());
+            self.socket_server.send(Message::Explicit { explicit: false });
+            return;
+        };
+        let server = self.socket_server.clone();
+        let documents = self.documents.clone();
+        tokio::spawn(async move {
+            server.send(Message::Status {
+                message: "Restarting server".to_string(),
+            });
+            if let Err(e) = rpc_client.restart(explicit, || documents.reopen_params()).await {
+                tracing::warn!("Could not restart server: {e}");
+                server.send(Message::Explicit {
+                    explicit: !explicit,
+                });
+                server.broadcast_error(format!("Server restart failed: {e}"));
+                return;
+            }
+            server.send(Message::Explicit { explicit });
+            let mode = rpc_client.dag_mode().await;
+            match rpc_client.get_proof_dag(&uri, position, mode).await {
+                Ok(proof_dag) => server.broadcast_proof_dag(uri, position, None, proof_dag),
+                Err(e) => {
+                    tracing::warn!("Could not refetch goals: {e}");
+                    server.broadcast_error(e.to_string());
+                }
+            }
+        });
+    }
+
+    /// Look up workspace symbols and send the matches back.
+    fn spawn_symbol_search(&self, query: String) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for symbol search".to_string());
+            return;
+        };
+        let socket_server = self.socket_server.clone();
+        tokio::spawn(async move {
+            match rpc_client.workspace_symbols(&query).await {
+                Ok(symbols) => socket_server.send(Message::Symbols { query, symbols }),
+                Err(e) => {
+                    tracing::warn!("Could not search symbols: {e}");
+                    socket_server.broadcast_error(format!("Symbol search failed: {e}"));
+                }
+            }
+        });
+    }
+
+    /// Show where the symbol at a position is implemented, or report that
+    /// the server knows no implementation.
+    fn spawn_implementation_lookup(&self, uri: Url, position: Position) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for go to implementation".to_string());
+            return;
+        };
+        let socket_server = self.socket_server.clone();
+        let mut socket = self.socket.clone();
+        tokio::spawn(async move {
+            match rpc_client.implementation(&uri, position).await {
+                Ok(Some(location)) => {
+                    let selection = Range::new(location.position, location.position);
+                    show_document(&mut socket, location.uri, selection).await;
+                }
+                Ok(None) => socket_server.send(Message::Status {
+                    message: "No implementation found".to_string(),
+                }),
+                Err(e) => {
+                    tracing::warn!("Could not find implementation: {e}");
+                    socket_server.broadcast_error(format!("Go to implementation failed: {e}"));
+                }
+            }
+        });
+    }
+
+    /// Reconnect the RPC session for a document and report the outcome.
+    fn spawn_session_reset(&self, uri: Url) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for session reset".to_string());
+            return;
+        };
+        let socket_server = self.socket_server.clone();
+        tokio::spawn(async move {
+            match rpc_client.reset_session(&uri).await {
+                Ok(session_id) => socket_server.send(Message::Status {
+                    message: format!("RPC session reset ({session_id})"),
+                }),
+                Err(e) => {
+                    tracing::warn!("Could not reset RPC session: {e}");
+                    socket_server.broadcast_error(format!("Session reset failed: {e}"));
+                }
+            }
+        });
+    }
+
+    /// Fetch the proof DAG at a comparison position and send it back.
+    fn spawn_comparison_fetch(&self, uri: Url, position: Position) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for comparison".to_string());
+            return;
+        };
+        let socket_server = self.socket_server.clone();
+        tokio::spawn(async move {
+            let mode = rpc_client.dag_mode().await;
+            match rpc_client.get_proof_dag(&uri, position, mode).await {
+                Ok(proof_dag) => socket_server.send(Message::Comparison {
+                    uri,
+                    position,
+                    revision: None,
+                    proof_dag,
+                }),
+                Err(e) => {
+                    tracing::warn!("Could not fetch comparison goals: {e}");
+                    socket_server.broadcast_error(e.to_string());
+                }
+            }
+        });
+    }
+
+    /// Fetch the proof DAG at the same position of the document as
+    /// committed at `HEAD` and send it back as a comparison.
+    fn spawn_head_comparison_fetch(&self, uri: Url, position: Position) {
+        let Some(rpc_client) = self.rpc_client.get().cloned() else {
+            self.socket_server
+                .broadcast_error("RPC client unavailable for comparison".to_string());
+            return;
+        };
+        let socket_server = self.socket_server.clone();
+        tokio::spawn(async move {
+            match fetch_at_head(&rpc_client, &uri, position).await {
+                Ok(proof_dag) => socket_server.send(Message::Comparison {
+                    uri,
+                    position,
+                    revision: Some("HEAD".to_string()),
+                    proof_dag,
+                }),
+                Err(e) => {
+                    tracing::warn!("Could not fetch HEAD comparison goals: {e}");
+                    socket_server.broadcast_error(format!("HEAD comparison failed: {e}"));
+                }
+            }
+        });
+    }
+
     async fn send_show_document(&mut self, uri: Url, position: Position) {
         let selection = Range::new(position, position);
         self.send_show_document_with_selection(uri, selection).await;
     }
 
     async fn send_show_document_with_selection(&mut self, uri: Url, selection: Range) {
-        let params = ShowDocumentParams {
-            uri,
-            external: None,
-            take_focus: Some(true),
-            selection: Some(selection),
-        };
+        show_document(&mut self.socket, uri, selection).await;
+    }
+}
 
-        tracing::debug!("showDocument params: {params:?}");
+/// Proof DAG at `position` of the document's `HEAD` version, opened beside
+/// the working copy for the fetch and closed again afterwards.
+async fn fetch_at_head(
+    rpc_client: &RpcClient,
+    uri: &Url,
+    position: Position,
+) -> Result<Option<ProofDag>, String> {
+    let path = uri
+        .to_file_path()
+        .map_err(|()| format!("{uri} is not a local file"))?;
+    let content = head_content(&path).await?;
+    let head_uri = head_copy_uri(uri).ok_or_else(|| format!("{uri} is not a local file"))?;
 
-        match self.socket.show_document(params).await {
-            Ok(result) => {
-                tracing::info!("showDocument result: success={}", result.success);
-            }
-            Err(e) => {
-                tracing::error!("showDocument failed: {e:?}");
-            }
+    let text_document = TextDocumentItem::new(head_uri.clone(), "lean".to_string(), 1, content);
+    rpc_client
+        .did_open(DidOpenTextDocumentParams { text_document })
+        .await
+        .map_err(|e| e.to_string())?;
+    let mode = rpc_client.dag_mode().await;
+    let result = rpc_client.get_proof_dag(&head_uri, position, mode).await;
+    if let Err(e) = rpc_client.did_close(&head_uri).await {
+        tracing::warn!("Could not close HEAD copy {head_uri}: {e}");
+    }
+    result.map_err(|e| e.to_string())
+}
+
+/// Ask the editor to show `selection` in a document.
+async fn show_document(socket: &mut ClientSocket, uri: Url, selection: Range) {
+    let params = ShowDocumentParams {
+        uri,
+        external: None,
+        take_focus: Some(true),
+        selection: Some(selection),
+    };
+
+    tracing::debug!("showDocument params: {params:?}");
+
+    match socket.show_document(params).await {
+        Ok(result) => {
+            tracing::info!("showDocument result: success={}", result.success);
         }
+        Err(e) => {
+            tracing::error!("showDocument failed: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+
+    use super::*;
+
+    /// Cancelling aborts the tracked fetch; a second cancel finds nothing.
+    #[tokio::test]
+    async fn test_cancel_aborts_tracked_fetch() {
+        let endpoint = LspProxySocketEndpoint::new(ServerMode::Library);
+        let task = tokio::spawn(pending::<()>());
+        endpoint.track_goal_fetch(task.abort_handle());
+
+        assert!(endpoint.cancel_goal_fetch());
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(!endpoint.cancel_goal_fetch());
+    }
+
+    /// Edits are timed while a client wants timings, and no longer once
+    /// it disconnects without saying otherwise.
+    #[test]
+    fn test_elaboration_viewer_lapses_on_disconnect() {
+        let endpoint = LspProxySocketEndpoint::new(ServerMode::Library);
+        let viewer = || ElaborationViewer::new(endpoint.elaboration_viewers.clone());
+        assert!(!endpoint.times_elaboration());
+
+        let mut first = viewer();
+        let mut second = viewer();
+        first.set(true);
+        first.set(true);
+        second.set(true);
+        second.set(false);
+        assert!(endpoint.times_elaboration());
+
+        drop(first);
+        assert!(!endpoint.times_elaboration());
+        drop(second);
+        assert!(!endpoint.times_elaboration());
     }
 }
@@ -1,14 +1,14 @@
 
-use std::{io, time::Duration};
+use std::time::Duration;
 
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::{unix::OwnedWriteHalf, UnixStream},
+    io::BufReader,
+    net::{unix::OwnedReadHalf, UnixStream},
     sync::mpsc,
     time::sleep,
 };
 
-use super::protocol::{socket_path, Command, Message};
+use super::protocol::{read_frame, socket_path, write_frame, Command, FrameError, Message};
 
 /// Handle for communicating with the proxy.
 pub struct TuiIpcSocketEndpoint {
@@ -36,7 +36,9 @@ async fn connection_loop(msg_tx: mpsc::Sender<Message>, mut cmd_rx: mpsc::Receiv
     loop {
         match UnixStream::connect(&path).await {
             Ok(stream) => {
-                handle_connection(stream, &msg_tx, &mut cmd_rx).await;
+                if !handle_connection(stream, &msg_tx, &mut cmd_rx).await {
+                    return;
+                }
             }
             Err(_) => {
                 // Retry connection after delay
@@ -46,53 +48,58 @@ async fn connection_loop(msg_tx: mpsc::Sender<Message>, mut cmd_rx: mpsc::Receiv
     }
 }
 
+/// Exchange frames with the proxy until the connection drops. Returns
+/// `false` once the TUI has gone away and there is no point reconnecting.
 async fn handle_connection(
     stream: UnixStream,
     msg_tx: &mpsc::Sender<Message>,
     cmd_rx: &mut mpsc::Receiver<Command>,
-) {
+) -> bool {
     let (reader, mut writer) = stream.into_split();
-    let reader = BufReader::new(reader);
-    let mut lines = reader.lines();
+    // Frames are read in a task of their own, as reading is not cancel safe
+    let mut messages = tokio::spawn(forward_messages(BufReader::new(reader), msg_tx.clone()));
 
-    loop {
+    let keep_going = loop {
         tokio::select! {
-            line_result = lines.next_line() => {
-                match handle_incoming_line(line_result, msg_tx).await {
-                    Ok(true) => {}
-                    Ok(false) | Err(()) => break,
-                }
-            }
+            forwarded = &mut messages => break forwarded.unwrap_or(true),
             Some(cmd) = cmd_rx.recv() => {
-                if send_command(&mut writer, &cmd).await.is_err() {
-                    break;
+                match write_frame(&mut writer, &cmd).await {
+                    // Skip a command that cannot be encoded, keep the connection
+                    Ok(()) | Err(FrameError::Malformed(_)) => {}
+                    Err(_) => break true,
                 }
             }
         }
-    }
+    };
+    messages.abort();
+    keep_going
 }
 
-async fn handle_incoming_line(
-    line_result: Result<Option<String>, io::Error>,
-    msg_tx: &mpsc::Sender<Message>,
-) -> Result<bool, ()> {
-    match line_result {
-        Ok(Some(line)) => {
-            if let Ok(msg) = serde_json::from_str::<Message>(&line) {
-                if msg_tx.send(msg).await.is_err() {
-                    return Err(()); // TUI closed, exit task
+/// Pass messages from the proxy on to the TUI until the connection drops.
+/// Returns `false` when the TUI closed its end.
+async fn forward_messages(
+    mut reader: BufReader<OwnedReadHalf>,
+    msg_tx: mpsc::Sender<Message>,
+) -> bool {
+    loop {
+        let msg = match read_frame::<_, Message>(&mut reader).await {
+            Ok(Some(msg)) => msg,
+            // Messages from a newer proxy this TUI does not know
+            Err(FrameError::Malformed(_)) => continue,
+            Err(e @ FrameError::VersionMismatch { .. }) => {
+                let error = e.to_string();
+                if msg_tx.send(Message::Error { error }).await.is_err() {
+                    return false;
                 }
+                // Wait before reconnecting to the same incompatible proxy
+                sleep(Duration::from_secs(5)).await;
+                return true;
             }
-            Ok(true)
+            // Connection closed, reconnect
+            Ok(None) | Err(_) => return true,
+        };
+        if msg_tx.send(msg).await.is_err() {
+            return false;
         }
-        Ok(None) | Err(_) => Ok(false), // Connection closed, reconnect
     }
 }
-
-async fn send_command(writer: &mut OwnedWriteHalf, cmd: &Command) -> Result<(), ()> {
-    let Ok(json) = serde_json::to_string(cmd) else {
-        return Ok(()); // Skip invalid command, don't break connection
-    };
-    let line = format!("{json}\n");
-    writer.write_all(line.as_bytes()).await.map_err(|_| ())
-}